@@ -0,0 +1,58 @@
+//! Measures the borrows of a `GrainedLock`, whose guards are stored in each `Ref`, and the
+//! retrievals of resources built on them.
+//!
+//! Run with `cargo bench --bench lock`.
+
+use std::{
+    hint::black_box,
+    time::{Duration, Instant},
+};
+
+use emark::lock::GrainedLock;
+use emark::prelude::*;
+use emark::store::ResourceContainer;
+
+const ITERATIONS: u32 = 1_000_000;
+
+struct Position;
+struct Velocity;
+
+fn measure(name: &str, mut f: impl FnMut()) -> Duration {
+    // warm up the caches before measuring
+    for _ in 0..ITERATIONS / 10 {
+        f();
+    }
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        f();
+    }
+    let elapsed = start.elapsed();
+    println!(
+        "{name:<24} {:>8.1} ns/iter",
+        elapsed.as_nanos() as f64 / f64::from(ITERATIONS)
+    );
+    elapsed
+}
+
+fn main() {
+    let lock = GrainedLock::new(0u64);
+    measure("borrow", || {
+        black_box(lock.borrow());
+    });
+    measure("borrow_mut", || {
+        black_box(lock.borrow_mut());
+    });
+
+    // one guard for the outer lock, one for the nested lock
+    let nested = GrainedLock::new(vec![GrainedLock::new(0u64)]);
+    measure("nested borrow_mut", || {
+        black_box(nested.borrow().map_cell(|locks| &locks[0]).borrow_mut());
+    });
+
+    let mut container = ResourceContainer::default();
+    container.add_resource(Position);
+    container.add_resource(Velocity);
+    measure("retrieve", || {
+        black_box(<(ResMut<Position>, Res<Velocity>)>::retrieve(&container));
+    });
+}
@@ -0,0 +1,109 @@
+//! Compares the retrieval of a tuple sorting its locking order on every call with the
+//! retrievals following a locking order sorted once: the ones of `Retriever::retrieve_with`,
+//! whose order is cached per thread, the parameters of a system and the retrievals of a
+//! handler through its `Context`.
+//!
+//! Run with `cargo bench --bench retrieve`.
+
+use std::{
+    hint::black_box,
+    time::{Duration, Instant},
+};
+
+use emark::event::{EventManager, HandlerRegistry};
+use emark::prelude::*;
+use emark::store::{ResourceContainer, RetrievalPlan, SystemTicks};
+
+const ITERATIONS: u32 = 1_000_000;
+
+struct Position;
+struct Velocity;
+struct Gravity;
+struct Drag;
+struct Frame;
+struct Scale;
+
+struct Step;
+
+impl Event for Step {}
+
+type Params<'a> = (
+    ResMut<'a, Position>,
+    Res<'a, Velocity>,
+    Res<'a, Gravity>,
+    Res<'a, Drag>,
+    Res<'a, Frame>,
+    Res<'a, Scale>,
+);
+
+fn integrate(
+    position: ResMut<Position>,
+    velocity: Res<Velocity>,
+    gravity: Res<Gravity>,
+    drag: Res<Drag>,
+    frame: Res<Frame>,
+    scale: Res<Scale>,
+) {
+    black_box((position, velocity, gravity, drag, frame, scale));
+}
+
+fn measure(name: &str, mut f: impl FnMut()) -> Duration {
+    // warm up the caches before measuring
+    for _ in 0..ITERATIONS / 10 {
+        f();
+    }
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        f();
+    }
+    let elapsed = start.elapsed();
+    println!(
+        "{name:<24} {:>8.1} ns/iter",
+        elapsed.as_nanos() as f64 / f64::from(ITERATIONS)
+    );
+    elapsed
+}
+
+fn main() {
+    let mut container = ResourceContainer::default();
+    container.add_resource(Position);
+    container.add_resource(Velocity);
+    container.add_resource(Gravity);
+    container.add_resource(Drag);
+    container.add_resource(Frame);
+    container.add_resource(Scale);
+    let ticks = SystemTicks::new(0, 1);
+
+    let sorted = measure("sorted on every call", || {
+        let plan = RetrievalPlan::new::<Params>();
+        black_box(Params::try_retrieve_planned(&container, ticks, &plan).unwrap());
+    });
+    measure("cached per thread", || {
+        black_box(Params::retrieve_with(&container, ticks));
+    });
+    let plan = RetrievalPlan::new::<Params>();
+    let planned = measure("sorted once", || {
+        black_box(Params::try_retrieve_planned(&container, ticks, &plan).unwrap());
+    });
+    println!(
+        "speedup                  {:>8.2}x",
+        sorted.as_secs_f64() / planned.as_secs_f64()
+    );
+
+    // the same retrieval through a system, including its run
+    let mut system = integrate.into_system();
+    let event_manager = EventManager::new();
+    measure("system run", || {
+        system.run(&container, &event_manager).unwrap();
+    });
+
+    // the same retrieval through the context of a handler, including the dispatch of its event
+    let mut handlers = HandlerRegistry::new();
+    handlers.add_context_handler(|_: &[Step], context| {
+        black_box(context.retrieve::<Params>());
+    });
+    measure("handler dispatch", || {
+        event_manager.emit(Step);
+        handlers.dispatch_with(&container, &event_manager);
+    });
+}
@@ -0,0 +1,310 @@
+//! Derive macros of `emark`, re-exported by `emark` with the `derive` feature.
+
+use proc_macro::{Delimiter, Spacing, TokenStream, TokenTree};
+
+// lifetime of the borrows in the generated implementations
+const LIFETIME: &str = "'__emark";
+
+/// Derives `Retriever` for a struct whose fields are all retrievers.
+///
+/// The struct takes at most one lifetime, the lifetime of the borrows of its fields. The
+/// resources of every field are locked together in sorted `TypeId` order, as for tuples, and
+/// assembled in declaration order.
+///
+/// # Examples
+/// ```ignore
+/// use emark::prelude::*;
+/// use emark::store::ResourceContainer;
+///
+/// #[derive(Retriever)]
+/// struct Params<'a> {
+///     factor: Res<'a, u32>,
+///     values: ResMut<'a, Vec<u32>>,
+/// }
+///
+/// let mut container = ResourceContainer::default();
+/// container.add_resource(2u32);
+/// container.add_resource(Vec::<u32>::new());
+///
+/// let mut params = Params::retrieve(&container);
+/// params.values.push(*params.factor * 2);
+/// assert_eq!(*params.values, vec![4]);
+/// ```
+#[proc_macro_derive(Retriever)]
+pub fn derive_retriever(input: TokenStream) -> TokenStream {
+    let expanded = match Struct::parse(input) {
+        Ok(parsed) => parsed.expand_retriever(),
+        Err(error) => format!("::core::compile_error!({error:?});"),
+    };
+    expanded.parse().unwrap()
+}
+
+// fields of a struct, with the names of named fields
+enum Fields {
+    Named(Vec<(String, String)>),
+    Unnamed(Vec<String>),
+    Unit,
+}
+
+// struct deriving a trait, its generics and field types kept as source
+struct Struct {
+    name: String,
+    params: Vec<String>,
+    args: Vec<String>,
+    lifetimes: Vec<String>,
+    where_clause: String,
+    fields: Fields,
+}
+
+impl Struct {
+    fn parse(input: TokenStream) -> Result<Self, String> {
+        let tokens = input.into_iter().collect::<Vec<_>>();
+        let mut cursor = skip_attributes(&tokens, 0);
+        cursor = skip_visibility(&tokens, cursor);
+        match tokens.get(cursor) {
+            Some(TokenTree::Ident(ident)) if ident.to_string() == "struct" => cursor += 1,
+            _ => return Err("`Retriever` can only be derived for structs".to_owned()),
+        }
+        let Some(TokenTree::Ident(name)) = tokens.get(cursor) else {
+            return Err("expected the name of the struct".to_owned());
+        };
+        cursor += 1;
+
+        // generics
+        let mut params = Vec::new();
+        if is_punct(tokens.get(cursor), '<') {
+            let end = closing_angle(&tokens, cursor)?;
+            params = split_commas(&tokens[cursor + 1..end]);
+            cursor = end + 1;
+        }
+        let mut args = Vec::new();
+        let mut lifetimes = Vec::new();
+        for param in &params {
+            let arg = match param.as_slice() {
+                [TokenTree::Punct(quote), TokenTree::Ident(ident), ..]
+                    if quote.as_char() == '\'' =>
+                {
+                    let lifetime = format!("'{ident}");
+                    lifetimes.push(lifetime.clone());
+                    lifetime
+                }
+                [TokenTree::Ident(keyword), TokenTree::Ident(ident), ..]
+                    if keyword.to_string() == "const" =>
+                {
+                    ident.to_string()
+                }
+                [TokenTree::Ident(ident), ..] => ident.to_string(),
+                _ => return Err("unsupported generic parameter".to_owned()),
+            };
+            args.push(arg);
+        }
+        if lifetimes.len() > 1 {
+            return Err("structs deriving `Retriever` take at most one lifetime".to_owned());
+        }
+
+        // where clause and fields, the where clause of tuple structs follows their fields
+        let mut where_clause = Vec::new();
+        let mut fields = None;
+        if let Some(TokenTree::Group(group)) = tokens.get(cursor) {
+            if group.delimiter() == Delimiter::Parenthesis {
+                fields = Some(parse_unnamed(group.stream()));
+                cursor += 1;
+            }
+        }
+        for token in &tokens[cursor..] {
+            match token {
+                TokenTree::Group(group) if group.delimiter() == Delimiter::Brace => {
+                    fields = Some(parse_named(group.stream())?);
+                }
+                TokenTree::Punct(punct) if punct.as_char() == ';' => {}
+                token => where_clause.push(token.clone()),
+            }
+        }
+
+        Ok(Struct {
+            name: name.to_string(),
+            params: params.iter().map(|param| source(param)).collect(),
+            args,
+            lifetimes,
+            where_clause: source(&where_clause),
+            fields: fields.unwrap_or(Fields::Unit),
+        })
+    }
+
+    // type of the struct with its lifetime replaced by `lifetime`
+    fn with_lifetime(&self, lifetime: &str) -> String {
+        if self.args.is_empty() {
+            return self.name.clone();
+        }
+        let args = self
+            .args
+            .iter()
+            .map(|arg| match self.lifetimes.contains(arg) {
+                true => lifetime,
+                false => arg.as_str(),
+            })
+            .collect::<Vec<_>>();
+        format!("{}<{}>", self.name, args.join(", "))
+    }
+
+    fn expand_retriever(&self) -> String {
+        let retriever = "::emark::store::Retriever";
+        let field_types = match &self.fields {
+            Fields::Named(fields) => fields.iter().map(|(_, ty)| ty.clone()).collect(),
+            Fields::Unnamed(fields) => fields.clone(),
+            Fields::Unit => Vec::new(),
+        };
+        let requests = field_types
+            .iter()
+            .map(|ty| format!("<{ty} as {retriever}>::requests(requests);"))
+            .collect::<String>();
+        let assemble = |ty: &String| format!("<{ty} as {retriever}>::assemble(retrieved)");
+        let item = match &self.fields {
+            Fields::Named(fields) => {
+                let fields = fields
+                    .iter()
+                    .map(|(name, ty)| format!("{name}: {},", assemble(ty)))
+                    .collect::<String>();
+                format!("{} {{ {fields} }}", self.name)
+            }
+            Fields::Unnamed(fields) => {
+                let fields = fields
+                    .iter()
+                    .map(|ty| format!("{},", assemble(ty)))
+                    .collect::<String>();
+                format!("{}({fields})", self.name)
+            }
+            Fields::Unit => self.name.clone(),
+        };
+
+        format!(
+            "impl<{params}> {retriever} for {ty} {where_clause} {{
+                type Item<{LIFETIME}> = {item_ty};
+
+                #[allow(unused_variables)]
+                fn requests(requests: &mut ::std::vec::Vec<::emark::store::Request>) {{
+                    {requests}
+                }}
+
+                #[allow(unused_variables)]
+                fn assemble<{LIFETIME}>(
+                    retrieved: &mut dyn ::std::iter::Iterator<
+                        Item = ::emark::store::Retrieved<{LIFETIME}>,
+                    >,
+                ) -> Self::Item<{LIFETIME}> {{
+                    {item}
+                }}
+            }}",
+            params = self.params.join(", "),
+            ty = self.with_lifetime(self.lifetimes.first().map_or("'_", String::as_str)),
+            where_clause = self.where_clause,
+            item_ty = self.with_lifetime(LIFETIME),
+        )
+    }
+}
+
+fn parse_named(stream: TokenStream) -> Result<Fields, String> {
+    let tokens = stream.into_iter().collect::<Vec<_>>();
+    let mut fields = Vec::new();
+    for field in split_commas(&tokens) {
+        let cursor = skip_visibility(&field, skip_attributes(&field, 0));
+        match (field.get(cursor), field.get(cursor + 1)) {
+            (Some(TokenTree::Ident(name)), Some(TokenTree::Punct(colon)))
+                if colon.as_char() == ':' =>
+            {
+                fields.push((name.to_string(), source(&field[cursor + 2..])));
+            }
+            _ => return Err("expected a named field".to_owned()),
+        }
+    }
+    Ok(Fields::Named(fields))
+}
+
+fn parse_unnamed(stream: TokenStream) -> Fields {
+    let tokens = stream.into_iter().collect::<Vec<_>>();
+    let fields = split_commas(&tokens)
+        .iter()
+        .map(|field| source(&field[skip_visibility(field, skip_attributes(field, 0))..]))
+        .collect();
+    Fields::Unnamed(fields)
+}
+
+fn is_punct(token: Option<&TokenTree>, char: char) -> bool {
+    matches!(token, Some(TokenTree::Punct(punct)) if punct.as_char() == char)
+}
+
+// skips the attributes starting at `cursor`, e.g. doc comments
+fn skip_attributes(tokens: &[TokenTree], mut cursor: usize) -> usize {
+    while is_punct(tokens.get(cursor), '#') {
+        cursor += 2;
+    }
+    cursor
+}
+
+// skips `pub`, `pub(crate)` and the like
+fn skip_visibility(tokens: &[TokenTree], mut cursor: usize) -> usize {
+    if matches!(tokens.get(cursor), Some(TokenTree::Ident(ident)) if ident.to_string() == "pub") {
+        cursor += 1;
+        if let Some(TokenTree::Group(group)) = tokens.get(cursor) {
+            if group.delimiter() == Delimiter::Parenthesis {
+                cursor += 1;
+            }
+        }
+    }
+    cursor
+}
+
+// index of the `>` closing the `<` at `open`, ignoring the arrows of `Fn` bounds
+fn closing_angle(tokens: &[TokenTree], open: usize) -> Result<usize, String> {
+    let mut depth = 0;
+    for (index, token) in tokens.iter().enumerate().skip(open) {
+        let TokenTree::Punct(punct) = token else {
+            continue;
+        };
+        match punct.as_char() {
+            '<' => depth += 1,
+            '>' if !is_arrow(tokens, index) => {
+                depth -= 1;
+                if depth == 0 {
+                    return Ok(index);
+                }
+            }
+            _ => {}
+        }
+    }
+    Err("unclosed generics".to_owned())
+}
+
+fn is_arrow(tokens: &[TokenTree], index: usize) -> bool {
+    index > 0
+        && matches!(
+            &tokens[index - 1],
+            TokenTree::Punct(punct) if punct.as_char() == '-' && punct.spacing() == Spacing::Joint
+        )
+}
+
+// splits tokens on the commas outside of generics, dropping empty items
+fn split_commas(tokens: &[TokenTree]) -> Vec<Vec<TokenTree>> {
+    let mut items = vec![Vec::new()];
+    let mut depth = 0usize;
+    for (index, token) in tokens.iter().enumerate() {
+        if let TokenTree::Punct(punct) = token {
+            match punct.as_char() {
+                '<' => depth += 1,
+                '>' if !is_arrow(tokens, index) => depth = depth.saturating_sub(1),
+                ',' if depth == 0 => {
+                    items.push(Vec::new());
+                    continue;
+                }
+                _ => {}
+            }
+        }
+        items.last_mut().unwrap().push(token.clone());
+    }
+    items.retain(|item| !item.is_empty());
+    items
+}
+
+fn source(tokens: &[TokenTree]) -> String {
+    tokens.iter().cloned().collect::<TokenStream>().to_string()
+}
@@ -0,0 +1,677 @@
+use std::{
+    any::TypeId,
+    collections::HashSet,
+    fmt::Debug,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use super::{
+    state::{ScopedHandler, StateDriver, StateMachine},
+    Plugin, States, SubWorld, Transition,
+};
+use crate::{
+    asset::{AssetEventSystem, Assets},
+    event::{handler::HandlerResult, schema::SchemaRegistry, Event, HandlerRegistry},
+    store::{Container, Context, FromContainer, ReadContext},
+    system::{stage, ExclusiveSystem, Executor, IntoSystem, Schedule},
+    world::World,
+};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+/// Event requesting an [App] to stop running.
+pub struct AppExit;
+
+impl Event for AppExit {}
+
+// callback run at a fixed point of each cycle
+type CycleHook = Box<dyn FnMut(&mut World)>;
+
+/// # App
+///
+/// Builder and runner of an application, owning its [World], its [Schedule] and its
+/// handlers.
+///
+/// # Examples
+/// ```
+/// use emark::event::EventManager;
+/// use emark::prelude::*;
+/// use emark::store::ResourceContainer;
+///
+/// struct Tick;
+/// impl Event for Tick {}
+///
+/// App::new()
+///     .add_resource(0u32)
+///     .add_event::<Tick>()
+///     .add_handler(|ticks: &[Tick]| assert_eq!(ticks.len(), 1))
+///     .add_system(|_: &ResourceContainer, event_manager: &EventManager| {
+///         event_manager.emit(Tick);
+///         event_manager.emit(AppExit);
+///     })
+///     .run();
+/// ```
+pub struct App {
+    world: World,
+    schedule: Schedule,
+    handlers: HandlerRegistry,
+    schemas: SchemaRegistry,
+    plugins: HashSet<TypeId>,
+    states: Vec<Box<dyn StateDriver>>,
+    sub_worlds: Vec<(&'static str, SubWorld)>,
+    begin_hooks: Vec<CycleHook>,
+    end_hooks: Vec<CycleHook>,
+    runner: Box<dyn FnOnce(App)>,
+    exit: Arc<AtomicBool>,
+}
+
+// run cycles until an `AppExit` event is dispatched
+fn run_until_exit(mut app: App) {
+    while !app.should_exit() {
+        app.update();
+    }
+}
+
+impl Default for App {
+    fn default() -> Self {
+        let exit = Arc::new(AtomicBool::new(false));
+        let mut handlers = HandlerRegistry::new();
+        let handler_exit = exit.clone();
+        handlers.add_handler(move |_: &[AppExit]| handler_exit.store(true, Ordering::Release));
+
+        Self {
+            world: World::new(),
+            schedule: Schedule::new(),
+            handlers,
+            schemas: SchemaRegistry::new(),
+            plugins: HashSet::new(),
+            states: Vec::new(),
+            sub_worlds: Vec::new(),
+            begin_hooks: Vec::new(),
+            end_hooks: Vec::new(),
+            runner: Box::new(run_until_exit),
+            exit,
+        }
+    }
+}
+
+impl App {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a resource to the world, replacing the resource of the same type.
+    ///
+    /// Emits a `ResourceInserted` event if resource events are enabled.
+    pub fn add_resource<T: Send + Sync + 'static>(&mut self, resource: T) -> &mut Self {
+        self.world.add_resource(resource);
+        self
+    }
+
+    /// Enables the emission of lifecycle events for the resources added and removed through
+    /// the world, see `World::set_resource_events`.
+    pub fn set_resource_events(&mut self, enabled: bool) -> &mut Self {
+        self.world.set_resource_events(enabled);
+        self
+    }
+
+    /// Adds a resource constructed with [FromContainer] to the world, unless it already holds
+    /// one, see `Container::init_resource`.
+    ///
+    /// # Panics
+    /// Panics if the dependencies of the resource are cyclic or missing.
+    pub fn init_resource<T: FromContainer>(&mut self) -> &mut Self {
+        self.world.container_mut().init_resource::<T>();
+        self
+    }
+
+    /// Adds an empty [Assets] store of type `T` to the world, unless it already holds one.
+    ///
+    /// The asset events recorded by the store are emitted at the end of the
+    /// [POST_UPDATE](crate::system::stage::POST_UPDATE) stage of each cycle.
+    pub fn add_asset<T: Send + Sync + 'static>(&mut self) -> &mut Self {
+        if self.world.contains_resource::<Assets<T>>() {
+            return self;
+        }
+        self.world.add_resource(Assets::<T>::new());
+        self.schedule
+            .add_system_to_stage(stage::POST_UPDATE, AssetEventSystem::<T>::new());
+        self
+    }
+
+    /// Registers event `T` in the schemas of the app under its type name.
+    ///
+    /// Events do not need to be registered to be emitted and handled, registration makes them
+    /// known to the introspection features relying on the [SchemaRegistry].
+    ///
+    /// # Panics
+    /// Panics if the type name is already bound to another event type.
+    pub fn add_event<T: Event + 'static>(&mut self) -> &mut Self {
+        if let Err(error) = self.schemas.register::<T>(std::any::type_name::<T>()) {
+            panic!("{error}");
+        }
+        self
+    }
+
+    /// Registers a handler of event `T`, see `HandlerRegistry::add_handler`.
+    pub fn add_handler<T, R, F>(&mut self, handler: F) -> &mut Self
+    where
+        T: Event + Send + Sync + 'static,
+        R: HandlerResult,
+        F: Fn(&[T]) -> R + Send + Sync + 'static,
+    {
+        self.handlers.add_handler(handler);
+        self
+    }
+
+    /// Registers a handler of event `T` receiving the [Context] of the world, see
+    /// `HandlerRegistry::add_context_handler`.
+    ///
+    /// The commands recorded by the handlers are applied once the handlers of the cycle are
+    /// dispatched.
+    pub fn add_context_handler<T, R, F>(&mut self, handler: F) -> &mut Self
+    where
+        T: Event + Send + Sync + 'static,
+        R: HandlerResult,
+        F: Fn(&[T], &Context) -> R + Send + Sync + 'static,
+    {
+        self.handlers.add_context_handler(handler);
+        self
+    }
+
+    /// Registers a handler of event `T` receiving the [ReadContext] of the world, see
+    /// `HandlerRegistry::add_read_handler`.
+    pub fn add_read_handler<T, R, F>(&mut self, handler: F) -> &mut Self
+    where
+        T: Event + Send + Sync + 'static,
+        R: HandlerResult,
+        F: Fn(&[T], &ReadContext) -> R + Send + Sync + 'static,
+    {
+        self.handlers.add_read_handler(handler);
+        self
+    }
+
+    /// Appends a system to the [UPDATE](crate::system::stage::UPDATE) stage of the schedule.
+    pub fn add_system<M>(&mut self, system: impl IntoSystem<M>) -> &mut Self {
+        self.schedule.add_system(system);
+        self
+    }
+
+    /// Appends an exclusive system to the [UPDATE](crate::system::stage::UPDATE) stage of the
+    /// schedule.
+    pub fn add_exclusive_system(&mut self, system: impl ExclusiveSystem + 'static) -> &mut Self {
+        self.schedule.add_exclusive_system(system);
+        self
+    }
+
+    /// Appends a startup system to the schedule, run once before the next cycle.
+    pub fn add_startup_system<M>(&mut self, system: impl IntoSystem<M>) -> &mut Self {
+        self.schedule.add_startup_system(system);
+        self
+    }
+
+    /// Adds a plugin, building it into the app.
+    ///
+    /// # Panics
+    /// Panics if a plugin of type `P` has already been added.
+    pub fn add_plugin<P: Plugin>(&mut self, plugin: P) -> &mut Self {
+        assert!(
+            self.plugins.insert(TypeId::of::<P>()),
+            "plugin `{}` is already added",
+            std::any::type_name::<P>()
+        );
+        plugin.build(self);
+        self
+    }
+
+    /// Returns `true` if a plugin of type `P` has been added.
+    pub fn has_plugin<P: Plugin>(&self) -> bool {
+        self.plugins.contains(&TypeId::of::<P>())
+    }
+
+    /// Adds the states of type `S`, starting in state `initial`.
+    ///
+    /// Inserts the [State](super::State) and [NextState](super::NextState) resources of `S`.
+    /// The state requested through `NextState` is entered at the beginning of the next cycle,
+    /// after running the schedule of the exited state and before the schedule of the entered
+    /// state.
+    ///
+    /// # Panics
+    /// Panics if the states of type `S` have already been added.
+    pub fn add_state<S: States>(&mut self, initial: S) -> &mut Self {
+        assert!(
+            self.state_machine::<S>().is_none(),
+            "state `{}` is already added",
+            std::any::type_name::<S>()
+        );
+        StateMachine::insert_resources(self.world.container_mut(), initial);
+        self.states.push(Box::new(StateMachine::<S>::new()));
+        self
+    }
+
+    /// Appends a system to the schedule run on `transition`, an [OnEnter](super::OnEnter) or
+    /// an [OnExit](super::OnExit) of a state.
+    ///
+    /// # Panics
+    /// Panics if the states of type `S` have not been added.
+    pub fn add_transition_system<S: States, M>(
+        &mut self,
+        transition: impl Transition<S>,
+        system: impl IntoSystem<M>,
+    ) -> &mut Self {
+        let Some(machine) = self.state_machine::<S>() else {
+            panic!("state `{}` is not added", std::any::type_name::<S>());
+        };
+        machine.schedule_mut(&transition).add_system(system);
+        self
+    }
+
+    /// Registers a handler of event `T` scoped to `state`.
+    ///
+    /// The handler is attached when entering the state and detached when exiting it, so it
+    /// only receives the events dispatched while the state is active.
+    ///
+    /// # Panics
+    /// Panics if the states of type `S` have not been added.
+    pub fn add_state_handler<S, T, R, F>(&mut self, state: S, handler: F) -> &mut Self
+    where
+        S: States,
+        T: Event + Send + Sync + 'static,
+        R: HandlerResult,
+        F: Fn(&[T]) -> R + Send + Sync + 'static,
+    {
+        let handler = Arc::new(handler);
+        let scoped: ScopedHandler = Box::new(move |registry| {
+            let handler = handler.clone();
+            registry.add_handler(move |events: &[T]| handler(events))
+        });
+
+        let Self {
+            world,
+            handlers,
+            states,
+            ..
+        } = self;
+        let Some(machine) = Self::find_state_machine::<S>(states) else {
+            panic!("state `{}` is not added", std::any::type_name::<S>());
+        };
+        machine.add_scoped(state, scoped, world.container(), handlers);
+        self
+    }
+
+    fn state_machine<S: States>(&mut self) -> Option<&mut StateMachine<S>> {
+        Self::find_state_machine(&mut self.states)
+    }
+
+    fn find_state_machine<S: States>(
+        states: &mut [Box<dyn StateDriver>],
+    ) -> Option<&mut StateMachine<S>> {
+        states
+            .iter_mut()
+            .find_map(|driver| driver.as_any_mut().downcast_mut::<StateMachine<S>>())
+    }
+
+    /// Adds a sub-world labeled `label`, run after the main world each cycle.
+    ///
+    /// # Panics
+    /// Panics if a sub-world labeled `label` has already been added.
+    pub fn add_sub_world(&mut self, label: &'static str, sub_world: SubWorld) -> &mut Self {
+        assert!(
+            self.sub_world(label).is_none(),
+            "sub-world `{label}` is already added"
+        );
+        self.sub_worlds.push((label, sub_world));
+        self
+    }
+
+    pub fn sub_world(&self, label: &str) -> Option<&SubWorld> {
+        self.sub_worlds
+            .iter()
+            .find(|(sub_label, _)| *sub_label == label)
+            .map(|(_, sub_world)| sub_world)
+    }
+
+    pub fn sub_world_mut(&mut self, label: &str) -> Option<&mut SubWorld> {
+        self.sub_worlds
+            .iter_mut()
+            .find(|(sub_label, _)| *sub_label == label)
+            .map(|(_, sub_world)| sub_world)
+    }
+
+    /// Sets the executor running the systems of the schedule.
+    ///
+    /// Apps run their systems with a [SequentialExecutor](crate::system::SequentialExecutor)
+    /// by default, in a reproducible order on the calling thread.
+    pub fn set_executor(&mut self, executor: impl Executor + 'static) -> &mut Self {
+        self.schedule.set_executor(executor);
+        self
+    }
+
+    pub fn world(&self) -> &World {
+        &self.world
+    }
+
+    pub fn world_mut(&mut self) -> &mut World {
+        &mut self.world
+    }
+
+    pub fn schedule(&self) -> &Schedule {
+        &self.schedule
+    }
+
+    pub fn schedule_mut(&mut self) -> &mut Schedule {
+        &mut self.schedule
+    }
+
+    pub fn handlers(&self) -> &HandlerRegistry {
+        &self.handlers
+    }
+
+    pub fn handlers_mut(&mut self) -> &mut HandlerRegistry {
+        &mut self.handlers
+    }
+
+    pub fn schemas(&self) -> &SchemaRegistry {
+        &self.schemas
+    }
+
+    pub fn schemas_mut(&mut self) -> &mut SchemaRegistry {
+        &mut self.schemas
+    }
+
+    /// Runs a single cycle of the app.
+    ///
+    /// # Panics
+    /// Panics if the schedule can not be built, see `Schedule::build`.
+    pub fn update(&mut self) {
+        self.world.container().clear_changes();
+        self.world.event_manager().begin_cycle();
+        for hook in &mut self.begin_hooks {
+            hook(&mut self.world);
+        }
+        for driver in &mut self.states {
+            driver.apply(&mut self.world, &mut self.handlers);
+        }
+        self.schedule.run_world(&mut self.world);
+
+        // extract before dispatch, while the events of the cycle are pending
+        for (_, sub_world) in &mut self.sub_worlds {
+            sub_world.extract(&mut self.world);
+        }
+        self.handlers
+            .dispatch_with(self.world.container(), self.world.event_manager());
+        self.world.container_mut().apply_commands();
+        for (_, sub_world) in &mut self.sub_worlds {
+            sub_world.update();
+        }
+        for hook in &mut self.end_hooks {
+            hook(&mut self.world);
+        }
+    }
+
+    /// Adds a hook called at the beginning of each cycle, before the state transitions.
+    ///
+    /// Hooks run in the order they were added, once the dispatch budgets are reset, so
+    /// integrations can pump OS events into the event manager ahead of the schedule.
+    ///
+    /// # Examples
+    /// ```
+    /// use emark::prelude::*;
+    ///
+    /// struct Resized;
+    /// impl Event for Resized {}
+    ///
+    /// let mut app = App::new();
+    /// app.on_begin_cycle(|world| {
+    ///     world.event_manager().emit(Resized);
+    /// })
+    /// .on_end_cycle(|world| {
+    ///     // swap buffers, flush metrics
+    ///     assert!(world.event_manager().peek(|_: &[Resized]| ()).is_none());
+    /// });
+    /// app.update();
+    /// ```
+    pub fn on_begin_cycle(&mut self, hook: impl FnMut(&mut World) + 'static) -> &mut Self {
+        self.begin_hooks.push(Box::new(hook));
+        self
+    }
+
+    /// Adds a hook called at the end of each cycle, once the events are dispatched and the
+    /// sub-worlds have run.
+    ///
+    /// Hooks run in the order they were added, e.g. to swap buffers or flush metrics.
+    pub fn on_end_cycle(&mut self, hook: impl FnMut(&mut World) + 'static) -> &mut Self {
+        self.end_hooks.push(Box::new(hook));
+        self
+    }
+
+    /// Returns `true` if an [AppExit] event has been dispatched.
+    pub fn should_exit(&self) -> bool {
+        self.exit.load(Ordering::Acquire)
+    }
+
+    /// Sets the runner controlling the main loop of the app.
+    ///
+    /// The runner takes ownership of the app and decides when each cycle runs by calling
+    /// [App::update], so the app can be embedded in the event loop of another library. The
+    /// default runner runs cycles until an [AppExit] event is dispatched.
+    ///
+    /// # Examples
+    /// ```
+    /// use emark::prelude::*;
+    ///
+    /// let mut app = App::new();
+    /// app.set_runner(|mut app| {
+    ///     // run a fixed number of cycles
+    ///     for _ in 0..3 {
+    ///         app.update();
+    ///     }
+    /// });
+    /// app.run();
+    /// ```
+    pub fn set_runner(&mut self, runner: impl FnOnce(App) + 'static) -> &mut Self {
+        self.runner = Box::new(runner);
+        self
+    }
+
+    /// Hands the app over to its runner, see [App::set_runner].
+    ///
+    /// The app is left empty, as if created with [App::new].
+    pub fn run(&mut self) {
+        let mut app = std::mem::take(self);
+        let runner = std::mem::replace(&mut app.runner, Box::new(run_until_exit));
+        runner(app);
+    }
+}
+
+impl Debug for App {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("App")
+            .field("world", &self.world)
+            .field("schedule", &self.schedule)
+            .field("schemas", &self.schemas)
+            .field("sub_worlds", &self.sub_worlds)
+            .field("begin_hooks", &self.begin_hooks.len())
+            .field("end_hooks", &self.end_hooks.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test_app {
+    use std::sync::atomic::AtomicUsize;
+
+    use super::*;
+    use crate::{
+        asset::AssetEvent,
+        store::{Res, ResMut, Retriever},
+        system::SequentialExecutor,
+        world::ResourceInserted,
+    };
+
+    struct TestEvent(u32);
+    impl Event for TestEvent {}
+
+    #[test]
+    fn test_app_run() {
+        let dispatched = Arc::new(AtomicUsize::new(0));
+        let handler_dispatched = dispatched.clone();
+        let mut app = App::new();
+        app.add_resource(0u32)
+            .add_event::<TestEvent>()
+            .add_handler(move |events: &[TestEvent]| {
+                assert_eq!(events[0].0, 1);
+                handler_dispatched.fetch_add(1, Ordering::SeqCst);
+            })
+            .add_startup_system(|mut count: ResMut<u32>| *count += 1)
+            .add_exclusive_system(|world: &mut World| {
+                let count = *Res::<u32>::retrieve(world.container());
+                world.event_manager().emit(TestEvent(1));
+                if count == 3 {
+                    world.event_manager().emit(AppExit);
+                }
+            })
+            .add_system(|mut count: ResMut<u32>| *count += 1);
+        assert_eq!(app.schedule().len(), 3);
+
+        assert!(app.schemas().schema::<TestEvent>().is_some());
+
+        // assert the app runs until exit is requested
+        app.run();
+        assert_eq!(dispatched.load(Ordering::SeqCst), 2);
+        assert!(app.schedule().is_empty());
+    }
+
+    #[test]
+    fn test_app_resource_events() {
+        let inserted = Arc::new(AtomicUsize::new(0));
+        let handler_inserted = inserted.clone();
+        let mut app = App::new();
+        app.set_resource_events(true)
+            .add_handler(move |events: &[ResourceInserted<u32>]| {
+                handler_inserted.fetch_add(events.len(), Ordering::SeqCst);
+            })
+            .add_resource(0u32)
+            .add_resource(1u32);
+        app.world_mut().container_mut().add_resource(2u32);
+
+        app.update();
+        assert_eq!(inserted.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_app_add_asset() {
+        let added = Arc::new(AtomicUsize::new(0));
+        let handler_added = added.clone();
+        let mut app = App::new();
+        app.add_asset::<String>()
+            .add_handler(move |events: &[AssetEvent<String>]| {
+                handler_added.fetch_add(events.len(), Ordering::SeqCst);
+            })
+            .add_system(|mut assets: ResMut<Assets<String>>| {
+                assets.insert("level".to_owned());
+            });
+        app.add_asset::<String>();
+        assert_eq!(app.schedule().len(), 2);
+
+        app.update();
+        assert_eq!(added.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_app_runner() {
+        let cycles = Arc::new(AtomicUsize::new(0));
+        let runner_cycles = cycles.clone();
+        let mut app = App::new();
+        app.add_resource(0u32)
+            .add_system(|mut count: ResMut<u32>| *count += 1)
+            .set_runner(move |mut app| {
+                // assert the runner drives the cycles of the app
+                while *Res::<u32>::retrieve(app.world().container()) < 5 {
+                    app.update();
+                    runner_cycles.fetch_add(1, Ordering::SeqCst);
+                }
+            });
+
+        app.run();
+        assert_eq!(cycles.load(Ordering::SeqCst), 5);
+    }
+
+    struct TestPlugin;
+
+    impl Plugin for TestPlugin {
+        fn build(&self, app: &mut App) {
+            app.add_resource(1u32).add_event::<TestEvent>();
+        }
+    }
+
+    #[test]
+    fn test_app_executor() {
+        let order = Arc::new(parking_lot::Mutex::new(Vec::new()));
+        let mut app = App::new();
+        app.set_executor(SequentialExecutor);
+        for index in 0..4 {
+            let order = order.clone();
+            app.add_system(move |_: Res<u32>| {
+                order.lock().push((index, std::thread::current().id()));
+            });
+        }
+        app.add_resource(0u32);
+        app.update();
+        app.update();
+
+        // assert systems ran in order on the calling thread
+        let thread = std::thread::current().id();
+        let expected = (0..4).chain(0..4).map(|index| (index, thread));
+        assert_eq!(*order.lock(), expected.collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_app_cycle_hooks() {
+        let log = |entry: &'static str| {
+            move |world: &mut World| {
+                ResMut::<Vec<&'static str>>::retrieve(world.container()).push(entry)
+            }
+        };
+        let mut app = App::new();
+        app.add_resource(Vec::<&'static str>::new())
+            .add_resource(0u32)
+            .on_end_cycle(log("end"))
+            .on_begin_cycle(log("begin"))
+            .on_begin_cycle(|world| {
+                world.event_manager().emit(TestEvent(1));
+            })
+            .add_handler(|events: &[TestEvent]| assert_eq!(events.len(), 1))
+            .add_exclusive_system(log("update"));
+        app.update();
+        app.update();
+
+        // assert events emitted by begin hooks are dispatched within the same cycle
+        let log = Res::<Vec<&'static str>>::retrieve(app.world().container());
+        assert_eq!(
+            *log,
+            vec!["begin", "update", "end", "begin", "update", "end"]
+        );
+    }
+
+    #[test]
+    fn test_app_plugin() {
+        let mut app = App::new();
+        app.add_plugin(TestPlugin).add_plugin(|app: &mut App| {
+            // assert plugins can depend on the plugins added before them
+            assert!(app.has_plugin::<TestPlugin>());
+            app.add_system(|mut value: ResMut<u32>| *value += 1);
+        });
+
+        app.update();
+        assert_eq!(*Res::<u32>::retrieve(app.world().container()), 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "is already added")]
+    fn test_app_duplicate_plugin() {
+        App::new().add_plugin(TestPlugin).add_plugin(TestPlugin);
+    }
+}
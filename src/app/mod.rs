@@ -0,0 +1,64 @@
+//! # App
+//!
+//! The [App] ties the resources, the events, the handlers and the schedule of an application
+//! together behind a builder API, so a working application does not need to wire a
+//! `ResourceContainer`, an `EventManager` and the dispatch of events by hand.
+//!
+//! ## Cycle
+//!
+//! 1. **Begin:** The changes reported outside of systems and the dispatch budgets of the
+//!    `EventManager` are reset, then the hooks added with `App::on_begin_cycle` are called.
+//! 2. **Transitions:** The requested state transitions are applied.
+//! 3. **Schedule:** The systems of the schedule are run, stage by stage.
+//! 4. **Extraction:** The selected resources and events are copied into the sub-worlds.
+//! 5. **Dispatch:** The pending events are dispatched to the handlers of the app.
+//! 6. **Sub-worlds:** Each sub-world runs its own schedule and dispatches its events.
+//! 7. **End:** The hooks added with `App::on_end_cycle` are called, e.g. to swap buffers or
+//!    flush metrics.
+//!
+//! `App::run` repeats the cycle until an [AppExit] event is dispatched, unless a custom runner
+//! is set with `App::set_runner`, e.g. to let the event loop of a windowing library or an
+//! existing game loop decide when each cycle runs.
+//!
+//! ## Plugins
+//!
+//! A [Plugin] packages resources, events, handlers and systems as a reusable unit, added to an
+//! app with `App::add_plugin`. A plugin type can only be added once.
+//!
+//! ## States
+//!
+//! Types implementing [States], such as the menu, loading and gameplay phases of a game, are
+//! added with `App::add_state`. Systems added to the [OnEnter] and [OnExit] schedules of a state
+//! run on its transitions, while the [in_state] run condition gates systems and whole system
+//! sets by the current state.
+//!
+//! Handlers registered with `App::add_state_handler` are scoped to a state, attached when
+//! entering it and detached when exiting it, so gameplay handlers do not react to the events of
+//! the pause menu.
+//!
+//! ## Sub-worlds
+//!
+//! A [SubWorld] is an isolated world with its own schedule and handlers, such as a render world
+//! next to the simulation world, added with `App::add_sub_world`. Its extractors copy selected
+//! resources and events of the main world into it each cycle.
+//!
+#[doc(hidden)]
+#[allow(clippy::module_inception)]
+mod app;
+#[doc(inline)]
+pub use app::{App, AppExit};
+
+#[doc(hidden)]
+mod plugin;
+#[doc(inline)]
+pub use plugin::Plugin;
+
+#[doc(hidden)]
+mod state;
+#[doc(inline)]
+pub use state::{in_state, NextState, OnEnter, OnExit, State, States, Transition};
+
+#[doc(hidden)]
+mod sub_world;
+#[doc(inline)]
+pub use sub_world::SubWorld;
@@ -0,0 +1,37 @@
+use super::App;
+
+/// Reusable bundle of resources, events, handlers and systems.
+///
+/// Plugins let crates package a feature, such as timing or networking, as a drop-in unit
+/// added with `App::add_plugin`. Closures taking `&mut App` are plugins.
+///
+/// # Examples
+/// ```
+/// use emark::prelude::*;
+///
+/// struct Frame(u64);
+///
+/// struct FramePlugin;
+///
+/// impl Plugin for FramePlugin {
+///     fn build(&self, app: &mut App) {
+///         app.add_resource(Frame(0))
+///             .add_system(|mut frame: ResMut<Frame>| frame.0 += 1);
+///     }
+/// }
+///
+/// let mut app = App::new();
+/// app.add_plugin(FramePlugin);
+/// app.update();
+/// assert_eq!(Res::<Frame>::retrieve(app.world().container()).0, 1);
+/// ```
+pub trait Plugin: 'static {
+    /// Configures the app.
+    fn build(&self, app: &mut App);
+}
+
+impl<F: Fn(&mut App) + 'static> Plugin for F {
+    fn build(&self, app: &mut App) {
+        self(app)
+    }
+}
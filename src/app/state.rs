@@ -0,0 +1,295 @@
+use std::{any::Any, collections::HashMap, fmt::Debug, hash::Hash};
+
+use crate::{
+    event::{EventManager, HandlerId, HandlerRegistry},
+    store::{Container, Res, ResMut, ResourceContainer, Retriever},
+    system::Schedule,
+    world::World,
+};
+
+/// State of an application, such as its menu, loading or gameplay phase.
+///
+/// The current state is stored as a [State] resource and changed by setting the [NextState]
+/// resource, see `App::add_state`.
+pub trait States: Debug + Clone + Eq + Hash + Send + Sync + 'static {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Resource holding the current state of type `S`.
+pub struct State<S: States> {
+    current: S,
+}
+
+impl<S: States> State<S> {
+    pub fn get(&self) -> &S {
+        &self.current
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Resource holding the state of type `S` entered at the beginning of the next cycle.
+pub struct NextState<S: States> {
+    next: Option<S>,
+}
+
+impl<S: States> NextState<S> {
+    /// Requests a transition to `state`, replacing the pending request.
+    pub fn set(&mut self, state: S) {
+        self.next = Some(state);
+    }
+
+    /// State requested for the next cycle.
+    pub fn pending(&self) -> Option<&S> {
+        self.next.as_ref()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// Schedule run when entering a state.
+pub struct OnEnter<S>(pub S);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// Schedule run when exiting a state.
+pub struct OnExit<S>(pub S);
+
+/// Transition of a state running a schedule, either [OnEnter] or [OnExit].
+pub trait Transition<S: States> {
+    /// State entered or exited.
+    fn state(&self) -> &S;
+
+    /// Returns `true` if the schedule runs when entering the state.
+    fn is_enter(&self) -> bool;
+}
+
+impl<S: States> Transition<S> for OnEnter<S> {
+    fn state(&self) -> &S {
+        &self.0
+    }
+
+    fn is_enter(&self) -> bool {
+        true
+    }
+}
+
+impl<S: States> Transition<S> for OnExit<S> {
+    fn state(&self) -> &S {
+        &self.0
+    }
+
+    fn is_enter(&self) -> bool {
+        false
+    }
+}
+
+/// Run condition holding while `state` is the current state of type `S`.
+///
+/// # Examples
+/// ```
+/// use emark::app::{in_state, States};
+/// use emark::prelude::*;
+/// use emark::system::SystemOptions;
+///
+/// #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+/// enum Phase {
+///     Menu,
+///     Game,
+/// }
+/// impl States for Phase {}
+///
+/// let mut app = App::new();
+/// app.add_state(Phase::Menu).add_resource(0u32);
+/// app.schedule_mut().add_system_with(
+///     |mut ticks: ResMut<u32>| *ticks += 1,
+///     SystemOptions::new().with_run_if(in_state(Phase::Game)),
+/// );
+///
+/// // assert the system does not run in the menu
+/// app.update();
+/// assert_eq!(*Res::<u32>::retrieve(app.world().container()), 0);
+/// ```
+pub fn in_state<S: States>(
+    state: S,
+) -> impl Fn(&ResourceContainer, &EventManager) -> bool + Send + Sync + 'static {
+    move |container: &ResourceContainer, _: &EventManager| {
+        container.contains_resource::<State<S>>()
+            && Res::<State<S>>::retrieve(container).current == state
+    }
+}
+
+// type-erased driver of the transitions of a state type
+pub(crate) trait StateDriver {
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+
+    // applies the requested transition, running the schedules of the exited and entered states
+    // and attaching the handlers scoped to the entered state
+    fn apply(&mut self, world: &mut World, handlers: &mut HandlerRegistry);
+}
+
+// registers a handler scoped to a state
+pub(crate) type ScopedHandler = Box<dyn Fn(&mut HandlerRegistry) -> HandlerId>;
+
+// transition schedules and scoped handlers of the states of type `S`
+pub(crate) struct StateMachine<S: States> {
+    on_enter: HashMap<S, Schedule>,
+    on_exit: HashMap<S, Schedule>,
+    scoped: HashMap<S, Vec<ScopedHandler>>,
+    // handlers attached to the current state
+    attached: Vec<HandlerId>,
+    entered: bool,
+}
+
+impl<S: States> StateMachine<S> {
+    pub(crate) fn new() -> Self {
+        Self {
+            on_enter: HashMap::new(),
+            on_exit: HashMap::new(),
+            scoped: HashMap::new(),
+            attached: Vec::new(),
+            entered: false,
+        }
+    }
+
+    // inserts the resources of state `initial` into the container
+    pub(crate) fn insert_resources(container: &mut ResourceContainer, initial: S) {
+        container.add_resource(State { current: initial });
+        container.add_resource(NextState::<S> { next: None });
+    }
+
+    pub(crate) fn schedule_mut(&mut self, transition: &impl Transition<S>) -> &mut Schedule {
+        let schedules = match transition.is_enter() {
+            true => &mut self.on_enter,
+            false => &mut self.on_exit,
+        };
+        schedules.entry(transition.state().clone()).or_default()
+    }
+
+    // adds a handler scoped to `state`, attached at once if `state` is the current state
+    pub(crate) fn add_scoped(
+        &mut self,
+        state: S,
+        handler: ScopedHandler,
+        container: &ResourceContainer,
+        handlers: &mut HandlerRegistry,
+    ) {
+        if self.entered && Res::<State<S>>::retrieve(container).current == state {
+            self.attached.push(handler(handlers));
+        }
+        self.scoped.entry(state).or_default().push(handler);
+    }
+
+    fn enter(&mut self, state: &S, world: &mut World, handlers: &mut HandlerRegistry) {
+        if let Some(scoped) = self.scoped.get(state) {
+            self.attached
+                .extend(scoped.iter().map(|handler| handler(handlers)));
+        }
+        if let Some(schedule) = self.on_enter.get_mut(state) {
+            schedule.run_world(world);
+        }
+    }
+
+    fn exit(&mut self, state: &S, world: &mut World, handlers: &mut HandlerRegistry) {
+        for id in self.attached.drain(..) {
+            handlers.remove_handler(id);
+        }
+        if let Some(schedule) = self.on_exit.get_mut(state) {
+            schedule.run_world(world);
+        }
+    }
+}
+
+impl<S: States> StateDriver for StateMachine<S> {
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn apply(&mut self, world: &mut World, handlers: &mut HandlerRegistry) {
+        let current = Res::<State<S>>::retrieve(world.container()).current.clone();
+
+        // enter the initial state on the first cycle
+        if !self.entered {
+            self.entered = true;
+            self.enter(&current, world, handlers);
+        }
+
+        let next = ResMut::<NextState<S>>::retrieve(world.container())
+            .next
+            .take();
+        let Some(next) = next.filter(|next| *next != current) else {
+            return;
+        };
+
+        self.exit(&current, world, handlers);
+        ResMut::<State<S>>::retrieve(world.container()).current = next.clone();
+        self.enter(&next, world, handlers);
+    }
+}
+
+#[cfg(test)]
+mod test_state {
+    use super::*;
+    use crate::{app::App, system::SystemOptions};
+
+    #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+    enum Phase {
+        Menu,
+        Game,
+    }
+    impl States for Phase {}
+
+    #[test]
+    fn test_state_transitions() {
+        let log = |entry: &'static str| move |mut log: ResMut<Vec<&'static str>>| log.push(entry);
+        let mut app = App::new();
+        app.add_resource(Vec::<&'static str>::new())
+            .add_state(Phase::Menu)
+            .add_transition_system(OnEnter(Phase::Menu), log("enter menu"))
+            .add_transition_system(OnExit(Phase::Menu), log("exit menu"))
+            .add_transition_system(OnEnter(Phase::Game), log("enter game"));
+        app.schedule_mut().add_system_with(
+            log("tick"),
+            SystemOptions::new().with_run_if(in_state(Phase::Game)),
+        );
+
+        app.update();
+        ResMut::<NextState<Phase>>::retrieve(app.world().container()).set(Phase::Game);
+        app.update();
+        app.update();
+
+        // assert transitions run before the systems gated by the state
+        let log = Res::<Vec<&'static str>>::retrieve(app.world().container());
+        assert_eq!(
+            *log,
+            vec!["enter menu", "exit menu", "enter game", "tick", "tick"]
+        );
+        let state = Res::<State<Phase>>::retrieve(app.world().container());
+        assert_eq!(*state.get(), Phase::Game);
+    }
+
+    #[test]
+    fn test_state_scoped_handler() {
+        struct Hit;
+        impl crate::event::Event for Hit {}
+
+        let hits = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let handler_hits = hits.clone();
+        let mut app = App::new();
+        app.add_state(Phase::Menu)
+            .add_state_handler(Phase::Game, move |events: &[Hit]| {
+                handler_hits.fetch_add(events.len(), std::sync::atomic::Ordering::SeqCst);
+            });
+        let mut cycle = |next: Option<Phase>| {
+            if let Some(next) = next {
+                ResMut::<NextState<Phase>>::retrieve(app.world().container()).set(next);
+            }
+            app.world().event_manager().emit(Hit);
+            app.update();
+            app.handlers().contains_handler::<Hit>()
+        };
+
+        // assert the handler only receives events while the state is active
+        assert!(!cycle(None));
+        assert!(cycle(Some(Phase::Game)));
+        assert!(cycle(None));
+        assert!(!cycle(Some(Phase::Menu)));
+        assert_eq!(hits.load(std::sync::atomic::Ordering::SeqCst), 2);
+    }
+}
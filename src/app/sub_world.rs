@@ -0,0 +1,245 @@
+use std::fmt::Debug;
+
+use crate::{
+    event::{handler::HandlerResult, Event, HandlerRegistry},
+    store::{Container, Context, FromContainer, Res, Retriever},
+    system::{ExclusiveSystem, IntoSystem, Schedule},
+    world::World,
+};
+
+// copies data from the main world into a sub-world, or back
+type Extractor = Box<dyn FnMut(&mut World, &mut World)>;
+
+/// Isolated world running alongside the main world of an [App](super::App).
+///
+/// A sub-world owns its [World], its [Schedule] and its handlers, e.g. a render world
+/// separated from the simulation world. Each cycle, once the schedule of the main world has
+/// run, its extractors copy the selected resources and events of the main world into the
+/// sub-world, which then runs its own cycle.
+///
+/// # Examples
+/// ```
+/// use emark::app::SubWorld;
+/// use emark::prelude::*;
+///
+/// let mut render = SubWorld::new();
+/// render
+///     .extract_resource::<u32>()
+///     .add_system(|frame: Res<u32>| assert_eq!(*frame, 1));
+///
+/// let mut app = App::new();
+/// app.add_resource(0u32)
+///     .add_system(|mut frame: ResMut<u32>| *frame += 1)
+///     .add_sub_world("render", render);
+/// app.update();
+/// ```
+#[derive(Default)]
+pub struct SubWorld {
+    world: World,
+    schedule: Schedule,
+    handlers: HandlerRegistry,
+    extractors: Vec<Extractor>,
+}
+
+impl SubWorld {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a resource to the sub-world, replacing the resource of the same type.
+    pub fn add_resource<T: Send + Sync + 'static>(&mut self, resource: T) -> &mut Self {
+        self.world.container_mut().add_resource(resource);
+        self
+    }
+
+    /// Adds a resource constructed with [FromContainer] to the sub-world, unless it already holds
+    /// one, see `Container::init_resource`.
+    pub fn init_resource<T: FromContainer>(&mut self) -> &mut Self {
+        self.world.container_mut().init_resource::<T>();
+        self
+    }
+
+    /// Appends a system to the [UPDATE](crate::system::stage::UPDATE) stage of the schedule of
+    /// the sub-world.
+    pub fn add_system<M>(&mut self, system: impl IntoSystem<M>) -> &mut Self {
+        self.schedule.add_system(system);
+        self
+    }
+
+    /// Appends an exclusive system to the [UPDATE](crate::system::stage::UPDATE) stage of the
+    /// schedule of the sub-world.
+    pub fn add_exclusive_system(&mut self, system: impl ExclusiveSystem + 'static) -> &mut Self {
+        self.schedule.add_exclusive_system(system);
+        self
+    }
+
+    /// Registers a handler of event `T` in the sub-world.
+    pub fn add_handler<T, R, F>(&mut self, handler: F) -> &mut Self
+    where
+        T: Event + Send + Sync + 'static,
+        R: HandlerResult,
+        F: Fn(&[T]) -> R + Send + Sync + 'static,
+    {
+        self.handlers.add_handler(handler);
+        self
+    }
+
+    /// Registers a handler of event `T` receiving the [Context] of the sub-world, see
+    /// `HandlerRegistry::add_context_handler`.
+    pub fn add_context_handler<T, R, F>(&mut self, handler: F) -> &mut Self
+    where
+        T: Event + Send + Sync + 'static,
+        R: HandlerResult,
+        F: Fn(&[T], &Context) -> R + Send + Sync + 'static,
+    {
+        self.handlers.add_context_handler(handler);
+        self
+    }
+
+    /// Copies the resource `T` of the main world into the sub-world each cycle.
+    ///
+    /// The copy replaces the resource of the sub-world. Nothing is copied while the main world
+    /// has no resource `T`.
+    pub fn extract_resource<T: Clone + Send + Sync + 'static>(&mut self) -> &mut Self {
+        self.add_extractor(|main, sub| {
+            if main.container().contains_resource::<T>() {
+                let resource = Res::<T>::retrieve(main.container()).clone();
+                sub.container_mut().insert_overwrite(resource);
+            }
+        })
+    }
+
+    /// Copies the pending events `T` of the main world into the sub-world each cycle.
+    ///
+    /// The events are emitted in the sub-world with their pending priority, in emission order,
+    /// and are still dispatched in the main world.
+    pub fn extract_events<T: Event + Clone + Send + Sync + 'static>(&mut self) -> &mut Self {
+        self.add_extractor(|main, sub| {
+            let Some(priority) = main.event_manager().pending_priority::<T>() else {
+                return;
+            };
+            let events = main
+                .event_manager()
+                .peek(|events: &[T]| events.to_vec())
+                .unwrap_or_default();
+            for event in events {
+                sub.event_manager().emit_priority(event, priority);
+            }
+        })
+    }
+
+    /// Adds an extractor, called each cycle with the main world and the sub-world.
+    ///
+    /// Extractors run in the order they were added, and may also copy data from the
+    /// sub-world back into the main world.
+    pub fn add_extractor(
+        &mut self,
+        extractor: impl FnMut(&mut World, &mut World) + 'static,
+    ) -> &mut Self {
+        self.extractors.push(Box::new(extractor));
+        self
+    }
+
+    pub fn world(&self) -> &World {
+        &self.world
+    }
+
+    pub fn world_mut(&mut self) -> &mut World {
+        &mut self.world
+    }
+
+    pub fn schedule(&self) -> &Schedule {
+        &self.schedule
+    }
+
+    pub fn schedule_mut(&mut self) -> &mut Schedule {
+        &mut self.schedule
+    }
+
+    pub fn handlers(&self) -> &HandlerRegistry {
+        &self.handlers
+    }
+
+    pub fn handlers_mut(&mut self) -> &mut HandlerRegistry {
+        &mut self.handlers
+    }
+
+    // runs the extractors against the main world
+    pub(crate) fn extract(&mut self, main: &mut World) {
+        for extractor in &mut self.extractors {
+            extractor(main, &mut self.world);
+        }
+    }
+
+    // runs a single cycle of the sub-world
+    pub(crate) fn update(&mut self) {
+        self.world.container().clear_changes();
+        self.world.event_manager().begin_cycle();
+        self.schedule.run_world(&mut self.world);
+        self.handlers
+            .dispatch_with(self.world.container(), self.world.event_manager());
+        self.world.container_mut().apply_commands();
+    }
+}
+
+impl Debug for SubWorld {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SubWorld")
+            .field("world", &self.world)
+            .field("schedule", &self.schedule)
+            .field("extractors", &self.extractors.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test_sub_world {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    use super::*;
+    use crate::{app::App, store::ResMut};
+
+    #[derive(Clone)]
+    struct Tick;
+    impl Event for Tick {}
+
+    #[test]
+    fn test_sub_world_extract() {
+        let ticks = Arc::new(AtomicUsize::new(0));
+        let handler_ticks = ticks.clone();
+        let mut render = SubWorld::new();
+        render
+            .add_resource(Vec::<u32>::new())
+            .extract_resource::<u32>()
+            .extract_events::<Tick>()
+            .add_system(|frame: Res<u32>, mut frames: ResMut<Vec<u32>>| frames.push(*frame))
+            .add_handler(move |events: &[Tick]| {
+                handler_ticks.fetch_add(events.len(), Ordering::SeqCst);
+            });
+
+        let main_ticks = Arc::new(AtomicUsize::new(0));
+        let handler_main_ticks = main_ticks.clone();
+        let mut app = App::new();
+        app.add_resource(0u32)
+            .add_system(|mut frame: ResMut<u32>| *frame += 1)
+            .add_exclusive_system(|world: &mut World| {
+                world.event_manager().emit(Tick);
+            })
+            .add_handler(move |events: &[Tick]| {
+                handler_main_ticks.fetch_add(events.len(), Ordering::SeqCst);
+            })
+            .add_sub_world("render", render);
+        app.update();
+        app.update();
+
+        // assert the sub-world received copies of the resource and the events
+        let render = app.sub_world("render").unwrap();
+        let frames = Res::<Vec<u32>>::retrieve(render.world().container());
+        assert_eq!(*frames, vec![1, 2]);
+        assert_eq!(ticks.load(Ordering::SeqCst), 2);
+        assert_eq!(main_ticks.load(Ordering::SeqCst), 2);
+    }
+}
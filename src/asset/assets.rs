@@ -0,0 +1,349 @@
+use std::{
+    any::TypeId,
+    collections::HashMap,
+    fmt::Debug,
+    hash::{Hash, Hasher},
+    marker::PhantomData,
+};
+
+use crate::{
+    event::{Event, EventManager},
+    store::{ResMut, ResourceContainer, Retriever},
+    system::{Access, System},
+    utils::error::EmarkError,
+};
+
+/// Id of an asset of type `T` stored in [Assets].
+///
+/// Ids are never reused, so a handle never refers to another asset once its asset is removed.
+pub struct Handle<T: 'static> {
+    id: u64,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T: 'static> Handle<T> {
+    fn new(id: u64) -> Self {
+        Self {
+            id,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+}
+
+impl<T: 'static> Clone for Handle<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T: 'static> Copy for Handle<T> {}
+
+impl<T: 'static> PartialEq for Handle<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl<T: 'static> Eq for Handle<T> {}
+
+impl<T: 'static> Hash for Handle<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+    }
+}
+
+impl<T: 'static> Debug for Handle<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Handle<{}>({})", std::any::type_name::<T>(), self.id)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+/// Load state of the asset of a [Handle].
+pub enum LoadState {
+    /// The handle is reserved, its asset is not set yet.
+    Loading,
+    Loaded,
+    /// The asset can not be loaded, for the given reason.
+    Failed(String),
+}
+
+/// Change of the assets of type `T`, emitted by `Assets::flush_events`.
+pub enum AssetEvent<T: 'static> {
+    Added(Handle<T>),
+    Modified(Handle<T>),
+    Removed(Handle<T>),
+    Failed(Handle<T>),
+}
+
+impl<T: 'static> AssetEvent<T> {
+    /// Handle of the asset that changed.
+    pub fn handle(&self) -> Handle<T> {
+        match self {
+            AssetEvent::Added(handle)
+            | AssetEvent::Modified(handle)
+            | AssetEvent::Removed(handle)
+            | AssetEvent::Failed(handle) => *handle,
+        }
+    }
+}
+
+impl<T: 'static> Event for AssetEvent<T> {}
+
+impl<T: 'static> Clone for AssetEvent<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T: 'static> Copy for AssetEvent<T> {}
+
+impl<T: 'static> PartialEq for AssetEvent<T> {
+    fn eq(&self, other: &Self) -> bool {
+        std::mem::discriminant(self) == std::mem::discriminant(other)
+            && self.handle() == other.handle()
+    }
+}
+
+impl<T: 'static> Eq for AssetEvent<T> {}
+
+impl<T: 'static> Debug for AssetEvent<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let kind = match self {
+            AssetEvent::Added(_) => "Added",
+            AssetEvent::Modified(_) => "Modified",
+            AssetEvent::Removed(_) => "Removed",
+            AssetEvent::Failed(_) => "Failed",
+        };
+        f.debug_tuple(kind).field(&self.handle()).finish()
+    }
+}
+
+/// Store of the assets of type `T`, identified by their [Handle].
+///
+/// # Examples
+/// ```
+/// use emark::asset::{AssetEvent, Assets, LoadState};
+/// use emark::event::EventManager;
+///
+/// let mut textures = Assets::new();
+/// let logo = textures.insert("logo.png".to_owned());
+///
+/// // reserve a handle while the texture is loaded in the background
+/// let background = textures.reserve();
+/// assert_eq!(textures.load_state(background), Some(&LoadState::Loading));
+/// assert!(textures.set(background, "background.png".to_owned()).is_ok());
+/// assert_eq!(textures.get(background).unwrap(), "background.png");
+///
+/// let event_manager = EventManager::new();
+/// assert_eq!(textures.flush_events(&event_manager), 2);
+/// let added = event_manager.peek(|events: &[AssetEvent<String>]| events.to_vec());
+/// assert_eq!(added, Some(vec![AssetEvent::Added(logo), AssetEvent::Added(background)]));
+/// ```
+pub struct Assets<T: 'static> {
+    assets: HashMap<u64, T>,
+    states: HashMap<u64, LoadState>,
+    next_id: u64,
+    // recorded until `flush_events`
+    events: Vec<AssetEvent<T>>,
+}
+
+impl<T: 'static> Default for Assets<T> {
+    fn default() -> Self {
+        Self {
+            assets: HashMap::new(),
+            states: HashMap::new(),
+            next_id: 0,
+            events: Vec::new(),
+        }
+    }
+}
+
+impl<T: 'static> Assets<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reserves a handle in the [Loading](LoadState::Loading) state, completed by `set` or
+    /// `fail`.
+    pub fn reserve(&mut self) -> Handle<T> {
+        let handle = Handle::new(self.next_id);
+        self.next_id += 1;
+        self.states.insert(handle.id, LoadState::Loading);
+        handle
+    }
+
+    /// Stores a loaded asset under a new handle.
+    pub fn insert(&mut self, asset: T) -> Handle<T> {
+        let handle = self.reserve();
+        self.set(handle, asset).ok();
+        handle
+    }
+
+    /// Stores the asset of `handle`, returning the asset it replaces.
+    ///
+    /// Returns the asset back as an error if the handle was not created by this store or its
+    /// asset is removed.
+    pub fn set(&mut self, handle: Handle<T>, asset: T) -> Result<Option<T>, T> {
+        let Some(state) = self.states.get_mut(&handle.id) else {
+            return Err(asset);
+        };
+        *state = LoadState::Loaded;
+        let previous = self.assets.insert(handle.id, asset);
+        self.events.push(match previous {
+            Some(_) => AssetEvent::Modified(handle),
+            None => AssetEvent::Added(handle),
+        });
+        Ok(previous)
+    }
+
+    /// Marks the asset of `handle` as failed to load, dropping its previous asset.
+    ///
+    /// Returns `false` if the handle was not created by this store or its asset is removed.
+    pub fn fail(&mut self, handle: Handle<T>, reason: impl Into<String>) -> bool {
+        let Some(state) = self.states.get_mut(&handle.id) else {
+            return false;
+        };
+        *state = LoadState::Failed(reason.into());
+        self.assets.remove(&handle.id);
+        self.events.push(AssetEvent::Failed(handle));
+        true
+    }
+
+    pub fn get(&self, handle: Handle<T>) -> Option<&T> {
+        self.assets.get(&handle.id)
+    }
+
+    /// Mutably borrows the asset of `handle`, recording a `Modified` event.
+    pub fn get_mut(&mut self, handle: Handle<T>) -> Option<&mut T> {
+        let asset = self.assets.get_mut(&handle.id)?;
+        self.events.push(AssetEvent::Modified(handle));
+        Some(asset)
+    }
+
+    /// Removes the asset of `handle`, its handle being no longer valid.
+    pub fn remove(&mut self, handle: Handle<T>) -> Option<T> {
+        self.states.remove(&handle.id)?;
+        self.events.push(AssetEvent::Removed(handle));
+        self.assets.remove(&handle.id)
+    }
+
+    /// Load state of `handle`, `None` if it was not created by this store or its asset is
+    /// removed.
+    pub fn load_state(&self, handle: Handle<T>) -> Option<&LoadState> {
+        self.states.get(&handle.id)
+    }
+
+    /// Returns `true` if the asset of `handle` is loaded.
+    pub fn contains(&self, handle: Handle<T>) -> bool {
+        self.assets.contains_key(&handle.id)
+    }
+
+    /// Number of loaded assets.
+    pub fn len(&self) -> usize {
+        self.assets.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.assets.is_empty()
+    }
+
+    /// Iterates over the loaded assets, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (Handle<T>, &T)> {
+        self.assets
+            .iter()
+            .map(|(&id, asset)| (Handle::new(id), asset))
+    }
+
+    /// Emits the events recorded since the last flush, in the order they were recorded,
+    /// returning their number.
+    pub fn flush_events(&mut self, event_manager: &EventManager) -> usize {
+        let count = self.events.len();
+        for event in self.events.drain(..) {
+            event_manager.emit(event);
+        }
+        count
+    }
+}
+
+impl<T: 'static> Debug for Assets<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Assets")
+            .field("type_name", &std::any::type_name::<T>())
+            .field("len", &self.assets.len())
+            .field("pending_events", &self.events.len())
+            .finish()
+    }
+}
+
+// flushes the events of `Assets<T>`, see `App::add_asset`
+pub(crate) struct AssetEventSystem<T: 'static> {
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T: 'static> AssetEventSystem<T> {
+    pub(crate) fn new() -> Self {
+        Self {
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T: Send + Sync + 'static> System for AssetEventSystem<T> {
+    fn name(&self) -> &str {
+        std::any::type_name::<Self>()
+    }
+
+    fn run(
+        &mut self,
+        container: &ResourceContainer,
+        event_manager: &EventManager,
+    ) -> Result<(), EmarkError> {
+        ResMut::<Assets<T>>::try_retrieve(container)?.flush_events(event_manager);
+        Ok(())
+    }
+
+    fn access(&self) -> Access {
+        Access::new().with_write(TypeId::of::<Assets<T>>())
+    }
+}
+
+#[cfg(test)]
+mod test_assets {
+    use super::*;
+
+    #[test]
+    fn test_assets_load_states() {
+        let mut assets = Assets::<u32>::new();
+        let loaded = assets.insert(1);
+        let failed = assets.reserve();
+        assert!(assets.fail(failed, "missing file"));
+        assert_eq!(
+            assets.load_state(failed),
+            Some(&LoadState::Failed("missing file".to_owned()))
+        );
+
+        *assets.get_mut(loaded).unwrap() += 1;
+        assert_eq!(assets.remove(loaded), Some(2));
+        assert_eq!(assets.set(loaded, 3), Err(3));
+        assert!(assets.load_state(loaded).is_none());
+        assert!(assets.is_empty());
+
+        let event_manager = EventManager::new();
+        assets.flush_events(&event_manager);
+        let events = event_manager.peek(|events: &[AssetEvent<u32>]| events.to_vec());
+        assert_eq!(
+            events.unwrap(),
+            [
+                AssetEvent::Added(loaded),
+                AssetEvent::Failed(failed),
+                AssetEvent::Modified(loaded),
+                AssetEvent::Removed(loaded),
+            ]
+        );
+    }
+}
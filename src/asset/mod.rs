@@ -0,0 +1,24 @@
+//! # Asset
+//!
+//! [Assets] stores the assets of type `T` of an application, such as textures or levels,
+//! behind copyable [Handle]s. Handles can be stored in resources and components and shared
+//! between systems, while the assets themselves are only borrowed through the store.
+//!
+//! ## Load States
+//!
+//! Assets loaded in the background reserve their handle up front with `Assets::reserve`, in
+//! the [Loading](LoadState::Loading) state. The loader then completes the handle with
+//! `Assets::set`, or marks it [Failed](LoadState::Failed) with `Assets::fail`.
+//!
+//! ## Notifications
+//!
+//! The store records an [AssetEvent] each time an asset is added, modified, removed or fails to
+//! load. `Assets::flush_events` emits the recorded events through the `EventManager`, which
+//! `App::add_asset` schedules at the end of each cycle's `POST_UPDATE` stage.
+//!
+#[doc(hidden)]
+mod assets;
+#[doc(inline)]
+pub use assets::{AssetEvent, Assets, Handle, LoadState};
+
+pub(crate) use assets::AssetEventSystem;
@@ -0,0 +1,154 @@
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+/// Dispatch budget of a priority lane.
+///
+/// A budget limits how much work a single priority lane may consume within one
+/// dispatch cycle. A lane may be limited by the number of batches it yields, by the
+/// wall-clock time elapsed since its first batch of the cycle, or both. Once either
+/// limit is reached the `EventManager` stops yielding events of that lane and
+/// dispatch yields back to the caller. The remaining events stay queued and are
+/// yielded on the next cycle.
+///
+/// The default budget is unlimited.
+///
+/// # Examples
+/// ```
+/// use std::time::Duration;
+/// use emark::event::{budget::Budget, priority::Priority, EventManager};
+///
+/// let event_manager = EventManager::new();
+/// event_manager.set_budget(
+///     Priority::Routine,
+///     Budget::batches(8).with_duration(Duration::from_millis(2)),
+/// );
+/// assert_eq!(event_manager.budget(Priority::Routine).max_batches(), Some(8));
+/// ```
+pub struct Budget {
+    max_batches: Option<usize>,
+    max_duration: Option<Duration>,
+}
+
+impl Budget {
+    /// Budget without any limit.
+    pub fn unlimited() -> Self {
+        Self::default()
+    }
+
+    /// Budget limited to `max_batches` batches per cycle.
+    pub fn batches(max_batches: usize) -> Self {
+        Self::unlimited().with_batches(max_batches)
+    }
+
+    /// Budget limited to `max_duration` of wall-clock time per cycle.
+    pub fn duration(max_duration: Duration) -> Self {
+        Self::unlimited().with_duration(max_duration)
+    }
+
+    /// Sets the maximum number of batches per cycle.
+    pub fn with_batches(mut self, max_batches: usize) -> Self {
+        self.max_batches = Some(max_batches);
+        self
+    }
+
+    /// Sets the maximum wall-clock time per cycle.
+    pub fn with_duration(mut self, max_duration: Duration) -> Self {
+        self.max_duration = Some(max_duration);
+        self
+    }
+
+    pub fn max_batches(&self) -> Option<usize> {
+        self.max_batches
+    }
+
+    pub fn max_duration(&self) -> Option<Duration> {
+        self.max_duration
+    }
+
+    pub fn is_unlimited(&self) -> bool {
+        self.max_batches.is_none() && self.max_duration.is_none()
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+// consumption of a priority lane within the current cycle.
+pub(crate) struct BudgetUsage {
+    batches: usize,
+    started: Option<Instant>,
+}
+
+impl BudgetUsage {
+    // check if the lane has consumed its whole budget
+    pub(crate) fn is_exhausted(&self, budget: &Budget) -> bool {
+        if let Some(max_batches) = budget.max_batches {
+            if self.batches >= max_batches {
+                return true;
+            }
+        }
+
+        if let (Some(max_duration), Some(started)) = (budget.max_duration, self.started) {
+            if started.elapsed() >= max_duration {
+                return true;
+            }
+        }
+
+        false
+    }
+
+    // number of batches that can still be yielded this cycle.
+    // returns None if the number of batches is not limited.
+    pub(crate) fn remaining_batches(&self, budget: &Budget) -> Option<usize> {
+        budget
+            .max_batches
+            .map(|max_batches| max_batches.saturating_sub(self.batches))
+    }
+
+    // record yielded batches
+    pub(crate) fn consume(&mut self, batches: usize) {
+        self.batches += batches;
+        self.started.get_or_insert_with(Instant::now);
+    }
+}
+
+#[cfg(test)]
+mod test_budget {
+    use std::time::Duration;
+
+    use super::{Budget, BudgetUsage};
+
+    #[test]
+    fn test_budget_default_unlimited() {
+        assert!(Budget::default().is_unlimited());
+        assert_eq!(Budget::unlimited(), Budget::default());
+    }
+
+    #[test]
+    fn test_budget_builder() {
+        let budget = Budget::batches(4).with_duration(Duration::from_millis(1));
+        assert_eq!(budget.max_batches(), Some(4));
+        assert_eq!(budget.max_duration(), Some(Duration::from_millis(1)));
+        assert!(!budget.is_unlimited());
+    }
+
+    #[test]
+    fn test_usage_batches() {
+        let budget = Budget::batches(2);
+        let mut usage = BudgetUsage::default();
+        assert_eq!(usage.remaining_batches(&budget), Some(2));
+        usage.consume(1);
+        assert!(!usage.is_exhausted(&budget));
+        usage.consume(1);
+        assert!(usage.is_exhausted(&budget));
+        assert_eq!(usage.remaining_batches(&budget), Some(0));
+    }
+
+    #[test]
+    fn test_usage_duration() {
+        let budget = Budget::duration(Duration::ZERO);
+        let mut usage = BudgetUsage::default();
+        // time is only measured once the lane yielded its first batch
+        assert!(!usage.is_exhausted(&budget));
+        usage.consume(1);
+        assert!(usage.is_exhausted(&budget));
+    }
+}
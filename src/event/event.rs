@@ -1,3 +1,5 @@
+use std::{any::Any, fmt::Debug};
+
 /// Event trait.
 ///
 /// An empty trait that is used to define events.
@@ -14,14 +16,125 @@
 /// ```
 pub trait Event {}
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-#[allow(dead_code)]
-pub(crate) struct GenericEvent;
-impl Event for GenericEvent {}
+/// Event batched by a key.
+///
+/// By default, the `EventManager` yields a single batch per event type. A keyed event
+/// declares a batch key, such as an entity or connection id, and is yielded as one
+/// batch per (type, key) pair, so handlers do not need to group events themselves.
+/// Batches are yielded in order of first appearance of their key.
+///
+/// Keyed events must be emitted with `emit_keyed` or `emit_keyed_priority`.
+///
+/// # Examples
+/// ```
+/// use emark::prelude::{Event, KeyedEvent};
+///
+/// struct Message {
+///     connection: u32,
+/// }
+///
+/// impl Event for Message {}
+/// impl KeyedEvent for Message {
+///     type Key = u32;
+///     fn batch_key(&self) -> u32 {
+///         self.connection
+///     }
+/// }
+/// ```
+pub trait KeyedEvent: Event {
+    type Key: std::hash::Hash + Eq;
+    fn batch_key(&self) -> Self::Key;
+}
+
+/// Payload-carrying dynamic event.
+///
+/// A `DynamicEvent` is identified by its name rather than its type, carrying an
+/// arbitrary payload. It is meant for prototyping and scripting bridges where
+/// defining a dedicated event type is not practical. All dynamic events share a
+/// single batch regardless of their name.
+///
+/// # Examples
+/// ```
+/// use emark::event::{DynamicEvent, EventManager, HandlerRegistry};
+///
+/// let mut registry = HandlerRegistry::new();
+/// registry.add_handler(|events: &[DynamicEvent]| {
+///     let scores = DynamicEvent::payloads::<u32>(events, "score").sum::<u32>();
+///     assert_eq!(scores, 3);
+/// });
+///
+/// let event_manager = EventManager::new();
+/// event_manager.emit(DynamicEvent::new("score", 1u32));
+/// event_manager.emit(DynamicEvent::new("score", 2u32));
+/// event_manager.emit(DynamicEvent::new("name", "emark"));
+/// registry.dispatch(&event_manager);
+/// ```
+pub struct DynamicEvent {
+    pub name: String,
+    pub payload: Box<dyn Any + Send + Sync>,
+}
+
+impl Event for DynamicEvent {}
+
+impl DynamicEvent {
+    pub fn new<T: Any + Send + Sync>(name: impl Into<String>, payload: T) -> Self {
+        Self {
+            name: name.into(),
+            payload: Box::new(payload),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns `true` if the payload is of type `T`.
+    pub fn is<T: Any>(&self) -> bool {
+        self.payload.is::<T>()
+    }
+
+    /// Returns a reference to the payload if it is of type `T`.
+    pub fn downcast_ref<T: Any>(&self) -> Option<&T> {
+        self.payload.downcast_ref::<T>()
+    }
+
+    /// Returns a mutable reference to the payload if it is of type `T`.
+    pub fn downcast_mut<T: Any>(&mut self) -> Option<&mut T> {
+        self.payload.downcast_mut::<T>()
+    }
+
+    /// Takes the payload if it is of type `T`, otherwise returns the event back.
+    pub fn downcast<T: Any>(self) -> Result<T, Self> {
+        let Self { name, payload } = self;
+        payload
+            .downcast::<T>()
+            .map(|payload| *payload)
+            .map_err(|payload| Self { name, payload })
+    }
+
+    /// Iterates through the payloads of type `T` of the events named `name` within a batch.
+    pub fn payloads<'a, T: Any>(
+        events: &'a [DynamicEvent],
+        name: &'a str,
+    ) -> impl Iterator<Item = &'a T> + 'a {
+        events
+            .iter()
+            .filter(move |event| event.name == name)
+            .filter_map(DynamicEvent::downcast_ref::<T>)
+    }
+}
+
+impl Debug for DynamicEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DynamicEvent")
+            .field("name", &self.name)
+            .finish_non_exhaustive()
+    }
+}
 
 #[cfg(test)]
 mod test_event {
-    use crate::event::event::Event;
+    use crate::event::event::{DynamicEvent, Event};
 
     #[test]
     fn test_event() {
@@ -29,4 +142,33 @@ mod test_event {
         struct SomeEvent;
         impl Event for SomeEvent {}
     }
+
+    #[test]
+    fn test_dynamic_event_downcast() {
+        let mut event = DynamicEvent::new("score", 1u32);
+        assert_eq!(event.name(), "score");
+        assert!(event.is::<u32>());
+        assert_eq!(event.downcast_ref::<i32>(), None);
+
+        *event.downcast_mut::<u32>().unwrap() += 1;
+        assert_eq!(event.downcast_ref::<u32>(), Some(&2));
+
+        // assert failed downcast gives the event back
+        let event = event.downcast::<String>().unwrap_err();
+        assert_eq!(event.downcast::<u32>().unwrap(), 2);
+    }
+
+    #[test]
+    fn test_dynamic_event_payloads() {
+        let events = [
+            DynamicEvent::new("score", 1u32),
+            DynamicEvent::new("score", "invalid"),
+            DynamicEvent::new("lives", 3u32),
+            DynamicEvent::new("score", 2u32),
+        ];
+        assert_eq!(
+            DynamicEvent::payloads::<u32>(&events, "score").collect::<Vec<_>>(),
+            vec![&1, &2]
+        );
+    }
 }
@@ -1,25 +1,98 @@
 use std::{
     any::{Any, TypeId},
     collections::HashMap,
+    ops::RangeInclusive,
+    sync::atomic::{AtomicBool, AtomicU64, Ordering},
+    time::Instant,
 };
 
-use crate::utils::lock::GrainedLock;
+#[cfg(feature = "async")]
+use std::sync::Arc;
+
+use crate::utils::{error::EmarkError, lock::GrainedLock};
+
+#[cfg(feature = "async")]
+use super::watch::{EventWatch, WatchState};
 
 use super::{
+    budget::{Budget, BudgetUsage},
     priority::{Priority, PriorityState},
-    Event,
+    stamp::EventStamp,
+    Event, KeyedEvent,
 };
 
-#[derive(PartialEq, Eq, Debug, Clone, Copy, Ord)]
-pub(crate) struct EmittedEventInfo {
-    priority: Priority,
-    event_type_id: TypeId,
-    vec_type_id: TypeId,
+#[derive(PartialEq, Eq, Debug, Clone)]
+/// Information of an emitted batch of events.
+pub struct EmittedEventInfo {
+    pub(crate) priority: Priority,
+    pub(crate) event_type_id: TypeId,
+    pub(crate) vec_type_id: TypeId,
+    pub(crate) event_type_name: &'static str,
+    pub(crate) stamps: Vec<EventStamp>,
+}
+
+impl EmittedEventInfo {
+    /// Priority the batch is dispatched with.
+    pub fn priority(&self) -> Priority {
+        self.priority
+    }
+
+    /// `TypeId` of the event type of the batch.
+    pub fn event_type_id(&self) -> TypeId {
+        self.event_type_id
+    }
+
+    /// `TypeId` of the `Vec` of events of the batch.
+    pub fn vec_type_id(&self) -> TypeId {
+        self.vec_type_id
+    }
+
+    /// Type name of the event type of the batch.
+    pub fn event_type_name(&self) -> &'static str {
+        self.event_type_name
+    }
+
+    /// Stamps of the events of the batch, in the same order as the events.
+    pub fn stamps(&self) -> &[EventStamp] {
+        &self.stamps
+    }
+
+    /// Number of events of the batch.
+    pub fn len(&self) -> usize {
+        self.stamps.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stamps.is_empty()
+    }
+
+    /// Sequence numbers of the first and last events of the batch, `None` if the batch is empty.
+    ///
+    /// The range is not contiguous when other events were emitted in between, or when the
+    /// events of a `KeyedEvent` were partitioned into batches.
+    pub fn sequence_range(&self) -> Option<RangeInclusive<u64>> {
+        let first = self.stamps.first()?.sequence();
+        let last = self.stamps.last()?.sequence();
+        Some(first..=last)
+    }
+}
+
+/// A batch of events of the same type.
+///
+/// The boxed events are a `Vec<T>` of the event type `T` described by the info.
+pub type EventBatch = (EmittedEventInfo, Box<dyn Any + Send + Sync>);
+
+impl Ord for EmittedEventInfo {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        // `Priority` is also an iterator, call `Ord::cmp` explicitly
+        Ord::cmp(&self.priority, &other.priority)
+            .then_with(|| self.event_type_id.cmp(&other.event_type_id))
+    }
 }
 
 impl PartialOrd for EmittedEventInfo {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        Some(self.priority.cmp(other.priority))
+        Some(self.cmp(other))
     }
 }
 
@@ -40,12 +113,92 @@ impl PartialOrd for EmittedEventInfo {
 /// priorities, and the `EventManager` ensures that higher-priority events are processed
 /// before lower-priority ones.
 ///
+/// Each priority lane can be given a dispatch [Budget](crate::event::budget::Budget).
+/// Once a lane has consumed its budget within the current cycle, the `EventManager`
+/// yields no more events until the next cycle is started with `begin_cycle`. The batches of
+/// a [KeyedEvent](crate::event::KeyedEvent) are each charged to the budget, and all yielded
+/// together, so the last event type yielded within a cycle may exceed the budget.
+///
+/// Priority lanes can also be paused, in which case they accumulate events without
+/// yielding them until resumed, while the other lanes keep being dispatched.
+///
 /// # Examples
 ///
 pub struct EventManager {
     events: GrainedLock<HashMap<TypeId, Box<dyn Any + Send + Sync>>>,
     events_set: GrainedLock<HashMap<TypeId, Priority>>,
     events_bus: GrainedLock<[Vec<EmittedEventInfo>; 4]>,
+    budgets: GrainedLock<[Budget; 4]>,
+    budgets_usage: GrainedLock<[BudgetUsage; 4]>,
+    paused: GrainedLock<[bool; 4]>,
+    partitioners: GrainedLock<HashMap<TypeId, Partitioner>>,
+    stamps: GrainedLock<HashMap<TypeId, Vec<EventStamp>>>,
+    sequence: AtomicU64,
+    timestamps: AtomicBool,
+    cloners: GrainedLock<HashMap<TypeId, Cloner>>,
+    #[cfg(feature = "async")]
+    watchers: GrainedLock<HashMap<TypeId, Vec<Arc<WatchState>>>>,
+}
+
+// clones a boxed `Vec<T>`
+type Cloner = fn(&(dyn Any + Send + Sync)) -> Box<dyn Any + Send + Sync>;
+
+fn clone_events<T: Clone + Send + Sync + 'static>(
+    events: &(dyn Any + Send + Sync),
+) -> Box<dyn Any + Send + Sync> {
+    Box::new(events.downcast_ref::<Vec<T>>().unwrap().clone())
+}
+
+#[derive(Debug)]
+/// Pending state of an `EventManager` captured by `snapshot`.
+///
+/// A snapshot can be restored any number of times with `restore`.
+pub struct EventSnapshot {
+    events: HashMap<TypeId, (Box<dyn Any + Send + Sync>, Cloner)>,
+    events_set: HashMap<TypeId, Priority>,
+    events_bus: [Vec<EmittedEventInfo>; 4],
+    stamps: HashMap<TypeId, Vec<EventStamp>>,
+    sequence: u64,
+}
+
+impl EventSnapshot {
+    /// Number of pending event types captured by the snapshot.
+    pub fn len(&self) -> usize {
+        self.events_set.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events_set.is_empty()
+    }
+}
+
+type Partition = (Box<dyn Any + Send + Sync>, Vec<EventStamp>);
+
+// splits a boxed `Vec<T>` and its stamps into one partition per batch key
+type Partitioner = fn(Box<dyn Any + Send + Sync>, Vec<EventStamp>) -> Vec<Partition>;
+
+fn partition<T: KeyedEvent + Send + Sync + 'static>(
+    events: Box<dyn Any + Send + Sync>,
+    stamps: Vec<EventStamp>,
+) -> Vec<Partition> {
+    let events = *events.downcast::<Vec<T>>().unwrap();
+
+    // group events by key, in order of first appearance
+    let mut keys = HashMap::new();
+    let mut partitions: Vec<(Vec<T>, Vec<EventStamp>)> = Vec::new();
+    for (event, stamp) in events.into_iter().zip(stamps) {
+        let index = *keys.entry(event.batch_key()).or_insert_with(|| {
+            partitions.push(Default::default());
+            partitions.len() - 1
+        });
+        partitions[index].0.push(event);
+        partitions[index].1.push(stamp);
+    }
+
+    partitions
+        .into_iter()
+        .map(|(events, stamps)| (Box::new(events) as Box<dyn Any + Send + Sync>, stamps))
+        .collect()
 }
 
 impl EventManager {
@@ -64,6 +217,78 @@ impl EventManager {
         &self,
         event: T,
         priority: Priority,
+    ) -> Option<TypeId> {
+        self.emit_with(event, priority, |events, event| {
+            events.push(event);
+            true
+        })
+    }
+
+    /// Emits an event with the specified priority only if no event of the same type is pending.
+    ///
+    /// Returns `Some(TypeId)` of the event if it was emitted, `None` if it was discarded.
+    pub fn emit_if_absent_priority<T: Event + Send + Sync + 'static>(
+        &self,
+        event: T,
+        priority: Priority,
+    ) -> Option<TypeId> {
+        self.emit_with(event, priority, |events, event| {
+            if !events.is_empty() {
+                return false;
+            }
+            events.push(event);
+            true
+        })
+    }
+
+    /// Emits an event with normal priority only if no event of the same type is pending.
+    ///
+    /// Returns `Some(TypeId)` of the event if it was emitted, `None` if it was discarded.
+    pub fn emit_if_absent<T: Event + Send + Sync + 'static>(&self, event: T) -> Option<TypeId> {
+        self.emit_if_absent_priority(event, Priority::Normal)
+    }
+
+    /// Emits an event with the specified priority or merges it into the pending batch.
+    ///
+    /// If an event of the same type is pending, `merge` is called with the last pending
+    /// event and the emitted event instead of enqueuing it. Otherwise the event is
+    /// enqueued as with `emit_priority`. In both cases the priority of the batch is
+    /// upgraded if needed.
+    ///
+    /// Always returns `Some(TypeId)` of the event that was emitted.
+    pub fn emit_or_merge_priority<T: Event + Send + Sync + 'static>(
+        &self,
+        event: T,
+        priority: Priority,
+        merge: impl FnOnce(&mut T, T),
+    ) -> Option<TypeId> {
+        self.emit_with(event, priority, |events, event| {
+            match events.last_mut() {
+                Some(pending) => merge(pending, event),
+                None => events.push(event),
+            }
+            true
+        })
+    }
+
+    /// Emits an event with normal priority or merges it into the pending batch.
+    ///
+    /// See `emit_or_merge_priority`.
+    pub fn emit_or_merge<T: Event + Send + Sync + 'static>(
+        &self,
+        event: T,
+        merge: impl FnOnce(&mut T, T),
+    ) -> Option<TypeId> {
+        self.emit_or_merge_priority(event, Priority::Normal, merge)
+    }
+
+    // insert an event into its pending batch using `insert`, then schedule the batch.
+    // `insert` returns false if the event is discarded, in which case nothing is scheduled.
+    fn emit_with<T: Event + Send + Sync + 'static>(
+        &self,
+        event: T,
+        priority: Priority,
+        insert: impl FnOnce(&mut Vec<T>, T) -> bool,
     ) -> Option<TypeId> {
         // get type id of event
         let event_type_id = TypeId::of::<T>();
@@ -78,7 +303,20 @@ impl EventManager {
             .unwrap();
 
         // insert event
-        events.push(event);
+        let len = events.len();
+        if !insert(events, event) {
+            return None;
+        }
+
+        // stamp event
+        // a merged event takes the stamp of the latest emission
+        let stamp = self.stamp();
+        let mut stamps = self.stamps.borrow_mut();
+        let stamps = stamps.entry(event_type_id).or_default();
+        match stamps.last_mut() {
+            Some(last) if events.len() == len => *last = stamp,
+            _ => stamps.push(stamp),
+        }
 
         // check if event_set already contains event.
         let mut event_set = self.events_set.borrow_mut();
@@ -86,32 +324,8 @@ impl EventManager {
             // event has already been fired beforehand
             // check if priority needs an upgrade
             if priority > *old_priority {
-                // update priority from events_bus
-                // get old index
-                let index = usize::from(*old_priority);
-                // get olf info
-                let mut info = {
-                    // get event bus
-                    let mut events_bus = self.events_bus.borrow_mut();
-                    let events = events_bus.get_mut(index).unwrap();
-                    // get info index
-                    let info_index = events
-                        .iter()
-                        .enumerate()
-                        .find(|(_, info)| info.event_type_id == event_type_id)
-                        .map(|(index, _)| index)
-                        .unwrap();
-
-                    // remove old info
-                    events.remove(info_index)
-                };
-
-                // set new priority
+                self.move_lane(event_type_id, *old_priority, priority);
                 *old_priority = priority;
-                info.priority = priority;
-
-                // insert new info
-                self.events_bus.borrow_mut()[index].push(info);
             }
         } else {
             // event has not been fired before
@@ -126,13 +340,79 @@ impl EventManager {
                     priority,
                     event_type_id,
                     vec_type_id,
+                    event_type_name: std::any::type_name::<T>(),
+                    stamps: Vec::new(),
                 });
         }
 
+        // notify watchers
+        #[cfg(feature = "async")]
+        if let Some(watchers) = self.watchers.borrow_mut().remove(&event_type_id) {
+            watchers.iter().for_each(|watcher| watcher.notify());
+        }
+
         // return event type id
         Some(event_type_id)
     }
 
+    /// Returns a future resolving when an event of type `T` is next emitted.
+    ///
+    /// Only emissions after the call to `watch` resolve the future, discarded
+    /// emissions such as `emit_if_absent` of a pending event do not.
+    #[cfg(feature = "async")]
+    pub fn watch<T: Event + 'static>(&self) -> EventWatch {
+        let state = Arc::new(WatchState::default());
+        let mut watchers = self.watchers.borrow_mut();
+        let watchers = watchers.entry(TypeId::of::<T>()).or_default();
+
+        // drop watchers of dropped watches
+        watchers.retain(|watcher| Arc::strong_count(watcher) > 1);
+        watchers.push(state.clone());
+
+        EventWatch::new(state)
+    }
+
+    // move the info of a pending event type from a priority lane to another
+    fn move_lane(&self, event_type_id: TypeId, from: Priority, to: Priority) {
+        // get old info
+        let mut info = {
+            // get event bus
+            let mut events_bus = self.events_bus.borrow_mut();
+            let events = events_bus.get_mut(usize::from(from)).unwrap();
+            // get info index
+            let info_index = events
+                .iter()
+                .position(|info| info.event_type_id == event_type_id)
+                .unwrap();
+
+            // remove old info
+            events.remove(info_index)
+        };
+
+        // set new priority
+        info.priority = to;
+
+        // insert new info
+        self.events_bus.borrow_mut()[usize::from(to)].push(info);
+    }
+
+    /// Promotes the pending events of type `T` to a higher priority lane without re-emitting them.
+    ///
+    /// Returns `true` if the events were promoted, `false` if no event of type `T` is pending
+    /// or if they are already pending with `priority` or a higher one.
+    pub fn boost<T: Event + 'static>(&self, priority: Priority) -> bool {
+        let event_type_id = TypeId::of::<T>();
+        let mut events_set = self.events_set.borrow_mut();
+        match events_set.get_mut(&event_type_id) {
+            Some(old_priority) if priority > *old_priority => {
+                self.move_lane(event_type_id, *old_priority, priority);
+                *old_priority = priority;
+                true
+            }
+            _ => false,
+        }
+    }
+
     /// Emits an event with normal priority.
     ///
     /// Always returns `Some(TypeId)` of the event that was emitted.
@@ -140,6 +420,33 @@ impl EventManager {
         self.emit_priority(event, Priority::Normal)
     }
 
+    /// Emits a keyed event with the specified priority.
+    ///
+    /// Pending events of type `T` are yielded as one batch per batch key
+    /// by `next_execution`, see [KeyedEvent](crate::event::KeyedEvent).
+    ///
+    /// Always returns `Some(TypeId)` of the event that was emitted.
+    pub fn emit_keyed_priority<T: KeyedEvent + Send + Sync + 'static>(
+        &self,
+        event: T,
+        priority: Priority,
+    ) -> Option<TypeId> {
+        // register partitioner of event
+        self.partitioners
+            .borrow_mut()
+            .entry(TypeId::of::<T>())
+            .or_insert(partition::<T>);
+
+        self.emit_priority(event, priority)
+    }
+
+    /// Emits a keyed event with normal priority.
+    ///
+    /// Always returns `Some(TypeId)` of the event that was emitted.
+    pub fn emit_keyed<T: KeyedEvent + Send + Sync + 'static>(&self, event: T) -> Option<TypeId> {
+        self.emit_keyed_priority(event, Priority::Normal)
+    }
+
     /// Emits and event like `emit_priority` but with a priority type state.
     /// for the supported type state see [PriorityState](crate::event::priority::PriorityState)
     pub fn emit_type_state<T: Event + Send + Sync + 'static, P: PriorityState>(
@@ -149,53 +456,426 @@ impl EventManager {
         self.emit_priority(event, P::priority())
     }
 
-    // get next events to be executed.
-    // returns None if no events are available.
-    pub(crate) fn next_execution(
+    /// Returns the number of pending events of type `T`.
+    pub fn pending_count<T: Event + Send + Sync + 'static>(&self) -> usize {
+        self.peek::<T, _>(|events| events.len()).unwrap_or(0)
+    }
+
+    /// Returns the priority pending events of type `T` will be dispatched with.
+    ///
+    /// Returns `None` if no event of type `T` is pending.
+    pub fn pending_priority<T: Event + 'static>(&self) -> Option<Priority> {
+        self.events_set.borrow().get(&TypeId::of::<T>()).copied()
+    }
+
+    /// Returns the `TypeId` of every pending event type in dispatch order.
+    pub fn pending_types(&self) -> Vec<TypeId> {
+        self.events_bus
+            .borrow()
+            .iter()
+            .flatten()
+            .map(|info| info.event_type_id)
+            .collect()
+    }
+
+    /// Returns `true` if no event is pending.
+    pub fn is_empty(&self) -> bool {
+        self.events_set.borrow().is_empty()
+    }
+
+    /// Inspects the pending events of type `T` without draining them.
+    ///
+    /// Calls `f` with the pending events in emission order and returns its result.
+    /// Returns `None` if no event of type `T` is pending.
+    ///
+    /// Note that the `EventManager` is locked while `f` is executed, emitting
+    /// events from within `f` will deadlock.
+    pub fn peek<T: Event + Send + Sync + 'static, R>(
         &self,
-    ) -> Option<Vec<(EmittedEventInfo, Box<dyn Any + Send + Sync>)>> {
-        // get first available priority
+        f: impl FnOnce(&[T]) -> R,
+    ) -> Option<R> {
+        self.events
+            .borrow()
+            .get(&TypeId::of::<T>())
+            .map(|events| f(events.downcast_ref::<Vec<T>>().unwrap()))
+    }
+
+    /// Retracts the pending events of type `T` before they are dispatched.
+    ///
+    /// Removes the pending batch of `T` along with its entry on the priority lane
+    /// and returns the events in emission order.
+    /// Returns `None` if no event of type `T` is pending.
+    pub fn retract<T: Event + Send + Sync + 'static>(&self) -> Option<Vec<T>> {
+        let event_type_id = TypeId::of::<T>();
+
+        // remove events
+        let events = self.events.borrow_mut().remove(&event_type_id)?;
+
+        // remove stamps
+        self.stamps.borrow_mut().remove(&event_type_id);
+
+        // remove event_set
+        let priority = self.events_set.borrow_mut().remove(&event_type_id).unwrap();
+
+        // remove info from events_bus
+        self.events_bus.borrow_mut()[usize::from(priority)]
+            .retain(|info| info.event_type_id != event_type_id);
+
+        // return events
+        Some(*events.downcast::<Vec<T>>().unwrap())
+    }
+
+    /// Discards every pending event.
+    pub fn clear(&self) {
+        for priority in Priority::ALL {
+            self.clear_priority(priority);
+        }
+    }
+
+    /// Discards every pending event of a priority lane.
+    pub fn clear_priority(&self, priority: Priority) {
+        self.take_priority(priority);
+    }
+
+    /// Reserves capacity for at least `additional` more pending event types, in the pending
+    /// batches and in each priority lane, e.g. at startup to avoid reallocations mid-frame.
+    pub fn reserve(&self, additional: usize) {
+        self.events.borrow_mut().reserve(additional);
+        self.events_set.borrow_mut().reserve(additional);
+        self.stamps.borrow_mut().reserve(additional);
+        for lane in self.events_bus.borrow_mut().iter_mut() {
+            lane.reserve(additional);
+        }
+    }
+
+    /// Shrinks the capacity of the pending batches and of the priority lanes as much as
+    /// possible, e.g. once a burst of events has been dispatched.
+    pub fn shrink_to_fit(&self) {
+        self.events.borrow_mut().shrink_to_fit();
+        self.events_set.borrow_mut().shrink_to_fit();
+        self.stamps.borrow_mut().shrink_to_fit();
+        for lane in self.events_bus.borrow_mut().iter_mut() {
+            lane.shrink_to_fit();
+        }
+    }
+
+    // capacity of the pending batches, the smallest across the priority lanes
+    #[cfg(test)]
+    fn capacity(&self) -> usize {
+        let lanes = self.events_bus.borrow();
+        let lanes = lanes.iter().map(Vec::capacity).min().unwrap();
+        lanes.min(self.events.borrow().capacity())
+    }
+
+    /// Drains every pending batch regardless of the dispatch budgets.
+    ///
+    /// Batches are returned in dispatch order, from the highest priority lane to
+    /// the lowest one.
+    pub fn drain_all(&self) -> Vec<EventBatch> {
+        Priority::ALL
+            .into_iter()
+            .flat_map(|priority| self.take_priority(priority))
+            .collect()
+    }
+
+    // take every batch of a priority lane
+    fn take_priority(&self, priority: Priority) -> Vec<EventBatch> {
+        let mut events = self.events.borrow_mut();
+        let mut stamps = self.stamps.borrow_mut();
+        let mut events_set = self.events_set.borrow_mut();
+        std::mem::take(&mut self.events_bus.borrow_mut()[usize::from(priority)])
+            .into_iter()
+            .flat_map(|mut info| {
+                // remove event_set
+                events_set.remove(&info.event_type_id).unwrap();
+
+                // remove stamps
+                info.stamps = stamps.remove(&info.event_type_id).unwrap_or_default();
+
+                // remove events
+                let events = events.remove(&info.event_type_id).unwrap();
+                self.partition(info, events)
+            })
+            .collect()
+    }
+
+    // split the events of a batch by their batch key.
+    // events without a batch key are returned as a single batch.
+    fn partition(
+        &self,
+        mut info: EmittedEventInfo,
+        events: Box<dyn Any + Send + Sync>,
+    ) -> Vec<EventBatch> {
+        match self.partitioners.borrow().get(&info.event_type_id) {
+            Some(partitioner) => partitioner(events, std::mem::take(&mut info.stamps))
+                .into_iter()
+                .map(|(events, stamps)| {
+                    let info = EmittedEventInfo {
+                        stamps,
+                        ..info.clone()
+                    };
+                    (info, events)
+                })
+                .collect(),
+            None => vec![(info, events)],
+        }
+    }
+
+    // stamp a new emission
+    fn stamp(&self) -> EventStamp {
+        let sequence = self.sequence.fetch_add(1, Ordering::Relaxed);
+        let timestamp = self.timestamps.load(Ordering::Relaxed).then(Instant::now);
+        EventStamp::new(sequence, timestamp)
+    }
+
+    /// Enables or disables timestamping of emissions.
+    ///
+    /// Timestamps are disabled by default. Sequence numbers are always recorded.
+    pub fn set_timestamps(&self, enabled: bool) {
+        self.timestamps.store(enabled, Ordering::Relaxed);
+    }
+
+    /// Returns `true` if emissions are timestamped.
+    pub fn timestamps_enabled(&self) -> bool {
+        self.timestamps.load(Ordering::Relaxed)
+    }
+
+    /// Returns the sequence number of the next emission.
+    pub fn next_sequence(&self) -> u64 {
+        self.sequence.load(Ordering::Relaxed)
+    }
+
+    /// Registers event `T` as cloneable, allowing its pending events to be captured by `snapshot`.
+    pub fn register_snapshot<T: Event + Clone + Send + Sync + 'static>(&self) {
+        self.cloners
+            .borrow_mut()
+            .entry(TypeId::of::<T>())
+            .or_insert(clone_events::<T>);
+    }
+
+    /// Captures every pending batch along with its priority and stamps.
+    ///
+    /// The dispatch budgets and registrations are not part of the snapshot.
+    /// Returns an error if a pending event type has not been registered with
+    /// `register_snapshot`.
+    ///
+    /// # Examples
+    /// ```
+    /// use emark::event::EventManager;
+    /// use emark::prelude::Event;
+    ///
+    /// #[derive(Clone)]
+    /// struct Ping;
+    /// impl Event for Ping {}
+    ///
+    /// let event_manager = EventManager::new();
+    /// event_manager.register_snapshot::<Ping>();
+    /// event_manager.emit(Ping);
+    ///
+    /// let snapshot = event_manager.snapshot().unwrap();
+    /// event_manager.drain_all();
+    /// assert!(event_manager.is_empty());
+    ///
+    /// // roll back and try again
+    /// event_manager.restore(&snapshot);
+    /// assert_eq!(event_manager.pending_count::<Ping>(), 1);
+    /// ```
+    pub fn snapshot(&self) -> Result<EventSnapshot, EmarkError> {
+        let events = self.events.borrow();
+        let stamps = self.stamps.borrow();
+        let events_set = self.events_set.borrow();
+        let events_bus = self.events_bus.borrow();
+        let cloners = self.cloners.borrow();
+
+        // clone pending events
+        let mut snapshot_events = HashMap::new();
+        for info in events_bus.iter().flatten() {
+            let cloner = *cloners
+                .get(&info.event_type_id)
+                .ok_or(EmarkError::UnregisteredSnapshot(info.event_type_name))?;
+            let pending = events.get(&info.event_type_id).unwrap();
+            snapshot_events.insert(info.event_type_id, (cloner(pending.as_ref()), cloner));
+        }
+
+        Ok(EventSnapshot {
+            events: snapshot_events,
+            events_set: events_set.clone(),
+            events_bus: events_bus.clone(),
+            stamps: stamps.clone(),
+            sequence: self.sequence.load(Ordering::Relaxed),
+        })
+    }
+
+    /// Replaces every pending batch with the content of `snapshot`.
+    ///
+    /// Events emitted since the snapshot are discarded and the sequence number
+    /// is rolled back.
+    pub fn restore(&self, snapshot: &EventSnapshot) {
+        let mut events = self.events.borrow_mut();
+        let mut stamps = self.stamps.borrow_mut();
+        let mut events_set = self.events_set.borrow_mut();
+        let mut events_bus = self.events_bus.borrow_mut();
+
+        *events = snapshot
+            .events
+            .iter()
+            .map(|(type_id, (pending, cloner))| (*type_id, cloner(pending.as_ref())))
+            .collect();
+        *stamps = snapshot.stamps.clone();
+        *events_set = snapshot.events_set.clone();
+        *events_bus = snapshot.events_bus.clone();
+        self.sequence.store(snapshot.sequence, Ordering::Relaxed);
+    }
+
+    /// Sets the dispatch budget of a priority lane.
+    ///
+    /// The budget applies from the next call to `next_execution` onwards.
+    pub fn set_budget(&self, priority: Priority, budget: Budget) {
+        self.budgets.borrow_mut()[usize::from(priority)] = budget;
+    }
+
+    /// Returns the dispatch budget of a priority lane.
+    pub fn budget(&self, priority: Priority) -> Budget {
+        self.budgets.borrow()[usize::from(priority)]
+    }
+
+    /// Starts a new dispatch cycle.
+    ///
+    /// Resets the budget consumption of every priority lane, so that lanes
+    /// exhausted during the previous cycle can be dispatched again.
+    pub fn begin_cycle(&self) {
+        *self.budgets_usage.borrow_mut() = Default::default();
+    }
+
+    /// Pauses the dispatch of a priority lane.
+    ///
+    /// Events emitted on a paused lane are kept until the lane is resumed,
+    /// `next_execution` yields the events of the other lanes in the meantime.
+    pub fn pause(&self, priority: Priority) {
+        self.paused.borrow_mut()[usize::from(priority)] = true;
+    }
+
+    /// Resumes the dispatch of a paused priority lane.
+    pub fn resume(&self, priority: Priority) {
+        self.paused.borrow_mut()[usize::from(priority)] = false;
+    }
+
+    /// Returns `true` if the priority lane is paused.
+    pub fn is_paused(&self, priority: Priority) -> bool {
+        self.paused.borrow()[usize::from(priority)]
+    }
+
+    /// Returns `true` if the priority lane has consumed its budget within the current cycle.
+    pub fn is_budget_exhausted(&self, priority: Priority) -> bool {
+        let index = usize::from(priority);
+        self.budgets_usage.borrow()[index].is_exhausted(&self.budgets.borrow()[index])
+    }
+
+    // get next events to be executed.
+    // returns None if no events are available or if the budget
+    // of the first available priority has been exhausted.
+    pub(crate) fn next_execution(&self) -> Option<Vec<EventBatch>> {
+        // get first available priority, skipping paused lanes
         let mut priority = None;
+        let paused = *self.paused.borrow();
         for (index, infos) in self.events_bus.borrow_mut().iter_mut().enumerate() {
-            if !infos.is_empty() {
+            if !infos.is_empty() && !paused[index] {
                 priority = Some(Priority::from(index as u8));
                 break;
             }
         }
 
         if let Some(priority) = priority {
-            // get infos
-            let infos = std::mem::take(&mut self.events_bus.borrow_mut()[usize::from(priority)])
-                .into_iter()
-                .map(|info| {
-                    // get events
-                    let event = self
-                        .events
-                        .borrow_mut()
-                        .remove(&info.event_type_id)
-                        .unwrap();
-
-                    // remove event_set
-                    self.events_set
-                        .borrow_mut()
-                        .remove(&info.event_type_id)
-                        .unwrap();
-
-                    // return info
-                    (info, event)
-                });
+            let index = usize::from(priority);
+
+            // check budget of priority
+            // yield back to the caller once it has been exhausted
+            let budget = self.budgets.borrow()[index];
+            let mut usage = self.budgets_usage.borrow_mut();
+            let usage = &mut usage[index];
+            if usage.is_exhausted(&budget) {
+                return None;
+            }
+
+            // take infos within the remaining budget, one at a time while the budget is
+            // limited, as keyed events are only split into their batches once taken
+            let mut batches = Vec::new();
+            loop {
+                let infos = {
+                    let mut events_bus = self.events_bus.borrow_mut();
+                    let infos = &mut events_bus[index];
+                    match usage.remaining_batches(&budget) {
+                        Some(0) => break,
+                        Some(_) if !infos.is_empty() => infos.drain(..1).collect::<Vec<_>>(),
+                        _ => std::mem::take(infos),
+                    }
+                };
+                if infos.is_empty() {
+                    break;
+                }
+
+                // consume budget with the batches split by key
+                for info in infos {
+                    let partitioned = self.take_execution(info);
+                    usage.consume(partitioned.len());
+                    batches.extend(partitioned);
+                }
+            }
 
             // return infos
-            return Some(infos.collect());
+            return Some(batches);
         }
         None
     }
+
+    // remove the events of a batch taken for execution, split by their batch key
+    fn take_execution(&self, mut info: EmittedEventInfo) -> Vec<EventBatch> {
+        // get events
+        let event = self
+            .events
+            .borrow_mut()
+            .remove(&info.event_type_id)
+            .unwrap();
+
+        // get stamps
+        info.stamps = self
+            .stamps
+            .borrow_mut()
+            .remove(&info.event_type_id)
+            .unwrap_or_default();
+
+        // remove event_set
+        self.events_set
+            .borrow_mut()
+            .remove(&info.event_type_id)
+            .unwrap();
+
+        self.partition(info, event)
+    }
 }
 
 #[cfg(test)]
 mod test_event_manager {
+    use std::time::Duration;
+
     use super::*;
-    use crate::event::{event::GenericEvent, event_manager::EventManager, priority::Interrupt};
+    use crate::event::{event_manager::EventManager, priority::Interrupt};
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    struct GenericEvent;
+    impl Event for GenericEvent {}
+
+    #[test]
+    fn test_reserve_shrink() {
+        let event_manager = EventManager::new();
+        event_manager.reserve(64);
+        assert!(event_manager.capacity() >= 64);
+
+        event_manager.emit(GenericEvent);
+        event_manager.drain_all();
+        event_manager.shrink_to_fit();
+        assert_eq!(event_manager.capacity(), 0);
+    }
 
     #[test]
     fn test_event_manager_new() {
@@ -295,6 +975,94 @@ mod test_event_manager {
         assert!(event_manager.next_execution().is_none());
     }
 
+    #[test]
+    fn test_event_manager_upgrade_lane() {
+        let event_manager = EventManager::new();
+
+        // insert events
+        event_manager
+            .emit_priority(GenericEvent, Priority::Normal)
+            .unwrap();
+        event_manager
+            .emit_priority(GenericEvent, Priority::High)
+            .unwrap();
+
+        // assert upgraded batch moved to the high lane
+        assert_eq!(
+            event_manager.pending_priority::<GenericEvent>(),
+            Some(Priority::High)
+        );
+        assert!(event_manager.events_bus.borrow()[usize::from(Priority::Normal)].is_empty());
+        let events = event_manager.next_execution().unwrap();
+        assert_eq!(events[0].0.priority, Priority::High);
+        assert_eq!(
+            events[0]
+                .1
+                .downcast_ref::<Vec<GenericEvent>>()
+                .unwrap()
+                .len(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_event_manager_emit_if_absent() {
+        #[derive(Debug, PartialEq)]
+        struct StateChanged(i32);
+
+        impl Event for StateChanged {}
+
+        let event_manager = EventManager::new();
+        assert_eq!(
+            event_manager.emit_if_absent(StateChanged(1)),
+            Some(TypeId::of::<StateChanged>())
+        );
+        assert_eq!(
+            event_manager.emit_if_absent_priority(StateChanged(2), Priority::Interrupt),
+            None
+        );
+
+        // assert discarded event did not upgrade the batch
+        assert_eq!(
+            event_manager.pending_priority::<StateChanged>(),
+            Some(Priority::Normal)
+        );
+        assert_eq!(
+            event_manager.retract::<StateChanged>(),
+            Some(vec![StateChanged(1)])
+        );
+
+        // assert event is emitted again once the batch is gone
+        assert!(event_manager.emit_if_absent(StateChanged(3)).is_some());
+    }
+
+    #[test]
+    fn test_event_manager_emit_or_merge() {
+        #[derive(Debug, PartialEq)]
+        struct Resized(u32, u32);
+
+        impl Event for Resized {}
+
+        let event_manager = EventManager::new();
+        let merge = |pending: &mut Resized, event: Resized| *pending = event;
+
+        event_manager.emit_or_merge(Resized(1, 1), merge).unwrap();
+        event_manager.emit_or_merge(Resized(2, 2), merge).unwrap();
+        event_manager
+            .emit_or_merge_priority(Resized(3, 3), Priority::High, merge)
+            .unwrap();
+
+        // assert a single merged event upgraded to high
+        assert_eq!(
+            event_manager.pending_priority::<Resized>(),
+            Some(Priority::High)
+        );
+        assert_eq!(
+            event_manager.retract::<Resized>(),
+            Some(vec![Resized(3, 3)])
+        );
+    }
+
     #[test]
     fn test_event_manager_next_execution() {
         struct TestEventInterrupt;
@@ -376,6 +1144,488 @@ mod test_event_manager {
         assert!(event_manager.next_execution().is_none());
     }
 
+    #[test]
+    fn test_event_manager_budget_batches() {
+        struct TestEventA;
+        struct TestEventB;
+
+        impl Event for TestEventA {}
+        impl Event for TestEventB {}
+
+        let event_manager = EventManager::new();
+        event_manager.set_budget(Priority::Routine, Budget::batches(1));
+
+        // insert events
+        event_manager
+            .emit_priority(TestEventA, Priority::Routine)
+            .unwrap();
+        event_manager
+            .emit_priority(TestEventB, Priority::Routine)
+            .unwrap();
+
+        // assert only one batch is yielded within the cycle
+        let events = event_manager.next_execution().unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].0.event_type_id, TypeId::of::<TestEventA>());
+        assert!(event_manager.is_budget_exhausted(Priority::Routine));
+        assert!(event_manager.next_execution().is_none());
+
+        // assert remaining batch is yielded on the next cycle
+        event_manager.begin_cycle();
+        let events = event_manager.next_execution().unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].0.event_type_id, TypeId::of::<TestEventB>());
+    }
+
+    #[test]
+    fn test_event_manager_budget_duration() {
+        struct TestEventHigh;
+
+        impl Event for TestEventHigh {}
+
+        let event_manager = EventManager::new();
+        event_manager.set_budget(Priority::Routine, Budget::duration(Duration::ZERO));
+
+        // a self-refiring event is yielded once per cycle
+        event_manager
+            .emit_priority(GenericEvent, Priority::Routine)
+            .unwrap();
+        assert!(event_manager.next_execution().is_some());
+        event_manager
+            .emit_priority(GenericEvent, Priority::Routine)
+            .unwrap();
+        assert!(event_manager.next_execution().is_none());
+
+        // assert higher priorities are not affected
+        event_manager
+            .emit_priority(TestEventHigh, Priority::High)
+            .unwrap();
+        assert_eq!(
+            event_manager
+                .next_execution()
+                .unwrap()
+                .first()
+                .unwrap()
+                .0
+                .priority,
+            Priority::High
+        );
+
+        // assert routine dispatch resumes on the next cycle
+        event_manager
+            .emit_priority(GenericEvent, Priority::Routine)
+            .unwrap();
+        event_manager.begin_cycle();
+        assert!(event_manager.next_execution().is_some());
+    }
+
+    #[test]
+    fn test_event_manager_introspection() {
+        struct TestEvent(i32);
+        struct TestEventHigh;
+
+        impl Event for TestEvent {}
+        impl Event for TestEventHigh {}
+
+        let event_manager = EventManager::new();
+        assert!(event_manager.is_empty());
+        assert_eq!(event_manager.pending_count::<TestEvent>(), 0);
+        assert_eq!(event_manager.pending_priority::<TestEvent>(), None);
+        assert!(event_manager.peek::<TestEvent, _>(|_| ()).is_none());
+
+        // insert events
+        event_manager.emit(TestEvent(1));
+        event_manager.emit(TestEvent(2));
+        event_manager
+            .emit_priority(TestEventHigh, Priority::High)
+            .unwrap();
+
+        assert!(!event_manager.is_empty());
+        assert_eq!(event_manager.pending_count::<TestEvent>(), 2);
+        assert_eq!(
+            event_manager.pending_priority::<TestEvent>(),
+            Some(Priority::Normal)
+        );
+        assert_eq!(
+            event_manager.pending_types(),
+            vec![TypeId::of::<TestEventHigh>(), TypeId::of::<TestEvent>()]
+        );
+        assert_eq!(
+            event_manager.peek(|events: &[TestEvent]| events.len()),
+            Some(2)
+        );
+        assert_eq!(
+            event_manager.peek(|events: &[TestEvent]| events.last().map(|event| event.0)),
+            Some(Some(2))
+        );
+
+        // assert peeking does not drain
+        assert_eq!(event_manager.next_execution().unwrap().len(), 1);
+        assert_eq!(event_manager.pending_count::<TestEvent>(), 2);
+    }
+
+    #[test]
+    fn test_event_manager_retract() {
+        #[derive(Debug, PartialEq)]
+        struct SaveRequested(i32);
+        struct TestEventOther;
+
+        impl Event for SaveRequested {}
+        impl Event for TestEventOther {}
+
+        let event_manager = EventManager::new();
+        assert_eq!(event_manager.retract::<SaveRequested>(), None);
+
+        // insert events
+        event_manager.emit(SaveRequested(1));
+        event_manager.emit(TestEventOther);
+        event_manager.emit(SaveRequested(2));
+
+        // assert events are retracted
+        assert_eq!(
+            event_manager.retract::<SaveRequested>(),
+            Some(vec![SaveRequested(1), SaveRequested(2)])
+        );
+        assert_eq!(event_manager.pending_count::<SaveRequested>(), 0);
+        assert_eq!(
+            event_manager.pending_types(),
+            vec![TypeId::of::<TestEventOther>()]
+        );
+
+        // assert only remaining events are executed
+        let events = event_manager.next_execution().unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].0.event_type_id, TypeId::of::<TestEventOther>());
+        assert!(event_manager.next_execution().is_none());
+
+        // assert retracted events can be emitted again
+        event_manager.emit(SaveRequested(3));
+        assert_eq!(event_manager.pending_count::<SaveRequested>(), 1);
+    }
+
+    #[test]
+    fn test_event_manager_clear() {
+        struct TestEventHigh;
+        struct TestEventNormal;
+
+        impl Event for TestEventHigh {}
+        impl Event for TestEventNormal {}
+
+        let event_manager = EventManager::new();
+        event_manager
+            .emit_priority(TestEventHigh, Priority::High)
+            .unwrap();
+        event_manager.emit(TestEventNormal).unwrap();
+
+        // assert only the normal lane is cleared
+        event_manager.clear_priority(Priority::Normal);
+        assert_eq!(event_manager.pending_count::<TestEventNormal>(), 0);
+        assert_eq!(
+            event_manager.pending_types(),
+            vec![TypeId::of::<TestEventHigh>()]
+        );
+
+        // assert every lane is cleared
+        event_manager.emit(TestEventNormal).unwrap();
+        event_manager.clear();
+        assert!(event_manager.is_empty());
+        assert!(event_manager.next_execution().is_none());
+    }
+
+    #[test]
+    fn test_event_manager_drain_all() {
+        struct TestEventInterrupt;
+        struct TestEventRoutine;
+
+        impl Event for TestEventInterrupt {}
+        impl Event for TestEventRoutine {}
+
+        let event_manager = EventManager::new();
+        event_manager.set_budget(Priority::Routine, Budget::batches(0));
+        event_manager
+            .emit_priority(TestEventRoutine, Priority::Routine)
+            .unwrap();
+        event_manager
+            .emit_priority(TestEventInterrupt, Priority::Interrupt)
+            .unwrap();
+
+        // assert budgets are ignored and batches are in dispatch order
+        let batches = event_manager.drain_all();
+        assert_eq!(
+            batches
+                .iter()
+                .map(|(info, _)| (info.priority(), info.event_type_id()))
+                .collect::<Vec<_>>(),
+            vec![
+                (Priority::Interrupt, TypeId::of::<TestEventInterrupt>()),
+                (Priority::Routine, TypeId::of::<TestEventRoutine>()),
+            ]
+        );
+        assert!(batches[1].1.is::<Vec<TestEventRoutine>>());
+        assert!(event_manager.is_empty());
+        assert!(event_manager.drain_all().is_empty());
+    }
+
+    #[test]
+    fn test_event_manager_keyed_batches() {
+        #[derive(Debug, PartialEq)]
+        struct NetworkMessage {
+            connection: u32,
+            payload: u8,
+        }
+
+        impl Event for NetworkMessage {}
+        impl KeyedEvent for NetworkMessage {
+            type Key = u32;
+            fn batch_key(&self) -> u32 {
+                self.connection
+            }
+        }
+
+        let message = |connection, payload| NetworkMessage {
+            connection,
+            payload,
+        };
+
+        let event_manager = EventManager::new();
+        event_manager.emit_keyed(message(1, 0)).unwrap();
+        event_manager.emit_keyed(message(2, 1)).unwrap();
+        event_manager
+            .emit_keyed_priority(message(1, 2), Priority::High)
+            .unwrap();
+
+        // assert one batch per key in order of first appearance
+        let batches = event_manager.next_execution().unwrap();
+        let batches = batches
+            .iter()
+            .map(|(info, events)| {
+                assert_eq!(info.priority, Priority::High);
+                events.downcast_ref::<Vec<NetworkMessage>>().unwrap()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            batches,
+            vec![&vec![message(1, 0), message(1, 2)], &vec![message(2, 1)],]
+        );
+        assert!(event_manager.next_execution().is_none());
+    }
+
+    #[test]
+    fn test_event_manager_budget_keyed_batches() {
+        struct Moved(u32);
+        struct TestEventA;
+        struct TestEventB;
+
+        impl Event for Moved {}
+        impl KeyedEvent for Moved {
+            type Key = u32;
+            fn batch_key(&self) -> u32 {
+                self.0
+            }
+        }
+        impl Event for TestEventA {}
+        impl Event for TestEventB {}
+
+        let event_manager = EventManager::new();
+        event_manager.set_budget(Priority::Normal, Budget::batches(3));
+        event_manager.emit(TestEventA).unwrap();
+        event_manager.emit_keyed(Moved(1)).unwrap();
+        event_manager.emit_keyed(Moved(2)).unwrap();
+        event_manager.emit(TestEventB).unwrap();
+
+        // assert every batch split by key is charged to the budget
+        let events = event_manager.next_execution().unwrap();
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[0].0.event_type_id, TypeId::of::<TestEventA>());
+        assert!(event_manager.is_budget_exhausted(Priority::Normal));
+        assert!(event_manager.next_execution().is_none());
+
+        event_manager.begin_cycle();
+        let events = event_manager.next_execution().unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].0.event_type_id, TypeId::of::<TestEventB>());
+
+        // assert the batches of a single keyed event are yielded together
+        event_manager.begin_cycle();
+        event_manager.set_budget(Priority::Normal, Budget::batches(1));
+        event_manager.emit_keyed(Moved(1)).unwrap();
+        event_manager.emit_keyed(Moved(2)).unwrap();
+        event_manager.emit(TestEventA).unwrap();
+        assert_eq!(event_manager.next_execution().unwrap().len(), 2);
+        assert!(event_manager.next_execution().is_none());
+    }
+
+    #[test]
+    fn test_event_manager_stamps() {
+        struct TestEventA;
+        struct TestEventB;
+
+        impl Event for TestEventA {}
+        impl Event for TestEventB {}
+
+        let event_manager = EventManager::new();
+        event_manager.emit(TestEventA).unwrap();
+        event_manager.emit(TestEventB).unwrap();
+        event_manager.set_timestamps(true);
+        event_manager.emit(TestEventA).unwrap();
+        assert_eq!(event_manager.next_sequence(), 3);
+
+        // assert stamps are exposed alongside the batch, in event order
+        let batches = event_manager.next_execution().unwrap();
+        let stamps = batches
+            .iter()
+            .map(|(info, _)| {
+                info.stamps()
+                    .iter()
+                    .map(|stamp| (stamp.sequence(), stamp.timestamp().is_some()))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(stamps, vec![vec![(0, false), (2, true)], vec![(1, false)]]);
+    }
+
+    #[test]
+    fn test_event_manager_stamps_merge_and_partition() {
+        struct Moved(u32);
+
+        impl Event for Moved {}
+        impl KeyedEvent for Moved {
+            type Key = u32;
+            fn batch_key(&self) -> u32 {
+                self.0
+            }
+        }
+
+        let event_manager = EventManager::new();
+        event_manager.emit_or_merge(Moved(0), |_, _| ()).unwrap();
+        event_manager.emit_or_merge(Moved(0), |_, _| ()).unwrap();
+
+        // assert merged event takes the latest stamp
+        let batches = event_manager.drain_all();
+        assert_eq!(batches[0].0.stamps().len(), 1);
+        assert_eq!(batches[0].0.stamps()[0].sequence(), 1);
+
+        // assert stamps follow their partition
+        event_manager.emit_keyed(Moved(1)).unwrap();
+        event_manager.emit_keyed(Moved(2)).unwrap();
+        event_manager.emit_keyed(Moved(1)).unwrap();
+        let sequences = event_manager
+            .next_execution()
+            .unwrap()
+            .iter()
+            .map(|(info, _)| info.stamps().iter().map(|stamp| stamp.sequence()).collect())
+            .collect::<Vec<Vec<_>>>();
+        assert_eq!(sequences, vec![vec![2, 4], vec![3]]);
+    }
+
+    #[test]
+    fn test_event_manager_snapshot_restore() {
+        #[derive(Debug, Clone, PartialEq)]
+        struct TestEvent(u32);
+
+        impl Event for TestEvent {}
+
+        let event_manager = EventManager::new();
+        event_manager.register_snapshot::<TestEvent>();
+        event_manager.register_snapshot::<GenericEvent>();
+        event_manager.emit(TestEvent(0));
+        event_manager.emit_priority(GenericEvent, Priority::High);
+
+        let snapshot = event_manager.snapshot().unwrap();
+        assert_eq!(snapshot.len(), 2);
+
+        // diverge from the snapshot
+        event_manager.emit_priority(TestEvent(1), Priority::Interrupt);
+        event_manager.retract::<GenericEvent>();
+
+        // assert restore rolls back events, priorities and stamps
+        for _ in 0..2 {
+            event_manager.restore(&snapshot);
+            assert_eq!(
+                event_manager.pending_priority::<TestEvent>(),
+                Some(Priority::Normal)
+            );
+            assert_eq!(
+                event_manager.pending_priority::<GenericEvent>(),
+                Some(Priority::High)
+            );
+            assert_eq!(event_manager.next_sequence(), 2);
+
+            let batches = event_manager.drain_all();
+            assert_eq!(batches.len(), 2);
+            assert_eq!(batches[1].0.stamps()[0].sequence(), 0);
+            assert_eq!(
+                batches[1].1.downcast_ref::<Vec<TestEvent>>().unwrap(),
+                &vec![TestEvent(0)]
+            );
+        }
+    }
+
+    #[test]
+    fn test_event_manager_snapshot_unregistered() {
+        let event_manager = EventManager::new();
+        assert!(event_manager.snapshot().unwrap().is_empty());
+
+        event_manager.emit(GenericEvent);
+        assert_eq!(
+            event_manager.snapshot().unwrap_err(),
+            EmarkError::UnregisteredSnapshot(std::any::type_name::<GenericEvent>())
+        );
+    }
+
+    #[test]
+    fn test_event_manager_boost() {
+        struct TestEvent;
+
+        impl Event for TestEvent {}
+
+        let event_manager = EventManager::new();
+        assert!(!event_manager.boost::<GenericEvent>(Priority::High));
+
+        event_manager.emit_priority(GenericEvent, Priority::Routine);
+        event_manager.emit_priority(TestEvent, Priority::High);
+        assert!(event_manager.boost::<GenericEvent>(Priority::High));
+
+        // assert lower or equal priorities are not a boost
+        assert!(!event_manager.boost::<GenericEvent>(Priority::Normal));
+        assert!(!event_manager.boost::<GenericEvent>(Priority::High));
+
+        // assert boosted events are dispatched on their new lane
+        let batches = event_manager.next_execution().unwrap();
+        assert_eq!(batches.len(), 2);
+        assert_eq!(batches[1].0.event_type_id(), TypeId::of::<GenericEvent>());
+        assert_eq!(batches[1].0.priority(), Priority::High);
+        assert!(event_manager.is_empty());
+    }
+
+    #[test]
+    fn test_event_manager_pause_resume() {
+        struct TestEvent;
+
+        impl Event for TestEvent {}
+
+        let event_manager = EventManager::new();
+        event_manager.pause(Priority::Normal);
+        event_manager.pause(Priority::Routine);
+        assert!(event_manager.is_paused(Priority::Normal));
+        event_manager.emit(GenericEvent);
+        event_manager.emit_priority(TestEvent, Priority::Interrupt);
+
+        // assert only unpaused lanes are dispatched
+        let batches = event_manager.next_execution().unwrap();
+        assert_eq!(batches[0].0.event_type_id(), TypeId::of::<TestEvent>());
+        assert!(event_manager.next_execution().is_none());
+
+        // assert paused lanes accumulate events
+        event_manager.emit(GenericEvent);
+        assert_eq!(event_manager.pending_count::<GenericEvent>(), 2);
+
+        event_manager.resume(Priority::Normal);
+        let batches = event_manager.next_execution().unwrap();
+        assert_eq!(batches[0].0.stamps().len(), 2);
+        assert!(event_manager.is_paused(Priority::Routine));
+    }
+
     #[test]
     fn test_event_manager_next_box() {
         let event_manager = EventManager::new();
@@ -0,0 +1,281 @@
+use std::{fmt::Debug, marker::PhantomData};
+
+use super::Event;
+
+#[derive(Debug)]
+/// # Events
+///
+/// Double-buffered frame events, an alternative to the queue-and-consume model of
+/// the `EventManager` for UI and frame loops.
+///
+/// Events sent to `Events<T>` live for exactly two update cycles: the cycle they
+/// were sent in and the following one. `update` must be called once per cycle, which
+/// drops the events of the previous cycle and makes the current ones previous.
+///
+/// Events are not consumed when read. Instead, every reader keeps its own
+/// [EventCursor] tracking which events it has already read, so any number of
+/// readers observe every event as long as they read at least once per cycle.
+///
+/// # Examples
+/// ```
+/// use emark::event::frame::Events;
+/// use emark::prelude::Event;
+///
+/// struct Clicked(u32);
+/// impl Event for Clicked {}
+///
+/// let mut events = Events::new();
+/// let mut button = events.reader();
+/// let mut overlay = events.reader();
+///
+/// events.send(Clicked(1));
+/// assert_eq!(button.read(&events).map(|event| event.0).collect::<Vec<_>>(), vec![1]);
+///
+/// // events survive one update
+/// events.update();
+/// assert_eq!(overlay.read(&events).count(), 1);
+///
+/// // and are dropped on the next one
+/// events.update();
+/// assert_eq!(events.len(), 0);
+/// ```
+pub struct Events<T> {
+    previous: Vec<T>,
+    current: Vec<T>,
+    // id of the first event of the previous buffer
+    previous_start: usize,
+    // id of the first event of the current buffer
+    current_start: usize,
+    // id of the next event sent
+    event_count: usize,
+}
+
+impl<T> Default for Events<T> {
+    fn default() -> Self {
+        Self {
+            previous: Vec::new(),
+            current: Vec::new(),
+            previous_start: 0,
+            current_start: 0,
+            event_count: 0,
+        }
+    }
+}
+
+impl<T: Event> Events<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sends an event into the current buffer.
+    pub fn send(&mut self, event: T) {
+        self.current.push(event);
+        self.event_count += 1;
+    }
+
+    /// Sends every event of `events` into the current buffer.
+    pub fn send_batch(&mut self, events: impl IntoIterator<Item = T>) {
+        for event in events {
+            self.send(event);
+        }
+    }
+
+    /// Swaps the buffers, dropping the events of the previous cycle.
+    ///
+    /// Must be called exactly once per cycle.
+    pub fn update(&mut self) {
+        self.previous = std::mem::take(&mut self.current);
+        self.previous_start = self.current_start;
+        self.current_start = self.event_count;
+    }
+
+    /// Drops every buffered event.
+    ///
+    /// Cursors are not reset, they simply observe no event until the next one is sent.
+    pub fn clear(&mut self) {
+        self.previous.clear();
+        self.current.clear();
+        self.previous_start = self.event_count;
+        self.current_start = self.event_count;
+    }
+
+    /// Creates a cursor reading every buffered event, including those sent before its creation.
+    pub fn reader(&self) -> EventCursor<T> {
+        EventCursor {
+            next: self.previous_start,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Creates a cursor reading only the events sent after its creation.
+    pub fn reader_current(&self) -> EventCursor<T> {
+        EventCursor {
+            next: self.event_count,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Number of buffered events, across both buffers.
+    pub fn len(&self) -> usize {
+        self.previous.len() + self.current.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Iterates through the events sent in the current cycle.
+    pub fn iter_current(&self) -> impl Iterator<Item = &T> {
+        self.current.iter()
+    }
+
+    // get the event with id `id`, if still buffered
+    fn get(&self, id: usize) -> Option<&T> {
+        if id >= self.current_start {
+            self.current.get(id - self.current_start)
+        } else if id >= self.previous_start {
+            self.previous.get(id - self.previous_start)
+        } else {
+            None
+        }
+    }
+}
+
+/// Cursor of a reader of [Events].
+///
+/// Tracks the id of the next event to read, so every reader reads each event once.
+pub struct EventCursor<T> {
+    next: usize,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Clone for EventCursor<T> {
+    fn clone(&self) -> Self {
+        Self {
+            next: self.next,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T> Debug for EventCursor<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EventCursor")
+            .field("next", &self.next)
+            .finish()
+    }
+}
+
+impl<T: Event> EventCursor<T> {
+    /// Reads the events not yet read by this cursor, oldest first.
+    pub fn read<'a>(&mut self, events: &'a Events<T>) -> impl Iterator<Item = &'a T> + 'a {
+        let start = self.next.max(events.previous_start);
+        self.next = events.event_count;
+        (start..events.event_count).filter_map(move |id| events.get(id))
+    }
+
+    /// Number of events not yet read by this cursor.
+    pub fn len(&self, events: &Events<T>) -> usize {
+        events.event_count - self.next.max(events.previous_start)
+    }
+
+    pub fn is_empty(&self, events: &Events<T>) -> bool {
+        self.len(events) == 0
+    }
+
+    /// Number of events dropped before this cursor could read them.
+    pub fn missed(&self, events: &Events<T>) -> usize {
+        events.previous_start.saturating_sub(self.next)
+    }
+
+    /// Marks every buffered event as read.
+    pub fn clear(&mut self, events: &Events<T>) {
+        self.next = events.event_count;
+    }
+}
+
+#[cfg(test)]
+mod test_frame {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct TestEvent(usize);
+    impl Event for TestEvent {}
+
+    fn read(cursor: &mut EventCursor<TestEvent>, events: &Events<TestEvent>) -> Vec<usize> {
+        cursor.read(events).map(|event| event.0).collect()
+    }
+
+    #[test]
+    fn test_events_lifetime() {
+        let mut events = Events::new();
+        events.send(TestEvent(0));
+        assert_eq!(events.len(), 1);
+
+        events.update();
+        events.send(TestEvent(1));
+        assert_eq!(events.len(), 2);
+        assert_eq!(
+            events.iter_current().collect::<Vec<_>>(),
+            vec![&TestEvent(1)]
+        );
+
+        // assert events of two cycles ago are dropped
+        events.update();
+        assert_eq!(events.len(), 1);
+        events.update();
+        assert!(events.is_empty());
+    }
+
+    #[test]
+    fn test_events_independent_readers() {
+        let mut events = Events::new();
+        let mut first = events.reader();
+        events.send_batch([TestEvent(0), TestEvent(1)]);
+        let mut second = events.reader_current();
+
+        assert_eq!(first.len(&events), 2);
+        assert_eq!(read(&mut first, &events), vec![0, 1]);
+        assert!(first.is_empty(&events));
+        assert!(second.is_empty(&events));
+
+        events.update();
+        events.send(TestEvent(2));
+
+        // assert each reader only sees unread events
+        assert_eq!(read(&mut first, &events), vec![2]);
+        assert_eq!(read(&mut second, &events), vec![2]);
+        assert_eq!(events.reader().len(&events), 3);
+    }
+
+    #[test]
+    fn test_events_missed() {
+        let mut events = Events::new();
+        let mut cursor = events.reader();
+        events.send(TestEvent(0));
+        events.update();
+        events.update();
+        events.send(TestEvent(1));
+
+        assert_eq!(cursor.missed(&events), 1);
+        assert_eq!(read(&mut cursor, &events), vec![1]);
+        assert_eq!(cursor.missed(&events), 0);
+    }
+
+    #[test]
+    fn test_events_clear() {
+        let mut events = Events::new();
+        let mut cursor = events.reader();
+        events.send(TestEvent(0));
+        events.clear();
+        assert!(events.is_empty());
+        assert!(cursor.is_empty(&events));
+
+        events.send(TestEvent(1));
+        assert_eq!(read(&mut cursor, &events), vec![1]);
+
+        cursor.clear(&events);
+        events.send(TestEvent(2));
+        assert_eq!(read(&mut cursor, &events), vec![2]);
+    }
+}
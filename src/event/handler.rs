@@ -0,0 +1,1782 @@
+use std::{
+    any::{Any, TypeId},
+    collections::{HashMap, HashSet, VecDeque},
+    error::Error,
+    fmt::{Debug, Display},
+    panic::{self, AssertUnwindSafe},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+#[cfg(feature = "async")]
+use std::{future::Future, pin::Pin, task::Poll};
+
+use parking_lot::{Condvar, Mutex};
+
+use crate::{
+    store::{Context, DeferredEmissions, ReadContext, ResourceContainer},
+    system::{Access, ThreadPool},
+    utils::{error::EmarkError, lock::GrainedLock},
+};
+
+use super::{priority::Priority, stamp::EventStamp, EmittedEventInfo, Event, EventManager};
+
+/// Boxed error returned by a fallible handler.
+pub type HandlerError = Box<dyn Error + Send + Sync>;
+
+/// Result of a handler.
+///
+/// Handlers may either return `()` or `Result<(), E>` where `E` is an error.
+/// Errors are converted into a [HandlerErrorEvent] by the dispatch layer.
+pub trait HandlerResult {
+    fn into_result(self) -> Result<(), HandlerError>;
+}
+
+impl HandlerResult for () {
+    fn into_result(self) -> Result<(), HandlerError> {
+        Ok(())
+    }
+}
+
+impl<E: Error + Send + Sync + 'static> HandlerResult for Result<(), E> {
+    fn into_result(self) -> Result<(), HandlerError> {
+        self.map_err(|error| Box::new(error) as HandlerError)
+    }
+}
+
+#[derive(Debug)]
+/// Event emitted when a handler returns an error.
+///
+/// The event is emitted with `High` priority, carrying the type name of the
+/// event being handled, the type name of the handler and the boxed error.
+/// Errors returned by handlers of `HandlerErrorEvent` itself are not emitted
+/// again to avoid feedback loops.
+pub struct HandlerErrorEvent {
+    event_type_name: &'static str,
+    handler_type_name: &'static str,
+    error: HandlerError,
+}
+
+impl Event for HandlerErrorEvent {}
+
+impl HandlerErrorEvent {
+    /// Type name of the event whose handler failed.
+    pub fn event_type_name(&self) -> &'static str {
+        self.event_type_name
+    }
+
+    /// Type name of the handler that failed.
+    pub fn handler_type_name(&self) -> &'static str {
+        self.handler_type_name
+    }
+
+    pub fn error(&self) -> &(dyn Error + Send + Sync + 'static) {
+        self.error.as_ref()
+    }
+
+    pub fn into_error(self) -> HandlerError {
+        self.error
+    }
+}
+
+impl Display for HandlerErrorEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "handler `{}` of `{}` failed: {}",
+            self.handler_type_name, self.event_type_name, self.error
+        )
+    }
+}
+
+// batch dispatched to a handler, the container being only known to `dispatch_with`
+struct BatchScope<'a> {
+    info: &'a EmittedEventInfo,
+    container: Option<&'a ResourceContainer>,
+    event_manager: &'a EventManager,
+    // events emitted through the `Context` of the handlers
+    deferred: DeferredEmissions,
+}
+
+impl<'a> BatchScope<'a> {
+    fn new(
+        info: &'a EmittedEventInfo,
+        container: Option<&'a ResourceContainer>,
+        event_manager: &'a EventManager,
+    ) -> Self {
+        Self {
+            info,
+            container,
+            event_manager,
+            deferred: DeferredEmissions::default(),
+        }
+    }
+
+    // emits the events deferred by the handlers of the batch
+    fn finish(self) {
+        self.deferred.flush(self.event_manager);
+    }
+}
+
+type BoxedHandler = Box<
+    dyn Fn(&BatchScope, &(dyn Any + Send + Sync)) -> Result<(), HandlerError> + Send + Sync,
+>;
+
+#[cfg(feature = "async")]
+type HandlerFuture<'a> = Pin<Box<dyn Future<Output = Result<(), HandlerError>> + 'a>>;
+
+#[cfg(feature = "async")]
+type BoxedAsyncHandler = Box<
+    dyn for<'a> Fn(&'a BatchScope<'_>, &'a (dyn Any + Send + Sync)) -> HandlerFuture<'a>
+        + Send
+        + Sync,
+>;
+
+// type-erased handler, async handlers returning the future of their result
+enum ErasedHandler {
+    Sync(BoxedHandler),
+    #[cfg(feature = "async")]
+    Async(BoxedAsyncHandler),
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+/// Registration options of a handler.
+///
+/// Handlers of the same event type are executed by increasing `order`, then in
+/// registration order. A handler may be given a label, which other handlers of the
+/// same event type can reference to be executed before or after it. Labels
+/// constrain the execution order regardless of `order`.
+///
+/// A handler may also belong to a group, which can be disabled at runtime on the
+/// [HandlerRegistry].
+///
+/// # Examples
+/// ```
+/// use emark::event::{HandlerOptions, HandlerRegistry};
+/// use emark::prelude::Event;
+///
+/// struct Tick;
+/// impl Event for Tick {}
+///
+/// let mut registry = HandlerRegistry::new();
+/// registry
+///     .add_handler_with(|_: &[Tick]| {}, HandlerOptions::labeled("render").with_after("physics"))
+///     .unwrap();
+/// registry
+///     .add_handler_with(|_: &[Tick]| {}, HandlerOptions::labeled("physics"))
+///     .unwrap();
+///
+/// // assert cyclic orders are rejected
+/// assert!(registry
+///     .add_handler_with(|_: &[Tick]| {}, HandlerOptions::new().with_before("physics").with_after("render"))
+///     .is_err());
+/// ```
+pub struct HandlerOptions {
+    order: i32,
+    label: Option<&'static str>,
+    before: Vec<&'static str>,
+    after: Vec<&'static str>,
+    group: Option<&'static str>,
+    access: Option<Access>,
+}
+
+impl HandlerOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Options with the execution order `order`.
+    pub fn ordered(order: i32) -> Self {
+        Self::new().with_order(order)
+    }
+
+    /// Options with the label `label`.
+    pub fn labeled(label: &'static str) -> Self {
+        Self::new().with_label(label)
+    }
+
+    /// Sets the execution order, lower orders are executed first.
+    pub fn with_order(mut self, order: i32) -> Self {
+        self.order = order;
+        self
+    }
+
+    /// Sets the label of the handler.
+    pub fn with_label(mut self, label: &'static str) -> Self {
+        self.label = Some(label);
+        self
+    }
+
+    /// Options with the group `group`.
+    pub fn grouped(group: &'static str) -> Self {
+        Self::new().with_group(group)
+    }
+
+    /// Sets the group of the handler.
+    pub fn with_group(mut self, group: &'static str) -> Self {
+        self.group = Some(group);
+        self
+    }
+
+    /// Declares the resources accessed by the handler.
+    ///
+    /// Handlers without declared access are exclusive, their batches are never dispatched
+    /// concurrently with other batches, see `HandlerRegistry::set_thread_pool`.
+    pub fn with_access(mut self, access: Access) -> Self {
+        self.access = Some(access);
+        self
+    }
+
+    /// Executes the handler before the handlers labeled `label`.
+    pub fn with_before(mut self, label: &'static str) -> Self {
+        self.before.push(label);
+        self
+    }
+
+    /// Executes the handler after the handlers labeled `label`.
+    pub fn with_after(mut self, label: &'static str) -> Self {
+        self.after.push(label);
+        self
+    }
+
+    // true if the handler must be executed before the handler with options `other`
+    fn precedes(&self, other: &HandlerOptions) -> bool {
+        let before = |options: &HandlerOptions, label: Option<&'static str>| {
+            label.is_some_and(|label| options.before.contains(&label))
+        };
+        let after = |options: &HandlerOptions, label: Option<&'static str>| {
+            label.is_some_and(|label| options.after.contains(&label))
+        };
+        before(self, other.label) || after(other, self.label)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Wall-clock time spent by a handler on a batch, recorded while profiling.
+pub struct HandlerTiming {
+    event_type_name: &'static str,
+    handler_type_name: &'static str,
+    duration: Duration,
+}
+
+impl HandlerTiming {
+    /// Type name of the event of the batch.
+    pub fn event_type_name(&self) -> &'static str {
+        self.event_type_name
+    }
+
+    /// Type name of the handler.
+    pub fn handler_type_name(&self) -> &'static str {
+        self.handler_type_name
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// Handle of a handler registered in a [HandlerRegistry], used to remove or replace it.
+pub struct HandlerId {
+    event_type_id: TypeId,
+    index: usize,
+}
+
+struct RegisteredHandler {
+    event_type_name: &'static str,
+    handler_type_name: &'static str,
+    handler: ErasedHandler,
+    options: HandlerOptions,
+    // registration index, breaks ties between equal orders
+    index: usize,
+}
+
+// sort handlers by their options, handlers are returned unsorted if their order is cyclic
+fn sort_handlers(
+    handlers: Vec<RegisteredHandler>,
+) -> Result<Vec<RegisteredHandler>, Vec<RegisteredHandler>> {
+    // count the handlers preceding each handler
+    let mut preceding = handlers
+        .iter()
+        .map(|handler| {
+            handlers
+                .iter()
+                .filter(|other| other.options.precedes(&handler.options))
+                .count()
+        })
+        .collect::<Vec<_>>();
+
+    // repeatedly take the lowest ordered handler without preceding handlers
+    let mut sorted = Vec::with_capacity(handlers.len());
+    let mut taken = vec![false; handlers.len()];
+    while sorted.len() < handlers.len() {
+        let Some(next) = (0..handlers.len())
+            .filter(|&i| !taken[i] && preceding[i] == 0)
+            .min_by_key(|&i| (handlers[i].options.order, handlers[i].index))
+        else {
+            return Err(handlers);
+        };
+
+        taken[next] = true;
+        sorted.push(next);
+        for (i, handler) in handlers.iter().enumerate() {
+            if !taken[i] && handlers[next].options.precedes(&handler.options) {
+                preceding[i] -= 1;
+            }
+        }
+    }
+
+    // reorder handlers
+    let mut handlers = handlers.into_iter().map(Some).collect::<Vec<_>>();
+    Ok(sorted
+        .into_iter()
+        .map(|i| handlers[i].take().unwrap())
+        .collect())
+}
+
+#[derive(Default)]
+/// # HandlerRegistry
+///
+/// The `HandlerRegistry` holds the handlers of each event type and dispatches
+/// the batches yielded by the `EventManager` to them.
+///
+/// A handler receives the whole batch of events of its type as a slice. Handlers
+/// may be fallible by returning `Result<(), E>`; an error does not interrupt the
+/// dispatch, instead it is emitted as a [HandlerErrorEvent].
+///
+/// # Examples
+/// ```
+/// use emark::event::{EventManager, HandlerErrorEvent, HandlerRegistry};
+/// use emark::prelude::Event;
+///
+/// struct Ping;
+/// impl Event for Ping {}
+///
+/// let mut registry = HandlerRegistry::new();
+/// registry.add_handler(|pings: &[Ping]| {
+///     assert_eq!(pings.len(), 2);
+/// });
+/// registry.add_handler(|_: &[Ping]| "pong".parse::<i32>().map(|_| ()));
+/// registry.add_handler(|errors: &[HandlerErrorEvent]| {
+///     assert_eq!(errors.len(), 1);
+/// });
+///
+/// let event_manager = EventManager::new();
+/// event_manager.emit(Ping);
+/// event_manager.emit(Ping);
+/// registry.dispatch(&event_manager);
+/// ```
+pub struct HandlerRegistry {
+    handlers: HashMap<TypeId, Vec<RegisteredHandler>>,
+    registered: usize,
+    disabled_groups: GrainedLock<HashSet<&'static str>>,
+    profiling: AtomicBool,
+    timings: GrainedLock<Vec<HandlerTiming>>,
+    pool: Option<Arc<dyn ThreadPool>>,
+}
+
+// progress of a parallel dispatch of the batches of a lane
+struct Progress {
+    // batches ready to be dispatched, one queue per worker
+    queues: Vec<VecDeque<usize>>,
+    remaining: Vec<usize>,
+    finished: usize,
+    panic: Option<Box<dyn Any + Send>>,
+}
+
+impl Progress {
+    // pop a batch of the worker, stealing the most recent batch of another worker if
+    // its own queue is empty
+    fn next(&mut self, worker: usize) -> Option<usize> {
+        if let Some(batch) = self.queues[worker].pop_front() {
+            return Some(batch);
+        }
+        let count = self.queues.len();
+        (1..count).find_map(|offset| self.queues[(worker + offset) % count].pop_back())
+    }
+}
+
+impl HandlerRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a handler of event `T`.
+    ///
+    /// Handlers of the same event type registered with default options are executed
+    /// in registration order.
+    pub fn add_handler<T, R, F>(&mut self, handler: F) -> HandlerId
+    where
+        T: Event + Send + Sync + 'static,
+        R: HandlerResult,
+        F: Fn(&[T]) -> R + Send + Sync + 'static,
+    {
+        // handlers without labels can not form a cycle
+        self.add_handler_with(handler, HandlerOptions::default())
+            .unwrap()
+    }
+
+    /// Registers a handler of event `T` with the specified options.
+    ///
+    /// Returns an error, leaving the registry unchanged, if the options make the
+    /// execution order of the handlers of `T` cyclic.
+    pub fn add_handler_with<T, R, F>(
+        &mut self,
+        handler: F,
+        options: HandlerOptions,
+    ) -> Result<HandlerId, EmarkError>
+    where
+        T: Event + Send + Sync + 'static,
+        R: HandlerResult,
+        F: Fn(&[T]) -> R + Send + Sync + 'static,
+    {
+        // erase the handler type
+        let handler: BoxedHandler = Box::new(move |_, events| {
+            let events = events.downcast_ref::<Vec<T>>().unwrap();
+            handler(events).into_result()
+        });
+
+        self.insert_handler::<T, F>(handler, options)
+    }
+
+    /// Registers a handler of event `T` only receiving the events matching `filter`.
+    ///
+    /// The batch is filtered once before calling the handler, which receives the
+    /// matching events in emission order. The handler is not called if no event matches.
+    pub fn add_filtered_handler<T, R, P, F>(&mut self, filter: P, handler: F) -> HandlerId
+    where
+        T: Event + Send + Sync + 'static,
+        R: HandlerResult,
+        P: Fn(&T) -> bool + Send + Sync + 'static,
+        F: Fn(&[&T]) -> R + Send + Sync + 'static,
+    {
+        // handlers without labels can not form a cycle
+        self.add_filtered_handler_with(filter, handler, HandlerOptions::default())
+            .unwrap()
+    }
+
+    /// Registers a filtered handler of event `T` with the specified options.
+    ///
+    /// See `add_filtered_handler` and `add_handler_with`.
+    pub fn add_filtered_handler_with<T, R, P, F>(
+        &mut self,
+        filter: P,
+        handler: F,
+        options: HandlerOptions,
+    ) -> Result<HandlerId, EmarkError>
+    where
+        T: Event + Send + Sync + 'static,
+        R: HandlerResult,
+        P: Fn(&T) -> bool + Send + Sync + 'static,
+        F: Fn(&[&T]) -> R + Send + Sync + 'static,
+    {
+        // erase the handler type
+        let handler: BoxedHandler = Box::new(move |_, events| {
+            let events = events.downcast_ref::<Vec<T>>().unwrap();
+
+            // slice matching events
+            let events = events
+                .iter()
+                .filter(|event| filter(event))
+                .collect::<Vec<_>>();
+            if events.is_empty() {
+                return Ok(());
+            }
+            handler(&events).into_result()
+        });
+
+        self.insert_handler::<T, F>(handler, options)
+    }
+
+    /// Registers a handler of event `T` receiving the stamps of the batch.
+    ///
+    /// Stamps are in the same order as the events, see [EventStamp].
+    pub fn add_stamped_handler<T, R, F>(&mut self, handler: F) -> HandlerId
+    where
+        T: Event + Send + Sync + 'static,
+        R: HandlerResult,
+        F: Fn(&[T], &[EventStamp]) -> R + Send + Sync + 'static,
+    {
+        // handlers without labels can not form a cycle
+        self.add_stamped_handler_with(handler, HandlerOptions::default())
+            .unwrap()
+    }
+
+    /// Registers a handler of event `T` receiving the stamps of the batch with the specified
+    /// options, see `add_stamped_handler` and `add_handler_with`.
+    pub fn add_stamped_handler_with<T, R, F>(
+        &mut self,
+        handler: F,
+        options: HandlerOptions,
+    ) -> Result<HandlerId, EmarkError>
+    where
+        T: Event + Send + Sync + 'static,
+        R: HandlerResult,
+        F: Fn(&[T], &[EventStamp]) -> R + Send + Sync + 'static,
+    {
+        // erase the handler type
+        let handler: BoxedHandler = Box::new(move |scope, events| {
+            let events = events.downcast_ref::<Vec<T>>().unwrap();
+            handler(events, scope.info.stamps()).into_result()
+        });
+
+        self.insert_handler::<T, F>(handler, options)
+    }
+
+    /// Registers a handler of event `T` receiving the [Context] of the dispatch, giving it
+    /// access to the resources of the container, the event emitter, a [Commands](crate::store::Commands)
+    /// buffer and the metadata of the batch.
+    ///
+    /// This is the handler-facing API of the application, the container being the one given
+    /// to `dispatch_with`. Dispatching with `dispatch` makes the handler fail with an error.
+    pub fn add_context_handler<T, R, F>(&mut self, handler: F) -> HandlerId
+    where
+        T: Event + Send + Sync + 'static,
+        R: HandlerResult,
+        F: Fn(&[T], &Context) -> R + Send + Sync + 'static,
+    {
+        // handlers without labels can not form a cycle
+        self.add_context_handler_with(handler, HandlerOptions::default())
+            .unwrap()
+    }
+
+    /// Registers a handler of event `T` receiving the [Context] of the dispatch with the
+    /// specified options, see `add_context_handler` and `add_handler_with`.
+    pub fn add_context_handler_with<T, R, F>(
+        &mut self,
+        handler: F,
+        options: HandlerOptions,
+    ) -> Result<HandlerId, EmarkError>
+    where
+        T: Event + Send + Sync + 'static,
+        R: HandlerResult,
+        F: Fn(&[T], &Context) -> R + Send + Sync + 'static,
+    {
+        // erase the handler type
+        let handler: BoxedHandler = Box::new(move |scope, events| {
+            let Some(container) = scope.container else {
+                let event_type_name = std::any::type_name::<T>();
+                return Err(Box::new(EmarkError::MissingDispatchContainer(
+                    event_type_name,
+                )));
+            };
+            let events = events.downcast_ref::<Vec<T>>().unwrap();
+            let context = Context::new(container, scope.event_manager, scope.info, &scope.deferred);
+            handler(events, &context).into_result()
+        });
+
+        self.insert_handler::<T, F>(handler, options)
+    }
+
+    /// Registers a handler of event `T` receiving a [ReadContext], only able to read the
+    /// resources of the container and the pending events.
+    ///
+    /// Unless its options declare another access, the handler reads every resource, so the
+    /// batches only handled by read-only handlers are dispatched concurrently on the thread
+    /// pool without ordering them, see `set_thread_pool`.
+    pub fn add_read_handler<T, R, F>(&mut self, handler: F) -> HandlerId
+    where
+        T: Event + Send + Sync + 'static,
+        R: HandlerResult,
+        F: Fn(&[T], &ReadContext) -> R + Send + Sync + 'static,
+    {
+        // handlers without labels can not form a cycle
+        self.add_read_handler_with(handler, HandlerOptions::default())
+            .unwrap()
+    }
+
+    /// Registers a handler of event `T` receiving a [ReadContext] with the specified options,
+    /// see `add_read_handler` and `add_handler_with`.
+    pub fn add_read_handler_with<T, R, F>(
+        &mut self,
+        handler: F,
+        mut options: HandlerOptions,
+    ) -> Result<HandlerId, EmarkError>
+    where
+        T: Event + Send + Sync + 'static,
+        R: HandlerResult,
+        F: Fn(&[T], &ReadContext) -> R + Send + Sync + 'static,
+    {
+        options
+            .access
+            .get_or_insert_with(|| Access::new().with_read_all());
+
+        // erase the handler type
+        let handler: BoxedHandler = Box::new(move |scope, events| {
+            let Some(container) = scope.container else {
+                let event_type_name = std::any::type_name::<T>();
+                return Err(Box::new(EmarkError::MissingDispatchContainer(
+                    event_type_name,
+                )));
+            };
+            let events = events.downcast_ref::<Vec<T>>().unwrap();
+            let context = ReadContext::new(container, scope.event_manager, scope.info);
+            handler(events, &context).into_result()
+        });
+
+        self.insert_handler::<T, F>(handler, options)
+    }
+
+    /// Registers an async handler of event `T`.
+    ///
+    /// The future of the handler is awaited during the dispatch without depending on a
+    /// particular async runtime. Without a thread pool, the futures of the batches of a lane
+    /// whose accesses do not conflict are awaited concurrently on the dispatching thread, so
+    /// that a pending handler does not hold back the other batches, see `add_handler_with`
+    /// to declare the access of the handler. With a thread pool, the future is awaited on the
+    /// worker dispatching its batch.
+    #[cfg(feature = "async")]
+    pub fn add_async_handler<T, R, F>(&mut self, handler: F) -> HandlerId
+    where
+        T: Event + Send + Sync + 'static,
+        R: HandlerResult,
+        F: AsyncFn(&[T]) -> R + Send + Sync + 'static,
+    {
+        // handlers without labels can not form a cycle
+        self.add_async_handler_with(handler, HandlerOptions::default())
+            .unwrap()
+    }
+
+    /// Registers an async handler of event `T` with the specified options, see
+    /// `add_async_handler` and `add_handler_with`.
+    #[cfg(feature = "async")]
+    pub fn add_async_handler_with<T, R, F>(
+        &mut self,
+        handler: F,
+        options: HandlerOptions,
+    ) -> Result<HandlerId, EmarkError>
+    where
+        T: Event + Send + Sync + 'static,
+        R: HandlerResult,
+        F: AsyncFn(&[T]) -> R + Send + Sync + 'static,
+    {
+        // erase the handler type, the future keeping the handler alive
+        let handler = Arc::new(handler);
+        let handler: BoxedAsyncHandler = Box::new(move |_, events| {
+            let events = events.downcast_ref::<Vec<T>>().unwrap();
+            let handler = handler.clone();
+            Box::pin(async move { handler(events).await.into_result() })
+        });
+
+        self.insert_erased::<T, F>(ErasedHandler::Async(handler), options)
+    }
+
+    /// Removes the handler `id`.
+    ///
+    /// The handler stops receiving events starting with the next dispatch. Returns `true` if
+    /// the handler was registered.
+    pub fn remove_handler(&mut self, id: HandlerId) -> bool {
+        let Some(handlers) = self.handlers.get_mut(&id.event_type_id) else {
+            return false;
+        };
+        let count = handlers.len();
+
+        // removing a handler keeps the remaining handlers sorted
+        handlers.retain(|handler| handler.index != id.index);
+        let removed = handlers.len() < count;
+        if handlers.is_empty() {
+            self.handlers.remove(&id.event_type_id);
+        }
+        removed
+    }
+
+    /// Replaces the handler `id` of event `T` by `handler`, keeping its options.
+    ///
+    /// Returns `false`, dropping `handler`, if `id` is not a registered handler of `T`.
+    pub fn replace_handler<T, R, F>(&mut self, id: HandlerId, handler: F) -> bool
+    where
+        T: Event + Send + Sync + 'static,
+        R: HandlerResult,
+        F: Fn(&[T]) -> R + Send + Sync + 'static,
+    {
+        if id.event_type_id != TypeId::of::<T>() {
+            return false;
+        }
+        let Some(registered) = self
+            .handlers
+            .get_mut(&id.event_type_id)
+            .and_then(|handlers| {
+                handlers
+                    .iter_mut()
+                    .find(|handler| handler.index == id.index)
+            })
+        else {
+            return false;
+        };
+
+        registered.handler = ErasedHandler::Sync(Box::new(move |_, events| {
+            let events = events.downcast_ref::<Vec<T>>().unwrap();
+            handler(events).into_result()
+        }));
+        registered.handler_type_name = std::any::type_name::<F>();
+        true
+    }
+
+    /// Returns `true` if the handler `id` is registered.
+    pub fn contains_handler_id(&self, id: HandlerId) -> bool {
+        self.handlers
+            .get(&id.event_type_id)
+            .is_some_and(|handlers| handlers.iter().any(|handler| handler.index == id.index))
+    }
+
+    fn insert_handler<T: 'static, F>(
+        &mut self,
+        handler: BoxedHandler,
+        options: HandlerOptions,
+    ) -> Result<HandlerId, EmarkError> {
+        self.insert_erased::<T, F>(ErasedHandler::Sync(handler), options)
+    }
+
+    // insert a handler and sort the handlers of its event type
+    fn insert_erased<T: 'static, F>(
+        &mut self,
+        handler: ErasedHandler,
+        options: HandlerOptions,
+    ) -> Result<HandlerId, EmarkError> {
+        let event_type_name = std::any::type_name::<T>();
+        let handlers = self.handlers.entry(TypeId::of::<T>()).or_default();
+        let mut unsorted = std::mem::take(handlers);
+        unsorted.push(RegisteredHandler {
+            event_type_name,
+            handler_type_name: std::any::type_name::<F>(),
+            handler,
+            options,
+            index: self.registered,
+        });
+
+        match sort_handlers(unsorted) {
+            Ok(sorted) => {
+                *handlers = sorted;
+                let id = HandlerId {
+                    event_type_id: TypeId::of::<T>(),
+                    index: self.registered,
+                };
+                self.registered += 1;
+                Ok(id)
+            }
+            Err(mut unsorted) => {
+                // roll back insertion, the remaining handlers are still sorted
+                unsorted.pop();
+                *handlers = unsorted;
+                if handlers.is_empty() {
+                    self.handlers.remove(&TypeId::of::<T>());
+                }
+                Err(EmarkError::CyclicHandlerOrder(event_type_name))
+            }
+        }
+    }
+
+    /// Returns `true` if at least one handler of event `T` is registered.
+    pub fn contains_handler<T: Event + 'static>(&self) -> bool {
+        self.handlers.contains_key(&TypeId::of::<T>())
+    }
+
+    /// Disables the handlers of group `group`.
+    ///
+    /// Disabled handlers are skipped by dispatch, starting with the next batch.
+    /// Returns `true` if the group was enabled.
+    pub fn disable_group(&self, group: &'static str) -> bool {
+        self.disabled_groups.borrow_mut().insert(group)
+    }
+
+    /// Enables the handlers of group `group`.
+    ///
+    /// Returns `true` if the group was disabled.
+    pub fn enable_group(&self, group: &'static str) -> bool {
+        self.disabled_groups.borrow_mut().remove(group)
+    }
+
+    /// Returns `true` if the handlers of group `group` are dispatched.
+    pub fn is_group_enabled(&self, group: &str) -> bool {
+        !self.disabled_groups.borrow().contains(group)
+    }
+
+    /// Enables or disables the recording of the time spent by each handler on each batch.
+    pub fn set_profiling(&self, enabled: bool) {
+        self.profiling.store(enabled, Ordering::Release);
+    }
+
+    pub fn is_profiling(&self) -> bool {
+        self.profiling.load(Ordering::Acquire)
+    }
+
+    /// Dispatches the independent batches of a priority lane concurrently on `pool`.
+    ///
+    /// Each batch accesses the resources declared by the `HandlerOptions` of its handlers.
+    /// Batches whose accesses conflict are dispatched one after the other in the order of the
+    /// lane, while the others are spread across the workers of the pool, idle workers stealing
+    /// batches queued on busy workers.
+    pub fn set_thread_pool(&mut self, pool: impl ThreadPool + 'static) {
+        self.pool = Some(Arc::new(pool));
+    }
+
+    /// Dispatches every batch on the dispatching thread.
+    pub fn clear_thread_pool(&mut self) {
+        self.pool = None;
+    }
+
+    /// Takes the timings recorded while profiling, in dispatch order.
+    pub fn take_timings(&self) -> Vec<HandlerTiming> {
+        std::mem::take(&mut *self.timings.borrow_mut())
+    }
+
+    /// Dispatches the events of the `EventManager` to the registered handlers.
+    ///
+    /// Starts a new dispatch cycle and executes batches until the `EventManager`
+    /// has no more events available or the budget of the next priority lane has
+    /// been exhausted. Events without handlers are discarded.
+    ///
+    /// Returns the number of batches dispatched.
+    pub fn dispatch(&self, event_manager: &EventManager) -> usize {
+        self.dispatch_in(None, event_manager)
+    }
+
+    /// Dispatches the events of the `EventManager` to the registered handlers, the handlers
+    /// registered with `add_context_handler` accessing the resources of `container`.
+    ///
+    /// See `dispatch`. The [Commands](crate::store::Commands) recorded by the handlers are
+    /// left for the caller to apply.
+    pub fn dispatch_with(
+        &self,
+        container: &ResourceContainer,
+        event_manager: &EventManager,
+    ) -> usize {
+        self.dispatch_in(Some(container), event_manager)
+    }
+
+    fn dispatch_in(
+        &self,
+        container: Option<&ResourceContainer>,
+        event_manager: &EventManager,
+    ) -> usize {
+        event_manager.begin_cycle();
+
+        let profiling = self.is_profiling();
+        let mut dispatched = 0;
+        while let Some(executions) = event_manager.next_execution() {
+            dispatched += executions.len();
+            match &self.pool {
+                Some(pool) if executions.len() > 1 => self.dispatch_parallel(
+                    container,
+                    event_manager,
+                    &executions,
+                    pool.as_ref(),
+                    profiling,
+                ),
+                #[cfg(feature = "async")]
+                None if executions.len() > 1 && self.has_async_handlers(&executions) => {
+                    self.dispatch_joined(container, event_manager, &executions, profiling)
+                }
+                _ => {
+                    for (info, events) in &executions {
+                        let scope = BatchScope::new(info, container, event_manager);
+                        self.dispatch_batch(&scope, events.as_ref(), profiling);
+                        scope.finish();
+                    }
+                }
+            }
+        }
+
+        dispatched
+    }
+
+    // dispatch the batches of a lane on the workers of `pool`
+    fn dispatch_parallel(
+        &self,
+        container: Option<&ResourceContainer>,
+        event_manager: &EventManager,
+        executions: &[(EmittedEventInfo, Box<dyn Any + Send + Sync>)],
+        pool: &dyn ThreadPool,
+        profiling: bool,
+    ) {
+        let count = executions.len();
+        let workers = pool.threads().clamp(1, count);
+        let scopes = executions
+            .iter()
+            .map(|(info, _)| BatchScope::new(info, container, event_manager))
+            .collect::<Vec<_>>();
+        let (dependents, remaining) = self.batch_dependencies(executions);
+
+        // spread the initially ready batches across the workers
+        let mut queues = vec![VecDeque::new(); workers];
+        (0..count)
+            .filter(|&batch| remaining[batch] == 0)
+            .enumerate()
+            .for_each(|(index, batch)| queues[index % workers].push_back(batch));
+        let progress = Mutex::new(Progress {
+            queues,
+            remaining,
+            finished: 0,
+            panic: None,
+        });
+        let condvar = Condvar::new();
+        let next_worker = Mutex::new(0);
+
+        let worker = || {
+            let worker = {
+                let mut next_worker = next_worker.lock();
+                *next_worker += 1;
+                (*next_worker - 1) % workers
+            };
+            loop {
+                // wait for a ready batch
+                let batch = {
+                    let mut progress = progress.lock();
+                    loop {
+                        if progress.finished == count {
+                            return;
+                        }
+                        if let Some(batch) = progress.next(worker) {
+                            break batch;
+                        }
+                        condvar.wait(&mut progress);
+                    }
+                };
+
+                // dispatch batch, keeping the other workers alive if a handler panics
+                let (_, events) = &executions[batch];
+                let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                    self.dispatch_batch(&scopes[batch], events.as_ref(), profiling)
+                }));
+
+                // release dependents on the queue of this worker
+                let mut progress = progress.lock();
+                progress.finished += 1;
+                if let Err(payload) = result {
+                    progress.panic.get_or_insert(payload);
+                }
+                for &dependent in &dependents[batch] {
+                    progress.remaining[dependent] -= 1;
+                    if progress.remaining[dependent] == 0 {
+                        progress.queues[worker].push_back(dependent);
+                    }
+                }
+                condvar.notify_all();
+            }
+        };
+        pool.run_workers(workers, &worker);
+
+        // flush the deferred events in the order of the lane
+        for scope in scopes {
+            scope.finish();
+        }
+
+        // propagate the first panic of a handler
+        if let Some(payload) = progress.into_inner().panic {
+            panic::resume_unwind(payload);
+        }
+    }
+
+    // dispatch the batches of a lane on the dispatching thread, awaiting the futures of the
+    // batches that do not conflict concurrently
+    #[cfg(feature = "async")]
+    fn dispatch_joined(
+        &self,
+        container: Option<&ResourceContainer>,
+        event_manager: &EventManager,
+        executions: &[(EmittedEventInfo, Box<dyn Any + Send + Sync>)],
+        profiling: bool,
+    ) {
+        let count = executions.len();
+        let scopes = executions
+            .iter()
+            .map(|(info, _)| BatchScope::new(info, container, event_manager))
+            .collect::<Vec<_>>();
+        let (dependents, mut remaining) = self.batch_dependencies(executions);
+
+        let mut batches = scopes
+            .iter()
+            .zip(executions)
+            .map(|(scope, (_, events))| {
+                let batch = self.dispatch_batch_async(scope, events.as_ref(), profiling);
+                Some(Box::pin(batch))
+            })
+            .collect::<Vec<_>>();
+        let mut finished = 0;
+        crate::utils::block_on::block_on(std::future::poll_fn(|cx| loop {
+            // poll the batches whose conflicting batches have finished, in the order of the
+            // lane, until none of them progresses
+            let mut progressed = false;
+            for batch in 0..count {
+                if remaining[batch] != 0 {
+                    continue;
+                }
+                let Some(future) = &mut batches[batch] else {
+                    continue;
+                };
+                if future.as_mut().poll(cx).is_pending() {
+                    continue;
+                }
+                batches[batch] = None;
+                finished += 1;
+                progressed = true;
+                for &dependent in &dependents[batch] {
+                    remaining[dependent] -= 1;
+                }
+            }
+            if finished == count {
+                return Poll::Ready(());
+            }
+            if !progressed {
+                return Poll::Pending;
+            }
+        }));
+        drop(batches);
+
+        // flush the deferred events in the order of the lane
+        for scope in scopes {
+            scope.finish();
+        }
+    }
+
+    // returns `true` if a handler of the batches is async
+    #[cfg(feature = "async")]
+    fn has_async_handlers(
+        &self,
+        executions: &[(EmittedEventInfo, Box<dyn Any + Send + Sync>)],
+    ) -> bool {
+        executions.iter().any(|(info, _)| {
+            self.handlers
+                .get(&info.event_type_id)
+                .is_some_and(|handlers| {
+                    handlers
+                        .iter()
+                        .any(|handler| matches!(handler.handler, ErasedHandler::Async(_)))
+                })
+        })
+    }
+
+    // conflict graph of the batches of a lane, each batch depending on the conflicting batches
+    // before it, as the dependents and the number of dependencies of each batch
+    fn batch_dependencies(
+        &self,
+        executions: &[(EmittedEventInfo, Box<dyn Any + Send + Sync>)],
+    ) -> (Vec<Vec<usize>>, Vec<usize>) {
+        let count = executions.len();
+        let accesses = executions
+            .iter()
+            .map(|(info, _)| self.batch_access(info))
+            .collect::<Vec<_>>();
+        let mut dependents = vec![Vec::new(); count];
+        let mut remaining = vec![0; count];
+        // read-only batches never conflict with each other
+        let read_only = accesses.iter().all(Access::is_read_only);
+        for later in (0..count).filter(|_| !read_only) {
+            for earlier in 0..later {
+                if accesses[earlier].conflicts_with(&accesses[later]) {
+                    dependents[earlier].push(later);
+                    remaining[later] += 1;
+                }
+            }
+        }
+        (dependents, remaining)
+    }
+
+    // union of the accesses of the handlers of a batch
+    fn batch_access(&self, info: &EmittedEventInfo) -> Access {
+        let Some(handlers) = self.handlers.get(&info.event_type_id) else {
+            return Access::new();
+        };
+
+        let mut access = Access::new();
+        for handler in handlers {
+            let Some(handler_access) = &handler.options.access else {
+                return Access::exclusive();
+            };
+            for &type_id in handler_access.reads() {
+                access = access.with_read(type_id);
+            }
+            for &type_id in handler_access.writes() {
+                access = access.with_write(type_id);
+            }
+            if handler_access.reads_all() {
+                access = access.with_read_all();
+            }
+            if handler_access.is_exclusive() {
+                return Access::exclusive();
+            }
+        }
+        access
+    }
+
+    // execute the handlers of a batch
+    fn dispatch_batch(
+        &self,
+        scope: &BatchScope,
+        events: &(dyn Any + Send + Sync),
+        profiling: bool,
+    ) {
+        let BatchScope {
+            info,
+            event_manager,
+            ..
+        } = *scope;
+        // get handlers of event
+        let Some(handlers) = self.handlers.get(&info.event_type_id) else {
+            return;
+        };
+
+        for handler in handlers {
+            // skip handlers of disabled groups
+            if !self.is_handler_enabled(handler) {
+                continue;
+            }
+
+            let start = profiling.then(Instant::now);
+            let result = match &handler.handler {
+                ErasedHandler::Sync(erased) => erased(scope, events),
+                #[cfg(feature = "async")]
+                ErasedHandler::Async(erased) => {
+                    crate::utils::block_on::block_on(erased(scope, events))
+                }
+            };
+            self.handled(info, event_manager, handler, start, result);
+        }
+    }
+
+    // execute the handlers of a batch, awaiting the async handlers
+    #[cfg(feature = "async")]
+    async fn dispatch_batch_async(
+        &self,
+        scope: &BatchScope<'_>,
+        events: &(dyn Any + Send + Sync),
+        profiling: bool,
+    ) {
+        let BatchScope {
+            info,
+            event_manager,
+            ..
+        } = *scope;
+        // get handlers of event
+        let Some(handlers) = self.handlers.get(&info.event_type_id) else {
+            return;
+        };
+
+        for handler in handlers {
+            // skip handlers of disabled groups
+            if !self.is_handler_enabled(handler) {
+                continue;
+            }
+
+            let start = profiling.then(Instant::now);
+            let result = match &handler.handler {
+                ErasedHandler::Sync(erased) => erased(scope, events),
+                ErasedHandler::Async(erased) => erased(scope, events).await,
+            };
+            self.handled(info, event_manager, handler, start, result);
+        }
+    }
+
+    fn is_handler_enabled(&self, handler: &RegisteredHandler) -> bool {
+        handler
+            .options
+            .group
+            .is_none_or(|group| self.is_group_enabled(group))
+    }
+
+    // record the timing of a handler started at `start` and emit its error
+    fn handled(
+        &self,
+        info: &EmittedEventInfo,
+        event_manager: &EventManager,
+        handler: &RegisteredHandler,
+        start: Option<Instant>,
+        result: Result<(), HandlerError>,
+    ) {
+        if let Some(start) = start {
+            self.timings.borrow_mut().push(HandlerTiming {
+                event_type_name: handler.event_type_name,
+                handler_type_name: handler.handler_type_name,
+                duration: start.elapsed(),
+            });
+        }
+
+        let Err(error) = result else {
+            return;
+        };
+
+        // do not emit errors of error handlers
+        if info.event_type_id == TypeId::of::<HandlerErrorEvent>() {
+            return;
+        }
+
+        event_manager.emit_priority(
+            HandlerErrorEvent {
+                event_type_name: handler.event_type_name,
+                handler_type_name: handler.handler_type_name,
+                error,
+            },
+            Priority::High,
+        );
+    }
+}
+
+impl Debug for HandlerRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_map()
+            .entries(self.handlers.values().filter_map(|handlers| {
+                handlers
+                    .first()
+                    .map(|handler| (handler.event_type_name, handlers.len()))
+            }))
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test_handler {
+    use std::{
+        fmt::Display,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+    };
+
+    use super::*;
+    use crate::{
+        event::budget::Budget,
+        store::{Container, Res, ResMut, RetrievalError, Retriever},
+    };
+
+    #[derive(Debug)]
+    struct TestError;
+
+    impl Display for TestError {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "test error")
+        }
+    }
+
+    impl Error for TestError {}
+
+    struct TestEvent(usize);
+    impl Event for TestEvent {}
+
+    #[test]
+    fn test_handler_receives_batch() {
+        let sum = Arc::new(AtomicUsize::new(0));
+        let mut registry = HandlerRegistry::new();
+        let handler_sum = sum.clone();
+        registry.add_handler(move |events: &[TestEvent]| {
+            for event in events {
+                handler_sum.fetch_add(event.0, Ordering::SeqCst);
+            }
+        });
+        assert!(registry.contains_handler::<TestEvent>());
+
+        let event_manager = EventManager::new();
+        event_manager.emit(TestEvent(1));
+        event_manager.emit(TestEvent(2));
+
+        assert_eq!(registry.dispatch(&event_manager), 1);
+        assert_eq!(sum.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn test_handler_remove_replace() {
+        struct OtherEvent;
+        impl Event for OtherEvent {}
+
+        let sum = Arc::new(AtomicUsize::new(0));
+        let mut registry = HandlerRegistry::new();
+        let handler_sum = sum.clone();
+        let id = registry.add_handler(move |events: &[TestEvent]| {
+            handler_sum.fetch_add(events.len(), Ordering::SeqCst);
+        });
+        let handler_sum = sum.clone();
+        assert!(registry.replace_handler(id, move |events: &[TestEvent]| {
+            handler_sum.fetch_add(10 * events.len(), Ordering::SeqCst);
+        }));
+        // assert handlers of another event are not replaced
+        assert!(!registry.replace_handler(id, |_: &[OtherEvent]| {}));
+
+        let event_manager = EventManager::new();
+        event_manager.emit(TestEvent(1));
+        registry.dispatch(&event_manager);
+        assert_eq!(sum.load(Ordering::SeqCst), 10);
+
+        assert!(registry.remove_handler(id));
+        assert!(!registry.remove_handler(id));
+        assert!(!registry.contains_handler_id(id));
+        assert!(!registry.contains_handler::<TestEvent>());
+    }
+
+    #[test]
+    fn test_handler_context() {
+        let mut registry = HandlerRegistry::new();
+        registry.add_context_handler(|events: &[TestEvent], context: &Context| {
+            let mut sum = context.try_retrieve::<ResMut<usize>>()?;
+            *sum += events.iter().map(|event| event.0).sum::<usize>();
+            context.commands().insert(context.batch().priority());
+            Ok::<_, RetrievalError>(())
+        });
+        let errors = Arc::new(parking_lot::Mutex::new(Vec::new()));
+        let handler_errors = errors.clone();
+        registry.add_handler(move |events: &[HandlerErrorEvent]| {
+            handler_errors
+                .lock()
+                .extend(events.iter().map(|event| event.to_string()))
+        });
+
+        let mut container = ResourceContainer::default();
+        container.add_resource(0usize);
+        let event_manager = EventManager::new();
+        event_manager.emit(TestEvent(1));
+        event_manager.emit(TestEvent(2));
+        registry.dispatch_with(&container, &event_manager);
+        container.apply_commands();
+        assert_eq!(*Res::<usize>::retrieve(&container), 3);
+        assert_eq!(*Res::<Priority>::retrieve(&container), Priority::Normal);
+
+        // assert dispatching without a container fails the handler
+        event_manager.emit(TestEvent(1));
+        registry.dispatch(&event_manager);
+        let errors = errors.lock();
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].ends_with("needs a container, dispatch with `dispatch_with`"));
+    }
+
+    #[test]
+    fn test_handler_context_metadata() {
+        let metadata = Arc::new(parking_lot::Mutex::new(Vec::new()));
+        let handler_metadata = metadata.clone();
+        let mut registry = HandlerRegistry::new();
+        registry.add_context_handler(move |_: &[TestEvent], context: &Context| {
+            handler_metadata.lock().push((
+                context.event_type_name(),
+                context.priority(),
+                context.batch_len(),
+                context.sequence_range(),
+            ));
+        });
+
+        let container = ResourceContainer::default();
+        let event_manager = EventManager::new();
+        let first = event_manager.next_sequence();
+        event_manager.emit_priority(TestEvent(1), Priority::High);
+        event_manager.emit(TestEvent(2));
+        registry.dispatch_with(&container, &event_manager);
+        assert_eq!(
+            *metadata.lock(),
+            [(
+                std::any::type_name::<TestEvent>(),
+                Priority::High,
+                2,
+                Some(first..=first + 1)
+            )]
+        );
+    }
+
+    #[test]
+    fn test_handler_context_deferred_emit() {
+        struct OtherEvent(usize);
+        impl Event for OtherEvent {}
+
+        let mut registry = HandlerRegistry::new();
+        registry.add_context_handler(|_: &[TestEvent], context: &Context| {
+            context.emit(OtherEvent(1));
+            assert_eq!(context.event_manager().pending_count::<OtherEvent>(), 0);
+        });
+        registry.add_context_handler(|_: &[TestEvent], context: &Context| {
+            context.emit(OtherEvent(2));
+            context.event_manager().emit(OtherEvent(0));
+        });
+        let received = Arc::new(parking_lot::Mutex::new(Vec::new()));
+        let handler_received = received.clone();
+        registry.add_handler(move |events: &[OtherEvent]| {
+            handler_received
+                .lock()
+                .extend(events.iter().map(|event| event.0))
+        });
+
+        // assert deferred events follow the direct emissions, in handler order
+        let container = ResourceContainer::default();
+        let event_manager = EventManager::new();
+        event_manager.emit(TestEvent(0));
+        registry.dispatch_with(&container, &event_manager);
+        assert_eq!(*received.lock(), [0, 1, 2]);
+    }
+
+    #[test]
+    fn test_handler_stamped() {
+        struct OtherEvent;
+        impl Event for OtherEvent {}
+
+        let sequences = Arc::new(parking_lot::Mutex::new(Vec::new()));
+        let mut registry = HandlerRegistry::new();
+        let handler_sequences = sequences.clone();
+        registry.add_stamped_handler(move |events: &[TestEvent], stamps: &[EventStamp]| {
+            assert_eq!(events.len(), stamps.len());
+            handler_sequences
+                .lock()
+                .extend(stamps.iter().map(|stamp| stamp.sequence()));
+        });
+
+        let event_manager = EventManager::new();
+        event_manager.emit(TestEvent(1));
+        event_manager.emit(OtherEvent);
+        event_manager.emit(TestEvent(2));
+
+        // assert the interleaving with other event types is observable
+        registry.dispatch(&event_manager);
+        assert_eq!(*sequences.lock(), vec![0, 2]);
+    }
+
+    #[test]
+    fn test_handler_order() {
+        let order = Arc::new(parking_lot::Mutex::new(Vec::new()));
+        let mut registry = HandlerRegistry::new();
+        let mut add = |name: &'static str, options: HandlerOptions| {
+            let order = order.clone();
+            registry.add_handler_with(move |_: &[TestEvent]| order.lock().push(name), options)
+        };
+
+        add("late", HandlerOptions::ordered(10)).unwrap();
+        add("first", HandlerOptions::default()).unwrap();
+        add("second", HandlerOptions::default()).unwrap();
+        add("early", HandlerOptions::ordered(-10)).unwrap();
+        add("render", HandlerOptions::labeled("render").with_order(-20)).unwrap();
+        add(
+            "physics",
+            HandlerOptions::labeled("physics").with_before("render"),
+        )
+        .unwrap();
+        add("input", HandlerOptions::ordered(20).with_before("physics")).unwrap();
+
+        let event_manager = EventManager::new();
+        event_manager.emit(TestEvent(0));
+        registry.dispatch(&event_manager);
+
+        // assert labels take precedence over orders, ties are broken by registration
+        assert_eq!(
+            *order.lock(),
+            vec!["early", "first", "second", "late", "input", "physics", "render"]
+        );
+    }
+
+    #[test]
+    fn test_handler_order_cycle() {
+        let mut registry = HandlerRegistry::new();
+        registry
+            .add_handler_with(
+                |_: &[TestEvent]| {},
+                HandlerOptions::labeled("a").with_after("b"),
+            )
+            .unwrap();
+        assert_eq!(
+            registry.add_handler_with(
+                |_: &[TestEvent]| {},
+                HandlerOptions::labeled("b").with_after("a")
+            ),
+            Err(EmarkError::CyclicHandlerOrder(std::any::type_name::<
+                TestEvent,
+            >()))
+        );
+
+        // assert the registry is left unchanged
+        assert_eq!(registry.handlers[&TypeId::of::<TestEvent>()].len(), 1);
+        registry
+            .add_handler_with(|_: &[TestEvent]| {}, HandlerOptions::labeled("b"))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_handler_groups() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let registry = Arc::new(parking_lot::RwLock::new(HandlerRegistry::new()));
+        {
+            let mut registry_mut = registry.write();
+            let handler_calls = calls.clone();
+            registry_mut
+                .add_handler_with(
+                    move |_: &[TestEvent]| {
+                        handler_calls.fetch_add(1, Ordering::SeqCst);
+                    },
+                    HandlerOptions::grouped("debug_overlay"),
+                )
+                .unwrap();
+
+            // disable the group from within a handler
+            let handler_registry = registry.clone();
+            registry_mut
+                .add_handler_with(
+                    move |events: &[TestEvent]| {
+                        if events[0].0 == 0 {
+                            handler_registry.read().disable_group("debug_overlay");
+                        }
+                    },
+                    HandlerOptions::ordered(-1),
+                )
+                .unwrap();
+        }
+
+        let event_manager = EventManager::new();
+        event_manager.emit(TestEvent(0));
+        registry.read().dispatch(&event_manager);
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+        assert!(!registry.read().is_group_enabled("debug_overlay"));
+
+        // assert re-enabled groups receive events again
+        assert!(registry.read().enable_group("debug_overlay"));
+        event_manager.emit(TestEvent(1));
+        registry.read().dispatch(&event_manager);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_handler_filtered() {
+        let received = Arc::new(parking_lot::Mutex::new(Vec::new()));
+        let calls = Arc::new(AtomicUsize::new(0));
+        let mut registry = HandlerRegistry::new();
+        let handler_received = received.clone();
+        let handler_calls = calls.clone();
+        registry.add_filtered_handler(
+            |event: &TestEvent| event.0 > 1,
+            move |events: &[&TestEvent]| {
+                handler_calls.fetch_add(1, Ordering::SeqCst);
+                handler_received
+                    .lock()
+                    .extend(events.iter().map(|event| event.0));
+            },
+        );
+
+        let event_manager = EventManager::new();
+        event_manager.emit(TestEvent(1));
+        event_manager.emit(TestEvent(2));
+        event_manager.emit(TestEvent(4));
+        registry.dispatch(&event_manager);
+        assert_eq!(*received.lock(), vec![2, 4]);
+
+        // assert the handler is not called without matching events
+        event_manager.emit(TestEvent(0));
+        registry.dispatch(&event_manager);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_handler_error_event() {
+        let errors = Arc::new(AtomicUsize::new(0));
+        let mut registry = HandlerRegistry::new();
+        registry.add_handler(|_: &[TestEvent]| Err(TestError));
+        let handler_errors = errors.clone();
+        registry.add_handler(move |events: &[HandlerErrorEvent]| {
+            for event in events {
+                assert_eq!(event.event_type_name(), std::any::type_name::<TestEvent>());
+                assert_eq!(event.error().to_string(), "test error");
+                handler_errors.fetch_add(1, Ordering::SeqCst);
+            }
+        });
+
+        let event_manager = EventManager::new();
+        event_manager.emit(TestEvent(1));
+
+        // the error event is dispatched within the same cycle
+        assert_eq!(registry.dispatch(&event_manager), 2);
+        assert_eq!(errors.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_handler_error_of_error_handler() {
+        let mut registry = HandlerRegistry::new();
+        registry.add_handler(|_: &[TestEvent]| Err(TestError));
+        registry.add_handler(|_: &[HandlerErrorEvent]| Err(TestError));
+
+        let event_manager = EventManager::new();
+        event_manager.emit(TestEvent(1));
+
+        // assert no error is emitted from the error handler
+        assert_eq!(registry.dispatch(&event_manager), 2);
+        assert!(event_manager.next_execution().is_none());
+    }
+
+    #[test]
+    fn test_handler_dispatch_budget() {
+        struct Refire;
+        impl Event for Refire {}
+
+        let event_manager = Arc::new(EventManager::new());
+        event_manager.set_budget(Priority::Routine, Budget::batches(3));
+
+        let mut registry = HandlerRegistry::new();
+        let handler_event_manager = event_manager.clone();
+        registry.add_handler(move |_: &[Refire]| {
+            handler_event_manager.emit_priority(Refire, Priority::Routine);
+        });
+
+        // assert dispatch yields once the budget is exhausted
+        event_manager.emit_priority(Refire, Priority::Routine);
+        assert_eq!(registry.dispatch(&event_manager), 3);
+        assert_eq!(registry.dispatch(&event_manager), 3);
+    }
+
+    #[test]
+    fn test_handler_parallel_dispatch() {
+        struct Left;
+        impl Event for Left {}
+        struct Right;
+        impl Event for Right {}
+
+        // both handlers only read, they must run concurrently to pass the barrier
+        let barrier = Arc::new(std::sync::Barrier::new(2));
+        let reads = || Access::new().with_read(TypeId::of::<u32>());
+        let mut registry = HandlerRegistry::new();
+        registry.set_thread_pool(crate::system::ScopedThreadPool::new(2));
+        let left_barrier = barrier.clone();
+        registry
+            .add_handler_with(
+                move |_: &[Left]| {
+                    left_barrier.wait();
+                },
+                HandlerOptions::new().with_access(reads()),
+            )
+            .unwrap();
+        registry
+            .add_handler_with(
+                move |_: &[Right]| {
+                    barrier.wait();
+                },
+                HandlerOptions::new().with_access(reads()),
+            )
+            .unwrap();
+
+        let event_manager = EventManager::new();
+        event_manager.emit(Left);
+        event_manager.emit(Right);
+        assert_eq!(registry.dispatch(&event_manager), 2);
+    }
+
+    #[cfg(not(feature = "unsync"))]
+    #[test]
+    fn test_handler_parallel_read_handlers() {
+        struct Left;
+        impl Event for Left {}
+        struct Right;
+        impl Event for Right {}
+
+        // read-only handlers declare no access, they must run concurrently to pass the barrier
+        let barrier = Arc::new(std::sync::Barrier::new(2));
+        let mut registry = HandlerRegistry::new();
+        registry.set_thread_pool(crate::system::ScopedThreadPool::new(2));
+        let left_barrier = barrier.clone();
+        registry.add_read_handler(move |_: &[Left], context: &ReadContext| {
+            assert_eq!(*context.retrieve::<Res<u32>>(), 1);
+            left_barrier.wait();
+        });
+        registry.add_read_handler(move |_: &[Right], context: &ReadContext| {
+            assert_eq!(*context.retrieve::<Res<u32>>(), 1);
+            barrier.wait();
+        });
+
+        let mut container = ResourceContainer::default();
+        container.add_resource(1u32);
+        let event_manager = EventManager::new();
+        event_manager.emit(Left);
+        event_manager.emit(Right);
+        assert_eq!(registry.dispatch_with(&container, &event_manager), 2);
+
+        // assert read-only handlers still conflict with writers
+        let access = Access::new().with_write(TypeId::of::<u32>());
+        registry
+            .add_handler_with(|_: &[Right]| {}, HandlerOptions::new().with_access(access))
+            .unwrap();
+        let info = |event_type_id| EmittedEventInfo {
+            priority: Priority::Normal,
+            event_type_id,
+            vec_type_id: event_type_id,
+            event_type_name: "",
+            stamps: Vec::new(),
+        };
+        let left = registry.batch_access(&info(TypeId::of::<Left>()));
+        let right = registry.batch_access(&info(TypeId::of::<Right>()));
+        assert!(left.is_read_only());
+        assert!(left.conflicts_with(&right));
+    }
+
+    #[test]
+    fn test_handler_parallel_dispatch_conflict() {
+        struct Left;
+        impl Event for Left {}
+        struct Right;
+        impl Event for Right {}
+
+        // both handlers write, they must never overlap
+        let running = Arc::new(AtomicBool::new(false));
+        let dispatched = Arc::new(AtomicUsize::new(0));
+        let writes = || Access::new().with_write(TypeId::of::<u32>());
+        let mut registry = HandlerRegistry::new();
+        registry.set_thread_pool(crate::system::ScopedThreadPool::new(2));
+        for index in 0..2 {
+            let running = running.clone();
+            let dispatched = dispatched.clone();
+            let handler = move || {
+                assert!(!running.swap(true, Ordering::SeqCst));
+                std::thread::sleep(std::time::Duration::from_millis(10));
+                running.store(false, Ordering::SeqCst);
+                dispatched.fetch_add(1, Ordering::SeqCst);
+            };
+            let options = HandlerOptions::new().with_access(writes());
+            match index {
+                0 => registry.add_handler_with(move |_: &[Left]| handler(), options),
+                _ => registry.add_handler_with(move |_: &[Right]| handler(), options),
+            }
+            .unwrap();
+        }
+
+        let event_manager = EventManager::new();
+        event_manager.emit(Left);
+        event_manager.emit(Right);
+        registry.dispatch(&event_manager);
+        assert_eq!(dispatched.load(Ordering::SeqCst), 2);
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn test_handler_async_joined() {
+        struct Left;
+        impl Event for Left {}
+        struct Right;
+        impl Event for Right {}
+
+        // the left handler is pending until the right handler ran, giving up after a while
+        let flag = Arc::new(AtomicBool::new(false));
+        let mut registry = HandlerRegistry::new();
+        let left = flag.clone();
+        let reads = || Access::new().with_read(TypeId::of::<u32>());
+        registry
+            .add_async_handler_with(
+                async move |_: &[Left]| {
+                    let mut polls = 0;
+                    let set = std::future::poll_fn(|cx| {
+                        polls += 1;
+                        if left.load(Ordering::SeqCst) || polls > 100 {
+                            return Poll::Ready(left.load(Ordering::SeqCst));
+                        }
+                        cx.waker().wake_by_ref();
+                        Poll::Pending
+                    });
+                    assert!(set.await);
+                },
+                HandlerOptions::new().with_access(reads()),
+            )
+            .unwrap();
+        let right = flag.clone();
+        registry
+            .add_stamped_handler_with(
+                move |_: &[Right], stamps: &[EventStamp]| {
+                    assert_eq!(stamps.len(), 1);
+                    right.store(true, Ordering::SeqCst);
+                },
+                HandlerOptions::new().with_access(reads()),
+            )
+            .unwrap();
+
+        // assert the handlers not conflicting are awaited concurrently without a thread pool
+        let event_manager = EventManager::new();
+        event_manager.emit(Left);
+        event_manager.emit(Right);
+        assert_eq!(registry.dispatch(&event_manager), 2);
+    }
+}
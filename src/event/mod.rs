@@ -31,21 +31,123 @@
 //! For example, 10 events of type `MyEvent` that has been emitted so far will be grouped into a single batch.
 //! batching of events ensures that event processing is efficient.
 //! 
+//! Events implementing `KeyedEvent` are further partitioned into one batch per batch key,
+//! e.g. one batch per connection id.
+//!
+//! ## Event Stamps
+//!
+//! Every emission is stamped with a sequence number, monotonically increasing across all event
+//! types, and optionally a timestamp. The stamps of a batch are exposed alongside it, so handlers
+//! and replays can reconstruct the exact interleaving of events.
+//!
 //! ## Priority Upgrading
 //! 
 //! When emitting events of the same type but on different priority say we emit on `Normal` first then on `High`. When such events has not been handled yet,
 //! the `EventManager` will promote the event to `High` from `Normal` priority. The priority of such case of events of the same type emitted on different priorities will be upgraded to 
 //! the highest priority emitted. 
 //! 
+//! Pending events can also be promoted externally with `boost`, e.g. by a watchdog noticing a
+//! deadline slip, without re-emitting them.
+//!
+//! ## Frame Events
+//!
+//! For UI and frame loops where queue-and-consume does not fit, [Events](frame::Events) offers a
+//! double-buffered storage where events live for exactly two update cycles and every reader tracks
+//! its own cursor.
+//!
+//! ## Conditional Emission
+//!
+//! `emit_if_absent` only enqueues an event when no event of the same type is pending, while
+//! `emit_or_merge` merges the emitted event into the pending one. Both keep frame-driven events
+//! at most one per cycle.
+//!
+//! ## Watching Events
+//!
+//! With the `async` feature, `EventManager::watch` returns a future resolving when an event of a
+//! type is next emitted, so async tasks outside of the dispatch loop can react to events without
+//! polling.
+//!
+//! ## Dispatch Budget
+//! 
+//! Self-refiring `Routine` events can monopolize a cycle. Each priority lane can be given a
+//! [Budget](budget::Budget) limiting the number of batches or the wall-clock time it may consume
+//! per cycle. Once exhausted, the `EventManager` yields back to the caller and the remaining events
+//! are kept until the next cycle.
+//! 
+//! Lanes can also be paused and resumed, e.g. to freeze `Normal` and `Routine` processing
+//! during a loading screen while still servicing `Interrupt` and `High`.
+//!
+//! ## Snapshots
+//!
+//! Pending batches of cloneable events can be captured with `snapshot` and rolled back with
+//! `restore`, so a test can emit, assert and try an alternative dispatch order on the same
+//! `EventManager`.
+//!
+//! ## Event Schemas
+//! 
+//! `TypeId`s are not stable between builds. The [SchemaRegistry](schema::SchemaRegistry) maps event
+//! types to stable names and identifiers, used wherever events cross the process boundary.
+//! Evolving events are versioned, the [UpgradeRegistry](version::UpgradeRegistry) upgrades
+//! events decoded from old recordings to their latest version.
+//! 
+//! ## Fallible Handlers
+//! 
+//! Handlers registered on the `HandlerRegistry` may return `Result<(), E>`. Instead of panicking,
+//! the dispatch layer converts an error into a `HandlerErrorEvent` carrying the type name of the
+//! event and the boxed error, so the application can keep running when one subsystem misbehaves.
+//!
+//! ## Handler Ordering
+//!
+//! Handlers of the same event type are executed in a stable order, given at registration by
+//! [HandlerOptions] as a numeric order or as before/after constraints on labeled handlers.
+//! Registering a handler that makes the order cyclic is rejected with an error.
+//!
+//! Handlers may be registered into groups, toggled at runtime with `disable_group` and
+//! `enable_group`, so whole subsystems stop receiving events without unregistering them.
+//!
+//! A handler may also be registered with a filter, only receiving the events of the batch
+//! matching it.
+//!
+//! ## Handler Context
+//!
+//! Handlers registered with `add_context_handler` receive a [Context](crate::store::Context)
+//! giving them access to the resources of the container dispatched with `dispatch_with`, the
+//! event emitter, a `Commands` buffer and the metadata of the batch being handled. Events
+//! emitted through the context are deferred until every handler of the batch ran.
+//!
+//! ## Parallel Dispatch
+//!
+//! A priority lane may yield many independent batches, e.g. one per key of a `KeyedEvent`.
+//! Given a thread pool, the `HandlerRegistry` dispatches them concurrently with work stealing,
+//! while batches whose handlers declare conflicting resource accesses keep their order.
 //! 
 #[doc(hidden)]
+#[allow(clippy::module_inception)]
 pub mod event;
 #[doc(inline)]
-pub use event::Event;
+pub use event::{DynamicEvent, Event, KeyedEvent};
 
 pub mod priority;
 
+pub mod budget;
+
+pub mod schema;
+
+pub mod version;
+
+pub mod frame;
+
+pub mod stamp;
+
+#[cfg(feature = "async")]
+pub mod watch;
+
 #[doc(hidden)]
 pub mod event_manager;
 #[doc(inline)]
-pub use event_manager::EventManager;
+pub use event_manager::{EmittedEventInfo, EventBatch, EventManager, EventSnapshot};
+
+#[doc(hidden)]
+pub mod handler;
+#[doc(inline)]
+pub use handler::{HandlerErrorEvent, HandlerId, HandlerOptions, HandlerRegistry, HandlerTiming};
@@ -1,4 +1,4 @@
-#[derive(Debug, Default, Clone, Copy, Hash, PartialEq, Eq, Ord)]
+#[derive(Debug, Default, Clone, Copy, Hash, PartialEq, Eq)]
 #[repr(u8)]
 /// Event priority.
 /// # Event Priority
@@ -33,9 +33,26 @@ pub enum Priority {
     Routine = 3,
 }
 
+impl Priority {
+    /// Every priority in dispatch order, from the highest to the lowest.
+    pub const ALL: [Priority; 4] = [
+        Priority::Interrupt,
+        Priority::High,
+        Priority::Normal,
+        Priority::Routine,
+    ];
+}
+
+impl Ord for Priority {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        // lower discriminant means higher priority
+        (*other as u8).cmp(&(*self as u8))
+    }
+}
+
 impl PartialOrd for Priority {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        Some(self.cmp(other).reverse())
+        Some(self.cmp(other))
     }
 }
 
@@ -181,6 +198,14 @@ mod test_priority {
         assert_eq!(priority.next(), Some(Priority::Interrupt));
     }
 
+    #[test]
+    fn test_priority_all() {
+        assert!(Priority::ALL.windows(2).all(|pair| pair[0] > pair[1]));
+        for (index, priority) in Priority::ALL.into_iter().enumerate() {
+            assert_eq!(usize::from(priority), index);
+        }
+    }
+
     #[test]
     fn test_priority_from_type_state() {
         assert_eq!(Priority::from(Interrupt), Priority::Interrupt);
@@ -0,0 +1,272 @@
+use std::{
+    any::TypeId,
+    collections::{hash_map::Entry, HashMap},
+};
+
+use crate::utils::error::EmarkError;
+
+use super::Event;
+
+/// Event with a stable name.
+///
+/// The name identifies the event type in persisted or cross-process data,
+/// where `TypeId` is not stable between builds.
+///
+/// # Examples
+/// ```
+/// use emark::event::schema::{NamedEvent, SchemaRegistry};
+/// use emark::prelude::Event;
+///
+/// struct SaveRequested;
+/// impl Event for SaveRequested {}
+/// impl NamedEvent for SaveRequested {
+///     const NAME: &'static str = "app.save_requested";
+/// }
+///
+/// let mut registry = SchemaRegistry::new();
+/// registry.register_named::<SaveRequested>().unwrap();
+/// assert_eq!(registry.name_of::<SaveRequested>(), Some("app.save_requested"));
+/// ```
+pub trait NamedEvent: Event {
+    const NAME: &'static str;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// Schema of a registered event type.
+pub struct EventSchema {
+    name: &'static str,
+    id: u64,
+    type_id: TypeId,
+    type_name: &'static str,
+}
+
+impl EventSchema {
+    /// Stable name of the event.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Stable numeric identifier of the event, derived from its name.
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// `TypeId` of the event, only valid within the running process.
+    pub fn type_id(&self) -> TypeId {
+        self.type_id
+    }
+
+    /// Rust type name of the event, for diagnostics only.
+    pub fn type_name(&self) -> &'static str {
+        self.type_name
+    }
+}
+
+/// Computes the stable identifier of an event name.
+///
+/// The identifier is the 64 bit FNV-1a hash of the name, which does not depend
+/// on the build or the platform.
+pub const fn stable_id(name: &str) -> u64 {
+    let bytes = name.as_bytes();
+    let mut hash = 0xcbf2_9ce4_8422_2325u64;
+    let mut index = 0;
+    while index < bytes.len() {
+        hash ^= bytes[index] as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+        index += 1;
+    }
+    hash
+}
+
+#[derive(Debug, Default)]
+/// # SchemaRegistry
+///
+/// The `SchemaRegistry` maps event types to stable string names and identifiers.
+/// Serialization, networking and introspection features use the registry so that
+/// `TypeId`s never leak into persisted or cross-process data.
+///
+/// A name is bound to a single event type and an event type to a single name.
+pub struct SchemaRegistry {
+    schemas: HashMap<TypeId, EventSchema>,
+    names: HashMap<&'static str, TypeId>,
+    ids: HashMap<u64, TypeId>,
+}
+
+impl SchemaRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers event `T` under `name`.
+    ///
+    /// Registering the same event under the same name again is a no-op.
+    /// Returns an error if the name is bound to another event type or if the
+    /// event type is registered under another name.
+    pub fn register<T: Event + 'static>(
+        &mut self,
+        name: &'static str,
+    ) -> Result<EventSchema, EmarkError> {
+        let type_id = TypeId::of::<T>();
+        let schema = EventSchema {
+            name,
+            id: stable_id(name),
+            type_id,
+            type_name: std::any::type_name::<T>(),
+        };
+
+        // check if event type already registered
+        if let Some(registered) = self.schemas.get(&type_id) {
+            if registered.name == name {
+                return Ok(*registered);
+            }
+            return Err(EmarkError::DuplicateEventType(schema.type_name));
+        }
+
+        // check if name or id already bound
+        if self.names.contains_key(name) || self.ids.contains_key(&schema.id) {
+            return Err(EmarkError::DuplicateEventName(name));
+        }
+
+        // insert schema
+        self.names.insert(name, type_id);
+        self.ids.insert(schema.id, type_id);
+        self.schemas.insert(type_id, schema);
+        Ok(schema)
+    }
+
+    /// Registers event `T` under its [NamedEvent] name.
+    pub fn register_named<T: NamedEvent + 'static>(&mut self) -> Result<EventSchema, EmarkError> {
+        self.register::<T>(T::NAME)
+    }
+
+    /// Unregisters event `T`, returning its schema.
+    pub fn unregister<T: Event + 'static>(&mut self) -> Option<EventSchema> {
+        match self.schemas.entry(TypeId::of::<T>()) {
+            Entry::Occupied(entry) => {
+                let schema = entry.remove();
+                self.names.remove(schema.name);
+                self.ids.remove(&schema.id);
+                Some(schema)
+            }
+            Entry::Vacant(_) => None,
+        }
+    }
+
+    /// Returns the schema of event `T`.
+    pub fn schema<T: Event + 'static>(&self) -> Option<&EventSchema> {
+        self.schema_of(TypeId::of::<T>())
+    }
+
+    /// Returns the schema of the event with the given `TypeId`.
+    pub fn schema_of(&self, type_id: TypeId) -> Option<&EventSchema> {
+        self.schemas.get(&type_id)
+    }
+
+    /// Returns the schema of the event registered under `name`.
+    pub fn schema_by_name(&self, name: &str) -> Option<&EventSchema> {
+        self.names
+            .get(name)
+            .and_then(|type_id| self.schemas.get(type_id))
+    }
+
+    /// Returns the schema of the event with the stable identifier `id`.
+    pub fn schema_by_id(&self, id: u64) -> Option<&EventSchema> {
+        self.ids
+            .get(&id)
+            .and_then(|type_id| self.schemas.get(type_id))
+    }
+
+    /// Returns the name of event `T`.
+    pub fn name_of<T: Event + 'static>(&self) -> Option<&'static str> {
+        self.schema::<T>().map(EventSchema::name)
+    }
+
+    /// Returns `true` if event `T` is registered.
+    pub fn contains<T: Event + 'static>(&self) -> bool {
+        self.schemas.contains_key(&TypeId::of::<T>())
+    }
+
+    /// Iterates through every registered schema in arbitrary order.
+    pub fn iter(&self) -> impl Iterator<Item = &EventSchema> {
+        self.schemas.values()
+    }
+
+    pub fn len(&self) -> usize {
+        self.schemas.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.schemas.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod test_schema {
+    use std::any::TypeId;
+
+    use super::*;
+
+    struct TestEvent;
+    impl Event for TestEvent {}
+
+    struct TestEventNamed;
+    impl Event for TestEventNamed {}
+    impl NamedEvent for TestEventNamed {
+        const NAME: &'static str = "test.named";
+    }
+
+    #[test]
+    fn test_stable_id() {
+        // FNV-1a reference values
+        assert_eq!(stable_id(""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(stable_id("a"), 0xaf63_dc4c_8601_ec8c);
+    }
+
+    #[test]
+    fn test_register() {
+        let mut registry = SchemaRegistry::new();
+        let schema = registry.register::<TestEvent>("test.event").unwrap();
+        assert_eq!(schema.name(), "test.event");
+        assert_eq!(schema.id(), stable_id("test.event"));
+        assert_eq!(schema.type_id(), TypeId::of::<TestEvent>());
+
+        assert_eq!(registry.schema::<TestEvent>(), Some(&schema));
+        assert_eq!(registry.schema_by_name("test.event"), Some(&schema));
+        assert_eq!(registry.schema_by_id(schema.id()), Some(&schema));
+        assert_eq!(registry.len(), 1);
+
+        // assert registering again is a no-op
+        assert_eq!(registry.register::<TestEvent>("test.event"), Ok(schema));
+        assert_eq!(registry.len(), 1);
+    }
+
+    #[test]
+    fn test_register_duplicate() {
+        let mut registry = SchemaRegistry::new();
+        registry.register::<TestEvent>("test.event").unwrap();
+
+        let type_name = std::any::type_name::<TestEvent>();
+        assert_eq!(
+            registry.register::<TestEvent>("test.other"),
+            Err(EmarkError::DuplicateEventType(type_name))
+        );
+        assert_eq!(
+            registry.register::<TestEventNamed>("test.event"),
+            Err(EmarkError::DuplicateEventName("test.event"))
+        );
+    }
+
+    #[test]
+    fn test_register_named_and_unregister() {
+        let mut registry = SchemaRegistry::new();
+        registry.register_named::<TestEventNamed>().unwrap();
+        assert!(registry.contains::<TestEventNamed>());
+        assert_eq!(registry.name_of::<TestEventNamed>(), Some("test.named"));
+
+        let schema = registry.unregister::<TestEventNamed>().unwrap();
+        assert!(registry.is_empty());
+        assert!(registry.schema_by_name("test.named").is_none());
+        assert!(registry.schema_by_id(schema.id()).is_none());
+        assert!(registry.unregister::<TestEventNamed>().is_none());
+    }
+}
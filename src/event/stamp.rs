@@ -0,0 +1,53 @@
+use std::time::Instant;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+/// Stamp of an emitted event.
+///
+/// Every emission into an `EventManager` is stamped with a sequence number,
+/// monotonically increasing across all event types, and optionally with the
+/// instant of the emission. Stamps are exposed alongside the batch, in the same
+/// order as the events, so that handlers and replays can reconstruct the exact
+/// interleaving of events across types.
+///
+/// Stamps are ordered by sequence number.
+pub struct EventStamp {
+    sequence: u64,
+    timestamp: Option<Instant>,
+}
+
+impl EventStamp {
+    pub(crate) fn new(sequence: u64, timestamp: Option<Instant>) -> Self {
+        Self {
+            sequence,
+            timestamp,
+        }
+    }
+
+    /// Sequence number of the emission.
+    pub fn sequence(&self) -> u64 {
+        self.sequence
+    }
+
+    /// Instant of the emission, if timestamps are enabled on the `EventManager`.
+    pub fn timestamp(&self) -> Option<Instant> {
+        self.timestamp
+    }
+}
+
+#[cfg(test)]
+mod test_stamp {
+    use std::time::Instant;
+
+    use super::EventStamp;
+
+    #[test]
+    fn test_stamp_order() {
+        let now = Instant::now();
+        let first = EventStamp::new(0, Some(now));
+        let second = EventStamp::new(1, None);
+        assert!(first < second);
+        assert_eq!(first.sequence(), 0);
+        assert_eq!(first.timestamp(), Some(now));
+        assert_eq!(second.timestamp(), None);
+    }
+}
@@ -0,0 +1,300 @@
+use std::{
+    any::{Any, TypeId},
+    collections::{hash_map::Entry, HashMap},
+    fmt::Debug,
+};
+
+use crate::utils::error::EmarkError;
+
+use super::schema::NamedEvent;
+
+/// Event with a stable name and a version.
+///
+/// Each revision of an event is a distinct Rust type sharing the same
+/// [NamedEvent] name with a greater version.
+///
+/// # Examples
+/// ```
+/// use emark::event::schema::NamedEvent;
+/// use emark::event::version::{UpgradeRegistry, VersionedEvent};
+/// use emark::prelude::Event;
+///
+/// struct PlayerMovedV1 {
+///     x: i32,
+/// }
+/// impl Event for PlayerMovedV1 {}
+/// impl NamedEvent for PlayerMovedV1 {
+///     const NAME: &'static str = "player.moved";
+/// }
+/// impl VersionedEvent for PlayerMovedV1 {
+///     const VERSION: u32 = 1;
+/// }
+///
+/// struct PlayerMoved {
+///     x: i32,
+///     y: i32,
+/// }
+/// impl Event for PlayerMoved {}
+/// impl NamedEvent for PlayerMoved {
+///     const NAME: &'static str = "player.moved";
+/// }
+/// impl VersionedEvent for PlayerMoved {
+///     const VERSION: u32 = 2;
+/// }
+///
+/// let mut registry = UpgradeRegistry::new();
+/// registry
+///     .register_upgrade(|old: PlayerMovedV1| PlayerMoved { x: old.x, y: 0 })
+///     .unwrap();
+///
+/// let event = registry
+///     .upgrade_into::<PlayerMoved>(1, Box::new(PlayerMovedV1 { x: 4 }))
+///     .unwrap();
+/// assert_eq!((event.x, event.y), (4, 0));
+/// ```
+pub trait VersionedEvent: NamedEvent {
+    const VERSION: u32;
+}
+
+type Upgrade = Box<dyn Fn(Box<dyn Any + Send + Sync>) -> Box<dyn Any + Send + Sync> + Send + Sync>;
+
+struct UpgradeStep {
+    from_type_id: TypeId,
+    from_type_name: &'static str,
+    to_version: u32,
+    upgrade: Upgrade,
+}
+
+/// An event upgraded by the [UpgradeRegistry].
+pub struct UpgradedEvent {
+    /// Version of the event after upgrading.
+    pub version: u32,
+    /// The upgraded event.
+    pub event: Box<dyn Any + Send + Sync>,
+}
+
+#[derive(Default)]
+/// # UpgradeRegistry
+///
+/// The `UpgradeRegistry` holds the upgrade functions of versioned events.
+/// When replaying or receiving serialized events, an event decoded at an old
+/// version is upgraded step by step up to the latest registered version, so old
+/// recordings remain replayable after the event types evolve.
+///
+/// Each version of an event can be upgraded to a single greater version.
+pub struct UpgradeRegistry {
+    steps: HashMap<&'static str, HashMap<u32, UpgradeStep>>,
+}
+
+impl UpgradeRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers an upgrade function from `Old` to `New`.
+    ///
+    /// Returns an error if both events do not share the same name, if `New` is not
+    /// a greater version than `Old`, or if `Old` already has an upgrade.
+    pub fn register_upgrade<Old, New, F>(&mut self, upgrade: F) -> Result<(), EmarkError>
+    where
+        Old: VersionedEvent + Send + Sync + 'static,
+        New: VersionedEvent + Send + Sync + 'static,
+        F: Fn(Old) -> New + Send + Sync + 'static,
+    {
+        // check upgrade validity
+        if Old::NAME != New::NAME || Old::VERSION >= New::VERSION {
+            return Err(EmarkError::InvalidUpgrade {
+                name: Old::NAME,
+                from: Old::VERSION,
+                to: New::VERSION,
+            });
+        }
+
+        match self.steps.entry(Old::NAME).or_default().entry(Old::VERSION) {
+            Entry::Occupied(_) => Err(EmarkError::DuplicateUpgrade {
+                name: Old::NAME,
+                version: Old::VERSION,
+            }),
+            Entry::Vacant(entry) => {
+                // erase the upgrade type
+                let upgrade: Upgrade = Box::new(move |event| {
+                    let event = *event.downcast::<Old>().unwrap();
+                    Box::new(upgrade(event))
+                });
+
+                entry.insert(UpgradeStep {
+                    from_type_id: TypeId::of::<Old>(),
+                    from_type_name: std::any::type_name::<Old>(),
+                    to_version: New::VERSION,
+                    upgrade,
+                });
+                Ok(())
+            }
+        }
+    }
+
+    /// Returns `true` if version `version` of event `name` can be upgraded.
+    pub fn contains_upgrade(&self, name: &str, version: u32) -> bool {
+        self.step(name, version).is_some()
+    }
+
+    /// Upgrades an event decoded at `version` to the latest reachable version.
+    ///
+    /// Events without upgrade at `version` are returned as is.
+    /// Returns an error if the event is not of the type registered for a step.
+    pub fn upgrade(
+        &self,
+        name: &str,
+        version: u32,
+        event: Box<dyn Any + Send + Sync>,
+    ) -> Result<UpgradedEvent, EmarkError> {
+        let mut upgraded = UpgradedEvent { version, event };
+
+        // apply upgrade steps
+        while let Some(step) = self.step(name, upgraded.version) {
+            if (*upgraded.event).type_id() != step.from_type_id {
+                return Err(EmarkError::PayloadMismatch {
+                    expected: step.from_type_name,
+                });
+            }
+            upgraded.event = (step.upgrade)(upgraded.event);
+            upgraded.version = step.to_version;
+        }
+
+        Ok(upgraded)
+    }
+
+    /// Upgrades an event decoded at `version` into `T`.
+    ///
+    /// Returns an error if the event can not be upgraded up to `T::VERSION`.
+    pub fn upgrade_into<T: VersionedEvent + 'static>(
+        &self,
+        version: u32,
+        event: Box<dyn Any + Send + Sync>,
+    ) -> Result<T, EmarkError> {
+        let upgraded = self.upgrade(T::NAME, version, event)?;
+        if upgraded.version != T::VERSION {
+            return Err(EmarkError::MissingUpgrade {
+                name: T::NAME.to_string(),
+                version: upgraded.version,
+            });
+        }
+
+        upgraded
+            .event
+            .downcast::<T>()
+            .map(|event| *event)
+            .map_err(|_| EmarkError::PayloadMismatch {
+                expected: std::any::type_name::<T>(),
+            })
+    }
+
+    fn step(&self, name: &str, version: u32) -> Option<&UpgradeStep> {
+        self.steps
+            .get(name)
+            .and_then(|versions| versions.get(&version))
+    }
+}
+
+impl Debug for UpgradeRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list()
+            .entries(self.steps.iter().flat_map(|(name, versions)| {
+                versions
+                    .iter()
+                    .map(move |(version, step)| (name, version, step.to_version))
+            }))
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test_version {
+    use super::*;
+    use crate::event::Event;
+
+    macro_rules! versioned {
+        ($name:ident, $version:expr) => {
+            #[derive(Debug, PartialEq)]
+            struct $name(i32);
+            impl Event for $name {}
+            impl NamedEvent for $name {
+                const NAME: &'static str = "test.versioned";
+            }
+            impl VersionedEvent for $name {
+                const VERSION: u32 = $version;
+            }
+        };
+    }
+
+    versioned!(TestEventV1, 1);
+    versioned!(TestEventV2, 2);
+    versioned!(TestEventV3, 3);
+
+    #[test]
+    fn test_upgrade_chain() {
+        let mut registry = UpgradeRegistry::new();
+        registry
+            .register_upgrade(|old: TestEventV1| TestEventV2(old.0 + 1))
+            .unwrap();
+        registry
+            .register_upgrade(|old: TestEventV2| TestEventV3(old.0 * 10))
+            .unwrap();
+        assert!(registry.contains_upgrade("test.versioned", 1));
+
+        // assert old recordings are upgraded step by step
+        assert_eq!(
+            registry.upgrade_into::<TestEventV3>(1, Box::new(TestEventV1(1))),
+            Ok(TestEventV3(20))
+        );
+        assert_eq!(
+            registry.upgrade_into::<TestEventV3>(3, Box::new(TestEventV3(5))),
+            Ok(TestEventV3(5))
+        );
+
+        let upgraded = registry
+            .upgrade("test.versioned", 2, Box::new(TestEventV2(1)))
+            .unwrap();
+        assert_eq!(upgraded.version, 3);
+        assert!(upgraded.event.is::<TestEventV3>());
+    }
+
+    #[test]
+    fn test_upgrade_errors() {
+        let mut registry = UpgradeRegistry::new();
+        assert_eq!(
+            registry.register_upgrade(|old: TestEventV2| TestEventV1(old.0)),
+            Err(EmarkError::InvalidUpgrade {
+                name: "test.versioned",
+                from: 2,
+                to: 1
+            })
+        );
+
+        registry
+            .register_upgrade(|old: TestEventV1| TestEventV2(old.0))
+            .unwrap();
+        assert_eq!(
+            registry.register_upgrade(|old: TestEventV1| TestEventV3(old.0)),
+            Err(EmarkError::DuplicateUpgrade {
+                name: "test.versioned",
+                version: 1
+            })
+        );
+
+        // assert payload of the wrong type is rejected
+        assert!(matches!(
+            registry.upgrade("test.versioned", 1, Box::new(0u8)),
+            Err(EmarkError::PayloadMismatch { .. })
+        ));
+
+        // assert missing upgrade up to the requested version
+        assert_eq!(
+            registry.upgrade_into::<TestEventV3>(1, Box::new(TestEventV1(0))),
+            Err(EmarkError::MissingUpgrade {
+                name: "test.versioned".to_string(),
+                version: 2
+            })
+        );
+    }
+}
@@ -0,0 +1,127 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    task::{Context, Poll, Waker},
+};
+
+use parking_lot::Mutex;
+
+#[derive(Debug, Default)]
+// state shared between a watch and the `EventManager`
+pub(crate) struct WatchState {
+    emitted: AtomicBool,
+    waker: Mutex<Option<Waker>>,
+}
+
+impl WatchState {
+    // mark the watched event as emitted and wake the watching task
+    pub(crate) fn notify(&self) {
+        self.emitted.store(true, Ordering::Release);
+        if let Some(waker) = self.waker.lock().take() {
+            waker.wake();
+        }
+    }
+}
+
+#[derive(Debug)]
+/// Future returned by `EventManager::watch`.
+///
+/// Resolves once an event of the watched type is emitted after the creation of the
+/// watch. The future does not depend on any particular async runtime.
+///
+/// # Examples
+/// ```
+/// use emark::event::EventManager;
+/// use emark::prelude::Event;
+///
+/// struct Loaded;
+/// impl Event for Loaded {}
+///
+/// async fn on_loaded(event_manager: &EventManager) {
+///     event_manager.watch::<Loaded>().await;
+///     // react to the event outside of the dispatch loop
+/// }
+/// ```
+pub struct EventWatch {
+    state: Arc<WatchState>,
+}
+
+impl EventWatch {
+    pub(crate) fn new(state: Arc<WatchState>) -> Self {
+        Self { state }
+    }
+
+    /// Returns `true` if the watched event has been emitted.
+    pub fn is_emitted(&self) -> bool {
+        self.state.emitted.load(Ordering::Acquire)
+    }
+}
+
+impl Future for EventWatch {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if self.is_emitted() {
+            return Poll::Ready(());
+        }
+
+        // register waker then check again, the event may have been emitted in between
+        *self.state.waker.lock() = Some(cx.waker().clone());
+        match self.is_emitted() {
+            true => Poll::Ready(()),
+            false => Poll::Pending,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_watch {
+    #[cfg(not(feature = "unsync"))]
+    use std::{sync::Arc, thread};
+
+    use crate::{
+        event::{Event, EventManager},
+        utils::block_on::block_on,
+    };
+
+    struct TestEvent;
+    impl Event for TestEvent {}
+
+    struct OtherEvent;
+    impl Event for OtherEvent {}
+
+    #[test]
+    fn test_watch_emitted_before_poll() {
+        let event_manager = EventManager::new();
+        let watch = event_manager.watch::<TestEvent>();
+        event_manager.emit(OtherEvent);
+        assert!(!watch.is_emitted());
+
+        event_manager.emit(TestEvent);
+        assert!(watch.is_emitted());
+        block_on(watch);
+    }
+
+    #[cfg(not(feature = "unsync"))]
+    #[test]
+    fn test_watch_wakes_task() {
+        let event_manager = Arc::new(EventManager::new());
+        let watch = event_manager.watch::<TestEvent>();
+
+        let emitter = event_manager.clone();
+        let handle = thread::spawn(move || {
+            thread::sleep(std::time::Duration::from_millis(10));
+            emitter.emit(TestEvent);
+        });
+
+        block_on(watch);
+        handle.join().unwrap();
+
+        // assert watches only observe emissions after their creation
+        assert!(!event_manager.watch::<TestEvent>().is_emitted());
+    }
+}
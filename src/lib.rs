@@ -1,5 +1,17 @@
+// lets the derive macros refer to `::emark` within the crate
+extern crate self as emark;
+
 mod utils;
+pub mod app;
+pub mod asset;
 pub mod event;
 pub mod store;
+pub mod system;
+pub mod world;
+
+pub mod prelude;
 
-pub mod prelude;
\ No newline at end of file
+#[doc(inline)]
+pub use utils::error::EmarkError;
+#[doc(inline)]
+pub use utils::lock;
@@ -1,4 +1,9 @@
+pub use crate::app::{in_state, App, AppExit, NextState, OnEnter, OnExit, Plugin, State, States};
 pub use crate::event;
-pub use crate::event::event::Event;
+pub use crate::event::event::{Event, KeyedEvent};
 pub use crate::event::priority::Priority;
-pub use crate::store::Container;
+pub use crate::store::{
+    Commands, Component, Container, Context, Entities, Entity, Query, Res, ResMut, Retriever,
+};
+pub use crate::system::{ExclusiveSystem, IntoSystem, Schedule, System};
+pub use crate::world::World;
@@ -0,0 +1,126 @@
+use std::{any::TypeId, convert::Infallible};
+
+use super::{res::Storage, ReadOnlyRetriever, Request, Retrievable, Retrieved, Retriever};
+
+/// Borrow of the first resource found among two to four candidates, e.g. for handlers working
+/// with either the GPU or the CPU implementation of a renderer, depending on the one registered
+/// by the application.
+///
+/// The candidates are [Retrievable]s such as `Res<T>` or `ResMut<T>`, tried in declaration
+/// order. The candidates found in the container are all locked along with the other resources
+/// of the retriever, so the access of a system includes every candidate. The retrieval fails
+/// with `RetrievalError::NotFoundAny` if none of them is found.
+///
+/// # Examples
+/// ```
+/// use emark::prelude::*;
+/// use emark::store::{AnyOf, ResourceContainer};
+///
+/// struct GpuRenderer;
+/// struct CpuRenderer;
+///
+/// type Renderer<'a> = AnyOf<Res<'a, GpuRenderer>, Res<'a, CpuRenderer>>;
+///
+/// fn backend(renderer: Renderer) -> &'static str {
+///     match renderer {
+///         AnyOf::First(_) => "gpu",
+///         AnyOf::Second(_) => "cpu",
+///     }
+/// }
+///
+/// let mut container = ResourceContainer::default();
+/// container.add_resource(CpuRenderer);
+/// assert_eq!(backend(Renderer::retrieve(&container)), "cpu");
+///
+/// container.add_resource(GpuRenderer);
+/// assert_eq!(backend(Renderer::retrieve(&container)), "gpu");
+/// ```
+#[derive(Debug)]
+pub enum AnyOf<A, B, C = Infallible, D = Infallible> {
+    First(A),
+    Second(B),
+    Third(C),
+    Fourth(D),
+}
+
+macro_rules! impl_any_of {
+    ($(($candidate:ident, $variant:ident)),*; $($unused:ty),*) => {
+        impl<$($candidate: Retrievable),*> Retriever for AnyOf<$($candidate,)* $($unused,)*> {
+            type Item<'a> = AnyOf<$(<$candidate as Retrievable>::Item<'a>,)* $($unused,)*>;
+
+            fn requests(requests: &mut Vec<Request>) {
+                requests.push(Request {
+                    type_id: TypeId::of::<Infallible>(),
+                    type_name: std::any::type_name::<Self>(),
+                    mutable: false,
+                    storage: Storage::Alternatives([$(stringify!($candidate)),*].len()),
+                    optional: false,
+                });
+                $(
+                    <$candidate as Retriever>::requests(requests);
+                    requests.last_mut().unwrap().optional = true;
+                )*
+            }
+
+            fn assemble<'a>(retrieved: &mut dyn Iterator<Item = Retrieved<'a>>) -> Self::Item<'a> {
+                retrieved.next().unwrap();
+                // the candidates after the first one found are released once assembled
+                let mut item = None;
+                $(
+                    let resource = retrieved.next().unwrap();
+                    if item.is_none() && !resource.is_absent() {
+                        item = Some(AnyOf::$variant($candidate::from_retrieved(resource)));
+                    }
+                )*
+                item.unwrap()
+            }
+        }
+
+        impl<$($candidate: Retrievable + ReadOnlyRetriever),*> ReadOnlyRetriever
+            for AnyOf<$($candidate,)* $($unused,)*>
+        {
+        }
+    };
+}
+
+impl_any_of!((A, First), (B, Second); Infallible, Infallible);
+impl_any_of!((A, First), (B, Second), (C, Third); Infallible);
+impl_any_of!((A, First), (B, Second), (C, Third), (D, Fourth););
+
+#[cfg(test)]
+mod test_any_of {
+    use super::*;
+    use crate::{
+        event::EventManager,
+        store::{Container, Res, ResMut, ResourceContainer, RetrievalError},
+        system::{IntoSystem, System},
+    };
+
+    #[test]
+    fn test_any_of() {
+        let mut container = ResourceContainer::default();
+        container.add_resource(2u64);
+        container.add_resource(3u8);
+
+        // assert the first candidate found is borrowed
+        match AnyOf::<Res<u32>, ResMut<u64>, Res<u8>>::retrieve(&container) {
+            AnyOf::Second(mut value) => *value += 1,
+            _ => panic!("expected the second candidate"),
+        }
+        assert_eq!(*Res::<u64>::retrieve(&container), 3);
+
+        // assert the other candidates are released
+        assert!(ResMut::<u8>::try_retrieve(&container).is_ok());
+
+        let error = AnyOf::<Res<u32>, Res<i32>>::try_retrieve(&container).unwrap_err();
+        assert_eq!(error, RetrievalError::NotFoundAny(vec!["u32", "i32"]));
+
+        // assert systems take alternatives
+        let system = |value: AnyOf<Res<u32>, ResMut<u8>>| {
+            assert!(matches!(value, AnyOf::Second(value) if *value == 3));
+        };
+        let mut system = system.into_system();
+        assert_eq!(system.access().writes(), &[TypeId::of::<u8>()]);
+        system.run(&container, &EventManager::new()).unwrap();
+    }
+}
@@ -0,0 +1,279 @@
+use std::{
+    any::{Any, TypeId},
+    ptr::NonNull,
+    sync::{Arc, Weak},
+    time::Duration,
+};
+
+use crate::utils::lock::{
+    grained_ref::{Immutable, LockState, Mutable},
+    GrainedLock, Ref,
+};
+
+#[cfg(debug_assertions)]
+use super::lock_order;
+use super::{tick::ChangeTicks, Retrieved};
+
+// resource added with a type only known at runtime, which can not be stored inline
+struct Boxed(Box<dyn Any + Send + Sync>);
+
+// moves the resource out of an entry removed from the container, given the pointer of its `Arc`
+type Take = unsafe fn(*const ()) -> Box<dyn Any>;
+
+unsafe fn take_inline<T: Any>(entry: *const ()) -> Box<dyn Any> {
+    Box::new(into_inner::<T>(entry))
+}
+
+unsafe fn take_boxed(entry: *const ()) -> Box<dyn Any> {
+    into_inner::<Boxed>(entry).0
+}
+
+// the entry must have been created with a resource of type `T`, and the container hold its only
+// strong reference
+unsafe fn into_inner<T>(entry: *const ()) -> T {
+    let entry = Arc::from_raw(entry as *const ResourceEntry<T>);
+    Arc::into_inner(entry).unwrap().lock.take()
+}
+
+// type of the resources of the entries once erased, `Send + Sync` for the resources shared with
+// all threads, and `dyn Any` for the non-send resources
+pub(crate) trait Erased: 'static {
+    fn erase(resource: NonNull<Self>) -> NonNull<dyn Any>;
+}
+
+impl Erased for dyn Any {
+    fn erase(resource: NonNull<Self>) -> NonNull<dyn Any> {
+        resource
+    }
+}
+
+impl Erased for dyn Any + Send + Sync {
+    fn erase(resource: NonNull<Self>) -> NonNull<dyn Any> {
+        resource
+    }
+}
+
+// locked resource with its change ticks.
+//
+// The resource is stored inline at the end of the shared allocation of the entry, so a
+// retrieval follows a single pointer to it, and is dropped through the vtable of the type-erased
+// entry. Only the resources added with a type known at runtime keep their own box.
+//
+// The entry is only `Send + Sync` if its resource is, the non-send resources being erased to an
+// entry of `dyn Any`.
+#[derive(Debug)]
+pub(crate) struct ResourceEntry<T: ?Sized = dyn Any + Send + Sync> {
+    pub(crate) ticks: ChangeTicks,
+    pub(crate) type_name: Option<&'static str>,
+    take: Take,
+    lock: GrainedLock<T>,
+}
+
+impl ResourceEntry {
+    pub(crate) fn new<T: Any + Send + Sync>(
+        resource: T,
+        ticks: ChangeTicks,
+        type_name: Option<&'static str>,
+    ) -> Arc<Self> {
+        Arc::new(ResourceEntry {
+            ticks,
+            type_name,
+            take: take_inline::<T>,
+            lock: GrainedLock::new(resource),
+        })
+    }
+
+    pub(crate) fn boxed(
+        resource: Box<dyn Any + Send + Sync>,
+        ticks: ChangeTicks,
+        type_name: Option<&'static str>,
+    ) -> Arc<Self> {
+        Arc::new(ResourceEntry {
+            ticks,
+            type_name,
+            take: take_boxed,
+            lock: GrainedLock::new(Boxed(resource)),
+        })
+    }
+
+    // weak reference never upgrading
+    pub(crate) fn dangling() -> Weak<Self> {
+        Weak::<ResourceEntry<()>>::new()
+    }
+}
+
+impl ResourceEntry<dyn Any> {
+    // entry of a resource only accessed from the thread owning the container
+    pub(crate) fn non_send<T: Any>(
+        resource: T,
+        ticks: ChangeTicks,
+        type_name: Option<&'static str>,
+    ) -> Arc<Self> {
+        Arc::new(ResourceEntry {
+            ticks,
+            type_name,
+            take: take_inline::<T>,
+            lock: GrainedLock::new(resource),
+        })
+    }
+}
+
+impl<E: ?Sized + Erased> ResourceEntry<E> {
+    pub(crate) fn borrow(&self) -> Ref<'_, dyn Any, Immutable> {
+        unboxed(erased(self.lock.borrow()))
+    }
+
+    // shared borrow acquired even while a writer is waiting, see `GrainedLock`
+    pub(crate) fn borrow_recursive(&self) -> Ref<'_, dyn Any, Immutable> {
+        unboxed(erased(self.lock.borrow_recursive()))
+    }
+
+    pub(crate) fn borrow_mut(&self) -> Ref<'_, dyn Any, Mutable> {
+        unboxed(erased(self.lock.borrow_mut()))
+    }
+
+    // borrow of the resource `type_id` for a retriever
+    pub(crate) fn retrieved(&self, type_id: TypeId, mutable: bool) -> Retrieved<'_> {
+        #[cfg(debug_assertions)]
+        let order = lock_order::lock(type_id, self.type_name.unwrap_or("<unnamed>"), mutable);
+        #[cfg(not(debug_assertions))]
+        let _ = type_id;
+        let retrieved = match mutable {
+            true => {
+                let resource = self.borrow_mut();
+                #[cfg(debug_assertions)]
+                let resource = resource.with_guard(order);
+                Retrieved::mutable(resource, Some(&self.ticks))
+            }
+            false => {
+                let resource = self.borrow();
+                #[cfg(debug_assertions)]
+                let resource = resource.with_guard(order);
+                Retrieved::immutable(resource, Some(&self.ticks))
+            }
+        };
+        Retrieved {
+            type_name: self.type_name,
+            ..retrieved
+        }
+    }
+
+    // borrow of the resource for a retriever, `None` if its lock is still held after waiting
+    // for `timeout`, or immediately without a timeout
+    pub(crate) fn try_retrieved(
+        &self,
+        mutable: bool,
+        timeout: Option<Duration>,
+    ) -> Option<Retrieved<'_>> {
+        let ticks = Some(&self.ticks);
+        let retrieved = match mutable {
+            true => Retrieved::mutable(
+                unboxed(erased(match timeout {
+                    Some(timeout) => self.lock.borrow_mut_timeout(timeout)?,
+                    None => self.lock.try_borrow_mut()?,
+                })),
+                ticks,
+            ),
+            false => Retrieved::immutable(
+                unboxed(erased(match timeout {
+                    Some(timeout) => self.lock.borrow_timeout(timeout)?,
+                    None => self.lock.try_borrow()?,
+                })),
+                ticks,
+            ),
+        };
+        Some(Retrieved {
+            type_name: self.type_name,
+            ..retrieved
+        })
+    }
+
+    // an exclusive borrow of the resource was released while unwinding from a panic
+    pub(crate) fn is_poisoned(&self) -> bool {
+        self.lock.is_poisoned()
+    }
+
+    pub(crate) fn clear_poison(&self) {
+        self.lock.clear_poison();
+    }
+
+    // takes the resource out of the entry removed from the container
+    pub(crate) fn take(self: Arc<Self>) -> Box<dyn Any> {
+        let take = self.take;
+        // the type-erased entry points to the entry of the type `take` was created for
+        unsafe { take(Arc::into_raw(self) as *const ()) }
+    }
+
+    // takes the resource of type `T` out of the entry, without boxing it if stored inline
+    pub(crate) fn take_as<T: Any>(self: Arc<Self>) -> T {
+        if erased(self.lock.borrow()).is::<T>() {
+            return unsafe { into_inner(Arc::into_raw(self) as *const ()) };
+        }
+        *self.take().downcast().unwrap()
+    }
+}
+
+// borrow of the resource as a `dyn Any`, dropping the auto traits of the erased type
+fn erased<E: ?Sized + Erased, S: LockState>(resource: Ref<'_, E, S>) -> Ref<'_, dyn Any, S> {
+    // the pointer is only cast to another trait object of the same resource
+    unsafe { resource.map_unchecked::<dyn Any, S>(E::erase) }
+}
+
+// borrow of the resource itself rather than of its box
+fn unboxed<S: LockState>(resource: Ref<'_, dyn Any, S>) -> Ref<'_, dyn Any, S> {
+    if !resource.is::<Boxed>() {
+        return resource;
+    }
+    // the box is kept alive and locked by the guards of the ref
+    unsafe {
+        resource.map_unchecked::<dyn Any, S>(|mut data| {
+            // only create a mutable reference from an exclusive lock
+            match S::MUTABLE {
+                true => NonNull::from(&mut *data.as_mut().downcast_mut::<Boxed>().unwrap().0),
+                false => NonNull::from(&*data.as_ref().downcast_ref::<Boxed>().unwrap().0),
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod test_blob {
+    use super::*;
+
+    #[test]
+    fn test_resource_entry() {
+        let dropped = Arc::new(());
+        let entry = ResourceEntry::new(dropped.clone(), ChangeTicks::new(0), None);
+        assert!(entry.borrow().is::<Arc<()>>());
+        drop(entry);
+        assert_eq!(Arc::strong_count(&dropped), 1);
+
+        let entry = ResourceEntry::new(1u32, ChangeTicks::new(0), None);
+        *entry.borrow_mut().downcast_mut::<u32>().unwrap() += 1;
+        assert_eq!(entry.take_as::<u32>(), 2);
+
+        // assert boxed resources are borrowed and taken without their box
+        let entry = ResourceEntry::boxed(Box::new(3u64), ChangeTicks::new(0), None);
+        assert_eq!(entry.borrow_recursive().downcast_ref::<u64>(), Some(&3));
+        assert_eq!(*entry.take().downcast::<u64>().unwrap(), 3);
+        let entry = ResourceEntry::boxed(Box::new(4u64), ChangeTicks::new(0), None);
+        assert_eq!(entry.take_as::<u64>(), 4);
+
+        // assert borrows fail instead of blocking on a held lock
+        let entry = ResourceEntry::boxed(Box::new(5u64), ChangeTicks::new(0), None);
+        let borrow = entry.try_retrieved(false, None).unwrap();
+        assert!(entry.try_retrieved(false, None).is_some());
+        assert!(entry.try_retrieved(true, None).is_none());
+        assert!(entry
+            .try_retrieved(true, Some(Duration::from_millis(1)))
+            .is_none());
+        drop(borrow);
+        assert_eq!(
+            entry
+                .try_retrieved(true, None)
+                .unwrap()
+                .downcast_ref::<u64>(),
+            Ok(&5)
+        );
+    }
+}
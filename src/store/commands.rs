@@ -0,0 +1,181 @@
+use std::{any::TypeId, fmt::Debug};
+
+use parking_lot::Mutex;
+
+use crate::utils::lock::{grained_ref::Immutable, Ref};
+
+use super::{
+    res::Storage,
+    Container, Request, ResourceContainer, Retrieved, Retriever,
+};
+
+type Command = Box<dyn FnOnce(&mut ResourceContainer) + Send>;
+
+// mutations recorded by `Commands`, applied by `ResourceContainer::apply_commands`
+#[derive(Default)]
+pub(crate) struct CommandQueue {
+    commands: Mutex<Vec<Command>>,
+}
+
+impl CommandQueue {
+    pub(crate) fn take(&mut self) -> Vec<Command> {
+        std::mem::take(self.commands.get_mut())
+    }
+}
+
+impl Debug for CommandQueue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CommandQueue")
+            .field("len", &self.commands.lock().len())
+            .finish()
+    }
+}
+
+/// Buffer of deferred mutations of a `ResourceContainer`.
+///
+/// Systems only hold a shared reference to the container, so they can not add or remove
+/// resources while other systems borrow them. `Commands` records these structural changes
+/// instead, applied in the order they were recorded by `ResourceContainer::apply_commands`.
+/// Schedules run with `Schedule::run_world` apply them at the barrier closing each stage, before
+/// its exclusive systems run.
+///
+/// `Commands` does not lock any resource, so systems recording commands still run concurrently.
+/// The commands recorded by concurrent systems are interleaved in no particular order.
+///
+/// # Examples
+/// ```
+/// use emark::prelude::*;
+/// use emark::store::{Commands, ResourceContainer};
+///
+/// let mut container = ResourceContainer::default();
+/// container.add_resource(1u32);
+///
+/// {
+///     let (count, mut commands) = <(Res<u32>, Commands)>::retrieve(&container);
+///     commands.insert(format!("count: {}", *count));
+///     commands.modify(|count: &mut u32| *count += 1);
+///     commands.remove::<u64>();
+/// }
+/// container.apply_commands();
+/// assert_eq!(*Res::<String>::retrieve(&container), "count: 1");
+/// assert_eq!(*Res::<u32>::retrieve(&container), 2);
+/// ```
+pub struct Commands<'a> {
+    queue: Ref<'a, CommandQueue, Immutable>,
+}
+
+impl Commands<'_> {
+    /// Records a custom mutation of the container.
+    pub fn add(&mut self, command: impl FnOnce(&mut ResourceContainer) + Send + 'static) {
+        self.queue.commands.lock().push(Box::new(command));
+    }
+
+    /// Records the addition of a resource, replacing the resource of the same type.
+    pub fn insert<T: Send + Sync + 'static>(&mut self, resource: T) {
+        self.add(move |container| container.insert_overwrite(resource));
+    }
+
+    /// Records the removal of the resource of type `T`, if any.
+    pub fn remove<T: 'static>(&mut self) {
+        self.add(|container| {
+            container.remove_resource::<T>();
+        });
+    }
+
+    /// Records a mutation of the resource of type `T`, skipped if the container holds none
+    /// once the commands are applied.
+    pub fn modify<T: 'static>(&mut self, f: impl FnOnce(&mut T) + Send + 'static) {
+        self.add(|container| {
+            if let Ok(mut resource) = super::ResMut::<T>::try_get(container) {
+                f(&mut resource);
+            }
+        });
+    }
+
+    /// Number of commands pending in the container, including those recorded by other systems.
+    pub fn len(&self) -> usize {
+        self.queue.commands.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Retriever for Commands<'_> {
+    type Item<'a> = Commands<'a>;
+
+    fn requests(requests: &mut Vec<Request>) {
+        requests.push(Request {
+            type_id: TypeId::of::<CommandQueue>(),
+            type_name: std::any::type_name::<Commands>(),
+            mutable: false,
+            storage: Storage::Commands,
+            optional: false,
+        });
+    }
+
+    fn assemble<'a>(retrieved: &mut dyn Iterator<Item = Retrieved<'a>>) -> Self::Item<'a> {
+        let queue = retrieved
+            .next()
+            .unwrap()
+            .take_shared(std::any::type_name::<Commands>())
+            .unwrap_or_else(|error| panic!("{error}"));
+        Commands { queue }
+    }
+}
+
+impl Debug for Commands<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Commands")
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test_commands {
+    use super::*;
+    use crate::{
+        event::EventManager,
+        store::Res,
+        system::{IntoSystem, Schedule, System},
+        world::World,
+    };
+
+    #[test]
+    fn test_commands_deferred() {
+        let mut container = ResourceContainer::default();
+        container.add_resource(1u32);
+
+        let system = |count: Res<u32>, mut commands: Commands| {
+            commands.insert(*count as u64);
+            commands.remove::<u32>();
+        };
+        system
+            .into_system()
+            .run(&container, &EventManager::new())
+            .unwrap();
+
+        // assert the commands are only applied on demand
+        assert!(container.contains_resource::<u32>());
+        container.apply_commands();
+        assert!(!container.contains_resource::<u32>());
+        assert_eq!(*Res::<u64>::retrieve(&container), 1);
+    }
+
+    #[test]
+    fn test_commands_stage_barrier() {
+        let mut schedule = Schedule::new();
+        schedule
+            .add_system(|mut commands: Commands| commands.insert(1u32))
+            .add_exclusive_system(|world: &mut World| {
+                assert!(world.container().contains_resource::<u32>());
+                world.container_mut().add_resource(2u32);
+            });
+
+        let mut world = World::new();
+        schedule.run_world(&mut world);
+        assert_eq!(*Res::<u32>::retrieve(world.container()), 2);
+    }
+}
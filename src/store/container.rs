@@ -1,56 +1,1082 @@
 use std::{
     any::{Any, TypeId},
     collections::HashMap,
+    io::{Read, Write},
+    ptr::NonNull,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Weak,
+    },
+    time::Duration,
 };
 
-use crate::utils::lock::GrainedLock;
+use crate::utils::{error::EmarkError, lock::GrainedLock};
 
+use super::{
+    blob::ResourceEntry,
+    commands::CommandQueue,
+    dependency::{init_ordered, Dependency},
+    interface::InterfaceRegistry,
+    mem_size::{MemSize, MemSizeRegistry},
+    non_send::NonSendResources,
+    observer::WriteObservers,
+    persist::{PersistRegistry, PersistentResource},
+    reflect::{Reflect, ReflectRegistry},
+    res::{downcast, erase, unbox, Storage},
+    sharded::ShardedMap,
+    tick::{ChangeClock, ChangeTicks},
+    transaction::Transaction,
+    Entities, MemoryUsage, Request, Res, ResHandle, Resource, RetrievalError, Retrieved, Retriever,
+    SystemTicks,
+};
+
+// source of the ids of the containers
+static CONTAINER_IDS: AtomicU64 = AtomicU64::new(0);
+
+/// Resource constructed from the resources of a `ResourceContainer`, see
+/// [Container::init_resource].
+///
+/// Implemented for every type implementing `Default`. The resources it depends on are declared
+/// by `dependencies`, initialized first in dependency order, so interdependent resources can be
+/// initialized in any order. Cyclic or missing dependencies are reported as errors by
+/// `ResourceContainer::try_init_resource`.
+///
+/// # Examples
+/// ```
+/// use emark::prelude::*;
+/// use emark::store::{Dependency, FromContainer, ResourceContainer};
+///
+/// #[derive(Default)]
+/// struct Settings {
+///     scale: u32,
+/// }
+///
+/// struct Viewport {
+///     width: u32,
+/// }
+///
+/// impl FromContainer for Viewport {
+///     fn dependencies() -> Vec<Dependency> {
+///         vec![Dependency::init::<Settings>()]
+///     }
+///
+///     fn from_container(container: &mut ResourceContainer) -> Self {
+///         let scale = Res::<Settings>::retrieve(container).scale;
+///         Viewport { width: 640 * scale.max(1) }
+///     }
+/// }
+///
+/// let mut container = ResourceContainer::default();
+/// container.init_resource::<Viewport>();
+/// assert!(container.contains_resource::<Settings>());
+/// assert_eq!(Res::<Viewport>::retrieve(&container).width, 640);
+/// ```
+pub trait FromContainer: Send + Sync + 'static {
+    /// Resources initialized before this one by `init_resource`. Defaults to none.
+    fn dependencies() -> Vec<Dependency>
+    where
+        Self: Sized,
+    {
+        Vec::new()
+    }
+
+    fn from_container(container: &mut ResourceContainer) -> Self;
+}
+
+impl<T: Default + Send + Sync + 'static> FromContainer for T {
+    fn from_container(_: &mut ResourceContainer) -> Self {
+        T::default()
+    }
+}
+
+/// Storage of the resources of an application.
+///
+/// Resources are shared with the systems running on every thread of an executor, so the
+/// resources added with `add_resource` and the other insertion methods must be `Send` and
+/// `Sync`, making the container itself `Send` and `Sync`. Resources that are not, such as
+/// window handles, are added with `add_non_send` and only retrieved on the thread that created
+/// the container.
 pub trait Container {
-    fn add_resource<T: 'static>(&mut self, resource: T);
-    fn add_resource_any(&mut self, type_id: TypeId, resource: Box<dyn Any>);
+    /// Adds a resource, returning the resource of the same type it replaces, if any.
+    fn add_resource<T: Send + Sync + 'static>(&mut self, resource: T) -> Option<T>;
+    /// Adds a resource, returning an error if the container already holds a resource of type
+    /// `T`.
+    ///
+    /// The resources of the parent are not considered.
+    fn try_add_resource<T: Send + Sync + 'static>(&mut self, resource: T)
+        -> Result<(), EmarkError>;
+    /// Adds a resource, dropping the resource of the same type it replaces.
+    fn insert_overwrite<T: Send + Sync + 'static>(&mut self, resource: T);
+    /// Adds a resource constructed with [FromContainer], unless the container already holds a
+    /// resource of type `T`, first initializing its dependencies.
+    ///
+    /// # Panics
+    /// Panics if the dependencies are cyclic or missing, see
+    /// `ResourceContainer::try_init_resource`.
+    fn init_resource<T: FromContainer>(&mut self);
+    /// Borrows the resource of type `T`, first inserting the resource returned by `f` if the
+    /// container holds none.
+    ///
+    /// The insertion only needs a shared reference to the container and is atomic, so systems
+    /// racing to create a cache insert it once, the others borrowing the inserted resource.
+    /// `f` runs while the resources are locked for the insertion, so it must not access the
+    /// container.
+    fn get_or_insert_with<T: Send + Sync + 'static>(&self, f: impl FnOnce() -> T) -> Res<'_, T>;
+    /// Adds a boxed trait object as the resource of type `T`, retrieved as `Res<T>`, see
+    /// [Resource](super::Resource).
+    ///
+    /// Each interface a value is added as holds its own value.
+    fn add_resource_as<T: ?Sized + Send + Sync + 'static>(&mut self, resource: Box<T>);
+    /// Adds the resource `type_id`, whose type is only known at runtime.
+    ///
+    /// Resources added with a static type are stored inline in their entry, while this resource
+    /// keeps its box, costing an extra pointer hop per retrieval.
+    fn add_resource_any(&mut self, type_id: TypeId, resource: Box<dyn Any + Send + Sync>);
     fn remove_resource<T: 'static>(&mut self) -> Option<T>;
+    /// Removes the trait object added with `add_resource_as`.
+    fn remove_resource_as<T: ?Sized + 'static>(&mut self) -> Option<Box<T>>;
     fn remove_resource_any(&mut self, type_id: TypeId) -> Option<Box<dyn Any>>;
-    fn contains_resource<T: 'static>(&self) -> bool;
+    fn contains_resource<T: ?Sized + 'static>(&self) -> bool;
     fn contains_resource_any(&self, type_id: TypeId) -> bool;
+    /// Adds a resource that is not `Send`, retrieved with `NonSend` or `NonSendMut` on the
+    /// thread that created the container only.
+    ///
+    /// # Panics
+    /// Panics on another thread than the one that created the container, as does
+    /// `remove_non_send`.
+    fn add_non_send<T: 'static>(&mut self, resource: T);
+    fn remove_non_send<T: 'static>(&mut self) -> Option<T>;
+    fn contains_non_send<T: 'static>(&self) -> bool;
+    /// Types of the resources held by the container, excluding the non-send resources and the
+    /// resources of the parent, in no particular order.
+    fn resource_types(&self) -> Vec<TypeId>;
+    /// Type name of the resource `type_id`, known for the resources added with a static type,
+    /// unlike the resources added with `add_resource_any`.
+    fn resource_type_name(&self, type_id: TypeId) -> Option<&'static str>;
+    /// Removes every resource, including the non-send resources.
+    ///
+    /// The non-send resources are leaked instead of dropped on another thread than the one
+    /// that created the container, as they are by `retain` and when dropping the container.
+    ///
+    /// Borrows of the resources hold a shared reference to the container, so no resource is
+    /// borrowed while it is cleared.
+    fn clear(&mut self);
+    /// Keeps the resources, including the non-send resources, whose type `retain` returns
+    /// `true` for, dropping the others.
+    fn retain(&mut self, retain: impl FnMut(TypeId) -> bool);
+}
+
+// clones a resource into a snapshot, and from the snapshot into a new entry of the container
+#[derive(Debug, Clone, Copy)]
+struct Cloner {
+    capture: fn(&dyn Any) -> Box<dyn Any + Send + Sync>,
+    restore: fn(&ResourceContainer, &dyn Any, Option<&'static str>) -> Arc<ResourceEntry>,
+}
+
+fn capture_resource<T: Clone + Send + Sync + 'static>(
+    resource: &dyn Any,
+) -> Box<dyn Any + Send + Sync> {
+    Box::new(resource.downcast_ref::<T>().unwrap().clone())
+}
+
+fn restore_resource<T: Clone + Send + Sync + 'static>(
+    container: &ResourceContainer,
+    resource: &dyn Any,
+    type_name: Option<&'static str>,
+) -> Arc<ResourceEntry> {
+    container.new_entry(resource.downcast_ref::<T>().unwrap().clone(), type_name)
+}
+
+// captured resource with its cloner and type name
+type SnapshotEntry = (Box<dyn Any + Send + Sync>, Cloner, Option<&'static str>);
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+/// Handling of the resources not registered for snapshots, see `ResourceContainer::snapshot`.
+pub enum SnapshotPolicy {
+    /// Fails the snapshot with an error. This is the default.
+    #[default]
+    Error,
+    /// Leaves the resource out of the snapshot.
+    Skip,
+}
+
+#[derive(Debug)]
+/// Cloneable resources of a `ResourceContainer` captured by `snapshot`.
+///
+/// A snapshot can be restored any number of times with `restore`.
+pub struct ContainerSnapshot {
+    resources: HashMap<TypeId, SnapshotEntry>,
+}
+
+impl ContainerSnapshot {
+    /// Number of resources captured by the snapshot.
+    pub fn len(&self) -> usize {
+        self.resources.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.resources.is_empty()
+    }
+
+    /// Returns `true` if the snapshot captured the resource of type `T`.
+    pub fn contains<T: 'static>(&self) -> bool {
+        self.resources.contains_key(&TypeId::of::<T>())
+    }
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug)]
 pub struct ResourceContainer {
-    resources: HashMap<TypeId, GrainedLock<Box<dyn Any>>>,
+    // unique id of the container, binding the handles to it
+    id: u64,
+    // entries are shared to be borrowed past the lock of their shard, and only dropped through
+    // `&mut self`, the handles only holding weak references
+    resources: ShardedMap<Arc<ResourceEntry>>,
+    // resources only accessed from the thread creating the container
+    non_send: NonSendResources,
+    // mutations recorded by `Commands`
+    commands: GrainedLock<CommandQueue>,
+    // resources captured by snapshots
+    cloners: HashMap<TypeId, Cloner>,
+    // resources persisted by `serialize`
+    persistent: PersistRegistry,
+    // resources inspected by `reflect`
+    reflected: ReflectRegistry,
+    // resources measured deeply by `memory_usage`
+    measured: MemSizeRegistry,
+    // observers of the writes through `ResMut`, kept when the resources are replaced
+    observers: HashMap<TypeId, WriteObservers>,
+    // resources retrieved by `ResAll`
+    interfaces: InterfaceRegistry,
+    // container retrieving the resources missing from this one
+    parent: Option<Arc<ResourceContainer>>,
+    clock: ChangeClock,
+    // tick of the last `clear_changes`
+    cleared: AtomicU64,
+}
+
+impl Default for ResourceContainer {
+    fn default() -> Self {
+        Self {
+            id: CONTAINER_IDS.fetch_add(1, Ordering::Relaxed),
+            resources: ShardedMap::default(),
+            non_send: NonSendResources::default(),
+            commands: GrainedLock::default(),
+            cloners: HashMap::new(),
+            persistent: PersistRegistry::default(),
+            reflected: ReflectRegistry::default(),
+            measured: MemSizeRegistry::default(),
+            observers: HashMap::new(),
+            interfaces: InterfaceRegistry::default(),
+            parent: None,
+            clock: ChangeClock::default(),
+            cleared: AtomicU64::default(),
+        }
+    }
+}
+
+impl ResourceContainer {
+    /// Creates a container falling back to `parent` for the resources it does not hold.
+    ///
+    /// Shared retrievals of a resource missing from the child borrow the resource of the
+    /// parent, while mutable retrievals only find the resources of the child, so writes never
+    /// reach the parent. Resources added to the child override those of the parent, e.g. to
+    /// override global configuration resources per scene. Components and non-send resources
+    /// are never looked up in the parent.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::sync::Arc;
+    ///
+    /// use emark::prelude::*;
+    /// use emark::store::ResourceContainer;
+    ///
+    /// let mut global = ResourceContainer::default();
+    /// global.add_resource(1.0f32);
+    /// global.add_resource("forest");
+    /// let global = Arc::new(global);
+    ///
+    /// let mut scene = ResourceContainer::new_child(&global);
+    /// scene.add_resource(0.5f32);
+    /// assert_eq!(*Res::<f32>::retrieve(&scene), 0.5);
+    /// assert_eq!(*Res::<&str>::retrieve(&scene), "forest");
+    /// assert_eq!(*Res::<f32>::retrieve(&global), 1.0);
+    /// ```
+    pub fn new_child(parent: &Arc<ResourceContainer>) -> Self {
+        Self {
+            parent: Some(parent.clone()),
+            // changes of the parent are compared with the ticks of the child
+            clock: parent.clock.clone(),
+            ..Self::default()
+        }
+    }
+
+    pub fn parent(&self) -> Option<&Arc<ResourceContainer>> {
+        self.parent.as_ref()
+    }
+
+    /// Registers resource `T` as cloneable, allowing it to be captured by `snapshot`.
+    pub fn register_snapshot<T: Clone + Send + Sync + 'static>(&mut self) {
+        self.cloners
+            .entry(TypeId::of::<T>())
+            .or_insert(Cloner {
+                capture: capture_resource::<T>,
+                restore: restore_resource::<T>,
+            });
+    }
+
+    /// Captures a deep copy of the resources registered with `register_snapshot`, e.g. for
+    /// rollback or quick-saves.
+    ///
+    /// Each resource is locked for reading while it is cloned. The resources of the parent and
+    /// the non-send resources are not part of the snapshot. Returns an error if a resource is
+    /// not registered, unless `policy` skips it.
+    ///
+    /// # Examples
+    /// ```
+    /// use emark::prelude::*;
+    /// use emark::store::{ResourceContainer, SnapshotPolicy};
+    ///
+    /// let mut container = ResourceContainer::default();
+    /// container.add_resource(100u32);
+    /// container.register_snapshot::<u32>();
+    ///
+    /// let save = container.snapshot(SnapshotPolicy::Error).unwrap();
+    /// *ResMut::<u32>::retrieve(&container) -= 40;
+    ///
+    /// // load the quick-save
+    /// container.restore(&save);
+    /// assert_eq!(*Res::<u32>::retrieve(&container), 100);
+    /// ```
+    pub fn snapshot(&self, policy: SnapshotPolicy) -> Result<ContainerSnapshot, EmarkError> {
+        let mut snapshot = HashMap::new();
+        for (type_id, entry) in self.resources.entries() {
+            let Some(&cloner) = self.cloners.get(&type_id) else {
+                match policy {
+                    SnapshotPolicy::Error => {
+                        let type_name = entry.type_name.unwrap_or("<unnamed>");
+                        return Err(EmarkError::UnregisteredResourceSnapshot(type_name));
+                    }
+                    SnapshotPolicy::Skip => continue,
+                }
+            };
+            let resource = (cloner.capture)(&*entry.borrow());
+            snapshot.insert(type_id, (resource, cloner, entry.type_name));
+        }
+        Ok(ContainerSnapshot {
+            resources: snapshot,
+        })
+    }
+
+    /// Replaces the resources captured by `snapshot` with a copy of their captured value.
+    ///
+    /// Registered resources missing from the snapshot were added since, and are removed.
+    /// The other resources are left untouched.
+    pub fn restore(&mut self, snapshot: &ContainerSnapshot) {
+        self.resources.retain(|type_id, _| {
+            snapshot.resources.contains_key(&type_id) || !self.cloners.contains_key(&type_id)
+        });
+        for (type_id, (resource, cloner, type_name)) in &snapshot.resources {
+            let entry = (cloner.restore)(self, &**resource, *type_name);
+            self.resources.insert(*type_id, entry);
+        }
+    }
+
+    /// Current change tick, advanced by every run of a function system.
+    pub fn change_tick(&self) -> u64 {
+        self.clock.now()
+    }
+
+    /// Marks the changes made so far as seen by the retrievals made outside of systems.
+    pub fn clear_changes(&self) {
+        self.cleared.store(self.clock.now(), Ordering::Release);
+    }
+
+    /// Returns `true` if a mutable borrow of the resource of type `T` was released while
+    /// unwinding from a panic, e.g. of a system, so the resource may be left half updated.
+    ///
+    /// Poisoned resources are still retrieved, the poison being kept until `clear_poison`, so
+    /// the application can report, reset or remove them.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::panic::{catch_unwind, AssertUnwindSafe};
+    ///
+    /// use emark::prelude::*;
+    /// use emark::store::ResourceContainer;
+    ///
+    /// let mut container = ResourceContainer::default();
+    /// container.add_resource(vec![1u32, 2]);
+    ///
+    /// let result = catch_unwind(AssertUnwindSafe(|| {
+    ///     let mut values = ResMut::<Vec<u32>>::retrieve(&container);
+    ///     values.push(3);
+    ///     panic!("interrupted before pushing 4");
+    /// }));
+    /// assert!(result.is_err());
+    /// assert!(container.is_poisoned::<Vec<u32>>());
+    ///
+    /// *ResMut::<Vec<u32>>::retrieve(&container) = vec![1, 2];
+    /// container.clear_poison::<Vec<u32>>();
+    /// assert!(container.poisoned_resources().is_empty());
+    /// ```
+    pub fn is_poisoned<T: ?Sized + 'static>(&self) -> bool {
+        self.entry(TypeId::of::<T>())
+            .is_some_and(ResourceEntry::is_poisoned)
+    }
+
+    /// Types of the poisoned resources, see `is_poisoned`, in no particular order.
+    pub fn poisoned_resources(&self) -> Vec<TypeId> {
+        self.resources
+            .entries()
+            .into_iter()
+            .filter(|(_, entry)| entry.is_poisoned())
+            .map(|(type_id, _)| type_id)
+            .collect()
+    }
+
+    /// Clears the poison of the resource of type `T` once it is known to be consistent again.
+    pub fn clear_poison<T: ?Sized + 'static>(&self) {
+        if let Some(entry) = self.entry(TypeId::of::<T>()) {
+            entry.clear_poison();
+        }
+    }
+
+    /// Registers resource `T` to be persisted by `serialize` under its stable name.
+    ///
+    /// Returns an error if another resource type is registered with the same name.
+    pub fn register_persistent<T: PersistentResource>(&mut self) -> Result<(), EmarkError> {
+        self.persistent.register::<T>()
+    }
+
+    /// Writes the resources registered with `register_persistent` to `writer`, e.g. to save
+    /// the world to a file.
+    ///
+    /// Resources are identified by their stable name rather than their `TypeId`, so the data
+    /// can be read by another build. Each resource is locked for reading while it is written.
+    /// The resources of the parent and the non-send resources are not persisted.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::io::{self, Read, Write};
+    ///
+    /// use emark::prelude::*;
+    /// use emark::store::{Persist, PersistentResource, ResourceContainer};
+    ///
+    /// struct Gold(u64);
+    ///
+    /// impl Persist for Gold {
+    ///     fn save(&self, writer: &mut dyn Write) -> io::Result<()> {
+    ///         self.0.save(writer)
+    ///     }
+    ///
+    ///     fn load(reader: &mut dyn Read) -> io::Result<Self> {
+    ///         u64::load(reader).map(Gold)
+    ///     }
+    /// }
+    ///
+    /// impl PersistentResource for Gold {
+    ///     const NAME: &'static str = "player.gold";
+    /// }
+    ///
+    /// let mut container = ResourceContainer::default();
+    /// container.register_persistent::<Gold>().unwrap();
+    /// container.add_resource(Gold(250));
+    ///
+    /// let mut save = Vec::new();
+    /// container.serialize(&mut save).unwrap();
+    ///
+    /// let mut loaded = ResourceContainer::default();
+    /// loaded.register_persistent::<Gold>().unwrap();
+    /// loaded.deserialize(save.as_slice()).unwrap();
+    /// assert_eq!(Res::<Gold>::retrieve(&loaded).0, 250);
+    /// ```
+    pub fn serialize(&self, mut writer: impl Write) -> Result<(), EmarkError> {
+        let resources = self.resources.entries();
+        let borrowed = resources
+            .iter()
+            .map(|(type_id, entry)| (*type_id, entry.borrow()))
+            .collect::<Vec<_>>();
+        let resources = borrowed
+            .iter()
+            .map(|(type_id, resource)| (*type_id, &**resource));
+        self.persistent.serialize(resources, &mut writer)
+    }
+
+    /// Reads the resources written by `serialize` from `reader`, replacing the resources of the
+    /// same types.
+    ///
+    /// Returns an error if the data is invalid or holds a resource whose name is not
+    /// registered, in which case the container is left untouched.
+    pub fn deserialize(&mut self, mut reader: impl Read) -> Result<(), EmarkError> {
+        for (type_id, resource, type_name) in self.persistent.deserialize(&mut reader)? {
+            let entry = self.new_boxed_entry(resource, Some(type_name));
+            self.resources.insert(type_id, entry);
+        }
+        Ok(())
+    }
+
+    /// Registers resource `T` to be inspected with `reflect` and `reflect_mut`.
+    pub fn register_reflect<T: Reflect>(&mut self) {
+        self.reflected.register::<T>();
+    }
+
+    pub fn is_reflected(&self, type_id: TypeId) -> bool {
+        self.reflected.contains(type_id)
+    }
+
+    /// Calls `f` with the resource `type_id` as a `dyn Reflect`, while it is locked for reading.
+    ///
+    /// Returns `None` if the resource is not found in this container, or is not registered with
+    /// `register_reflect`.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::any::TypeId;
+    ///
+    /// use emark::prelude::*;
+    /// use emark::store::ResourceContainer;
+    ///
+    /// let mut container = ResourceContainer::default();
+    /// container.register_reflect::<f32>();
+    /// container.add_resource(0.5f32);
+    ///
+    /// // tweak every reflected resource, e.g. from a debug UI
+    /// for type_id in container.resource_types() {
+    ///     container.reflect_mut(type_id, |resource| resource.set(1.0f32));
+    /// }
+    /// let name = container.reflect(TypeId::of::<f32>(), |resource| resource.type_name());
+    /// assert_eq!(name, Some("f32"));
+    /// assert_eq!(*Res::<f32>::retrieve(&container), 1.0);
+    /// ```
+    pub fn reflect<R>(&self, type_id: TypeId, f: impl FnOnce(&dyn Reflect) -> R) -> Option<R> {
+        if !self.reflected.contains(type_id) {
+            return None;
+        }
+        let resource = self.entry(type_id)?.borrow();
+        Some(f(self.reflected.reflect(type_id, &*resource)))
+    }
+
+    /// Calls `f` with the resource `type_id` as a mutable `dyn Reflect`, while it is locked for
+    /// writing, marking the resource changed.
+    pub fn reflect_mut<R>(
+        &self,
+        type_id: TypeId,
+        f: impl FnOnce(&mut dyn Reflect) -> R,
+    ) -> Option<R> {
+        if !self.reflected.contains(type_id) {
+            return None;
+        }
+        let entry = self.entry(type_id)?;
+        let mut resource = entry.borrow_mut();
+        entry.ticks.set_changed(self.clock.advance());
+        Some(f(self.reflected.reflect_mut(type_id, &mut *resource)))
+    }
+
+    /// Registers resource `T` to be measured with its [MemSize] implementation by
+    /// `memory_usage`.
+    pub fn register_mem_size<T: MemSize + 'static>(&mut self) {
+        self.measured.register::<T>();
+    }
+
+    /// Memory used by each resource, largest first, e.g. to find the resource growing in a
+    /// long-running process.
+    ///
+    /// Resources registered with `register_mem_size` report their deep size, the others their
+    /// shallow size only. Each resource is locked for reading while it is measured. The
+    /// resources of the parent and the non-send resources are not reported.
+    ///
+    /// # Examples
+    /// ```
+    /// use emark::prelude::*;
+    /// use emark::store::ResourceContainer;
+    ///
+    /// let mut container = ResourceContainer::default();
+    /// container.register_mem_size::<Vec<u64>>();
+    /// container.add_resource(vec![0u64; 1024]);
+    /// container.add_resource(0u32);
+    ///
+    /// let usage = container.memory_usage();
+    /// assert_eq!(usage[0].type_name, Some("alloc::vec::Vec<u64>"));
+    /// assert!(usage[0].bytes >= 8 * 1024);
+    /// assert_eq!((usage[1].bytes, usage[1].deep), (4, false));
+    /// ```
+    pub fn memory_usage(&self) -> Vec<MemoryUsage> {
+        let mut usage = self
+            .resources
+            .entries()
+            .into_iter()
+            .map(|(type_id, entry)| {
+                let resource = entry.borrow();
+                self.measured
+                    .measure(type_id, entry.type_name, &*resource)
+            })
+            .collect::<Vec<_>>();
+        usage.sort_by_key(|usage| std::cmp::Reverse(usage.bytes));
+        usage
+    }
+
+    /// Locks the resources of `T` up front, in sorted `TypeId` order, and calls `f` with their
+    /// borrows, rolling back the resources borrowed mutably if `f` returns an error.
+    ///
+    /// The resources borrowed mutably must be `Clone`, as they are copied before calling `f`,
+    /// giving all-or-nothing updates of invariants spanning several resources. A panic in `f`
+    /// does not roll the resources back.
+    ///
+    /// # Examples
+    /// ```
+    /// use emark::prelude::*;
+    /// use emark::store::ResourceContainer;
+    /// use emark::EmarkError;
+    ///
+    /// #[derive(Clone)]
+    /// struct Gold(u32);
+    /// #[derive(Clone)]
+    /// struct Inventory(Vec<&'static str>);
+    ///
+    /// let mut container = ResourceContainer::default();
+    /// container.add_resource(Gold(10));
+    /// container.add_resource(Inventory(Vec::new()));
+    ///
+    /// let buy = |container: &ResourceContainer, price: u32| {
+    ///     container.transaction::<(ResMut<Gold>, ResMut<Inventory>), _>(|(gold, inventory)| {
+    ///         inventory.0.push("sword");
+    ///         gold.0 = gold.0.checked_sub(price).ok_or(EmarkError::Custom("no gold".into()))?;
+    ///         Ok(())
+    ///     })
+    /// };
+    /// assert!(buy(&container, 8).is_ok());
+    /// assert!(buy(&container, 8).is_err());
+    ///
+    /// // assert the failed purchase did not add the sword
+    /// assert_eq!(Res::<Inventory>::retrieve(&container).0.len(), 1);
+    /// assert_eq!(Res::<Gold>::retrieve(&container).0, 2);
+    /// ```
+    pub fn transaction<T: Transaction, O>(
+        &self,
+        f: impl FnOnce(&mut T::Item<'_>) -> Result<O, EmarkError>,
+    ) -> Result<O, EmarkError> {
+        let mut item = T::try_retrieve(self)?;
+        let backup = T::backup(&item);
+        f(&mut item).inspect_err(|_| T::rollback(&mut item, backup))
+    }
+
+    /// Creates a weak handle to the resource of type `T`, see [ResHandle].
+    ///
+    /// The handle never upgrades if the container holds no resource of type `T`. The resources
+    /// of the parent are not considered.
+    pub fn weak_handle<T: Resource + ?Sized>(&self) -> ResHandle<T> {
+        let entry = self.resources.get(TypeId::of::<T>());
+        let entry = entry.as_ref().map(Arc::downgrade);
+        ResHandle::new(self.id, entry.unwrap_or_else(ResourceEntry::dangling))
+    }
+
+    /// Registers an observer called with the resource of type `T` each time a
+    /// [ResMut](super::ResMut) borrow that mutably dereferenced it is released, e.g. to mirror a
+    /// settings resource to disk when it changes.
+    ///
+    /// Observers are called in registration order, on the thread releasing the borrow, while
+    /// the resource is still locked, so they must not retrieve it. Writes made through other
+    /// means than `ResMut`, such as `reflect_mut`, are not observed.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::sync::{Arc, Mutex};
+    ///
+    /// use emark::prelude::*;
+    /// use emark::store::ResourceContainer;
+    ///
+    /// struct Settings {
+    ///     volume: u32,
+    /// }
+    ///
+    /// let saved = Arc::new(Mutex::new(Vec::new()));
+    /// let observer_saved = saved.clone();
+    /// let mut container = ResourceContainer::default();
+    /// container.add_resource(Settings { volume: 10 });
+    /// container.observe_writes(move |settings: &Settings| {
+    ///     observer_saved.lock().unwrap().push(settings.volume);
+    /// });
+    ///
+    /// ResMut::<Settings>::retrieve(&container).volume = 5;
+    /// assert_eq!(*saved.lock().unwrap(), [5]);
+    /// ```
+    pub fn observe_writes<T: ?Sized + 'static>(
+        &mut self,
+        observer: impl Fn(&T) + Send + Sync + 'static,
+    ) {
+        self.observers
+            .entry(TypeId::of::<T>())
+            .or_insert_with(WriteObservers::new::<T>)
+            .push(observer);
+    }
+
+    /// Binds the resource of type `T` to the interface `I`, retrieving it with every other
+    /// resource bound to `I` through [ResAll](super::ResAll).
+    ///
+    /// `cast` converts the resource to the interface, usually `|resource| resource` for trait
+    /// objects. Binding a type again keeps the first cast.
+    pub fn bind_interface<T: Send + Sync + 'static, I: ?Sized + 'static>(
+        &mut self,
+        cast: fn(&T) -> &I,
+    ) {
+        self.interfaces.bind(cast);
+    }
+
+    /// Adds a resource constructed with [FromContainer], unless the container already holds a
+    /// resource of type `T`, first initializing its dependencies in dependency order.
+    ///
+    /// Returns an error on cyclic dependencies, or if a required dependency is missing. The
+    /// dependencies initialized before the error is found are kept.
+    pub fn try_init_resource<T: FromContainer>(&mut self) -> Result<(), EmarkError> {
+        init_ordered(self, Dependency::init::<T>(), None, &mut Vec::new())
+    }
+
+    /// Reserves capacity for at least `additional` more resources, e.g. at startup to avoid
+    /// reallocations while systems run.
+    pub fn reserve(&mut self, additional: usize) {
+        self.resources.reserve(additional);
+    }
+
+    /// Shrinks the capacity of the resources and of the non-send resources as much as
+    /// possible.
+    pub fn shrink_to_fit(&mut self) {
+        self.resources.shrink_to_fit();
+        self.non_send.shrink_to_fit();
+    }
+
+    /// Number of resources the container can hold without reallocating, excluding the
+    /// non-send resources.
+    pub fn capacity(&self) -> usize {
+        self.resources.capacity()
+    }
+
+    /// Applies the mutations recorded by [Commands](super::Commands), in the order they were
+    /// recorded, including the commands recorded while applying them.
+    pub fn apply_commands(&mut self) {
+        loop {
+            let commands = self.commands.get_mut().take();
+            if commands.is_empty() {
+                break;
+            }
+            for command in commands {
+                command(self);
+            }
+        }
+    }
+
+    pub(crate) fn clock(&self) -> &ChangeClock {
+        &self.clock
+    }
+
+    // ticks of a retrieval made outside of systems
+    pub(crate) fn ticks(&self) -> SystemTicks {
+        SystemTicks::new(self.cleared.load(Ordering::Acquire), self.clock.advance())
+    }
+
+    // entry of the resource `type_id`
+    fn entry(&self, type_id: TypeId) -> Option<&ResourceEntry> {
+        let entry = self
+            .resources
+            .get_with(type_id, |entry| NonNull::from(&**entry))?;
+        // the shared entry outlives the shared borrow of the container
+        Some(unsafe { entry.as_ref() })
+    }
+
+    fn new_entry<T: Any + Send + Sync>(
+        &self,
+        mut resource: T,
+        type_name: Option<&'static str>,
+    ) -> Arc<ResourceEntry> {
+        self.set_entities_clock(&mut resource);
+        ResourceEntry::new(resource, ChangeTicks::new(self.clock.advance()), type_name)
+    }
+
+    // entry of a resource whose type is only known at runtime
+    fn new_boxed_entry(
+        &self,
+        mut resource: Box<dyn Any + Send + Sync>,
+        type_name: Option<&'static str>,
+    ) -> Arc<ResourceEntry> {
+        self.set_entities_clock(&mut *resource);
+        ResourceEntry::boxed(resource, ChangeTicks::new(self.clock.advance()), type_name)
+    }
+
+    // components are marked changed with the ticks of the container
+    fn set_entities_clock(&self, resource: &mut dyn Any) {
+        if let Some(entities) = resource.downcast_mut::<Entities>() {
+            entities.set_clock(self.clock.clone());
+        }
+    }
+
+    // lock a resource for retrieval, shared borrows falling back to the parent
+    pub(crate) fn retrieve_any(&self, type_id: TypeId, mutable: bool) -> Option<Retrieved<'_>> {
+        let Some(resource) = self.entry(type_id) else {
+            let parent = self.parent.as_ref().filter(|_| !mutable)?;
+            return parent.retrieve_any(type_id, false);
+        };
+        Some(match mutable {
+            true => self.observed(type_id, resource.retrieved(type_id, true)),
+            false => resource.retrieved(type_id, false),
+        })
+    }
+
+    // `retrieve_any` failing if another borrow still holds the lock of the resource after
+    // waiting for `timeout`, or immediately without a timeout
+    pub(crate) fn try_retrieve_any_within(
+        &self,
+        type_id: TypeId,
+        type_name: &'static str,
+        mutable: bool,
+        timeout: Option<Duration>,
+    ) -> Result<Retrieved<'_>, RetrievalError> {
+        let Some(resource) = self.entry(type_id) else {
+            let parent = self.parent.as_ref().filter(|_| !mutable);
+            return match parent {
+                Some(parent) => parent.try_retrieve_any_within(type_id, type_name, false, timeout),
+                None => Err(RetrievalError::NotFound(type_name)),
+            };
+        };
+        let retrieved = resource
+            .try_retrieved(mutable, timeout)
+            .ok_or(match timeout {
+                Some(_) => RetrievalError::Timeout(type_name),
+                None => RetrievalError::WouldBlock(type_name),
+            })?;
+        Ok(Retrieved {
+            system: self.ticks(),
+            ..match mutable {
+                true => self.observed(type_id, retrieved),
+                false => retrieved,
+            }
+        })
+    }
+
+    // true if a request can be retrieved, checked without locking anything
+    pub(crate) fn can_retrieve(&self, request: &Request) -> bool {
+        match request.storage {
+            Storage::Resource => {
+                self.resources.contains_key(request.type_id)
+                    || (!request.mutable
+                        && self
+                            .parent
+                            .as_ref()
+                            .is_some_and(|parent| parent.can_retrieve(request)))
+            }
+            Storage::Component => self.resources.contains_key(TypeId::of::<Entities>()),
+            Storage::NonSend => self.non_send.contains_key(request.type_id),
+            Storage::Commands | Storage::Interface | Storage::Alternatives(_) => true,
+        }
+    }
+
+    // attach the write observers of `type_id` to a mutable retrieval
+    fn observed<'a>(&'a self, type_id: TypeId, retrieved: Retrieved<'a>) -> Retrieved<'a> {
+        Retrieved {
+            observers: self.observers.get(&type_id),
+            ..retrieved
+        }
+    }
+
+    // lock the resource of a handle created by the container `id`
+    pub(crate) fn retrieve_handle(
+        &self,
+        id: u64,
+        type_id: TypeId,
+        entry: &Weak<ResourceEntry>,
+        mutable: bool,
+    ) -> Option<Retrieved<'_>> {
+        if id != self.id {
+            return None;
+        }
+        let entry = NonNull::from(&*entry.upgrade()?);
+        // entries are only dropped through `&mut self`, so the entry outlives the borrow
+        let entry = unsafe { entry.as_ref() };
+        let retrieved = match mutable {
+            true => self.observed(type_id, entry.retrieved(type_id, true)),
+            false => entry.retrieved(type_id, false),
+        };
+        Some(Retrieved {
+            system: self.ticks(),
+            ..retrieved
+        })
+    }
+
+    // types bound to the interface `interface`
+    pub(crate) fn interface_types(&self, interface: TypeId) -> &[TypeId] {
+        self.interfaces.bound(interface)
+    }
+
+    // `Cast` of the resource `type_id` to the interface `interface`
+    pub(crate) fn interface_cast(
+        &self,
+        interface: TypeId,
+        type_id: TypeId,
+    ) -> &(dyn Any + Send + Sync) {
+        self.interfaces.cast(interface, type_id)
+    }
+
+    // lock the command queue for `Commands`
+    pub(crate) fn retrieve_commands(&self) -> Retrieved<'_> {
+        Retrieved::immutable(erase(self.commands.borrow()), None)
+    }
+
+    // true if the current thread created the container
+    pub(crate) fn is_owner_thread(&self) -> bool {
+        self.non_send.is_owner_thread()
+    }
+
+    // lock a non-send resource for retrieval, on the owner thread
+    pub(crate) fn retrieve_non_send(
+        &self,
+        type_id: TypeId,
+        mutable: bool,
+    ) -> Option<Retrieved<'_>> {
+        Some(self.non_send.get(type_id)?.retrieved(type_id, mutable))
+    }
+
+    // lock the column `column_id` of the `Entities` resource, or the resource itself
+    pub(crate) fn retrieve_component(
+        &self,
+        column_id: TypeId,
+        mutable: bool,
+    ) -> Option<Retrieved<'_>> {
+        // the retriever may hold the entities lock already
+        let entities = self
+            .entry(TypeId::of::<Entities>())?
+            .borrow_recursive();
+        if column_id == TypeId::of::<Entities>() {
+            return Some(Retrieved::immutable(entities, None));
+        }
+
+        let entities = downcast::<Entities, _>(entities);
+        if entities.column(column_id).is_none() {
+            return Some(Retrieved::missing());
+        }
+        let column = entities.map_cell(|entities| entities.column(column_id).unwrap());
+        Some(match mutable {
+            true => Retrieved::mutable(unbox(column.borrow_mut()), None),
+            false => Retrieved::immutable(unbox(column.borrow()), None),
+        })
+    }
 }
 
 impl Container for ResourceContainer {
-    fn add_resource<T: 'static>(&mut self, resource: T) {
-        self.add_resource_any(TypeId::of::<T>(), Box::new(resource));
+    fn add_resource<T: Send + Sync + 'static>(&mut self, resource: T) -> Option<T> {
+        let entry = self.new_entry(resource, Some(std::any::type_name::<T>()));
+        self.resources
+            .insert(TypeId::of::<T>(), entry)
+            .map(ResourceEntry::take_as::<T>)
+    }
+
+    fn try_add_resource<T: Send + Sync + 'static>(
+        &mut self,
+        resource: T,
+    ) -> Result<(), EmarkError> {
+        if self.resources.contains_key(TypeId::of::<T>()) {
+            return Err(EmarkError::DuplicateResource(std::any::type_name::<T>()));
+        }
+        self.insert_overwrite(resource);
+        Ok(())
+    }
+
+    fn insert_overwrite<T: Send + Sync + 'static>(&mut self, resource: T) {
+        let entry = self.new_entry(resource, Some(std::any::type_name::<T>()));
+        self.resources.insert(TypeId::of::<T>(), entry);
+    }
+
+    fn init_resource<T: FromContainer>(&mut self) {
+        if let Err(error) = self.try_init_resource::<T>() {
+            panic!("{error}");
+        }
+    }
+
+    fn get_or_insert_with<T: Send + Sync + 'static>(&self, f: impl FnOnce() -> T) -> Res<'_, T> {
+        let type_id = TypeId::of::<T>();
+        if !self.contains_resource_any(type_id) {
+            // checked again under the lock, a concurrent call may have inserted it since
+            self.resources.get_or_insert_with(type_id, || {
+                let type_name = std::any::type_name::<T>();
+                self.new_entry(f(), Some(type_name))
+            });
+        }
+        Res::<T>::retrieve(self)
+    }
+
+    fn add_resource_as<T: ?Sized + Send + Sync + 'static>(&mut self, resource: Box<T>) {
+        let entry = self.new_entry(resource, Some(std::any::type_name::<T>()));
+        self.resources.insert(TypeId::of::<T>(), entry);
     }
 
-    fn add_resource_any(&mut self, type_id: TypeId, resource: Box<dyn Any>) {
-        self.resources.insert(type_id, GrainedLock::new(resource));
+    fn add_resource_any(&mut self, type_id: TypeId, resource: Box<dyn Any + Send + Sync>) {
+        let entry = self.new_boxed_entry(resource, None);
+        self.resources.insert(type_id, entry);
     }
 
     fn remove_resource<T: 'static>(&mut self) -> Option<T> {
-        self.remove_resource_any(TypeId::of::<T>())
-            .map(|resource| *resource.downcast::<T>().unwrap())
+        self.resources
+            .remove(TypeId::of::<T>())
+            .map(ResourceEntry::take_as::<T>)
     }
 
-    fn remove_resource_any(&mut self, type_id: TypeId) -> Option<Box<dyn Any>> {
+    fn remove_resource_as<T: ?Sized + 'static>(&mut self) -> Option<Box<T>> {
         self.resources
-            .remove(&type_id)
-            .map(|resource| resource.take())
+            .remove(TypeId::of::<T>())
+            .map(ResourceEntry::take_as::<Box<T>>)
     }
 
-    fn contains_resource<T: 'static>(&self) -> bool {
+    fn remove_resource_any(&mut self, type_id: TypeId) -> Option<Box<dyn Any>> {
+        self.resources.remove(type_id).map(ResourceEntry::take)
+    }
+
+    fn contains_resource<T: ?Sized + 'static>(&self) -> bool {
         self.contains_resource_any(TypeId::of::<T>())
     }
 
     fn contains_resource_any(&self, type_id: TypeId) -> bool {
-        self.resources.contains_key(&type_id)
+        self.resources.contains_key(type_id)
+            || self
+                .parent
+                .as_ref()
+                .is_some_and(|parent| parent.contains_resource_any(type_id))
+    }
+
+    fn add_non_send<T: 'static>(&mut self, mut resource: T) {
+        self.set_entities_clock(&mut resource);
+        let ticks = ChangeTicks::new(self.clock.advance());
+        let entry = ResourceEntry::non_send(resource, ticks, Some(std::any::type_name::<T>()));
+        self.non_send.insert(TypeId::of::<T>(), entry);
+    }
+
+    fn remove_non_send<T: 'static>(&mut self) -> Option<T> {
+        self.non_send.remove::<T>()
+    }
+
+    fn contains_non_send<T: 'static>(&self) -> bool {
+        self.non_send.contains_key(TypeId::of::<T>())
+    }
+
+    fn resource_types(&self) -> Vec<TypeId> {
+        self.resources.keys()
+    }
+
+    fn resource_type_name(&self, type_id: TypeId) -> Option<&'static str> {
+        self.entry(type_id)?.type_name
+    }
+
+    fn clear(&mut self) {
+        self.resources.clear();
+        self.non_send.clear();
+    }
+
+    fn retain(&mut self, mut retain: impl FnMut(TypeId) -> bool) {
+        self.resources.retain(|type_id, _| retain(type_id));
+        self.non_send.retain(retain);
     }
 }
 
 #[cfg(test)]
 mod test_container {
     use super::*;
+    use crate::store::ResMut;
 
     #[test]
     fn test_container_add_contains() {
@@ -68,6 +1094,197 @@ mod test_container {
         assert_eq!(removed, Some(1));
     }
 
+    #[test]
+    fn test_init_resource() {
+        struct Doubled(i32);
+        impl FromContainer for Doubled {
+            fn from_container(container: &mut ResourceContainer) -> Self {
+                container.init_resource::<i32>();
+                Doubled(*Res::<i32>::retrieve(container) * 2)
+            }
+        }
+
+        let mut container = ResourceContainer::default();
+        container.add_resource(2);
+        container.init_resource::<Doubled>();
+        assert_eq!(container.remove_resource::<Doubled>().unwrap().0, 4);
+
+        // assert existing resources are kept
+        container.init_resource::<i32>();
+        assert_eq!(container.remove_resource::<i32>(), Some(2));
+        container.init_resource::<i32>();
+        assert_eq!(container.remove_resource::<i32>(), Some(0));
+    }
+
+    #[cfg(not(feature = "unsync"))]
+    #[test]
+    fn test_get_or_insert_with() {
+        let container = ResourceContainer::default();
+        let inserted = std::sync::atomic::AtomicUsize::new(0);
+
+        // assert racing threads insert the resource once
+        std::thread::scope(|scope| {
+            for value in 0..8 {
+                let (container, inserted) = (&container, &inserted);
+                scope.spawn(move || {
+                    container.get_or_insert_with(|| {
+                        inserted.fetch_add(1, Ordering::SeqCst);
+                        value
+                    });
+                });
+            }
+        });
+        assert_eq!(inserted.load(Ordering::SeqCst), 1);
+        let value = *container.get_or_insert_with(|| -1);
+        assert_ne!(value, -1);
+    }
+
+    #[test]
+    fn test_resource_types() {
+        let mut container = ResourceContainer::default();
+        container.add_resource(1u32);
+        container.add_resource_any(TypeId::of::<u64>(), Box::new(2u64));
+
+        let mut types = container.resource_types();
+        types.sort();
+        let mut expected = vec![TypeId::of::<u32>(), TypeId::of::<u64>()];
+        expected.sort();
+        assert_eq!(types, expected);
+        assert_eq!(
+            container.resource_type_name(TypeId::of::<u32>()),
+            Some("u32")
+        );
+        assert_eq!(container.resource_type_name(TypeId::of::<u64>()), None);
+    }
+
+    #[test]
+    fn test_add_resource_overwrite() {
+        let mut container = ResourceContainer::default();
+        assert_eq!(container.add_resource(1u32), None);
+        assert_eq!(container.add_resource(2u32), Some(1));
+
+        assert_eq!(
+            container.try_add_resource(3u32),
+            Err(EmarkError::DuplicateResource("u32"))
+        );
+        assert_eq!(container.try_add_resource(4u64), Ok(()));
+        container.insert_overwrite(5u32);
+        assert_eq!(container.remove_resource::<u32>(), Some(5));
+    }
+
+    #[test]
+    fn test_container_send_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<ResourceContainer>();
+    }
+
+    #[test]
+    fn test_snapshot_restore() {
+        let mut container = ResourceContainer::default();
+        container.register_snapshot::<u32>();
+        container.register_snapshot::<String>();
+        container.add_resource(1u32);
+        container.add_resource(2u64);
+
+        assert_eq!(
+            container.snapshot(SnapshotPolicy::Error).unwrap_err(),
+            EmarkError::UnregisteredResourceSnapshot("u64")
+        );
+        let snapshot = container.snapshot(SnapshotPolicy::Skip).unwrap();
+        assert_eq!(snapshot.len(), 1);
+        assert!(!snapshot.contains::<u64>());
+
+        // diverge from the snapshot
+        *ResMut::<u32>::retrieve(&container) = 10;
+        container.add_resource(String::from("added"));
+
+        // assert the snapshot can be restored more than once
+        for _ in 0..2 {
+            container.restore(&snapshot);
+            assert_eq!(*Res::<u32>::retrieve(&container), 1);
+            *ResMut::<u32>::retrieve(&container) = 10;
+        }
+        assert!(!container.contains_resource::<String>());
+        assert!(container.contains_resource::<u64>());
+    }
+
+    #[test]
+    fn test_reflect() {
+        let mut container = ResourceContainer::default();
+        container.register_reflect::<u32>();
+        container.add_resource(1u32);
+        container.add_resource(2u64);
+
+        let type_id = TypeId::of::<u32>();
+        assert!(container.is_reflected(type_id));
+        assert_eq!(container.reflect(TypeId::of::<u64>(), |_| ()), None);
+        container.clear_changes();
+
+        let result = container.reflect_mut(type_id, |resource| resource.set(3u32));
+        assert_eq!(result, Some(Ok(())));
+        let resource = Res::<u32>::retrieve(&container);
+        assert!(resource.is_changed());
+        assert_eq!(*resource, 3);
+    }
+
+    #[test]
+    fn test_reserve_shrink() {
+        let mut container = ResourceContainer::default();
+        container.reserve(32);
+        assert!(container.capacity() >= 32);
+
+        container.add_resource(1u32);
+        container.shrink_to_fit();
+        assert!(container.capacity() < 32);
+        assert!(container.contains_resource::<u32>());
+    }
+
+    #[test]
+    fn test_clear_retain() {
+        let mut container = ResourceContainer::default();
+        container.add_resource(1u32);
+        container.add_resource(2u64);
+        container.add_non_send(std::rc::Rc::new(3u32));
+
+        container.retain(|type_id| type_id != TypeId::of::<u32>());
+        assert!(!container.contains_resource::<u32>());
+        assert!(container.contains_resource::<u64>());
+        assert!(container.contains_non_send::<std::rc::Rc<u32>>());
+
+        container.clear();
+        assert!(!container.contains_resource::<u64>());
+        assert!(!container.contains_non_send::<std::rc::Rc<u32>>());
+    }
+
+    #[test]
+    fn test_child_container() {
+        let mut parent = ResourceContainer::default();
+        parent.add_resource(1u32);
+        parent.add_resource(String::from("global"));
+        let parent = Arc::new(parent);
+
+        let mut child = ResourceContainer::new_child(&parent);
+        child.add_resource(2u32);
+        assert_eq!(*Res::<u32>::retrieve(&child), 2);
+        assert_eq!(*Res::<String>::retrieve(&child), "global");
+        assert!(child.contains_resource::<String>());
+
+        // assert writes stay in the child
+        *ResMut::<u32>::retrieve(&child) += 1;
+        assert_eq!(child.remove_resource::<u32>(), Some(3));
+        assert_eq!(*Res::<u32>::retrieve(&child), 1);
+        assert_eq!(*Res::<u32>::retrieve(&parent), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Resource not found")]
+    fn test_child_container_write_parent() {
+        let mut parent = ResourceContainer::default();
+        parent.add_resource(1u32);
+        let child = ResourceContainer::new_child(&Arc::new(parent));
+        ResMut::<u32>::retrieve(&child);
+    }
+
     #[test]
     fn test_add_resource_any() {
         let mut container = ResourceContainer::default();
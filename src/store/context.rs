@@ -0,0 +1,301 @@
+use std::{fmt::Debug, ops::RangeInclusive};
+
+use parking_lot::Mutex;
+
+use crate::event::{priority::Priority, EmittedEventInfo, Event, EventManager};
+
+use super::{Commands, Res, ResAll, Resource, ResourceContainer, RetrievalError, Retriever};
+
+/// Access of a handler to the application while it handles a batch of events, see
+/// `HandlerRegistry::add_context_handler`.
+///
+/// The context retrieves resources from the container of the dispatch, emits events, records
+/// structural changes of the container with [Commands], and describes the batch being handled.
+/// Commands are applied once the dispatch is over, or at the next stage barrier of the schedule.
+///
+/// Events emitted with `emit` are buffered until every handler of the batch ran, then emitted
+/// in the order of the handlers and of the calls, so they are stamped after the events emitted
+/// directly on the `EventManager` during the batch. Batches dispatched concurrently on a thread
+/// pool flush their events in the order of their lane, as if dispatched one after the other.
+///
+/// # Examples
+/// ```
+/// use emark::event::{EventManager, HandlerRegistry};
+/// use emark::prelude::*;
+/// use emark::store::{Context, ResourceContainer};
+///
+/// struct Damage(u32);
+/// impl Event for Damage {}
+///
+/// struct Died;
+/// impl Event for Died {}
+///
+/// let mut registry = HandlerRegistry::new();
+/// registry.add_context_handler(|damages: &[Damage], context: &Context| {
+///     let mut health = context.retrieve::<ResMut<u32>>();
+///     *health = health.saturating_sub(damages.iter().map(|damage| damage.0).sum());
+///     if *health == 0 {
+///         context.emit(Died);
+///         context.commands().insert("game over");
+///     }
+/// });
+///
+/// let mut container = ResourceContainer::default();
+/// container.add_resource(10u32);
+/// let event_manager = EventManager::new();
+/// event_manager.emit(Damage(4));
+/// event_manager.emit(Damage(8));
+/// registry.dispatch_with(&container, &event_manager);
+///
+/// container.apply_commands();
+/// assert_eq!(*Res::<&str>::retrieve(&container), "game over");
+/// ```
+pub struct Context<'a> {
+    container: &'a ResourceContainer,
+    event_manager: &'a EventManager,
+    batch: &'a EmittedEventInfo,
+    deferred: &'a DeferredEmissions,
+}
+
+impl<'a> Context<'a> {
+    pub(crate) fn new(
+        container: &'a ResourceContainer,
+        event_manager: &'a EventManager,
+        batch: &'a EmittedEventInfo,
+        deferred: &'a DeferredEmissions,
+    ) -> Self {
+        Self {
+            container,
+            event_manager,
+            batch,
+            deferred,
+        }
+    }
+
+    /// Retrieves a set of resources from the container, see [Retriever::retrieve].
+    pub fn retrieve<R: Retriever>(&self) -> R::Item<'a> {
+        R::retrieve(self.container)
+    }
+
+    /// Retrieves a set of resources from the container, see [Retriever::try_retrieve].
+    pub fn try_retrieve<R: Retriever>(&self) -> Result<R::Item<'a>, RetrievalError> {
+        R::try_retrieve(self.container)
+    }
+
+    /// Records the structural changes of the container, see [Commands].
+    pub fn commands(&self) -> Commands<'a> {
+        Commands::retrieve(self.container)
+    }
+
+    /// Emits an event with `Normal` priority once the batch is dispatched.
+    pub fn emit<T: Event + Send + Sync + 'static>(&self, event: T) {
+        self.emit_priority(event, Priority::Normal);
+    }
+
+    /// Emits an event with the specified priority once the batch is dispatched.
+    pub fn emit_priority<T: Event + Send + Sync + 'static>(&self, event: T, priority: Priority) {
+        self.deferred.push(move |event_manager| {
+            event_manager.emit_priority(event, priority);
+        });
+    }
+
+    /// Batch being handled, with its priority and the stamps of its events.
+    pub fn batch(&self) -> &'a EmittedEventInfo {
+        self.batch
+    }
+
+    /// Type name of the events being handled.
+    pub fn event_type_name(&self) -> &'static str {
+        self.batch.event_type_name()
+    }
+
+    /// Priority the batch is dispatched with.
+    pub fn priority(&self) -> Priority {
+        self.batch.priority()
+    }
+
+    /// Number of events of the batch.
+    pub fn batch_len(&self) -> usize {
+        self.batch.len()
+    }
+
+    /// Sequence numbers of the events of the batch, see [EmittedEventInfo::sequence_range].
+    pub fn sequence_range(&self) -> Option<RangeInclusive<u64>> {
+        self.batch.sequence_range()
+    }
+
+    pub fn container(&self) -> &'a ResourceContainer {
+        self.container
+    }
+
+    pub fn event_manager(&self) -> &'a EventManager {
+        self.event_manager
+    }
+}
+
+type Emission = Box<dyn FnOnce(&EventManager) + Send>;
+
+// events emitted through the contexts of a batch, flushed once the batch is dispatched
+#[derive(Default)]
+pub(crate) struct DeferredEmissions {
+    emissions: Mutex<Vec<Emission>>,
+}
+
+impl DeferredEmissions {
+    fn push(&self, emission: impl FnOnce(&EventManager) + Send + 'static) {
+        self.emissions.lock().push(Box::new(emission));
+    }
+
+    // emits the buffered events in emission order
+    pub(crate) fn flush(self, event_manager: &EventManager) {
+        for emission in self.emissions.into_inner() {
+            emission(event_manager);
+        }
+    }
+}
+
+impl Debug for Context<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Context")
+            .field("batch", &self.batch.event_type_name())
+            .field("priority", &self.batch.priority())
+            .finish()
+    }
+}
+
+/// Retriever only borrowing resources immutably, see [ReadContext].
+///
+/// Implemented for `Res`, `ResAll`, `AnyOf` of read-only candidates and tuples of up to 32
+/// read-only retrievers.
+pub trait ReadOnlyRetriever: Retriever {}
+
+impl<T: Resource + ?Sized> ReadOnlyRetriever for Res<'_, T> {}
+
+impl<I: ?Sized + 'static> ReadOnlyRetriever for ResAll<'_, I> {}
+
+macro_rules! impl_read_only_retriever {
+    ($($retriever:ident),*) => {
+        impl<$($retriever: ReadOnlyRetriever),*> ReadOnlyRetriever for ($($retriever,)*) {}
+    };
+}
+
+impl_tuples!(impl_read_only_retriever);
+
+/// Read-only [Context] of a handler, see `HandlerRegistry::add_read_handler`.
+///
+/// The context only retrieves [ReadOnlyRetriever]s and reads the pending events, so any number
+/// of read-only handlers can run concurrently.
+///
+/// # Examples
+/// ```
+/// use emark::event::{EventManager, HandlerRegistry};
+/// use emark::prelude::*;
+/// use emark::store::{ReadContext, ResourceContainer};
+///
+/// struct Frame;
+/// impl Event for Frame {}
+///
+/// let mut registry = HandlerRegistry::new();
+/// registry.add_read_handler(|_: &[Frame], context: &ReadContext| {
+///     let (width, height) = context.retrieve::<(Res<u32>, Res<u64>)>();
+///     assert_eq!((*width, *height), (640, 480));
+/// });
+///
+/// let mut container = ResourceContainer::default();
+/// container.add_resource(640u32);
+/// container.add_resource(480u64);
+/// let event_manager = EventManager::new();
+/// event_manager.emit(Frame);
+/// registry.dispatch_with(&container, &event_manager);
+/// ```
+///
+/// Mutable borrows do not compile:
+/// ```compile_fail
+/// # use emark::prelude::*;
+/// # use emark::store::ReadContext;
+/// fn handler(context: &ReadContext) {
+///     context.retrieve::<ResMut<u32>>();
+/// }
+/// ```
+pub struct ReadContext<'a> {
+    container: &'a ResourceContainer,
+    event_manager: &'a EventManager,
+    batch: &'a EmittedEventInfo,
+}
+
+impl<'a> ReadContext<'a> {
+    pub(crate) fn new(
+        container: &'a ResourceContainer,
+        event_manager: &'a EventManager,
+        batch: &'a EmittedEventInfo,
+    ) -> Self {
+        Self {
+            container,
+            event_manager,
+            batch,
+        }
+    }
+
+    /// Retrieves a set of resources from the container, see [Retriever::retrieve].
+    pub fn retrieve<R: ReadOnlyRetriever>(&self) -> R::Item<'a> {
+        R::retrieve(self.container)
+    }
+
+    /// Retrieves a set of resources from the container, see [Retriever::try_retrieve].
+    pub fn try_retrieve<R: ReadOnlyRetriever>(&self) -> Result<R::Item<'a>, RetrievalError> {
+        R::try_retrieve(self.container)
+    }
+
+    /// Inspects the pending events of type `T`, see `EventManager::peek`.
+    pub fn peek<T: Event + Send + Sync + 'static, R>(
+        &self,
+        f: impl FnOnce(&[T]) -> R,
+    ) -> Option<R> {
+        self.event_manager.peek(f)
+    }
+
+    /// Returns the number of pending events of type `T`.
+    pub fn pending_count<T: Event + Send + Sync + 'static>(&self) -> usize {
+        self.event_manager.pending_count::<T>()
+    }
+
+    /// Batch being handled, with its priority and the stamps of its events.
+    pub fn batch(&self) -> &'a EmittedEventInfo {
+        self.batch
+    }
+
+    /// Type name of the events being handled.
+    pub fn event_type_name(&self) -> &'static str {
+        self.batch.event_type_name()
+    }
+
+    /// Priority the batch is dispatched with.
+    pub fn priority(&self) -> Priority {
+        self.batch.priority()
+    }
+
+    /// Number of events of the batch.
+    pub fn batch_len(&self) -> usize {
+        self.batch.len()
+    }
+
+    /// Sequence numbers of the events of the batch, see [EmittedEventInfo::sequence_range].
+    pub fn sequence_range(&self) -> Option<RangeInclusive<u64>> {
+        self.batch.sequence_range()
+    }
+}
+
+impl<'a> From<&Context<'a>> for ReadContext<'a> {
+    fn from(context: &Context<'a>) -> Self {
+        ReadContext::new(context.container, context.event_manager, context.batch)
+    }
+}
+
+impl Debug for ReadContext<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ReadContext")
+            .field("batch", &self.batch.event_type_name())
+            .field("priority", &self.batch.priority())
+            .finish()
+    }
+}
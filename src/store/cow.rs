@@ -0,0 +1,119 @@
+use std::{fmt::Debug, sync::Arc};
+
+use parking_lot::{Mutex, RwLock};
+
+/// Copy-on-write resource, for configuration resources read by many systems and handlers
+/// every cycle and rarely written.
+///
+/// Readers `load` a cheap `Arc` snapshot of the value, only locking the resource for the time
+/// of cloning the `Arc`, and keep reading it while writers `update` a copy of the value and
+/// swap it in. Both go through a shared `Res<CowResource<T>>`, so readers and writers never
+/// wait for each other's borrows, and systems reading and writing it run concurrently.
+///
+/// Writes through `Res` do not mark the resource changed.
+///
+/// # Examples
+/// ```
+/// use emark::prelude::*;
+/// use emark::store::{CowResource, ResourceContainer};
+///
+/// #[derive(Clone)]
+/// struct Config {
+///     volume: f32,
+/// }
+///
+/// let mut container = ResourceContainer::default();
+/// container.add_resource(CowResource::new(Config { volume: 1.0 }));
+///
+/// let config = Res::<CowResource<Config>>::retrieve(&container);
+/// let snapshot = config.load();
+/// config.update(|config| config.volume = 0.5);
+///
+/// // assert the snapshot is not affected by the update
+/// assert_eq!(snapshot.volume, 1.0);
+/// assert_eq!(config.load().volume, 0.5);
+/// ```
+pub struct CowResource<T> {
+    current: RwLock<Arc<T>>,
+    // serializes the writers, so concurrent updates are not lost
+    writer: Mutex<()>,
+}
+
+impl<T> CowResource<T> {
+    pub fn new(value: T) -> Self {
+        Self {
+            current: RwLock::new(Arc::new(value)),
+            writer: Mutex::new(()),
+        }
+    }
+
+    /// Snapshot of the current value, unaffected by later writes.
+    pub fn load(&self) -> Arc<T> {
+        self.current.read().clone()
+    }
+
+    /// Replaces the value, returning the previous snapshot.
+    pub fn store(&self, value: T) -> Arc<T> {
+        let _writer = self.writer.lock();
+        std::mem::replace(&mut *self.current.write(), Arc::new(value))
+    }
+
+    /// Updates a copy of the value with `f` and swaps it in, returning the result of `f`.
+    ///
+    /// Readers keep loading the previous value while `f` runs. Concurrent updates run one after
+    /// the other, each updating the value written by the previous one.
+    pub fn update<R>(&self, f: impl FnOnce(&mut T) -> R) -> R
+    where
+        T: Clone,
+    {
+        let _writer = self.writer.lock();
+        // only writers replace the value, so it can be copied outside of the lock
+        let mut value = T::clone(&self.load());
+        let result = f(&mut value);
+        *self.current.write() = Arc::new(value);
+        result
+    }
+
+    /// Takes the current value out, cloning it if snapshots of it are still loaded.
+    pub fn into_inner(self) -> T
+    where
+        T: Clone,
+    {
+        Arc::unwrap_or_clone(self.current.into_inner())
+    }
+}
+
+impl<T: Default> Default for CowResource<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T: Debug> Debug for CowResource<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("CowResource").field(&self.load()).finish()
+    }
+}
+
+#[cfg(test)]
+mod test_cow {
+    use super::*;
+
+    #[test]
+    fn test_cow_concurrent_updates() {
+        let counter = CowResource::new(0u32);
+        let snapshot = counter.load();
+        std::thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| {
+                    for _ in 0..100 {
+                        counter.update(|count| *count += 1);
+                    }
+                });
+            }
+        });
+        assert_eq!(*snapshot, 0);
+        assert_eq!(*counter.store(0), 400);
+        assert_eq!(counter.into_inner(), 0);
+    }
+}
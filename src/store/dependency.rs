@@ -0,0 +1,175 @@
+use std::{any::TypeId, fmt::Debug};
+
+use crate::utils::error::EmarkError;
+
+use super::{Container, FromContainer, ResourceContainer};
+
+#[derive(Clone, Copy)]
+enum DependencyKind {
+    // initialized with `FromContainer` if missing
+    Init {
+        init: fn(&mut ResourceContainer),
+        dependencies: fn() -> Vec<Dependency>,
+    },
+    // must be added before the dependent resource is initialized
+    Required,
+}
+
+/// Resource a [FromContainer] resource depends on, see `FromContainer::dependencies`.
+#[derive(Clone, Copy)]
+pub struct Dependency {
+    type_id: TypeId,
+    type_name: &'static str,
+    kind: DependencyKind,
+}
+
+fn init_resource<T: FromContainer>(container: &mut ResourceContainer) {
+    let resource = T::from_container(container);
+    container.insert_overwrite(resource);
+}
+
+impl Dependency {
+    /// Dependency on resource `T`, initialized with its own dependencies if it is missing.
+    pub fn init<T: FromContainer>() -> Self {
+        Self {
+            type_id: TypeId::of::<T>(),
+            type_name: std::any::type_name::<T>(),
+            kind: DependencyKind::Init {
+                init: init_resource::<T>,
+                dependencies: T::dependencies,
+            },
+        }
+    }
+
+    /// Dependency on resource `T`, which must already be in the container.
+    pub fn required<T: 'static>() -> Self {
+        Self {
+            type_id: TypeId::of::<T>(),
+            type_name: std::any::type_name::<T>(),
+            kind: DependencyKind::Required,
+        }
+    }
+
+    pub fn type_id(&self) -> TypeId {
+        self.type_id
+    }
+
+    pub fn type_name(&self) -> &'static str {
+        self.type_name
+    }
+
+    pub fn is_required(&self) -> bool {
+        matches!(self.kind, DependencyKind::Required)
+    }
+}
+
+impl Debug for Dependency {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Dependency")
+            .field("type_name", &self.type_name)
+            .field("required", &self.is_required())
+            .finish()
+    }
+}
+
+// initializes the missing dependencies of `dependency` depth first, then `dependency` itself,
+// `path` holding the resources being initialized
+pub(crate) fn init_ordered(
+    container: &mut ResourceContainer,
+    dependency: Dependency,
+    dependent: Option<&'static str>,
+    path: &mut Vec<Dependency>,
+) -> Result<(), EmarkError> {
+    if container.contains_resource_any(dependency.type_id) {
+        return Ok(());
+    }
+    let DependencyKind::Init { init, dependencies } = dependency.kind else {
+        return Err(EmarkError::MissingResourceDependency {
+            resource: dependent.unwrap_or(dependency.type_name),
+            dependency: dependency.type_name,
+        });
+    };
+    if let Some(start) = path
+        .iter()
+        .position(|visited| visited.type_id == dependency.type_id)
+    {
+        let mut cycle = path[start..]
+            .iter()
+            .map(|visited| visited.type_name)
+            .collect::<Vec<_>>();
+        cycle.push(dependency.type_name);
+        return Err(EmarkError::CyclicResourceDependency(cycle));
+    }
+
+    path.push(dependency);
+    for prerequisite in dependencies() {
+        init_ordered(container, prerequisite, Some(dependency.type_name), path)?;
+    }
+    path.pop();
+    init(container);
+    Ok(())
+}
+
+#[cfg(test)]
+mod test_dependency {
+    use super::*;
+    use crate::store::{Res, Retriever};
+
+    struct Window(u32);
+    struct Renderer(u32);
+
+    impl FromContainer for Renderer {
+        fn dependencies() -> Vec<Dependency> {
+            vec![Dependency::init::<Window>()]
+        }
+
+        fn from_container(container: &mut ResourceContainer) -> Self {
+            Renderer(Res::<Window>::retrieve(container).0)
+        }
+    }
+
+    impl FromContainer for Window {
+        fn dependencies() -> Vec<Dependency> {
+            vec![Dependency::required::<u32>()]
+        }
+
+        fn from_container(container: &mut ResourceContainer) -> Self {
+            Window(*Res::<u32>::retrieve(container))
+        }
+    }
+
+    struct Cyclic;
+
+    impl FromContainer for Cyclic {
+        fn dependencies() -> Vec<Dependency> {
+            vec![Dependency::init::<Cyclic>()]
+        }
+
+        fn from_container(_: &mut ResourceContainer) -> Self {
+            Cyclic
+        }
+    }
+
+    #[test]
+    fn test_init_dependencies() {
+        let mut container = ResourceContainer::default();
+        assert_eq!(
+            container.try_init_resource::<Renderer>(),
+            Err(EmarkError::MissingResourceDependency {
+                resource: std::any::type_name::<Window>(),
+                dependency: "u32",
+            })
+        );
+        assert!(!container.contains_resource::<Renderer>());
+
+        container.add_resource(3u32);
+        container.try_init_resource::<Renderer>().unwrap();
+        assert_eq!(Res::<Renderer>::retrieve(&container).0, 3);
+
+        let cyclic = std::any::type_name::<Cyclic>();
+        assert_eq!(
+            container.try_init_resource::<Cyclic>(),
+            Err(EmarkError::CyclicResourceDependency(vec![cyclic, cyclic]))
+        );
+    }
+}
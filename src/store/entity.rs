@@ -0,0 +1,500 @@
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    fmt::{Debug, Display},
+    ops::{Deref, DerefMut},
+    ptr::NonNull,
+};
+
+use crate::utils::lock::{
+    grained_ref::{Immutable, LockState, Mutable},
+    GrainedLock, Ref,
+};
+
+use super::tick::{ChangeClock, ChangeTicks};
+
+/// Data attached to an [Entity].
+///
+/// Components are shared with the systems running on other threads, so they must be `Send`
+/// and `Sync`.
+pub trait Component: Send + Sync + 'static {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+/// Generational id of an entity of [Entities].
+///
+/// The index of a despawned entity is reused by the next spawned entity with an incremented
+/// generation, so stale ids never refer to the new entity.
+pub struct Entity {
+    index: u32,
+    generation: u32,
+}
+
+impl Entity {
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+}
+
+impl Display for Entity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}v{}", self.index, self.generation)
+    }
+}
+
+// components of type `T`, indexed by the index of their entity
+pub(crate) struct Column<T> {
+    pub(crate) components: Vec<Option<T>>,
+}
+
+impl<T> Column<T> {
+    pub(crate) fn get(&self, index: usize) -> Option<&T> {
+        self.components.get(index)?.as_ref()
+    }
+
+    pub(crate) fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        self.components.get_mut(index)?.as_mut()
+    }
+
+    fn insert(&mut self, index: usize, component: T) -> Option<T> {
+        if index >= self.components.len() {
+            self.components.resize_with(index + 1, || None);
+        }
+        self.components[index].replace(component)
+    }
+
+    fn remove(&mut self, index: usize) -> Option<T> {
+        self.components.get_mut(index)?.take()
+    }
+}
+
+// id of the column of the components of type `T`, distinct from the id of a resource `T`
+pub(crate) fn column_id<T: 'static>() -> TypeId {
+    TypeId::of::<Column<T>>()
+}
+
+// locked column of components with the change ticks of the entities having one
+struct ColumnEntry {
+    lock: GrainedLock<Box<dyn Any>>,
+    // readable without locking the column, since components are only added and removed
+    // through `&mut Entities`
+    ticks: Vec<Option<ChangeTicks>>,
+    remove: fn(&mut dyn Any, usize),
+}
+
+// columns only hold components, which are `Send + Sync`
+unsafe impl Send for ColumnEntry {}
+unsafe impl Sync for ColumnEntry {}
+
+impl ColumnEntry {
+    fn new<T: 'static>() -> Self {
+        Self {
+            lock: GrainedLock::new(Box::new(Column::<T> {
+                components: Vec::new(),
+            })),
+            ticks: Vec::new(),
+            remove: |column, index| {
+                downcast_mut::<T>(column).remove(index);
+            },
+        }
+    }
+
+    fn set_ticks(&mut self, index: usize, ticks: Option<ChangeTicks>) {
+        if index >= self.ticks.len() {
+            self.ticks.resize_with(index + 1, || None);
+        }
+        self.ticks[index] = ticks;
+    }
+
+    fn ticks(&self, index: usize) -> Option<&ChangeTicks> {
+        self.ticks.get(index)?.as_ref()
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Slot {
+    generation: u32,
+    alive: bool,
+}
+
+#[derive(Default)]
+/// Entities and their components.
+///
+/// Entities are spawned and despawned through `&mut Entities`, while their components are
+/// stored per type, each type behind its own lock. Components of distinct types can thus be
+/// borrowed mutably at the same time through `&Entities`, e.g. from systems retrieving
+/// `Res<Entities>` and running concurrently.
+///
+/// `Entities` is a resource, added to a `ResourceContainer` like any other.
+///
+/// # Examples
+/// ```
+/// use emark::store::{Component, Entities};
+///
+/// struct Position(f32);
+/// impl Component for Position {}
+///
+/// struct Velocity(f32);
+/// impl Component for Velocity {}
+///
+/// let mut entities = Entities::new();
+/// let agent = entities.spawn();
+/// entities.insert(agent, Position(0.0));
+/// entities.insert(agent, Velocity(2.0));
+///
+/// // borrow both columns at once
+/// let velocity = entities.get::<Velocity>(agent).unwrap();
+/// entities.get_mut::<Position>(agent).unwrap().0 += velocity.0;
+/// drop(velocity);
+/// assert_eq!(entities.get::<Position>(agent).unwrap().0, 2.0);
+///
+/// entities.despawn(agent);
+/// assert!(entities.get::<Position>(agent).is_none());
+/// ```
+pub struct Entities {
+    slots: Vec<Slot>,
+    free: Vec<u32>,
+    len: usize,
+    columns: HashMap<TypeId, ColumnEntry>,
+    clock: ChangeClock,
+}
+
+impl Entities {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Spawns an entity without components.
+    pub fn spawn(&mut self) -> Entity {
+        self.len += 1;
+        if let Some(index) = self.free.pop() {
+            let slot = &mut self.slots[index as usize];
+            slot.alive = true;
+            return Entity {
+                index,
+                generation: slot.generation,
+            };
+        }
+
+        let index = u32::try_from(self.slots.len()).expect("too many entities");
+        self.slots.push(Slot {
+            generation: 0,
+            alive: true,
+        });
+        Entity {
+            index,
+            generation: 0,
+        }
+    }
+
+    /// Despawns `entity`, dropping its components.
+    ///
+    /// Returns `true` if the entity was alive.
+    pub fn despawn(&mut self, entity: Entity) -> bool {
+        if !self.contains(entity) {
+            return false;
+        }
+
+        let slot = &mut self.slots[entity.index as usize];
+        slot.alive = false;
+        slot.generation = slot.generation.wrapping_add(1);
+        self.free.push(entity.index);
+        self.len -= 1;
+        for column in self.columns.values_mut() {
+            column.set_ticks(entity.index as usize, None);
+            (column.remove)(&mut **column.lock.borrow_mut(), entity.index as usize);
+        }
+        true
+    }
+
+    /// Returns `true` if `entity` is alive.
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.slots
+            .get(entity.index as usize)
+            .is_some_and(|slot| slot.alive && slot.generation == entity.generation)
+    }
+
+    /// Number of alive entities.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Iterates over the alive entities, in index order.
+    pub fn iter(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.slots
+            .iter()
+            .enumerate()
+            .filter(|(_, slot)| slot.alive)
+            .map(|(index, slot)| Entity {
+                index: index as u32,
+                generation: slot.generation,
+            })
+    }
+
+    /// Attaches `component` to `entity`, returning the component of the same type it replaces.
+    ///
+    /// # Panics
+    /// Panics if `entity` is not alive.
+    pub fn insert<T: Component>(&mut self, entity: Entity, component: T) -> Option<T> {
+        assert!(self.contains(entity), "entity {entity} is not alive");
+        let index = entity.index as usize;
+        let column = self
+            .columns
+            .entry(column_id::<T>())
+            .or_insert_with(ColumnEntry::new::<T>);
+        column.set_ticks(index, Some(ChangeTicks::new(self.clock.advance())));
+        downcast_mut::<T>(&mut **column.lock.borrow_mut()).insert(index, component)
+    }
+
+    /// Detaches the component of type `T` from `entity`.
+    pub fn remove<T: Component>(&mut self, entity: Entity) -> Option<T> {
+        if !self.contains(entity) {
+            return None;
+        }
+        let index = entity.index as usize;
+        let column = self.columns.get_mut(&column_id::<T>())?;
+        column.set_ticks(index, None);
+        downcast_mut::<T>(&mut **column.lock.borrow_mut()).remove(index)
+    }
+
+    /// Returns `true` if `entity` is alive and has a component of type `T`.
+    pub fn has<T: Component>(&self, entity: Entity) -> bool {
+        self.contains(entity) && self.has_component(column_id::<T>(), entity.index as usize)
+    }
+
+    /// Borrows the component of type `T` of `entity`, locking the components of type `T`
+    /// for reading.
+    pub fn get<T: Component>(&self, entity: Entity) -> Option<ComponentRef<'_, T>> {
+        Some(ComponentRef {
+            component: self.lock_component::<T, Immutable>(entity)?,
+        })
+    }
+
+    /// Mutably borrows the component of type `T` of `entity`, locking the components of type
+    /// `T` for writing.
+    ///
+    /// Mutably dereferencing the borrow marks the component changed.
+    pub fn get_mut<T: Component>(&self, entity: Entity) -> Option<ComponentMut<'_, T>> {
+        Some(ComponentMut {
+            component: self.lock_component::<T, Mutable>(entity)?,
+            ticks: self.ticks(column_id::<T>(), entity.index as usize)?,
+            tick: self.clock.advance(),
+        })
+    }
+
+    // locks the column of `T` and narrows the borrow to the component of `entity`
+    fn lock_component<T: Component, S: LockState>(&self, entity: Entity) -> Option<Ref<'_, T, S>> {
+        if !self.contains(entity) {
+            return None;
+        }
+        let index = entity.index as usize;
+        if !self.has_component(column_id::<T>(), index) {
+            return None;
+        }
+        let column = lock::<S>(&self.columns[&column_id::<T>()].lock);
+
+        // the column is kept alive and locked by the guards of the ref
+        Some(unsafe {
+            column.map_unchecked::<T, S>(|mut data| {
+                // only create a mutable reference from an exclusive lock
+                match S::MUTABLE {
+                    true => NonNull::from(
+                        downcast_mut::<T>(&mut **data.as_mut())
+                            .get_mut(index)
+                            .unwrap(),
+                    ),
+                    false => NonNull::from(downcast::<T>(&**data.as_ref()).get(index).unwrap()),
+                }
+            })
+        })
+    }
+
+    // lock of the column `column_id`
+    pub(crate) fn column(&self, column_id: TypeId) -> Option<&GrainedLock<Box<dyn Any>>> {
+        Some(&self.columns.get(&column_id)?.lock)
+    }
+
+    // true if the entity at `index` has a component in the column `column_id`
+    pub(crate) fn has_component(&self, column_id: TypeId, index: usize) -> bool {
+        self.ticks(column_id, index).is_some()
+    }
+
+    // change ticks of the component of the entity at `index` in the column `column_id`
+    pub(crate) fn ticks(&self, column_id: TypeId, index: usize) -> Option<&ChangeTicks> {
+        self.columns.get(&column_id)?.ticks(index)
+    }
+
+    // shares the change tick of the container holding the entities, the components being
+    // added along with the entities
+    pub(crate) fn set_clock(&mut self, clock: ChangeClock) {
+        let tick = clock.advance();
+        for column in self.columns.values_mut() {
+            for ticks in column.ticks.iter_mut().flatten() {
+                *ticks = ChangeTicks::new(tick);
+            }
+        }
+        self.clock = clock;
+    }
+
+    // alive entity at `index`
+    pub(crate) fn entity_at(&self, index: usize) -> Option<Entity> {
+        let slot = self.slots.get(index).filter(|slot| slot.alive)?;
+        Some(Entity {
+            index: index as u32,
+            generation: slot.generation,
+        })
+    }
+
+    // number of indices ever allocated
+    pub(crate) fn slot_count(&self) -> usize {
+        self.slots.len()
+    }
+}
+
+// locks a column with the access state `S`
+fn lock<S: LockState>(column: &GrainedLock<Box<dyn Any>>) -> Ref<'_, Box<dyn Any>, S> {
+    // the marker of the ref matches the guard taken
+    unsafe {
+        match S::MUTABLE {
+            true => column.borrow_mut().map_unchecked(|data| data),
+            false => column.borrow().map_unchecked(|data| data),
+        }
+    }
+}
+
+fn downcast<T: 'static>(column: &dyn Any) -> &Column<T> {
+    column.downcast_ref::<Column<T>>().unwrap()
+}
+
+fn downcast_mut<T: 'static>(column: &mut dyn Any) -> &mut Column<T> {
+    column.downcast_mut::<Column<T>>().unwrap()
+}
+
+impl Debug for Entities {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Entities")
+            .field("len", &self.len)
+            .field("components", &self.columns.len())
+            .finish()
+    }
+}
+
+/// Shared borrow of a component of type `T`, see [Entities::get].
+pub struct ComponentRef<'a, T: 'static> {
+    component: Ref<'a, T, Immutable>,
+}
+
+impl<T: 'static> Deref for ComponentRef<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.component
+    }
+}
+
+impl<T: Debug + 'static> Debug for ComponentRef<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("ComponentRef").field(self.deref()).finish()
+    }
+}
+
+/// Exclusive borrow of a component of type `T`, see [Entities::get_mut].
+pub struct ComponentMut<'a, T: 'static> {
+    component: Ref<'a, T, Mutable>,
+    ticks: &'a ChangeTicks,
+    tick: u64,
+}
+
+impl<T: 'static> Deref for ComponentMut<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.component
+    }
+}
+
+impl<T: 'static> DerefMut for ComponentMut<'_, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.ticks.set_changed(self.tick);
+        &mut self.component
+    }
+}
+
+impl<T: Debug + 'static> Debug for ComponentMut<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("ComponentMut").field(self.deref()).finish()
+    }
+}
+
+#[cfg(test)]
+mod test_entity {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Health(u32);
+    impl Component for Health {}
+
+    #[derive(Debug, PartialEq)]
+    struct Speed(u32);
+    impl Component for Speed {}
+
+    #[test]
+    fn test_entity_spawn_despawn() {
+        let mut entities = Entities::new();
+        let first = entities.spawn();
+        let second = entities.spawn();
+        assert_eq!(entities.len(), 2);
+
+        assert!(entities.despawn(first));
+        assert!(!entities.despawn(first));
+
+        // assert the index is reused with a new generation
+        let third = entities.spawn();
+        assert_eq!(third.index(), first.index());
+        assert_ne!(third, first);
+        assert!(!entities.contains(first));
+        assert_eq!(entities.iter().collect::<Vec<_>>(), vec![third, second]);
+    }
+
+    #[test]
+    fn test_entity_components() {
+        let mut entities = Entities::new();
+        let entity = entities.spawn();
+        assert_eq!(entities.insert(entity, Health(10)), None);
+        assert_eq!(entities.insert(entity, Health(20)), Some(Health(10)));
+        entities.insert(entity, Speed(1));
+
+        // assert components of distinct types are borrowed independently
+        {
+            let speed = entities.get::<Speed>(entity).unwrap();
+            let mut health = entities.get_mut::<Health>(entity).unwrap();
+            health.0 -= speed.0;
+        }
+        assert_eq!(*entities.get::<Health>(entity).unwrap(), Health(19));
+
+        assert_eq!(entities.remove::<Speed>(entity), Some(Speed(1)));
+        assert!(!entities.has::<Speed>(entity));
+        entities.despawn(entity);
+        assert!(!entities.has::<Health>(entity));
+
+        // assert components of a despawned entity do not leak into the next one
+        let next = entities.spawn();
+        assert!(entities.get::<Health>(next).is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "is not alive")]
+    fn test_entity_insert_despawned() {
+        let mut entities = Entities::new();
+        let entity = entities.spawn();
+        entities.despawn(entity);
+        entities.insert(entity, Health(1));
+    }
+}
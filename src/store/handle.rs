@@ -0,0 +1,101 @@
+use std::{any::TypeId, fmt::Debug, marker::PhantomData, sync::Weak};
+
+use super::{blob::ResourceEntry, Res, ResMut, Resource, ResourceContainer, Retriever};
+
+/// Weak handle to a resource of type `T`, created by `ResourceContainer::weak_handle`.
+///
+/// A handle does not borrow the container, so it can be stored across cycles, and is upgraded
+/// to a borrow of the resource without looking it up again. Upgrading fails once the resource
+/// is removed or replaced, or if the handle is upgraded against another container.
+///
+/// # Examples
+/// ```
+/// use emark::prelude::*;
+/// use emark::store::ResourceContainer;
+///
+/// let mut container = ResourceContainer::default();
+/// container.add_resource(1u32);
+///
+/// let handle = container.weak_handle::<u32>();
+/// *handle.get_mut(&container).unwrap() += 1;
+/// assert_eq!(*handle.get(&container).unwrap(), 2);
+///
+/// container.remove_resource::<u32>();
+/// assert!(handle.get(&container).is_none());
+/// ```
+pub struct ResHandle<T: Resource + ?Sized> {
+    // id of the container holding the resource
+    container: u64,
+    entry: Weak<ResourceEntry>,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T: Resource + ?Sized> ResHandle<T> {
+    pub(crate) fn new(container: u64, entry: Weak<ResourceEntry>) -> Self {
+        Self {
+            container,
+            entry,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns `true` if the resource has not been removed or replaced since the handle was
+    /// created.
+    pub fn is_alive(&self) -> bool {
+        self.entry.strong_count() > 0
+    }
+
+    /// Borrows the resource, or returns `None` if it no longer exists in `container`.
+    pub fn get<'a>(&self, container: &'a ResourceContainer) -> Option<Res<'a, T>> {
+        let retrieved =
+            container.retrieve_handle(self.container, TypeId::of::<T>(), &self.entry, false)?;
+        Some(Res::<T>::assemble(&mut std::iter::once(retrieved)))
+    }
+
+    /// Mutably borrows the resource, or returns `None` if it no longer exists in `container`.
+    pub fn get_mut<'a>(&self, container: &'a ResourceContainer) -> Option<ResMut<'a, T>> {
+        let retrieved =
+            container.retrieve_handle(self.container, TypeId::of::<T>(), &self.entry, true)?;
+        Some(ResMut::<T>::assemble(&mut std::iter::once(retrieved)))
+    }
+}
+
+impl<T: Resource + ?Sized> Clone for ResHandle<T> {
+    fn clone(&self) -> Self {
+        Self::new(self.container, self.entry.clone())
+    }
+}
+
+impl<T: Resource + ?Sized> Debug for ResHandle<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ResHandle")
+            .field("type_name", &std::any::type_name::<T>())
+            .field("alive", &self.is_alive())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test_handle {
+    use super::*;
+    use crate::store::Container;
+
+    #[test]
+    fn test_handle_replaced() {
+        let mut container = ResourceContainer::default();
+        let missing = container.weak_handle::<u32>();
+        container.add_resource(1u32);
+        let handle = container.weak_handle::<u32>();
+        assert!(!missing.is_alive());
+        assert!(handle.clone().get(&container).is_some());
+
+        // assert handles are bound to their container
+        let mut other = ResourceContainer::default();
+        other.add_resource(1u32);
+        assert!(handle.get(&other).is_none());
+
+        container.add_resource(2u32);
+        assert!(!handle.is_alive());
+        assert!(handle.get_mut(&container).is_none());
+    }
+}
@@ -0,0 +1,192 @@
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    fmt::Debug,
+    ops::Deref,
+};
+
+use crate::utils::lock::{grained_ref::Immutable, Ref};
+
+use super::{
+    res::Storage,
+    Request, Retrieved, Retriever,
+};
+
+// casts a type-erased resource of a bound type to the interface `I`
+type Cast<I> = Box<dyn Fn(&dyn Any) -> &I + Send + Sync>;
+
+// resource types bound to each interface by `ResourceContainer::bind_interface`
+#[derive(Default)]
+pub(crate) struct InterfaceRegistry {
+    bound: HashMap<TypeId, Vec<TypeId>>,
+    // `Cast<I>` by interface and resource type
+    casts: HashMap<(TypeId, TypeId), Box<dyn Any + Send + Sync>>,
+}
+
+impl InterfaceRegistry {
+    pub(crate) fn bind<T: 'static, I: ?Sized + 'static>(&mut self, cast: fn(&T) -> &I) {
+        let (interface, type_id) = (TypeId::of::<I>(), TypeId::of::<T>());
+        let cast: Cast<I> = Box::new(move |resource| cast(resource.downcast_ref::<T>().unwrap()));
+        if self
+            .casts
+            .insert((interface, type_id), Box::new(cast))
+            .is_none()
+        {
+            self.bound.entry(interface).or_default().push(type_id);
+        }
+    }
+
+    pub(crate) fn bound(&self, interface: TypeId) -> &[TypeId] {
+        self.bound.get(&interface).map_or(&[], Vec::as_slice)
+    }
+
+    pub(crate) fn cast(&self, interface: TypeId, type_id: TypeId) -> &(dyn Any + Send + Sync) {
+        &*self.casts[&(interface, type_id)]
+    }
+}
+
+impl Debug for InterfaceRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("InterfaceRegistry")
+            .field("interfaces", &self.bound.len())
+            .field("bindings", &self.casts.len())
+            .finish()
+    }
+}
+
+/// Shared borrows of every resource bound to the interface `I` with
+/// `ResourceContainer::bind_interface`, e.g. to iterate every `dyn Diagnostic` provider of a
+/// plugin based application without knowing their types.
+///
+/// The bound resources missing from the container are skipped. They are locked along with the
+/// other resources of the retriever, in sorted `TypeId` order, while the access of the system
+/// only reports a read of `I`, so executors may schedule systems writing to the bound resources
+/// concurrently, one waiting for the other.
+///
+/// # Examples
+/// ```
+/// use emark::prelude::*;
+/// use emark::store::{ResAll, ResourceContainer};
+///
+/// trait Diagnostic {
+///     fn report(&self) -> String;
+/// }
+///
+/// struct Fps(u32);
+/// struct Memory(usize);
+///
+/// impl Diagnostic for Fps {
+///     fn report(&self) -> String {
+///         format!("{} fps", self.0)
+///     }
+/// }
+///
+/// impl Diagnostic for Memory {
+///     fn report(&self) -> String {
+///         format!("{} bytes", self.0)
+///     }
+/// }
+///
+/// let mut container = ResourceContainer::default();
+/// container.add_resource(Fps(60));
+/// container.add_resource(Memory(1024));
+/// container.bind_interface::<Fps, dyn Diagnostic>(|fps| fps);
+/// container.bind_interface::<Memory, dyn Diagnostic>(|memory| memory);
+///
+/// let diagnostics = ResAll::<dyn Diagnostic>::retrieve(&container);
+/// let mut reports = diagnostics.iter().map(|diagnostic| diagnostic.report()).collect::<Vec<_>>();
+/// reports.sort();
+/// assert_eq!(reports, ["1024 bytes", "60 fps"]);
+/// ```
+pub struct ResAll<'a, I: ?Sized + 'static> {
+    resources: Vec<Ref<'a, I, Immutable>>,
+}
+
+impl<I: ?Sized + 'static> ResAll<'_, I> {
+    /// Iterates over the bound resources, in sorted `TypeId` order.
+    pub fn iter(&self) -> impl Iterator<Item = &I> {
+        self.resources.iter().map(Deref::deref)
+    }
+
+    pub fn len(&self) -> usize {
+        self.resources.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.resources.is_empty()
+    }
+}
+
+impl<I: ?Sized + 'static> Retriever for ResAll<'_, I> {
+    type Item<'a> = ResAll<'a, I>;
+
+    fn requests(requests: &mut Vec<Request>) {
+        requests.push(Request {
+            type_id: TypeId::of::<I>(),
+            type_name: std::any::type_name::<I>(),
+            mutable: false,
+            storage: Storage::Interface,
+            optional: false,
+        });
+    }
+
+    fn assemble<'a>(retrieved: &mut dyn Iterator<Item = Retrieved<'a>>) -> Self::Item<'a> {
+        let bound = retrieved
+            .next()
+            .unwrap()
+            .take_bound(std::any::type_name::<Self>())
+            .unwrap_or_else(|error| panic!("{error}"));
+        let resources = bound
+            .into_iter()
+            .map(|(resource, cast)| {
+                let cast = cast.downcast_ref::<Cast<I>>().unwrap();
+                resource.map(cast)
+            })
+            .collect();
+        ResAll { resources }
+    }
+}
+
+impl<'r, I: ?Sized + 'static> IntoIterator for &'r ResAll<'_, I> {
+    type Item = &'r I;
+    type IntoIter = Box<dyn Iterator<Item = &'r I> + 'r>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        Box::new(self.iter())
+    }
+}
+
+impl<I: Debug + ?Sized + 'static> Debug for ResAll<'_, I> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod test_interface {
+    use std::fmt::Display;
+
+    use super::*;
+    use crate::store::{Container, ResMut, ResourceContainer};
+
+    #[test]
+    fn test_res_all() {
+        let mut container = ResourceContainer::default();
+        container.bind_interface::<u32, dyn Display>(|value| value);
+        container.bind_interface::<u32, dyn Display>(|value| value);
+        container.bind_interface::<String, dyn Display>(|value| value);
+        container.bind_interface::<u64, dyn Display>(|value| value);
+        container.add_resource(1u32);
+        container.add_resource("two".to_owned());
+        container.add_resource(0u8);
+
+        // assert missing resources are skipped, and the bindings are not duplicated
+        let (all, mut count) = <(ResAll<dyn Display>, ResMut<u8>)>::retrieve(&container);
+        for value in &all {
+            *count += 1;
+            assert!(["1", "two"].contains(&value.to_string().as_str()));
+        }
+        assert_eq!(*count, 2);
+        assert!(ResAll::<dyn Debug>::retrieve(&container).is_empty());
+    }
+}
@@ -0,0 +1,97 @@
+use std::{any::TypeId, cell::RefCell, ops::Deref};
+
+thread_local! {
+    // resources locked by the retrievals of the thread, in locking order, and whether they
+    // are locked mutably
+    static HELD: RefCell<Vec<(TypeId, &'static str, bool)>> = const { RefCell::new(Vec::new()) };
+}
+
+// asserts resources are locked in sorted `TypeId` order, the order preventing two threads
+// from each holding a resource the other one waits for.
+//
+// Retrievals lock their resources in that order, but a retriever retrieving other retrievers
+// by hand, or a system holding a borrow while it retrieves another resource, may not. Shared
+// borrows taken while holding shared borrows only are accepted in any order, as when reading
+// several resources one after the other.
+pub(crate) fn lock(type_id: TypeId, type_name: &'static str, mutable: bool) -> OrderGuard {
+    HELD.with_borrow_mut(|held| {
+        let out_of_order = held
+            .iter()
+            .find(|&&(held, _, held_mutable)| held > type_id && (mutable || held_mutable));
+        if let Some(&(_, after, _)) = out_of_order {
+            panic!(
+                "Resource {type_name} locked while holding {after}, out of the sorted `TypeId` \
+                 order of retrievals"
+            );
+        }
+        held.push((type_id, type_name, mutable));
+    });
+    OrderGuard(type_id)
+}
+
+// lock of a resource held by the thread, released with the borrow of the resource
+pub(crate) struct OrderGuard(TypeId);
+
+impl Deref for OrderGuard {
+    type Target = ();
+
+    fn deref(&self) -> &Self::Target {
+        &()
+    }
+}
+
+impl Drop for OrderGuard {
+    fn drop(&mut self) {
+        HELD.with_borrow_mut(|held| {
+            if let Some(index) = held.iter().rposition(|&(type_id, ..)| type_id == self.0) {
+                held.remove(index);
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod test_lock_order {
+    use std::fmt::Debug;
+
+    use super::*;
+    use crate::store::{Container, Res, ResMut, ResourceContainer, Retriever};
+
+    #[test]
+    fn test_lock_order() {
+        let mut container = ResourceContainer::default();
+        container.add_resource(1u32);
+        container.add_resource(2u64);
+
+        // assert retrievals lock in order, whatever the order of declaration
+        let (a, b) = <(Res<u32>, ResMut<u64>)>::retrieve(&container);
+        drop((a, b));
+        let (b, a) = <(ResMut<u64>, Res<u32>)>::retrieve(&container);
+        drop((b, a));
+
+        // assert resources locked in sorted order by successive retrievals are accepted
+        let (first, second) = match TypeId::of::<u32>() < TypeId::of::<u64>() {
+            true => ("u32", "u64"),
+            false => ("u64", "u32"),
+        };
+        let lock = |name| match name {
+            "u32" => Box::new(ResMut::<u32>::retrieve(&container)) as Box<dyn Debug>,
+            _ => Box::new(Res::<u64>::retrieve(&container)),
+        };
+        drop((lock(first), lock(second)));
+        let shared = <(Res<u32>, Res<u64>)>::retrieve(&container);
+        drop((
+            Res::<u64>::retrieve(&container),
+            Res::<u32>::retrieve(&container),
+        ));
+        drop(shared);
+
+        // assert resources locked out of order panic with both type names
+        let held = lock(second);
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| lock(first)));
+        let message = *result.err().unwrap().downcast::<String>().unwrap();
+        assert!(message.starts_with(&format!("Resource {first} locked while holding {second}")));
+        drop(held);
+        drop(lock(first));
+    }
+}
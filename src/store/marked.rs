@@ -0,0 +1,260 @@
+use std::{convert::Infallible, fmt::Debug, marker::PhantomData};
+
+use super::{
+    AnyOf, Commands, Component, Container, Entity, NonSend, NonSendMut, Query, QueryData,
+    QueryFilter, Res, ResMut, ResourceContainer, Retrievable, RetrievalError, Retriever,
+};
+
+/// Resource stored in the [MarkedContainer]s of marker `M`.
+///
+/// A resource can belong to several markers, and markers are usually empty types naming the
+/// store, such as `RenderWorld` and `SimWorld`.
+pub trait BelongsTo<M> {}
+
+/// Retriever of resources belonging to the marker `M`, see [MarkedContainer].
+///
+/// Implemented for `Res<T>`, `ResMut<T>`, `NonSend<T>` and `NonSendMut<T>` of resources
+/// implementing [BelongsTo] `M`, queries of [MarkedQueryData], [AnyOf] marked candidates, and
+/// tuples of up to 32 marked retrievers. `Commands` retrieve no resource, so they belong to
+/// every marker, the resources they add being not checked.
+pub trait MarkedRetriever<M>: Retriever {}
+
+impl<M, T: BelongsTo<M> + super::Resource + ?Sized> MarkedRetriever<M> for Res<'_, T> {}
+
+impl<M, T: BelongsTo<M> + super::Resource + ?Sized> MarkedRetriever<M> for ResMut<'_, T> {}
+
+impl<M, T: BelongsTo<M> + 'static> MarkedRetriever<M> for NonSend<'_, T> {}
+
+impl<M, T: BelongsTo<M> + 'static> MarkedRetriever<M> for NonSendMut<'_, T> {}
+
+impl<M, Q: MarkedQueryData<M>, F: QueryFilter> MarkedRetriever<M> for Query<'_, Q, F> {}
+
+impl<M> MarkedRetriever<M> for Commands<'_> {}
+
+macro_rules! impl_marked_any_of {
+    ($($candidate:ident),*; $($unused:ty),*) => {
+        impl<Marker, $($candidate: Retrievable + MarkedRetriever<Marker>),*> MarkedRetriever<Marker>
+            for AnyOf<$($candidate,)* $($unused,)*>
+        {
+        }
+    };
+}
+
+impl_marked_any_of!(A, B; Infallible, Infallible);
+impl_marked_any_of!(A, B, C; Infallible);
+impl_marked_any_of!(A, B, C, D;);
+
+/// [QueryData] of components belonging to the marker `M`, see [MarkedRetriever].
+///
+/// Implemented for `&T` and `&mut T` of components implementing [BelongsTo] `M`, [Entity],
+/// and tuples of up to 8 marked query data.
+pub trait MarkedQueryData<M>: QueryData {}
+
+impl<M, T: Component + BelongsTo<M>> MarkedQueryData<M> for &T {}
+
+impl<M, T: Component + BelongsTo<M>> MarkedQueryData<M> for &mut T {}
+
+impl<M> MarkedQueryData<M> for Entity {}
+
+macro_rules! impl_marked_query_data {
+    ($($data:ident),*) => {
+        impl<Marker, $($data: MarkedQueryData<Marker>),*> MarkedQueryData<Marker> for ($($data,)*) {}
+    };
+}
+
+impl_marked_query_data!(A);
+impl_marked_query_data!(A, B);
+impl_marked_query_data!(A, B, C);
+impl_marked_query_data!(A, B, C, D);
+impl_marked_query_data!(A, B, C, D, E);
+impl_marked_query_data!(A, B, C, D, E, F);
+impl_marked_query_data!(A, B, C, D, E, F, G);
+impl_marked_query_data!(A, B, C, D, E, F, G, H);
+
+macro_rules! impl_marked_retriever {
+    ($($retriever:ident),*) => {
+        impl<Marker, $($retriever: MarkedRetriever<Marker>),*> MarkedRetriever<Marker>
+            for ($($retriever,)*)
+        {
+        }
+    };
+}
+
+impl_tuples!(impl_marked_retriever);
+
+/// `ResourceContainer` only holding the resources belonging to the marker `M`, so that
+/// applications with several independent stores can not retrieve a resource from the wrong
+/// one.
+///
+/// Adding or retrieving a resource that does not implement [BelongsTo] `M` fails to compile.
+/// The untyped container is still reachable with `container`, e.g. to run a schedule against it.
+///
+/// Systems bypass the marker: a schedule runs against the untyped container, so the retrievers
+/// of its systems and handlers are not checked against `M`, only the retrievals made through
+/// the `MarkedContainer` itself are.
+///
+/// # Examples
+/// ```
+/// use emark::prelude::*;
+/// use emark::store::{BelongsTo, MarkedContainer};
+///
+/// struct RenderWorld;
+/// struct SimWorld;
+///
+/// struct Camera(f32);
+/// impl BelongsTo<RenderWorld> for Camera {}
+///
+/// struct Gravity(f32);
+/// impl BelongsTo<SimWorld> for Gravity {}
+///
+/// let mut render = MarkedContainer::<RenderWorld>::new();
+/// render.add_resource(Camera(1.0));
+/// assert_eq!(render.retrieve::<Res<Camera>>().0, 1.0);
+/// ```
+///
+/// Retrieving a simulation resource from the render container does not compile:
+/// ```compile_fail
+/// # use emark::prelude::*;
+/// # use emark::store::{BelongsTo, MarkedContainer};
+/// # struct RenderWorld;
+/// # struct SimWorld;
+/// # struct Gravity(f32);
+/// # impl BelongsTo<SimWorld> for Gravity {}
+/// let render = MarkedContainer::<RenderWorld>::new();
+/// render.retrieve::<Res<Gravity>>();
+/// ```
+pub struct MarkedContainer<M> {
+    container: ResourceContainer,
+    _marker: PhantomData<fn() -> M>,
+}
+
+impl<M> MarkedContainer<M> {
+    pub fn new() -> Self {
+        Self::from_container(ResourceContainer::default())
+    }
+
+    /// Marks an existing container, whose resources are assumed to belong to `M`.
+    pub fn from_container(container: ResourceContainer) -> Self {
+        Self {
+            container,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Adds a resource, returning the resource of the same type it replaces, if any.
+    pub fn add_resource<T: BelongsTo<M> + Send + Sync + 'static>(
+        &mut self,
+        resource: T,
+    ) -> Option<T> {
+        self.container.add_resource(resource)
+    }
+
+    pub fn remove_resource<T: BelongsTo<M> + 'static>(&mut self) -> Option<T> {
+        self.container.remove_resource::<T>()
+    }
+
+    pub fn contains_resource<T: BelongsTo<M> + ?Sized + 'static>(&self) -> bool {
+        self.container.contains_resource::<T>()
+    }
+
+    /// Retrieves a set of resources belonging to `M`, see [Retriever::retrieve].
+    pub fn retrieve<R: MarkedRetriever<M>>(&self) -> R::Item<'_> {
+        R::retrieve(&self.container)
+    }
+
+    /// Retrieves a set of resources belonging to `M`, see [Retriever::try_retrieve].
+    pub fn try_retrieve<R: MarkedRetriever<M>>(&self) -> Result<R::Item<'_>, RetrievalError> {
+        R::try_retrieve(&self.container)
+    }
+
+    /// Untyped container, giving access to every resource regardless of its marker.
+    pub fn container(&self) -> &ResourceContainer {
+        &self.container
+    }
+
+    pub fn container_mut(&mut self) -> &mut ResourceContainer {
+        &mut self.container
+    }
+
+    pub fn into_inner(self) -> ResourceContainer {
+        self.container
+    }
+}
+
+impl<M> Default for MarkedContainer<M> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<M> Debug for MarkedContainer<M> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MarkedContainer")
+            .field("marker", &std::any::type_name::<M>())
+            .field("container", &self.container)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test_marked {
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::store::Entities;
+
+    struct Sim;
+    struct Render;
+
+    struct Tick(u32);
+    impl BelongsTo<Sim> for Tick {}
+    impl BelongsTo<Render> for Tick {}
+
+    struct Frame(u32);
+    impl BelongsTo<Render> for Frame {}
+
+    #[test]
+    fn test_marked_container() {
+        let mut sim = MarkedContainer::<Sim>::new();
+        let mut render = MarkedContainer::<Render>::new();
+        sim.add_resource(Tick(1));
+        render.add_resource(Tick(2));
+        render.add_resource(Frame(0));
+
+        let (tick, mut frame) = render.retrieve::<(Res<Tick>, ResMut<Frame>)>();
+        frame.0 += tick.0;
+        drop((tick, frame));
+        assert_eq!(render.remove_resource::<Frame>().unwrap().0, 2);
+        assert_eq!(sim.try_retrieve::<Res<Tick>>().unwrap().0, 1);
+        assert!(!sim.container().contains_resource::<Frame>());
+    }
+
+    #[test]
+    fn test_marked_retrievers() {
+        struct Window(Rc<u32>);
+        impl BelongsTo<Render> for Window {}
+
+        struct Speed(u32);
+        impl Component for Speed {}
+        impl BelongsTo<Render> for Speed {}
+
+        let mut entities = Entities::new();
+        let entity = entities.spawn();
+        entities.insert(entity, Speed(3));
+        let mut render = MarkedContainer::<Render>::new();
+        render.container_mut().add_resource(entities);
+        render.container_mut().add_non_send(Window(Rc::new(4)));
+        render.add_resource(Frame(5));
+
+        // assert queries, non-send resources, commands and alternatives are marked
+        let (query, window, _, frame) = render.retrieve::<(
+            Query<(Entity, &Speed)>,
+            NonSendMut<Window>,
+            Commands,
+            AnyOf<Res<Tick>, Res<Frame>>,
+        )>();
+        assert_eq!(query.get(entity).unwrap().1 .0, 3);
+        assert_eq!(*window.0, 4);
+        assert!(matches!(frame, AnyOf::Second(frame) if frame.0 == 5));
+    }
+}
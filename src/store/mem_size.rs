@@ -0,0 +1,181 @@
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    mem::size_of,
+};
+
+/// Memory used by a value, including the heap memory it owns, reported by
+/// `ResourceContainer::memory_usage`.
+///
+/// Resources not implementing `MemSize` are reported with their shallow size, as returned by
+/// `std::mem::size_of_val`, which is exact for the values owning no heap memory.
+///
+/// # Examples
+/// ```
+/// use std::mem::size_of;
+///
+/// use emark::store::MemSize;
+///
+/// struct Cache {
+///     entries: Vec<String>,
+///     hits: u64,
+/// }
+///
+/// impl MemSize for Cache {
+///     fn heap_size(&self) -> usize {
+///         self.entries.heap_size()
+///     }
+/// }
+///
+/// let cache = Cache { entries: vec!["a".repeat(100)], hits: 0 };
+/// assert!(cache.mem_size() >= size_of::<Cache>() + size_of::<String>() + 100);
+/// ```
+pub trait MemSize {
+    /// Heap memory owned by the value, in bytes.
+    fn heap_size(&self) -> usize;
+
+    /// Total memory used by the value, in bytes.
+    fn mem_size(&self) -> usize
+    where
+        Self: Sized,
+    {
+        size_of::<Self>() + self.heap_size()
+    }
+}
+
+macro_rules! impl_mem_size_plain {
+    ($($type:ty),*) => {
+        $(impl MemSize for $type {
+            fn heap_size(&self) -> usize {
+                0
+            }
+        })*
+    };
+}
+
+impl_mem_size_plain!(
+    u8,
+    u16,
+    u32,
+    u64,
+    u128,
+    usize,
+    i8,
+    i16,
+    i32,
+    i64,
+    i128,
+    isize,
+    f32,
+    f64,
+    bool,
+    char,
+    ()
+);
+
+impl MemSize for String {
+    fn heap_size(&self) -> usize {
+        self.capacity()
+    }
+}
+
+impl<T: MemSize> MemSize for Option<T> {
+    fn heap_size(&self) -> usize {
+        self.as_ref().map_or(0, T::heap_size)
+    }
+}
+
+impl<T: MemSize> MemSize for Box<T> {
+    fn heap_size(&self) -> usize {
+        (**self).mem_size()
+    }
+}
+
+impl<T: MemSize> MemSize for Vec<T> {
+    fn heap_size(&self) -> usize {
+        // spare capacity is counted as allocated
+        self.capacity() * size_of::<T>() + self.iter().map(T::heap_size).sum::<usize>()
+    }
+}
+
+impl<K: MemSize, V: MemSize> MemSize for HashMap<K, V> {
+    fn heap_size(&self) -> usize {
+        // approximation ignoring the control bytes of the table
+        self.capacity() * size_of::<(K, V)>()
+            + self
+                .iter()
+                .map(|(key, value)| key.heap_size() + value.heap_size())
+                .sum::<usize>()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// Memory used by a resource, see `ResourceContainer::memory_usage`.
+pub struct MemoryUsage {
+    pub type_id: TypeId,
+    pub type_name: Option<&'static str>,
+    /// Memory used by the resource, in bytes.
+    pub bytes: usize,
+    /// Whether `bytes` includes the heap memory of the resource, measured by its [MemSize]
+    /// implementation, rather than its shallow size only.
+    pub deep: bool,
+}
+
+fn mem_size_of<T: MemSize + 'static>(resource: &dyn Any) -> usize {
+    resource.downcast_ref::<T>().unwrap().mem_size()
+}
+
+// measured resource types of a container
+#[derive(Debug, Default)]
+pub(crate) struct MemSizeRegistry {
+    measures: HashMap<TypeId, fn(&dyn Any) -> usize>,
+}
+
+impl MemSizeRegistry {
+    pub(crate) fn register<T: MemSize + 'static>(&mut self) {
+        self.measures.insert(TypeId::of::<T>(), mem_size_of::<T>);
+    }
+
+    pub(crate) fn measure(
+        &self,
+        type_id: TypeId,
+        type_name: Option<&'static str>,
+        resource: &dyn Any,
+    ) -> MemoryUsage {
+        let (bytes, deep) = match self.measures.get(&type_id) {
+            Some(measure) => (measure(resource), true),
+            None => (std::mem::size_of_val(resource), false),
+        };
+        MemoryUsage {
+            type_id,
+            type_name,
+            bytes,
+            deep,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_mem_size {
+    use super::*;
+
+    #[test]
+    fn test_mem_size() {
+        let mut names = Vec::with_capacity(4);
+        names.push(String::with_capacity(10));
+        assert_eq!(
+            names.mem_size(),
+            size_of::<Vec<String>>() + 4 * size_of::<String>() + 10
+        );
+
+        let mut registry = MemSizeRegistry::default();
+        registry.register::<Vec<String>>();
+        let usage = registry.measure(TypeId::of::<Vec<String>>(), None, &names);
+        assert_eq!(usage.bytes, names.mem_size());
+        assert!(usage.deep);
+
+        // assert unregistered resources report their shallow size
+        let usage = registry.measure(TypeId::of::<[u8; 32]>(), None, &[0u8; 32]);
+        assert_eq!((usage.bytes, usage.deep), (32, false));
+    }
+}
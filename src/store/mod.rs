@@ -1,5 +1,146 @@
+// implements a trait for the tuples of up to 32 elements with `$impl`, tuples can nest
+macro_rules! impl_tuples {
+    ($impl:ident) => {
+        impl_tuples!(
+            $impl;
+            A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P,
+            Q, R, S, T, U, V, W, X, Y, Z, AA, AB, AC, AD, AE, AF
+        );
+    };
+    ($impl:ident;) => {
+        $impl!();
+    };
+    ($impl:ident; $first:ident $(, $rest:ident)*) => {
+        $impl!($first $(, $rest)*);
+        impl_tuples!($impl; $($rest),*);
+    };
+}
+
+#[doc(hidden)]
+mod any_of;
+
+#[doc(inline)]
+pub use any_of::AnyOf;
+
+#[doc(hidden)]
+mod blob;
+
+#[doc(hidden)]
+mod commands;
+
+#[doc(inline)]
+pub use commands::Commands;
+
 #[doc(hidden)]
 mod container;
 
 #[doc(inline)]
-pub use container::*;
\ No newline at end of file
+pub use container::*;
+
+#[doc(hidden)]
+mod context;
+
+#[doc(inline)]
+pub use context::{Context, ReadContext, ReadOnlyRetriever};
+
+pub(crate) use context::DeferredEmissions;
+
+#[doc(hidden)]
+mod cow;
+
+#[doc(inline)]
+pub use cow::CowResource;
+
+#[doc(hidden)]
+mod dependency;
+
+#[doc(inline)]
+pub use dependency::Dependency;
+
+#[doc(hidden)]
+mod entity;
+
+#[doc(inline)]
+pub use entity::*;
+
+#[doc(hidden)]
+mod handle;
+
+#[doc(inline)]
+pub use handle::ResHandle;
+
+#[doc(hidden)]
+mod interface;
+
+#[doc(inline)]
+pub use interface::ResAll;
+
+#[cfg(debug_assertions)]
+mod lock_order;
+
+#[doc(hidden)]
+mod marked;
+
+#[doc(inline)]
+pub use marked::{BelongsTo, MarkedContainer, MarkedQueryData, MarkedRetriever};
+
+#[doc(hidden)]
+mod mem_size;
+
+#[doc(inline)]
+pub use mem_size::{MemSize, MemoryUsage};
+
+#[doc(hidden)]
+mod non_send;
+
+#[doc(inline)]
+pub use non_send::*;
+
+#[doc(hidden)]
+mod observer;
+
+#[doc(hidden)]
+mod persist;
+
+#[doc(inline)]
+pub use persist::{Persist, PersistentResource};
+
+#[doc(hidden)]
+mod query;
+
+#[doc(inline)]
+pub use query::*;
+
+#[doc(hidden)]
+mod reflect;
+
+#[doc(inline)]
+pub use reflect::Reflect;
+
+#[doc(hidden)]
+mod res;
+
+#[doc(inline)]
+pub use res::*;
+
+#[cfg(feature = "derive")]
+#[doc(inline)]
+pub use emark_derive::Retriever;
+
+#[doc(hidden)]
+mod sharded;
+
+#[doc(hidden)]
+mod tick;
+
+#[doc(hidden)]
+mod transaction;
+
+#[doc(inline)]
+pub use transaction::Transaction;
+
+#[doc(inline)]
+pub use tick::SystemTicks;
+
+#[doc(inline)]
+pub use crate::utils::lock::grained_ref::{Immutable, LockState, Mutable};
@@ -0,0 +1,282 @@
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    fmt::Debug,
+    ops::{Deref, DerefMut},
+    sync::Arc,
+    thread::{self, ThreadId},
+};
+
+use crate::utils::lock::{
+    grained_ref::{Immutable, Mutable},
+    Ref,
+};
+
+use super::{
+    blob::ResourceEntry,
+    res::Storage,
+    Request, Retrieved, Retriever,
+};
+
+// non-send resources of a container, only accessed from the thread that created it
+#[derive(Debug)]
+pub(crate) struct NonSendResources {
+    entries: HashMap<TypeId, Arc<ResourceEntry<dyn Any>>>,
+    owner: ThreadId,
+}
+
+// the resources are only added, borrowed, removed and dropped on the owner thread, the other
+// threads panicking before, or leaking the resources they would drop
+unsafe impl Send for NonSendResources {}
+unsafe impl Sync for NonSendResources {}
+
+impl Default for NonSendResources {
+    fn default() -> Self {
+        Self {
+            entries: HashMap::new(),
+            owner: thread::current().id(),
+        }
+    }
+}
+
+impl NonSendResources {
+    pub(crate) fn is_owner_thread(&self) -> bool {
+        thread::current().id() == self.owner
+    }
+
+    fn check_thread(&self) {
+        assert!(
+            self.is_owner_thread(),
+            "non-send resources accessed from another thread than the one creating the container"
+        );
+    }
+
+    pub(crate) fn contains_key(&self, type_id: TypeId) -> bool {
+        self.entries.contains_key(&type_id)
+    }
+
+    pub(crate) fn get(&self, type_id: TypeId) -> Option<&ResourceEntry<dyn Any>> {
+        self.check_thread();
+        self.entries.get(&type_id).map(|entry| &**entry)
+    }
+
+    pub(crate) fn insert(&mut self, type_id: TypeId, entry: Arc<ResourceEntry<dyn Any>>) {
+        self.check_thread();
+        self.entries.insert(type_id, entry);
+    }
+
+    pub(crate) fn remove<T: 'static>(&mut self) -> Option<T> {
+        self.check_thread();
+        self.entries
+            .remove(&TypeId::of::<T>())
+            .map(ResourceEntry::take_as::<T>)
+    }
+
+    // the resources are leaked on another thread than the owner
+    pub(crate) fn clear(&mut self) {
+        match self.is_owner_thread() {
+            true => self.entries.clear(),
+            false => std::mem::forget(std::mem::take(&mut self.entries)),
+        }
+    }
+
+    // the resources not retained are leaked on another thread than the owner
+    pub(crate) fn retain(&mut self, mut retain: impl FnMut(TypeId) -> bool) {
+        let owner = self.is_owner_thread();
+        self.entries.retain(|&type_id, entry| {
+            let retained = retain(type_id);
+            if !retained && !owner {
+                std::mem::forget(entry.clone());
+            }
+            retained
+        });
+    }
+
+    pub(crate) fn shrink_to_fit(&mut self) {
+        self.entries.shrink_to_fit();
+    }
+}
+
+impl Drop for NonSendResources {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+fn non_send_request<T: 'static>(requests: &mut Vec<Request>, mutable: bool) {
+    requests.push(Request {
+        type_id: TypeId::of::<T>(),
+        type_name: std::any::type_name::<T>(),
+        mutable,
+        storage: Storage::NonSend,
+        optional: false,
+    });
+}
+
+/// Shared borrow of a resource of type `T` added with `Container::add_non_send`.
+///
+/// Non-send resources, such as window handles or graphics contexts, are only retrieved on the
+/// thread that created the container. Systems retrieving them always run on the thread running
+/// the schedule, even on a [ParallelExecutor](crate::system::ParallelExecutor), so schedules
+/// using them must be run on that thread.
+///
+/// # Panics
+/// Retrieving the resource panics on another thread than the one that created the container,
+/// or if the resource is not found.
+///
+/// # Examples
+/// ```
+/// use std::rc::Rc;
+///
+/// use emark::prelude::*;
+/// use emark::store::{NonSend, ResourceContainer};
+///
+/// let mut container = ResourceContainer::default();
+/// container.add_non_send(Rc::new("window"));
+/// assert_eq!(**NonSend::<Rc<&str>>::retrieve(&container), "window");
+/// ```
+pub struct NonSend<'a, T: 'static> {
+    resource: Ref<'a, T, Immutable>,
+}
+
+impl<T: 'static> Retriever for NonSend<'_, T> {
+    type Item<'a> = NonSend<'a, T>;
+
+    fn requests(requests: &mut Vec<Request>) {
+        non_send_request::<T>(requests, false);
+    }
+
+    fn assemble<'a>(retrieved: &mut dyn Iterator<Item = Retrieved<'a>>) -> Self::Item<'a> {
+        let resource = retrieved
+            .next()
+            .unwrap()
+            .take_shared(std::any::type_name::<Self>())
+            .unwrap_or_else(|error| panic!("{error}"));
+        NonSend { resource }
+    }
+}
+
+impl<T: 'static> Deref for NonSend<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.resource
+    }
+}
+
+impl<T: Debug + 'static> Debug for NonSend<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("NonSend").field(self.deref()).finish()
+    }
+}
+
+/// Exclusive borrow of a resource of type `T` added with `Container::add_non_send`, see
+/// [NonSend].
+pub struct NonSendMut<'a, T: 'static> {
+    resource: Ref<'a, T, Mutable>,
+}
+
+impl<T: 'static> Retriever for NonSendMut<'_, T> {
+    type Item<'a> = NonSendMut<'a, T>;
+
+    fn requests(requests: &mut Vec<Request>) {
+        non_send_request::<T>(requests, true);
+    }
+
+    fn assemble<'a>(retrieved: &mut dyn Iterator<Item = Retrieved<'a>>) -> Self::Item<'a> {
+        let resource = retrieved
+            .next()
+            .unwrap()
+            .take_exclusive(std::any::type_name::<Self>())
+            .unwrap_or_else(|error| panic!("{error}"));
+        NonSendMut { resource }
+    }
+}
+
+impl<T: 'static> Deref for NonSendMut<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.resource
+    }
+}
+
+impl<T: 'static> DerefMut for NonSendMut<'_, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.resource
+    }
+}
+
+impl<T: Debug + 'static> Debug for NonSendMut<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("NonSendMut").field(self.deref()).finish()
+    }
+}
+
+#[cfg(test)]
+mod test_non_send {
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::store::{Container, ResourceContainer};
+
+    #[test]
+    fn test_non_send_retrieve() {
+        let mut container = ResourceContainer::default();
+        container.add_non_send(Rc::new(1));
+
+        *NonSendMut::<Rc<i32>>::retrieve(&container) = Rc::new(2);
+        assert_eq!(**NonSend::<Rc<i32>>::retrieve(&container), 2);
+
+        // assert non-send resources are stored apart from the resources
+        assert!(!container.contains_resource::<Rc<i32>>());
+        assert_eq!(container.remove_non_send::<Rc<i32>>(), Some(Rc::new(2)));
+    }
+
+    #[test]
+    fn test_non_send_other_thread() {
+        let mut container = ResourceContainer::default();
+        container.add_non_send(Rc::new(1));
+
+        let container = &container;
+        let result = std::thread::scope(|scope| {
+            scope
+                .spawn(move || {
+                    NonSend::<Rc<i32>>::retrieve(container);
+                })
+                .join()
+        });
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_non_send_other_thread_drop() {
+        let resource = Rc::new(1);
+        let mut container = ResourceContainer::default();
+        container.add_non_send(resource.clone());
+        container.add_non_send(Rc::new(2u8));
+
+        std::thread::scope(|scope| {
+            let container = &mut container;
+            // assert other threads can not remove the resources
+            let result = scope
+                .spawn(move || {
+                    container.remove_non_send::<Rc<i32>>();
+                })
+                .join();
+            assert!(result.is_err());
+        });
+        assert!(container.contains_non_send::<Rc<i32>>());
+
+        // assert other threads leak the resources they would drop
+        let container = std::thread::spawn(move || {
+            container.retain(|type_id| type_id != TypeId::of::<Rc<u8>>());
+            container
+        })
+        .join()
+        .unwrap();
+        assert!(!container.contains_non_send::<Rc<u8>>());
+        std::thread::spawn(move || drop(container)).join().unwrap();
+        assert_eq!(Rc::strong_count(&resource), 2);
+    }
+}
@@ -0,0 +1,79 @@
+use std::{any::Any, fmt::Debug};
+
+type Observer<T> = Box<dyn Fn(&T) + Send + Sync>;
+
+// write observers of a resource type, a type-erased `Vec<Observer<T>>`
+pub(crate) struct WriteObservers {
+    observers: Box<dyn Any + Send + Sync>,
+    len: usize,
+}
+
+impl WriteObservers {
+    pub(crate) fn new<T: ?Sized + 'static>() -> Self {
+        Self {
+            observers: Box::new(Vec::<Observer<T>>::new()),
+            len: 0,
+        }
+    }
+
+    pub(crate) fn push<T: ?Sized + 'static>(
+        &mut self,
+        observer: impl Fn(&T) + Send + Sync + 'static,
+    ) {
+        let observers = self.observers.downcast_mut::<Vec<Observer<T>>>().unwrap();
+        observers.push(Box::new(observer));
+        self.len += 1;
+    }
+
+    // calls the observers in registration order
+    pub(crate) fn notify<T: ?Sized + 'static>(&self, resource: &T) {
+        let observers = self.observers.downcast_ref::<Vec<Observer<T>>>().unwrap();
+        for observer in observers {
+            observer(resource);
+        }
+    }
+}
+
+impl Debug for WriteObservers {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WriteObservers")
+            .field("len", &self.len)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test_observer {
+    use std::sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    };
+
+    use crate::store::{Container, ResMut, ResourceContainer, Retriever};
+
+    #[test]
+    fn test_observe_writes() {
+        let saved = Arc::new(AtomicU32::new(0));
+        let observer_saved = saved.clone();
+        let mut container = ResourceContainer::default();
+        container
+            .observe_writes(move |volume: &u32| observer_saved.store(*volume, Ordering::SeqCst));
+        container.add_resource(1u32);
+
+        // assert borrows without writes are not observed
+        drop(ResMut::<u32>::retrieve(&container));
+        assert_eq!(saved.load(Ordering::SeqCst), 0);
+
+        let mut volume = ResMut::<u32>::retrieve(&container);
+        *volume = 2;
+        *volume += 1;
+        assert_eq!(saved.load(Ordering::SeqCst), 0);
+        drop(volume);
+        assert_eq!(saved.load(Ordering::SeqCst), 3);
+
+        // assert observers outlive the replaced resources
+        container.add_resource(5u32);
+        *ResMut::<u32>::retrieve(&container) += 1;
+        assert_eq!(saved.load(Ordering::SeqCst), 6);
+    }
+}
@@ -0,0 +1,312 @@
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    io::{self, Read, Write},
+};
+
+use crate::utils::error::EmarkError;
+
+/// Binary encoding of a value, used to persist the resources of a `ResourceContainer`.
+///
+/// Integers and floats are encoded in little endian, so the encoding does not depend on the
+/// platform. Composite types encode their fields one after the other.
+///
+/// There is no `serde` feature persisting the resources through `Serialize` and
+/// `DeserializeOwned`: `serde` is not a dependency of emark, so the resources implement this
+/// encoding instead, and their data can not be written in another format such as JSON.
+///
+/// # Examples
+/// ```
+/// use std::io::{self, Read, Write};
+///
+/// use emark::store::Persist;
+///
+/// #[derive(Debug, PartialEq)]
+/// struct Player {
+///     name: String,
+///     level: u32,
+/// }
+///
+/// impl Persist for Player {
+///     fn save(&self, writer: &mut dyn Write) -> io::Result<()> {
+///         self.name.save(writer)?;
+///         self.level.save(writer)
+///     }
+///
+///     fn load(reader: &mut dyn Read) -> io::Result<Self> {
+///         Ok(Player {
+///             name: String::load(reader)?,
+///             level: u32::load(reader)?,
+///         })
+///     }
+/// }
+///
+/// let player = Player { name: "ferris".to_owned(), level: 3 };
+/// let mut bytes = Vec::new();
+/// player.save(&mut bytes).unwrap();
+/// assert_eq!(Player::load(&mut bytes.as_slice()).unwrap(), player);
+/// ```
+pub trait Persist: Sized {
+    fn save(&self, writer: &mut dyn Write) -> io::Result<()>;
+
+    fn load(reader: &mut dyn Read) -> io::Result<Self>;
+}
+
+/// Resource with a stable name, persisted by `ResourceContainer::serialize`.
+///
+/// The name identifies the resource type in persisted data, where `TypeId` is not stable
+/// between builds.
+pub trait PersistentResource: Persist + Send + Sync + 'static {
+    const NAME: &'static str;
+}
+
+macro_rules! impl_persist_number {
+    ($($type:ty),*) => {
+        $(impl Persist for $type {
+            fn save(&self, writer: &mut dyn Write) -> io::Result<()> {
+                writer.write_all(&self.to_le_bytes())
+            }
+
+            fn load(reader: &mut dyn Read) -> io::Result<Self> {
+                let mut bytes = [0; std::mem::size_of::<$type>()];
+                reader.read_exact(&mut bytes)?;
+                Ok(<$type>::from_le_bytes(bytes))
+            }
+        })*
+    };
+}
+
+impl_persist_number!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128, f32, f64);
+
+// sizes are encoded as `u64` to be independent of the platform
+impl Persist for usize {
+    fn save(&self, writer: &mut dyn Write) -> io::Result<()> {
+        (*self as u64).save(writer)
+    }
+
+    fn load(reader: &mut dyn Read) -> io::Result<Self> {
+        usize::try_from(u64::load(reader)?)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+    }
+}
+
+impl Persist for bool {
+    fn save(&self, writer: &mut dyn Write) -> io::Result<()> {
+        (*self as u8).save(writer)
+    }
+
+    fn load(reader: &mut dyn Read) -> io::Result<Self> {
+        match u8::load(reader)? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(io::Error::new(io::ErrorKind::InvalidData, "invalid bool")),
+        }
+    }
+}
+
+impl Persist for String {
+    fn save(&self, writer: &mut dyn Write) -> io::Result<()> {
+        self.len().save(writer)?;
+        writer.write_all(self.as_bytes())
+    }
+
+    fn load(reader: &mut dyn Read) -> io::Result<Self> {
+        let mut bytes = vec![0; usize::load(reader)?];
+        reader.read_exact(&mut bytes)?;
+        String::from_utf8(bytes).map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+    }
+}
+
+impl<T: Persist> Persist for Option<T> {
+    fn save(&self, writer: &mut dyn Write) -> io::Result<()> {
+        self.is_some().save(writer)?;
+        match self {
+            Some(value) => value.save(writer),
+            None => Ok(()),
+        }
+    }
+
+    fn load(reader: &mut dyn Read) -> io::Result<Self> {
+        match bool::load(reader)? {
+            true => T::load(reader).map(Some),
+            false => Ok(None),
+        }
+    }
+}
+
+impl<T: Persist> Persist for Vec<T> {
+    fn save(&self, writer: &mut dyn Write) -> io::Result<()> {
+        self.len().save(writer)?;
+        self.iter().try_for_each(|value| value.save(writer))
+    }
+
+    fn load(reader: &mut dyn Read) -> io::Result<Self> {
+        (0..usize::load(reader)?).map(|_| T::load(reader)).collect()
+    }
+}
+
+// codec of a registered resource type
+#[derive(Debug, Clone, Copy)]
+struct Persister {
+    name: &'static str,
+    type_name: &'static str,
+    save: fn(&dyn Any, &mut dyn Write) -> io::Result<()>,
+    load: fn(&mut dyn Read) -> io::Result<Box<dyn Any + Send + Sync>>,
+}
+
+fn save_resource<T: PersistentResource>(
+    resource: &dyn Any,
+    writer: &mut dyn Write,
+) -> io::Result<()> {
+    resource.downcast_ref::<T>().unwrap().save(writer)
+}
+
+fn load_resource<T: PersistentResource>(
+    reader: &mut dyn Read,
+) -> io::Result<Box<dyn Any + Send + Sync>> {
+    Ok(Box::new(T::load(reader)?))
+}
+
+// resource read by `deserialize`, with its type name
+type LoadedResource = (TypeId, Box<dyn Any + Send + Sync>, &'static str);
+
+fn io_error(error: io::Error) -> EmarkError {
+    EmarkError::Io(error.to_string())
+}
+
+// persistent resource types of a container, by `TypeId` and by name
+#[derive(Debug, Default)]
+pub(crate) struct PersistRegistry {
+    persisters: HashMap<TypeId, Persister>,
+    names: HashMap<&'static str, TypeId>,
+}
+
+impl PersistRegistry {
+    pub(crate) fn register<T: PersistentResource>(&mut self) -> Result<(), EmarkError> {
+        let type_id = TypeId::of::<T>();
+        match self.names.get(T::NAME) {
+            Some(&registered) if registered == type_id => return Ok(()),
+            Some(_) => return Err(EmarkError::DuplicateResourceName(T::NAME)),
+            None => {}
+        }
+        self.names.insert(T::NAME, type_id);
+        self.persisters.insert(
+            type_id,
+            Persister {
+                name: T::NAME,
+                type_name: std::any::type_name::<T>(),
+                save: save_resource::<T>,
+                load: load_resource::<T>,
+            },
+        );
+        Ok(())
+    }
+
+    // writes the registered resources among `resources`, in name order
+    pub(crate) fn serialize<'a>(
+        &self,
+        resources: impl Iterator<Item = (TypeId, &'a dyn Any)>,
+        writer: &mut dyn Write,
+    ) -> Result<(), EmarkError> {
+        let mut persisted = resources
+            .filter_map(|(type_id, resource)| Some((self.persisters.get(&type_id)?, resource)))
+            .collect::<Vec<_>>();
+        persisted.sort_by_key(|(persister, _)| persister.name);
+
+        persisted.len().save(writer).map_err(io_error)?;
+        for (persister, resource) in persisted {
+            // the payload is length prefixed to be read without knowing its type
+            let mut payload = Vec::new();
+            (persister.save)(resource, &mut payload).map_err(io_error)?;
+            persister.name.to_owned().save(writer).map_err(io_error)?;
+            payload.len().save(writer).map_err(io_error)?;
+            writer.write_all(&payload).map_err(io_error)?;
+        }
+        Ok(())
+    }
+
+    // reads the resources written by `serialize`, with their type name
+    pub(crate) fn deserialize(
+        &self,
+        reader: &mut dyn Read,
+    ) -> Result<Vec<LoadedResource>, EmarkError> {
+        let len = usize::load(reader).map_err(io_error)?;
+        let mut resources = Vec::new();
+        for _ in 0..len {
+            let name = String::load(reader).map_err(io_error)?;
+            let payload = Vec::<u8>::load(reader).map_err(io_error)?;
+            let Some(&type_id) = self.names.get(name.as_str()) else {
+                return Err(EmarkError::UnknownResourceName(name));
+            };
+            let persister = &self.persisters[&type_id];
+            let resource = (persister.load)(&mut payload.as_slice()).map_err(io_error)?;
+            resources.push((type_id, resource, persister.type_name));
+        }
+        Ok(resources)
+    }
+}
+
+#[cfg(test)]
+mod test_persist {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Score(Vec<Option<u32>>);
+
+    impl Persist for Score {
+        fn save(&self, writer: &mut dyn Write) -> io::Result<()> {
+            self.0.save(writer)
+        }
+
+        fn load(reader: &mut dyn Read) -> io::Result<Self> {
+            Vec::load(reader).map(Score)
+        }
+    }
+
+    impl PersistentResource for Score {
+        const NAME: &'static str = "test.score";
+    }
+
+    #[test]
+    fn test_persist_roundtrip() {
+        let mut bytes = Vec::new();
+        (-3i64).save(&mut bytes).unwrap();
+        true.save(&mut bytes).unwrap();
+        "emark".to_owned().save(&mut bytes).unwrap();
+
+        let reader = &mut bytes.as_slice();
+        assert_eq!(i64::load(reader).unwrap(), -3);
+        assert!(bool::load(reader).unwrap());
+        assert_eq!(String::load(reader).unwrap(), "emark");
+        assert!(u8::load(reader).is_err());
+    }
+
+    #[test]
+    fn test_persist_registry() {
+        let mut registry = PersistRegistry::default();
+        registry.register::<Score>().unwrap();
+        registry.register::<Score>().unwrap();
+
+        let score = Score(vec![Some(1), None]);
+        let resources = [
+            (TypeId::of::<Score>(), &score as &dyn Any),
+            (TypeId::of::<u32>(), &0u32 as &dyn Any),
+        ];
+        let mut bytes = Vec::new();
+        registry
+            .serialize(resources.into_iter(), &mut bytes)
+            .unwrap();
+
+        let resources = registry.deserialize(&mut bytes.as_slice()).unwrap();
+        assert_eq!(resources.len(), 1);
+        assert_eq!(resources[0].1.downcast_ref::<Score>(), Some(&score));
+
+        // assert unknown names are rejected
+        assert_eq!(
+            PersistRegistry::default()
+                .deserialize(&mut bytes.as_slice())
+                .unwrap_err(),
+            EmarkError::UnknownResourceName("test.score".to_owned())
+        );
+    }
+}
@@ -0,0 +1,685 @@
+use std::{
+    any::TypeId,
+    fmt::Debug,
+    marker::PhantomData,
+    ops::{Deref, DerefMut},
+};
+
+use crate::utils::lock::{grained_ref::Immutable, Ref};
+
+use super::{
+    entity::{column_id, Column},
+    res::{RetrievedRef, Storage},
+    tick::ChangeTicks,
+    Component, Entities, Entity, Request, Retrieved, Retriever, SystemTicks,
+};
+
+/// Data fetched by a [Query] for each matching entity.
+///
+/// Implemented for [Entity], `&T` and `&mut T` for components `T`, and for tuples of up to 8
+/// query data. `&mut T` is fetched as a [Mut], marking the component changed when mutably
+/// dereferenced. Components borrowed by the data are required, entities without them do not
+/// match the query.
+pub trait QueryData {
+    /// Borrows of the data of an entity.
+    type Item<'w>;
+
+    #[doc(hidden)]
+    /// Locked columns of the components.
+    type State<'a>;
+
+    #[doc(hidden)]
+    /// Appends the columns requested, in declaration order.
+    fn requests(requests: &mut Vec<Request>);
+
+    #[doc(hidden)]
+    /// Converts the locked columns, in declaration order, into the state.
+    fn assemble<'a>(retrieved: &mut dyn Iterator<Item = Retrieved<'a>>) -> Self::State<'a>;
+
+    #[doc(hidden)]
+    /// Borrows the data of `entity`, which has every requested component.
+    ///
+    /// # Safety
+    /// The borrows must not outlive the state, and mutable borrows must be unique.
+    unsafe fn fetch<'w>(
+        state: &Self::State<'_>,
+        entities: &'w Entities,
+        entity: Entity,
+        ticks: SystemTicks,
+    ) -> Self::Item<'w>;
+}
+
+/// [QueryData] borrowing its components immutably, iterable through a shared query.
+pub trait ReadOnlyQueryData: QueryData {}
+
+// locked column with a pointer to its components
+#[doc(hidden)]
+pub struct ColumnState<'a, T> {
+    // guards are only held to be released on drop
+    #[allow(dead_code)]
+    retrieved: Retrieved<'a>,
+    components: *mut Option<T>,
+}
+
+impl<'a, T: 'static> ColumnState<'a, T> {
+    fn new(mut retrieved: Retrieved<'a>) -> Self {
+        // the components are not moved while the column is locked
+        let components = match &mut retrieved.resource {
+            RetrievedRef::Immutable(column) => unsafe {
+                (*column.as_ptr())
+                    .downcast_ref::<Column<T>>()
+                    .unwrap()
+                    .components
+                    .as_ptr() as *mut Option<T>
+            },
+            RetrievedRef::Mutable(column) => unsafe {
+                (*column.as_ptr())
+                    .downcast_mut::<Column<T>>()
+                    .unwrap()
+                    .components
+                    .as_mut_ptr()
+            },
+            RetrievedRef::Missing => std::ptr::null_mut(),
+            RetrievedRef::Interface(_) | RetrievedRef::Absent => unreachable!(),
+        };
+        Self {
+            retrieved,
+            components,
+        }
+    }
+}
+
+fn column_request<T: 'static>(requests: &mut Vec<Request>, mutable: bool) {
+    requests.push(Request {
+        type_id: column_id::<T>(),
+        type_name: std::any::type_name::<Column<T>>(),
+        mutable,
+        storage: Storage::Component,
+        optional: false,
+    });
+}
+
+impl<T: Component> QueryData for &T {
+    type Item<'w> = &'w T;
+    type State<'a> = ColumnState<'a, T>;
+
+    fn requests(requests: &mut Vec<Request>) {
+        column_request::<T>(requests, false);
+    }
+
+    fn assemble<'a>(retrieved: &mut dyn Iterator<Item = Retrieved<'a>>) -> Self::State<'a> {
+        ColumnState::new(retrieved.next().unwrap())
+    }
+
+    unsafe fn fetch<'w>(
+        state: &Self::State<'_>,
+        _: &'w Entities,
+        entity: Entity,
+        _: SystemTicks,
+    ) -> Self::Item<'w> {
+        (*state.components.add(entity.index() as usize))
+            .as_ref()
+            .unwrap()
+    }
+}
+
+impl<T: Component> ReadOnlyQueryData for &T {}
+
+impl<T: Component> QueryData for &mut T {
+    type Item<'w> = Mut<'w, T>;
+    type State<'a> = ColumnState<'a, T>;
+
+    fn requests(requests: &mut Vec<Request>) {
+        column_request::<T>(requests, true);
+    }
+
+    fn assemble<'a>(retrieved: &mut dyn Iterator<Item = Retrieved<'a>>) -> Self::State<'a> {
+        ColumnState::new(retrieved.next().unwrap())
+    }
+
+    unsafe fn fetch<'w>(
+        state: &Self::State<'_>,
+        entities: &'w Entities,
+        entity: Entity,
+        ticks: SystemTicks,
+    ) -> Self::Item<'w> {
+        let index = entity.index() as usize;
+        Mut {
+            component: (*state.components.add(index)).as_mut().unwrap(),
+            ticks: entities.ticks(column_id::<T>(), index).unwrap(),
+            system: ticks,
+        }
+    }
+}
+
+impl QueryData for Entity {
+    type Item<'w> = Entity;
+    type State<'a> = ();
+
+    fn requests(_: &mut Vec<Request>) {}
+
+    fn assemble<'a>(_: &mut dyn Iterator<Item = Retrieved<'a>>) -> Self::State<'a> {}
+
+    unsafe fn fetch<'w>(
+        _: &Self::State<'_>,
+        _: &'w Entities,
+        entity: Entity,
+        _: SystemTicks,
+    ) -> Self::Item<'w> {
+        entity
+    }
+}
+
+impl ReadOnlyQueryData for Entity {}
+
+macro_rules! impl_query_data {
+    ($(($data:ident, $state:ident)),*) => {
+        impl<$($data: QueryData),*> QueryData for ($($data,)*) {
+            type Item<'w> = ($($data::Item<'w>,)*);
+            type State<'a> = ($($data::State<'a>,)*);
+
+            fn requests(requests: &mut Vec<Request>) {
+                $($data::requests(requests);)*
+            }
+
+            fn assemble<'a>(retrieved: &mut dyn Iterator<Item = Retrieved<'a>>) -> Self::State<'a> {
+                ($($data::assemble(retrieved),)*)
+            }
+
+            unsafe fn fetch<'w>(
+                state: &Self::State<'_>,
+                entities: &'w Entities,
+                entity: Entity,
+                ticks: SystemTicks,
+            ) -> Self::Item<'w> {
+                let ($($state,)*) = state;
+                ($($data::fetch($state, entities, entity, ticks),)*)
+            }
+        }
+
+        impl<$($data: ReadOnlyQueryData),*> ReadOnlyQueryData for ($($data,)*) {}
+    };
+}
+
+impl_query_data!((A, a));
+impl_query_data!((A, a), (B, b));
+impl_query_data!((A, a), (B, b), (C, c));
+impl_query_data!((A, a), (B, b), (C, c), (D, d));
+impl_query_data!((A, a), (B, b), (C, c), (D, d), (E, e));
+impl_query_data!((A, a), (B, b), (C, c), (D, d), (E, e), (F, f));
+impl_query_data!((A, a), (B, b), (C, c), (D, d), (E, e), (F, f), (G, g));
+impl_query_data!(
+    (A, a),
+    (B, b),
+    (C, c),
+    (D, d),
+    (E, e),
+    (F, f),
+    (G, g),
+    (H, h)
+);
+
+/// Exclusive borrow of a component of type `T` fetched by a [Query].
+///
+/// Mutably dereferencing the borrow marks the component changed, see [Changed].
+pub struct Mut<'w, T> {
+    component: &'w mut T,
+    ticks: &'w ChangeTicks,
+    system: SystemTicks,
+}
+
+impl<T> Mut<'_, T> {
+    /// Returns `true` if the component was added since the last run of the system.
+    pub fn is_added(&self) -> bool {
+        self.system.is_newer(self.ticks.added)
+    }
+
+    /// Returns `true` if the component was added or mutably dereferenced since the last run of
+    /// the system, including through this borrow.
+    pub fn is_changed(&self) -> bool {
+        self.system.is_newer(self.ticks.changed())
+    }
+}
+
+impl<T> Deref for Mut<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        self.component
+    }
+}
+
+impl<T> DerefMut for Mut<'_, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.ticks.set_changed(self.system.this_run());
+        self.component
+    }
+}
+
+impl<T: Debug> Debug for Mut<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("Mut").field(self.deref()).finish()
+    }
+}
+
+/// Filter of the entities matching a [Query].
+///
+/// Implemented for [With], [Without], [Added], [Changed] and tuples of up to 8 filters,
+/// matching the entities matching every filter of the tuple.
+pub trait QueryFilter {
+    #[doc(hidden)]
+    fn matches(entities: &Entities, index: usize, ticks: SystemTicks) -> bool;
+}
+
+impl QueryFilter for () {
+    fn matches(_: &Entities, _: usize, _: SystemTicks) -> bool {
+        true
+    }
+}
+
+/// Filter matching the entities having a component of type `T`, without borrowing it.
+pub struct With<T>(PhantomData<fn() -> T>);
+
+impl<T: Component> QueryFilter for With<T> {
+    fn matches(entities: &Entities, index: usize, _: SystemTicks) -> bool {
+        entities.has_component(column_id::<T>(), index)
+    }
+}
+
+/// Filter matching the entities without a component of type `T`.
+pub struct Without<T>(PhantomData<fn() -> T>);
+
+impl<T: Component> QueryFilter for Without<T> {
+    fn matches(entities: &Entities, index: usize, _: SystemTicks) -> bool {
+        !entities.has_component(column_id::<T>(), index)
+    }
+}
+
+/// Filter matching the entities whose component of type `T` was added since the last run of
+/// the system.
+pub struct Added<T>(PhantomData<fn() -> T>);
+
+impl<T: Component> QueryFilter for Added<T> {
+    fn matches(entities: &Entities, index: usize, ticks: SystemTicks) -> bool {
+        entities
+            .ticks(column_id::<T>(), index)
+            .is_some_and(|component| ticks.is_newer(component.added))
+    }
+}
+
+/// Filter matching the entities whose component of type `T` was added or mutably dereferenced
+/// since the last run of the system, see [SystemTicks].
+pub struct Changed<T>(PhantomData<fn() -> T>);
+
+impl<T: Component> QueryFilter for Changed<T> {
+    fn matches(entities: &Entities, index: usize, ticks: SystemTicks) -> bool {
+        entities
+            .ticks(column_id::<T>(), index)
+            .is_some_and(|component| ticks.is_newer(component.changed()))
+    }
+}
+
+macro_rules! impl_query_filter {
+    ($($filter:ident),*) => {
+        impl<$($filter: QueryFilter),*> QueryFilter for ($($filter,)*) {
+            fn matches(entities: &Entities, index: usize, ticks: SystemTicks) -> bool {
+                $($filter::matches(entities, index, ticks))&&*
+            }
+        }
+    };
+}
+
+impl_query_filter!(A);
+impl_query_filter!(A, B);
+impl_query_filter!(A, B, C);
+impl_query_filter!(A, B, C, D);
+impl_query_filter!(A, B, C, D, E);
+impl_query_filter!(A, B, C, D, E, F);
+impl_query_filter!(A, B, C, D, E, F, G);
+impl_query_filter!(A, B, C, D, E, F, G, H);
+
+/// Retriever iterating over the entities having the components of `Q` and matching the
+/// filter `F`.
+///
+/// A query borrows the `Entities` resource immutably and locks the columns of the components
+/// it fetches, immutably for `&T` and mutably for `&mut T`. The columns take part in the access
+/// of function systems like resources do, so systems querying distinct components mutably run
+/// concurrently on a [ParallelExecutor](crate::system::ParallelExecutor).
+///
+/// # Panics
+/// Retrieving a query panics if the container has no `Entities` resource, or if the query
+/// fetches a component mutably while also fetching it elsewhere in the query.
+///
+/// # Examples
+/// ```
+/// use emark::prelude::*;
+/// use emark::store::{Query, ResourceContainer, Without};
+///
+/// struct Position(f32);
+/// impl Component for Position {}
+///
+/// struct Velocity(f32);
+/// impl Component for Velocity {}
+///
+/// struct Frozen;
+/// impl Component for Frozen {}
+///
+/// fn movement(mut query: Query<(&Velocity, &mut Position), Without<Frozen>>) {
+///     for (velocity, mut position) in query.iter_mut() {
+///         position.0 += velocity.0;
+///     }
+/// }
+///
+/// let mut entities = Entities::new();
+/// for frozen in [false, true] {
+///     let agent = entities.spawn();
+///     entities.insert(agent, Position(0.0));
+///     entities.insert(agent, Velocity(1.0));
+///     if frozen {
+///         entities.insert(agent, Frozen);
+///     }
+/// }
+/// let mut container = ResourceContainer::default();
+/// container.add_resource(entities);
+///
+/// let mut schedule = Schedule::new();
+/// schedule.add_system(movement);
+/// schedule.run(&container, &Default::default());
+///
+/// let positions = Query::<&Position>::retrieve(&container);
+/// let positions = positions.iter().map(|position| position.0).collect::<Vec<_>>();
+/// assert_eq!(positions, vec![1.0, 0.0]);
+/// ```
+pub struct Query<'a, Q: QueryData, F: QueryFilter = ()> {
+    entities: Ref<'a, Entities, Immutable>,
+    state: Q::State<'a>,
+    // columns of the required components
+    required: Vec<TypeId>,
+    ticks: SystemTicks,
+    _marker: PhantomData<fn() -> F>,
+}
+
+impl<'a, Q: QueryData, F: QueryFilter> Query<'a, Q, F> {
+    /// Iterates over the data of the matching entities, in index order.
+    pub fn iter(&self) -> QueryIter<'_, 'a, Q, F>
+    where
+        Q: ReadOnlyQueryData,
+    {
+        QueryIter {
+            query: self,
+            index: 0,
+        }
+    }
+
+    /// Iterates mutably over the data of the matching entities, in index order.
+    pub fn iter_mut(&mut self) -> QueryIter<'_, 'a, Q, F> {
+        QueryIter {
+            query: self,
+            index: 0,
+        }
+    }
+
+    /// Borrows the data of `entity`, if it matches the query.
+    pub fn get(&self, entity: Entity) -> Option<Q::Item<'_>>
+    where
+        Q: ReadOnlyQueryData,
+    {
+        self.contains(entity)
+            .then(|| unsafe { Q::fetch(&self.state, &self.entities, entity, self.ticks) })
+    }
+
+    /// Mutably borrows the data of `entity`, if it matches the query.
+    pub fn get_mut(&mut self, entity: Entity) -> Option<Q::Item<'_>> {
+        self.contains(entity)
+            .then(|| unsafe { Q::fetch(&self.state, &self.entities, entity, self.ticks) })
+    }
+
+    /// Returns `true` if `entity` is alive and matches the query.
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.entities.contains(entity) && self.matches(entity.index() as usize)
+    }
+
+    // true if the entity at `index` has the required components and matches the filter
+    fn matches(&self, index: usize) -> bool {
+        self.required
+            .iter()
+            .all(|&column| self.entities.has_component(column, index))
+            && F::matches(&self.entities, index, self.ticks)
+    }
+}
+
+impl<Q: QueryData, F: QueryFilter> Retriever for Query<'_, Q, F> {
+    type Item<'a> = Query<'a, Q, F>;
+
+    fn requests(requests: &mut Vec<Request>) {
+        requests.push(Request {
+            type_id: TypeId::of::<Entities>(),
+            type_name: std::any::type_name::<Entities>(),
+            mutable: false,
+            storage: Storage::Component,
+            optional: false,
+        });
+
+        // borrowing a column twice with a mutable borrow would deadlock
+        let start = requests.len();
+        Q::requests(requests);
+        let columns = &requests[start..];
+        for (index, request) in columns.iter().enumerate() {
+            let aliased = columns[index + 1..].iter().any(|other| {
+                other.type_id == request.type_id && (other.mutable || request.mutable)
+            });
+            assert!(
+                !aliased,
+                "query accesses `{}` mutably more than once",
+                request.type_name
+            );
+        }
+    }
+
+    fn assemble<'a>(retrieved: &mut dyn Iterator<Item = Retrieved<'a>>) -> Self::Item<'a> {
+        let mut entities = retrieved.next().unwrap();
+        let ticks = entities.system;
+        let entities = entities
+            .take_shared::<Entities>(std::any::type_name::<Self>())
+            .unwrap_or_else(|error| panic!("{error}"));
+        let mut requests = Vec::new();
+        Q::requests(&mut requests);
+        Query {
+            entities,
+            state: Q::assemble(retrieved),
+            required: requests.iter().map(|request| request.type_id).collect(),
+            ticks,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<Q: QueryData, F: QueryFilter> Debug for Query<'_, Q, F> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Query")
+            .field("data", &std::any::type_name::<Q>())
+            .field("filter", &std::any::type_name::<F>())
+            .finish()
+    }
+}
+
+/// Iterator over the data of the entities matching a [Query].
+pub struct QueryIter<'w, 'a, Q: QueryData, F: QueryFilter> {
+    query: &'w Query<'a, Q, F>,
+    index: usize,
+}
+
+impl<'w, Q: QueryData, F: QueryFilter> Iterator for QueryIter<'w, '_, Q, F> {
+    type Item = Q::Item<'w>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.index < self.query.entities.slot_count() {
+            let index = self.index;
+            self.index += 1;
+            let Some(entity) = self.query.entities.entity_at(index) else {
+                continue;
+            };
+            if self.query.matches(index) {
+                // each entity is fetched once, mutable borrows are unique
+                let query = self.query;
+                return Some(unsafe {
+                    Q::fetch(&query.state, &query.entities, entity, query.ticks)
+                });
+            }
+        }
+        None
+    }
+}
+
+impl<'w, 'a, Q: QueryData, F: QueryFilter> IntoIterator for &'w mut Query<'a, Q, F> {
+    type Item = Q::Item<'w>;
+    type IntoIter = QueryIter<'w, 'a, Q, F>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+#[cfg(test)]
+mod test_query {
+    use std::any::TypeId;
+
+    use super::*;
+    use crate::{
+        event::EventManager,
+        store::{Container, ResMut, ResourceContainer},
+        system::{IntoSystem, System},
+    };
+
+    #[derive(Debug, PartialEq)]
+    struct Health(u32);
+    impl Component for Health {}
+
+    #[derive(Debug, PartialEq)]
+    struct Armor(u32);
+    impl Component for Armor {}
+
+    struct Dead;
+    impl Component for Dead {}
+
+    fn container() -> (ResourceContainer, Vec<Entity>) {
+        let mut entities = Entities::new();
+        let spawned = (0..4)
+            .map(|index| {
+                let entity = entities.spawn();
+                entities.insert(entity, Health(10 * index));
+                if index % 2 == 0 {
+                    entities.insert(entity, Armor(index));
+                }
+                entity
+            })
+            .collect::<Vec<_>>();
+        entities.insert(spawned[2], Dead);
+
+        let mut container = ResourceContainer::default();
+        container.add_resource(entities);
+        (container, spawned)
+    }
+
+    #[test]
+    fn test_query_iter() {
+        let (container, spawned) = container();
+        {
+            let mut query = <Query<(&Armor, &mut Health), Without<Dead>>>::retrieve(&container);
+            for (armor, mut health) in &mut query {
+                health.0 += armor.0;
+            }
+        }
+
+        let query = <Query<(Entity, &Health), With<Armor>>>::retrieve(&container);
+        let matched = query
+            .iter()
+            .map(|(entity, health)| (entity, health.0))
+            .collect::<Vec<_>>();
+        assert_eq!(matched, vec![(spawned[0], 0), (spawned[2], 20)]);
+        assert_eq!(query.get(spawned[1]), None);
+        assert!(!query.contains(spawned[3]));
+    }
+
+    #[test]
+    fn test_query_missing_component() {
+        struct Unused;
+        impl Component for Unused {}
+
+        let (container, _) = container();
+        let query = Query::<&Unused>::retrieve(&container);
+        assert_eq!(query.iter().count(), 0);
+    }
+
+    #[test]
+    fn test_query_access() {
+        fn heal(mut query: Query<&mut Health, With<Armor>>) {
+            for mut health in query.iter_mut() {
+                health.0 += 1;
+            }
+        }
+
+        fn despawn(mut entities: ResMut<Entities>) {
+            let dead = entities.iter().collect::<Vec<_>>();
+            for entity in dead {
+                entities.despawn(entity);
+            }
+        }
+
+        // assert columns are accessed like resources, filters do not borrow their component
+        let mut heal = heal.into_system();
+        let access = heal.access();
+        assert_eq!(access.reads(), &[TypeId::of::<Entities>()]);
+        assert_eq!(access.writes(), &[column_id::<Health>()]);
+        assert!(heal
+            .access()
+            .conflicts_with(&despawn.into_system().access()));
+
+        let (container, spawned) = container();
+        heal.run(&container, &EventManager::new()).unwrap();
+        let entities = crate::store::Res::<Entities>::retrieve(&container);
+        assert_eq!(entities.get::<Health>(spawned[0]).unwrap().0, 1);
+        assert_eq!(entities.get::<Health>(spawned[1]).unwrap().0, 10);
+    }
+
+    #[test]
+    fn test_query_change_detection() {
+        fn observe(
+            changed: Query<Entity, Changed<Health>>,
+            added: Query<Entity, Added<Armor>>,
+            mut log: ResMut<Vec<(usize, usize)>>,
+        ) {
+            log.push((changed.iter().count(), added.iter().count()));
+        }
+
+        let (mut container, spawned) = container();
+        container.add_resource(Vec::<(usize, usize)>::new());
+        let event_manager = EventManager::new();
+        let mut system = observe.into_system();
+        system.run(&container, &event_manager).unwrap();
+        system.run(&container, &event_manager).unwrap();
+
+        // assert only mutable dereferences mark the component changed
+        container.clear_changes();
+        {
+            let mut query = Query::<&mut Health>::retrieve(&container);
+            let _ = query.get_mut(spawned[0]).unwrap().0;
+            let mut health = query.get_mut(spawned[1]).unwrap();
+            health.0 += 1;
+            assert!(health.is_changed() && !health.is_added());
+        }
+        ResMut::<Entities>::retrieve(&container).insert(spawned[3], Armor(3));
+        system.run(&container, &event_manager).unwrap();
+        assert_eq!(
+            *crate::store::Res::<Vec<(usize, usize)>>::retrieve(&container),
+            vec![(4, 2), (0, 0), (1, 1)]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "mutably more than once")]
+    fn test_query_aliased() {
+        let (container, _) = container();
+        <Query<(&Health, &mut Health)>>::retrieve(&container);
+    }
+}
@@ -0,0 +1,233 @@
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+};
+
+use crate::utils::error::EmarkError;
+
+/// Runtime inspection of a resource, used by debug UIs and scripting layers to read and tweak
+/// resources without knowing their types at compile time.
+///
+/// Structs expose their fields by name, while leaf values such as numbers and strings have no
+/// fields and are read and written through the downcasting methods of `dyn Reflect`.
+///
+/// # Examples
+/// ```
+/// use emark::store::Reflect;
+///
+/// struct Camera {
+///     zoom: f32,
+///     label: String,
+/// }
+///
+/// impl Reflect for Camera {
+///     fn field_names(&self) -> &'static [&'static str] {
+///         &["zoom", "label"]
+///     }
+///
+///     fn field(&self, name: &str) -> Option<&dyn Reflect> {
+///         match name {
+///             "zoom" => Some(&self.zoom),
+///             "label" => Some(&self.label),
+///             _ => None,
+///         }
+///     }
+///
+///     fn field_mut(&mut self, name: &str) -> Option<&mut dyn Reflect> {
+///         match name {
+///             "zoom" => Some(&mut self.zoom),
+///             "label" => Some(&mut self.label),
+///             _ => None,
+///         }
+///     }
+/// }
+///
+/// let mut camera = Camera { zoom: 1.0, label: "main".to_owned() };
+/// let reflect: &mut dyn Reflect = &mut camera;
+/// reflect.field_mut("zoom").unwrap().set(2.0f32).unwrap();
+/// assert_eq!(reflect.field("zoom").unwrap().downcast_ref::<f32>(), Some(&2.0));
+/// assert_eq!(camera.zoom, 2.0);
+/// ```
+pub trait Reflect: Any {
+    /// Rust type name of the value, for display only.
+    fn type_name(&self) -> &'static str {
+        std::any::type_name::<Self>()
+    }
+
+    /// Names of the fields of the value, empty for leaf values.
+    fn field_names(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    fn field(&self, _name: &str) -> Option<&dyn Reflect> {
+        None
+    }
+
+    fn field_mut(&mut self, _name: &str) -> Option<&mut dyn Reflect> {
+        None
+    }
+}
+
+impl dyn Reflect {
+    pub fn is<T: Reflect>(&self) -> bool {
+        (self as &dyn Any).is::<T>()
+    }
+
+    pub fn downcast_ref<T: Reflect>(&self) -> Option<&T> {
+        (self as &dyn Any).downcast_ref()
+    }
+
+    pub fn downcast_mut<T: Reflect>(&mut self) -> Option<&mut T> {
+        (self as &mut dyn Any).downcast_mut()
+    }
+
+    /// Replaces the value, returning an error if it is not of type `T`.
+    pub fn set<T: Reflect>(&mut self, value: T) -> Result<(), EmarkError> {
+        let expected = self.type_name();
+        let target = self
+            .downcast_mut::<T>()
+            .ok_or(EmarkError::PayloadMismatch { expected })?;
+        *target = value;
+        Ok(())
+    }
+
+    /// Nested field at the dot separated `path`, such as `"player.position.x"`.
+    pub fn path(&self, path: &str) -> Option<&dyn Reflect> {
+        path.split('.')
+            .try_fold(self, |value, name| value.field(name))
+    }
+
+    pub fn path_mut(&mut self, path: &str) -> Option<&mut dyn Reflect> {
+        path.split('.')
+            .try_fold(self, |value, name| value.field_mut(name))
+    }
+}
+
+macro_rules! impl_reflect_leaf {
+    ($($type:ty),*) => {
+        $(impl Reflect for $type {})*
+    };
+}
+
+impl_reflect_leaf!(
+    u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, f32, f64, bool, char, String
+);
+
+// casts of a registered resource type
+#[derive(Debug, Clone, Copy)]
+struct Reflector {
+    as_reflect: fn(&dyn Any) -> &dyn Reflect,
+    as_reflect_mut: fn(&mut dyn Any) -> &mut dyn Reflect,
+}
+
+fn as_reflect<T: Reflect>(resource: &dyn Any) -> &dyn Reflect {
+    resource.downcast_ref::<T>().unwrap()
+}
+
+fn as_reflect_mut<T: Reflect>(resource: &mut dyn Any) -> &mut dyn Reflect {
+    resource.downcast_mut::<T>().unwrap()
+}
+
+// reflected resource types of a container
+#[derive(Debug, Default)]
+pub(crate) struct ReflectRegistry {
+    reflectors: HashMap<TypeId, Reflector>,
+}
+
+impl ReflectRegistry {
+    pub(crate) fn register<T: Reflect>(&mut self) {
+        self.reflectors.insert(
+            TypeId::of::<T>(),
+            Reflector {
+                as_reflect: as_reflect::<T>,
+                as_reflect_mut: as_reflect_mut::<T>,
+            },
+        );
+    }
+
+    pub(crate) fn contains(&self, type_id: TypeId) -> bool {
+        self.reflectors.contains_key(&type_id)
+    }
+
+    pub(crate) fn reflect<'a>(&self, type_id: TypeId, resource: &'a dyn Any) -> &'a dyn Reflect {
+        (self.reflectors[&type_id].as_reflect)(resource)
+    }
+
+    pub(crate) fn reflect_mut<'a>(
+        &self,
+        type_id: TypeId,
+        resource: &'a mut dyn Any,
+    ) -> &'a mut dyn Reflect {
+        (self.reflectors[&type_id].as_reflect_mut)(resource)
+    }
+}
+
+#[cfg(test)]
+mod test_reflect {
+    use super::*;
+
+    struct Position {
+        x: i32,
+        y: i32,
+    }
+
+    impl Reflect for Position {
+        fn field_names(&self) -> &'static [&'static str] {
+            &["x", "y"]
+        }
+
+        fn field(&self, name: &str) -> Option<&dyn Reflect> {
+            match name {
+                "x" => Some(&self.x),
+                "y" => Some(&self.y),
+                _ => None,
+            }
+        }
+
+        fn field_mut(&mut self, name: &str) -> Option<&mut dyn Reflect> {
+            match name {
+                "x" => Some(&mut self.x),
+                "y" => Some(&mut self.y),
+                _ => None,
+            }
+        }
+    }
+
+    struct Player {
+        position: Position,
+    }
+
+    impl Reflect for Player {
+        fn field_names(&self) -> &'static [&'static str] {
+            &["position"]
+        }
+
+        fn field(&self, name: &str) -> Option<&dyn Reflect> {
+            (name == "position").then_some(&self.position as &dyn Reflect)
+        }
+
+        fn field_mut(&mut self, name: &str) -> Option<&mut dyn Reflect> {
+            (name == "position").then_some(&mut self.position as &mut dyn Reflect)
+        }
+    }
+
+    #[test]
+    fn test_reflect_path() {
+        let mut player = Player {
+            position: Position { x: 1, y: 2 },
+        };
+        let reflect: &mut dyn Reflect = &mut player;
+        assert_eq!(reflect.field_names(), ["position"]);
+        assert!(reflect.path("position.z").is_none());
+
+        let x = reflect.path_mut("position.x").unwrap();
+        assert_eq!(x.type_name(), "i32");
+        x.set(5).unwrap();
+        assert_eq!(
+            x.set(5u8),
+            Err(EmarkError::PayloadMismatch { expected: "i32" })
+        );
+        assert_eq!(player.position.x, 5);
+        assert_eq!(player.position.y, 2);
+    }
+}
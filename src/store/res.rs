@@ -0,0 +1,1658 @@
+use std::{
+    any::{Any, TypeId},
+    borrow::Cow,
+    cell::RefCell,
+    collections::HashMap,
+    error::Error,
+    fmt::{Debug, Display},
+    marker::PhantomData,
+    mem::ManuallyDrop,
+    ops::{Deref, DerefMut},
+    ptr::NonNull,
+    rc::Rc,
+    time::Duration,
+};
+
+use crate::{
+    system::Access,
+    utils::lock::{
+        grained_ref::{Immutable, LockState, Mutable},
+        Ref,
+    },
+};
+
+use super::{
+    observer::WriteObservers,
+    tick::{ChangeTicks, SystemTicks},
+    ResourceContainer,
+};
+
+#[doc(hidden)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// For internal use only.
+///
+/// Resource requested by a [Retriever].
+pub struct Request {
+    pub(crate) type_id: TypeId,
+    pub(crate) type_name: &'static str,
+    pub(crate) mutable: bool,
+    pub(crate) storage: Storage,
+    // retrieved as absent rather than failing the retrieval when missing
+    pub(crate) optional: bool,
+}
+
+// resource locked by a retrieval, the index being the one of its request
+type Lock = (TypeId, Storage, usize);
+
+#[doc(hidden)]
+#[derive(Debug, Clone)]
+/// For internal use only.
+///
+/// Requests of a [Retriever] sorted in locking order, see [Retriever::try_retrieve_planned].
+pub struct RetrievalPlan {
+    requests: Vec<Request>,
+    // `None` if the retriever requests interfaces, whose bound resources depend on the container
+    order: Option<Vec<Lock>>,
+}
+
+impl RetrievalPlan {
+    pub fn new<R: Retriever + ?Sized>() -> Self {
+        let mut requests = Vec::new();
+        R::requests(&mut requests);
+        let order = requests
+            .iter()
+            .all(|request| request.storage != Storage::Interface)
+            .then(|| lock_order(&requests, None));
+        Self { requests, order }
+    }
+
+    // plan of `R`, computed once per thread for the retrievals outside of the function systems,
+    // e.g. the ones of the handlers
+    pub(crate) fn cached<R: Retriever + ?Sized>() -> Rc<Self> {
+        thread_local! {
+            static PLANS: RefCell<HashMap<TypeId, Rc<RetrievalPlan>>> = RefCell::default();
+        }
+        let type_id = erased_type_id::<R>();
+        let plan = PLANS.try_with(|plans| {
+            let mut plans = plans.borrow_mut();
+            plans
+                .entry(type_id)
+                .or_insert_with(|| Rc::new(Self::new::<R>()))
+                .clone()
+        });
+        // the cache is already destroyed while the thread exits
+        plan.unwrap_or_else(|_| Rc::new(Self::new::<R>()))
+    }
+}
+
+// `TypeId` of `T`, which may borrow, the requests of a retriever not depending on its lifetimes
+fn erased_type_id<T: ?Sized>() -> TypeId {
+    trait ErasedAny {
+        fn type_id(&self) -> TypeId
+        where
+            Self: 'static;
+    }
+
+    impl<T: ?Sized> ErasedAny for PhantomData<T> {
+        fn type_id(&self) -> TypeId
+        where
+            Self: 'static,
+        {
+            TypeId::of::<T>()
+        }
+    }
+
+    let marker: &dyn ErasedAny = &PhantomData::<T>;
+    // type ids do not depend on lifetimes, so extending them does not change the id
+    let marker =
+        unsafe { std::mem::transmute::<&dyn ErasedAny, &(dyn ErasedAny + 'static)>(marker) };
+    marker.type_id()
+}
+
+// lock resources in sorted order, resources before the columns of components, and the
+// resources bound to an interface in the order of their own type
+fn lock_order(requests: &[Request], container: Option<&ResourceContainer>) -> Vec<Lock> {
+    let mut order = Vec::with_capacity(requests.len());
+    for (index, request) in requests.iter().enumerate() {
+        match request.storage {
+            Storage::Alternatives(_) => {}
+            Storage::Interface => order.extend(
+                container
+                    .unwrap()
+                    .interface_types(request.type_id)
+                    .iter()
+                    .map(|&type_id| (type_id, Storage::Resource, index)),
+            ),
+            storage => order.push((request.type_id, storage, index)),
+        }
+    }
+    order.sort_by_key(|&(type_id, storage, _)| (type_id, storage));
+    order
+}
+
+// storage of a requested resource
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub(crate) enum Storage {
+    Resource,
+    // column of components locked through the `Entities` resource
+    Component,
+    // resource restricted to the thread owning the container
+    NonSend,
+    // queue of the deferred mutations of the container
+    Commands,
+    // resources bound to the requested interface
+    Interface,
+    // nothing, at least one of the next requests of the count must be retrieved
+    Alternatives(usize),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+/// Error of a retrieval, see [Retriever::try_retrieve].
+pub enum RetrievalError {
+    /// The container holds no resource of the type.
+    NotFound(&'static str),
+    /// The container holds none of the resources of several types, in declaration order.
+    NotFoundMultiple(Vec<&'static str>),
+    /// The container holds none of the alternative resources, see `AnyOf`.
+    NotFoundAny(Vec<&'static str>),
+    /// The non-send resource is retrieved on another thread than the one that created the
+    /// container.
+    WrongThread(&'static str),
+    /// The resource is borrowed mutably and borrowed again by the same retrieval, e.g.
+    /// `(Res<T>, ResMut<T>)`, which would deadlock on its own lock.
+    Conflict(&'static str),
+    /// The resource is locked by another borrow, see [Res::try_retrieve_now].
+    WouldBlock(&'static str),
+    /// The resource is still locked by another borrow after the timeout of the retrieval, see
+    /// [Res::try_retrieve_timeout].
+    Timeout(&'static str),
+    /// The borrow handed to a retriever is not the one it requested, e.g. a mutable borrow or
+    /// a resource of another type.
+    Mismatch {
+        expected: &'static str,
+        found: &'static str,
+    },
+}
+
+impl RetrievalError {
+    /// Name of the type of the resource that can not be retrieved, the first one if several
+    /// resources are missing.
+    pub fn type_name(&self) -> &'static str {
+        self.type_names()[0]
+    }
+
+    /// Names of the types of the resources that can not be retrieved.
+    pub fn type_names(&self) -> &[&'static str] {
+        match self {
+            RetrievalError::NotFound(type_name)
+            | RetrievalError::WrongThread(type_name)
+            | RetrievalError::Conflict(type_name)
+            | RetrievalError::WouldBlock(type_name)
+            | RetrievalError::Timeout(type_name)
+            | RetrievalError::Mismatch {
+                expected: type_name,
+                ..
+            } => std::slice::from_ref(type_name),
+            RetrievalError::NotFoundMultiple(type_names)
+            | RetrievalError::NotFoundAny(type_names) => type_names,
+        }
+    }
+
+    // every resource of `requests` missing from the container, including the one of `request`
+    fn not_found(container: &ResourceContainer, requests: &[Request], request: &Request) -> Self {
+        let type_names = requests
+            .iter()
+            .filter(|request| !request.optional && !container.can_retrieve(request))
+            .map(|request| request.type_name)
+            .collect::<Vec<_>>();
+        match type_names.len() {
+            0 | 1 => RetrievalError::NotFound(request.type_name),
+            _ => RetrievalError::NotFoundMultiple(type_names),
+        }
+    }
+}
+
+impl Display for RetrievalError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RetrievalError::NotFound(type_name) => write!(f, "Resource not found: {type_name}"),
+            RetrievalError::NotFoundMultiple(type_names) => {
+                write!(f, "Resources not found: {}", type_names.join(", "))
+            }
+            RetrievalError::NotFoundAny(type_names) => {
+                write!(f, "None of the resources found: {}", type_names.join(", "))
+            }
+            RetrievalError::WrongThread(type_name) => {
+                write!(
+                    f,
+                    "Non-send resource accessed outside of its thread: {type_name}"
+                )
+            }
+            RetrievalError::Conflict(type_name) => {
+                write!(f, "Resource borrowed mutably more than once: {type_name}")
+            }
+            RetrievalError::WouldBlock(type_name) => {
+                write!(f, "Resource locked by another borrow: {type_name}")
+            }
+            RetrievalError::Timeout(type_name) => {
+                write!(f, "Timed out waiting for the lock of resource: {type_name}")
+            }
+            RetrievalError::Mismatch { expected, found } => {
+                write!(
+                    f,
+                    "Retrieved borrow mismatch: expected {expected}, found {found}"
+                )
+            }
+        }
+    }
+}
+
+impl Error for RetrievalError {}
+
+// resource bound to an interface, with its type-erased cast
+pub(crate) type BoundRef<'a> = (Ref<'a, dyn Any, Immutable>, &'a (dyn Any + Send + Sync));
+
+pub(crate) enum RetrievedRef<'a> {
+    Immutable(Ref<'a, dyn Any, Immutable>),
+    Mutable(Ref<'a, dyn Any, Mutable>),
+    // column of components not created yet
+    Missing,
+    // resources bound to an interface
+    Interface(Vec<BoundRef<'a>>),
+    // optional resource missing from the container
+    Absent,
+}
+
+/// A type-erased borrow of a resource, locked by a [Retriever].
+pub struct Retrieved<'a> {
+    pub(crate) resource: RetrievedRef<'a>,
+    // change ticks of the resource, `None` for the columns of components
+    pub(crate) ticks: Option<&'a ChangeTicks>,
+    pub(crate) system: SystemTicks,
+    // observers notified when a mutable borrow of the resource is released
+    pub(crate) observers: Option<&'a WriteObservers>,
+    // type name of the resource, when retrieved from its entry
+    pub(crate) type_name: Option<&'static str>,
+}
+
+impl<'a> Retrieved<'a> {
+    fn new(resource: RetrievedRef<'a>, ticks: Option<&'a ChangeTicks>) -> Self {
+        Self {
+            resource,
+            ticks,
+            system: SystemTicks::default(),
+            observers: None,
+            type_name: None,
+        }
+    }
+
+    pub(crate) fn immutable(
+        resource: Ref<'a, dyn Any, Immutable>,
+        ticks: Option<&'a ChangeTicks>,
+    ) -> Self {
+        Self::new(RetrievedRef::Immutable(resource), ticks)
+    }
+
+    pub(crate) fn mutable(
+        resource: Ref<'a, dyn Any, Mutable>,
+        ticks: Option<&'a ChangeTicks>,
+    ) -> Self {
+        Self::new(RetrievedRef::Mutable(resource), ticks)
+    }
+
+    pub(crate) fn missing() -> Self {
+        Self::new(RetrievedRef::Missing, None)
+    }
+
+    fn interface(resources: Vec<BoundRef<'a>>) -> Self {
+        Self::new(RetrievedRef::Interface(resources), None)
+    }
+
+    fn absent() -> Self {
+        Self::new(RetrievedRef::Absent, None)
+    }
+
+    // another shared borrow of the resource, holding the guards of this one again without
+    // waiting for the lock, `None` if the resource is not borrowed immutably
+    fn share(&self) -> Option<Self> {
+        let RetrievedRef::Immutable(resource) = &self.resource else {
+            return None;
+        };
+        Some(Self {
+            resource: RetrievedRef::Immutable(Ref::clone(resource)),
+            observers: None,
+            ..*self
+        })
+    }
+
+    /// Returns `true` if the optional resource is missing from the container.
+    pub fn is_absent(&self) -> bool {
+        matches!(self.resource, RetrievedRef::Absent)
+    }
+
+    /// Returns `true` if the resource is borrowed mutably.
+    pub fn is_mutable(&self) -> bool {
+        matches!(self.resource, RetrievedRef::Mutable(_))
+    }
+
+    /// Type name of the resource, if known.
+    pub fn type_name(&self) -> Option<&'static str> {
+        self.type_name
+    }
+
+    /// Borrows the resource as a `T`.
+    ///
+    /// # Errors
+    /// Returns [RetrievalError::Mismatch] with the type name of the resource if it is not a `T`.
+    pub fn downcast_ref<T: Resource + ?Sized>(&self) -> Result<&T, RetrievalError> {
+        let resource = match &self.resource {
+            RetrievedRef::Immutable(resource) => T::from_any(&**resource),
+            RetrievedRef::Mutable(resource) => T::from_any(&**resource),
+            _ => None,
+        };
+        resource.ok_or_else(|| self.mismatch(std::any::type_name::<T>(), None))
+    }
+
+    /// Borrows the resource mutably as a `T`.
+    ///
+    /// # Errors
+    /// Returns [RetrievalError::Mismatch] if the resource is not a `T` or is not borrowed
+    /// mutably.
+    pub fn downcast_mut<T: Resource + ?Sized>(&mut self) -> Result<&mut T, RetrievalError> {
+        let error = self.mismatch(std::any::type_name::<T>(), Some(true));
+        match &mut self.resource {
+            RetrievedRef::Mutable(resource) => T::from_any_mut(&mut **resource).ok_or(error),
+            _ => Err(error),
+        }
+    }
+
+    // shared borrow of the resource as a `T`, for the retriever `expected`
+    pub(crate) fn take_shared<T: Resource + ?Sized>(
+        &mut self,
+        expected: &'static str,
+    ) -> Result<Ref<'a, T, Immutable>, RetrievalError> {
+        match std::mem::replace(&mut self.resource, RetrievedRef::Missing) {
+            RetrievedRef::Immutable(resource) if T::from_any(&*resource).is_some() => {
+                Ok(downcast(resource))
+            }
+            resource => {
+                self.resource = resource;
+                Err(self.mismatch(expected, Some(false)))
+            }
+        }
+    }
+
+    // exclusive borrow of the resource as a `T`, for the retriever `expected`
+    pub(crate) fn take_exclusive<T: Resource + ?Sized>(
+        &mut self,
+        expected: &'static str,
+    ) -> Result<Ref<'a, T, Mutable>, RetrievalError> {
+        match std::mem::replace(&mut self.resource, RetrievedRef::Missing) {
+            RetrievedRef::Mutable(resource) if T::from_any(&*resource).is_some() => {
+                Ok(downcast(resource))
+            }
+            resource => {
+                self.resource = resource;
+                Err(self.mismatch(expected, Some(true)))
+            }
+        }
+    }
+
+    // resources bound to an interface, for the retriever `expected`
+    pub(crate) fn take_bound(
+        &mut self,
+        expected: &'static str,
+    ) -> Result<Vec<BoundRef<'a>>, RetrievalError> {
+        match std::mem::replace(&mut self.resource, RetrievedRef::Missing) {
+            RetrievedRef::Interface(bound) => Ok(bound),
+            resource => {
+                self.resource = resource;
+                Err(self.mismatch(expected, None))
+            }
+        }
+    }
+
+    // error of a retriever expecting `expected`, borrowed mutably if `mutable` is `Some(true)`
+    fn mismatch(&self, expected: &'static str, mutable: Option<bool>) -> RetrievalError {
+        let found = match (&self.resource, mutable) {
+            (RetrievedRef::Immutable(_), Some(true)) => "a shared borrow",
+            (RetrievedRef::Mutable(_), Some(false)) => "a mutable borrow",
+            (RetrievedRef::Immutable(_) | RetrievedRef::Mutable(_), _) => {
+                self.type_name.unwrap_or("a resource of another type")
+            }
+            (RetrievedRef::Missing, _) => "a missing column of components",
+            (RetrievedRef::Interface(_), _) => "the resources bound to an interface",
+            (RetrievedRef::Absent, _) => "an absent resource",
+        };
+        RetrievalError::Mismatch { expected, found }
+    }
+}
+
+impl Debug for Retrieved<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Retrieved")
+            .field("mutable", &self.is_mutable())
+            .finish()
+    }
+}
+
+/// Type of a resource stored in a `ResourceContainer`.
+///
+/// Implemented for every sized type. Trait objects implement it with
+/// [resource_interface](crate::resource_interface), to be added with
+/// `Container::add_resource_as` and retrieved as `Res<dyn Trait>`, so that systems and
+/// handlers can depend on an abstraction rather than a concrete type.
+///
+/// # Examples
+/// ```
+/// use emark::prelude::*;
+/// use emark::resource_interface;
+/// use emark::store::ResourceContainer;
+///
+/// trait Logger: Send + Sync {
+///     fn log(&mut self, message: &str);
+/// }
+///
+/// #[derive(Default)]
+/// struct MemoryLogger(Vec<String>);
+///
+/// impl Logger for MemoryLogger {
+///     fn log(&mut self, message: &str) {
+///         self.0.push(message.to_owned());
+///     }
+/// }
+///
+/// resource_interface!(dyn Logger);
+///
+/// let mut container = ResourceContainer::default();
+/// container.add_resource_as::<dyn Logger>(Box::new(MemoryLogger::default()));
+/// ResMut::<dyn Logger>::retrieve(&container).log("loaded");
+/// assert!(container.contains_resource::<dyn Logger>());
+/// ```
+pub trait Resource: 'static {
+    #[doc(hidden)]
+    /// Borrows the resource from its type-erased storage.
+    fn from_any(resource: &dyn Any) -> Option<&Self>;
+
+    #[doc(hidden)]
+    fn from_any_mut(resource: &mut dyn Any) -> Option<&mut Self>;
+}
+
+impl<T: 'static> Resource for T {
+    fn from_any(resource: &dyn Any) -> Option<&Self> {
+        resource.downcast_ref()
+    }
+
+    fn from_any_mut(resource: &mut dyn Any) -> Option<&mut Self> {
+        resource.downcast_mut()
+    }
+}
+
+/// Implements [Resource] for trait objects, stored boxed by `Container::add_resource_as`.
+///
+/// Must be invoked in the crate defining the trait, see [Resource].
+#[macro_export]
+macro_rules! resource_interface {
+    ($($interface:ty),* $(,)?) => {
+        $(impl $crate::store::Resource for $interface {
+            fn from_any(resource: &dyn ::std::any::Any) -> Option<&Self> {
+                resource
+                    .downcast_ref::<::std::boxed::Box<$interface>>()
+                    .map(|resource| &**resource)
+            }
+
+            fn from_any_mut(resource: &mut dyn ::std::any::Any) -> Option<&mut Self> {
+                resource
+                    .downcast_mut::<::std::boxed::Box<$interface>>()
+                    .map(|resource| &mut **resource)
+            }
+        })*
+    };
+}
+
+/// A single resource retrievable from a `ResourceContainer`.
+///
+/// `Access` is either `Immutable` or `Mutable` and defines how the resource is locked.
+pub trait Retrievable {
+    /// Type of the stored resource.
+    type Resource: Resource + ?Sized;
+    /// Access state the resource is locked with.
+    type Access: LockState;
+    /// Borrow of the resource.
+    type Item<'a>;
+
+    /// Converts the locked resource into its borrow.
+    fn from_retrieved(retrieved: Retrieved<'_>) -> Self::Item<'_>;
+}
+
+/// Retrieves a set of resources from a `ResourceContainer`.
+///
+/// Every [Retrievable] is a retriever, as well as tuples of up to 32 retrievers, which can nest.
+/// Tuples lock their resources in sorted `TypeId` order, so that retrievers locking
+/// the same resources concurrently can not deadlock, and lock a resource requested several
+/// times immutably once, sharing its borrow.
+///
+/// With the `derive` feature, `#[derive(Retriever)]` implements the trait for structs whose
+/// fields are all retrievers, locked together like the elements of a tuple.
+///
+/// # Examples
+/// ```
+/// use emark::prelude::*;
+/// use emark::store::{Res, ResMut, ResourceContainer};
+///
+/// let mut container = ResourceContainer::default();
+/// container.add_resource(2u32);
+/// container.add_resource(Vec::<u32>::new());
+///
+/// let (factor, mut values) = <(Res<u32>, ResMut<Vec<u32>>)>::retrieve(&container);
+/// values.push(*factor * 2);
+/// assert_eq!(*values, vec![4]);
+/// ```
+pub trait Retriever {
+    /// Borrows of the resources.
+    type Item<'a>;
+
+    #[doc(hidden)]
+    /// Appends the resources requested, in declaration order.
+    fn requests(requests: &mut Vec<Request>);
+
+    #[doc(hidden)]
+    /// Converts the locked resources, in declaration order, into their borrows.
+    fn assemble<'a>(retrieved: &mut dyn Iterator<Item = Retrieved<'a>>) -> Self::Item<'a>;
+
+    /// Resources read and written by the retriever, known without retrieving them.
+    ///
+    /// Schedulers compare the accesses of systems to find the ones that can run concurrently.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::any::TypeId;
+    ///
+    /// use emark::prelude::*;
+    ///
+    /// let access = <(Res<u32>, ResMut<String>)>::access();
+    /// assert_eq!(access.reads(), [TypeId::of::<u32>()]);
+    /// assert_eq!(access.writes(), [TypeId::of::<String>()]);
+    /// assert!(access.conflicts_with(&ResMut::<u32>::access()));
+    /// assert!(!access.conflicts_with(&Res::<u32>::access()));
+    /// ```
+    fn access() -> Access {
+        let mut requests = Vec::new();
+        Self::requests(&mut requests);
+        Access::from_requests(&requests)
+    }
+
+    /// Locks and borrows the resources from the container.
+    ///
+    /// Changes are reported since the last `ResourceContainer::clear_changes`, see
+    /// [SystemTicks].
+    ///
+    /// # Panics
+    /// Panics if a resource can not be retrieved, see [Retriever::try_retrieve].
+    fn retrieve(container: &ResourceContainer) -> Self::Item<'_> {
+        Self::retrieve_with(container, container.ticks())
+    }
+
+    /// Locks and borrows the resources from the container, reporting changes since
+    /// `ticks.last_run()` and marking writes changed at `ticks.this_run()`.
+    ///
+    /// # Panics
+    /// Panics if a resource can not be retrieved, see [Retriever::try_retrieve].
+    fn retrieve_with(container: &ResourceContainer, ticks: SystemTicks) -> Self::Item<'_> {
+        Self::try_retrieve_with(container, ticks).unwrap_or_else(|error| panic!("{error}"))
+    }
+
+    /// Locks and borrows the resources from the container, returning an error instead of
+    /// panicking if a resource is not found.
+    ///
+    /// The error lists every missing resource, and the resources locked before the error are
+    /// released.
+    ///
+    /// # Examples
+    /// ```
+    /// use emark::prelude::*;
+    /// use emark::store::{RetrievalError, ResourceContainer};
+    ///
+    /// let mut container = ResourceContainer::default();
+    /// container.add_resource(1u32);
+    ///
+    /// let error = <(Res<u32>, ResMut<String>)>::try_retrieve(&container).unwrap_err();
+    /// assert_eq!(error, RetrievalError::NotFound("alloc::string::String"));
+    /// assert!(ResMut::<u32>::try_retrieve(&container).is_ok());
+    /// ```
+    fn try_retrieve(container: &ResourceContainer) -> Result<Self::Item<'_>, RetrievalError> {
+        Self::try_retrieve_with(container, container.ticks())
+    }
+
+    /// Fallible [Retriever::retrieve_with], see [Retriever::try_retrieve].
+    fn try_retrieve_with(
+        container: &ResourceContainer,
+        ticks: SystemTicks,
+    ) -> Result<Self::Item<'_>, RetrievalError> {
+        Self::try_retrieve_planned(container, ticks, &RetrievalPlan::cached::<Self>())
+    }
+
+    #[doc(hidden)]
+    /// Fallible [Retriever::retrieve_with] following the plan of `Self`, computed once by the
+    /// systems retrieving their parameters on every run, and cached per thread for the other
+    /// retrievals.
+    fn try_retrieve_planned<'a>(
+        container: &'a ResourceContainer,
+        ticks: SystemTicks,
+        plan: &RetrievalPlan,
+    ) -> Result<Self::Item<'a>, RetrievalError> {
+        let requests = &plan.requests;
+        let order = match &plan.order {
+            Some(order) => Cow::Borrowed(order.as_slice()),
+            None => Cow::Owned(lock_order(requests, Some(container))),
+        };
+
+        // reject a resource borrowed twice, once mutably, before locking it into a deadlock
+        for pair in order.windows(2) {
+            let [(type_id, storage, first), (next_type_id, next_storage, second)] = *pair else {
+                unreachable!()
+            };
+            let (first, second) = (&requests[first], &requests[second]);
+            if (type_id, storage) == (next_type_id, next_storage)
+                && (first.mutable || second.mutable)
+            {
+                let request = if first.mutable { first } else { second };
+                return Err(RetrievalError::Conflict(request.type_name));
+            }
+        }
+        let mut retrieved = (0..requests.len()).map(|_| None).collect::<Vec<_>>();
+        // resources bound to the requested interfaces, by request
+        let mut bound = match plan.order {
+            Some(_) => Vec::new(),
+            None => (0..requests.len()).map(|_| Vec::new()).collect::<Vec<_>>(),
+        };
+        // shared borrow of the previous lock, shared by the next request of the same resource
+        // rather than locking it again, which would wait behind a writer waiting for the lock
+        let mut shared: Option<Retrieved<'a>> = None;
+        for (position, &(type_id, storage, index)) in order.iter().enumerate() {
+            let request = &requests[index];
+            let next = order
+                .get(position + 1)
+                .map(|&(type_id, storage, _)| (type_id, storage));
+            let shared_next = next == Some((type_id, storage));
+            if request.storage == Storage::Interface {
+                let resource = shared
+                    .take()
+                    .or_else(|| container.retrieve_any(type_id, false));
+                if let Some(resource) = resource {
+                    if shared_next {
+                        shared = resource.share();
+                    }
+                    let RetrievedRef::Immutable(resource) = resource.resource else {
+                        unreachable!()
+                    };
+                    let cast = container.interface_cast(request.type_id, type_id);
+                    bound[index].push((resource, cast));
+                }
+                continue;
+            }
+            let resource = match (shared.take(), request.storage) {
+                (Some(resource), _) => Some(resource),
+                (None, Storage::Resource) => {
+                    container.retrieve_any(request.type_id, request.mutable)
+                }
+                (None, Storage::Component) => {
+                    container.retrieve_component(request.type_id, request.mutable)
+                }
+                (None, Storage::NonSend) => {
+                    if !container.is_owner_thread() {
+                        return Err(RetrievalError::WrongThread(request.type_name));
+                    }
+                    container.retrieve_non_send(request.type_id, request.mutable)
+                }
+                (None, Storage::Commands) => Some(container.retrieve_commands()),
+                (None, Storage::Interface | Storage::Alternatives(_)) => unreachable!(),
+            };
+            if shared_next {
+                shared = resource.as_ref().and_then(Retrieved::share);
+            }
+            match resource {
+                Some(resource) => {
+                    retrieved[index] = Some(Retrieved {
+                        system: ticks,
+                        ..resource
+                    })
+                }
+                None if request.optional => retrieved[index] = Some(Retrieved::absent()),
+                None => return Err(RetrievalError::not_found(container, requests, request)),
+            }
+        }
+
+        for (index, request) in requests.iter().enumerate() {
+            match request.storage {
+                Storage::Interface => {
+                    retrieved[index] = Some(Retrieved::interface(std::mem::take(&mut bound[index])))
+                }
+                Storage::Alternatives(count) => {
+                    let alternatives = index + 1..index + 1 + count;
+                    let absent = retrieved[alternatives.clone()]
+                        .iter()
+                        .all(|resource| resource.as_ref().unwrap().is_absent());
+                    if absent {
+                        let type_names = requests[alternatives]
+                            .iter()
+                            .map(|request| request.type_name);
+                        return Err(RetrievalError::NotFoundAny(type_names.collect()));
+                    }
+                    retrieved[index] = Some(Retrieved::absent());
+                }
+                _ => {}
+            }
+        }
+
+        // convert resources in declaration order
+        Ok(Self::assemble(
+            &mut retrieved.into_iter().map(Option::unwrap),
+        ))
+    }
+}
+
+impl<R: Retrievable> Retriever for R {
+    type Item<'a> = R::Item<'a>;
+
+    fn requests(requests: &mut Vec<Request>) {
+        requests.push(Request {
+            type_id: TypeId::of::<R::Resource>(),
+            type_name: std::any::type_name::<R::Resource>(),
+            mutable: R::Access::MUTABLE,
+            storage: Storage::Resource,
+            optional: false,
+        });
+    }
+
+    fn assemble<'a>(retrieved: &mut dyn Iterator<Item = Retrieved<'a>>) -> Self::Item<'a> {
+        R::from_retrieved(retrieved.next().unwrap())
+    }
+}
+
+macro_rules! impl_retrievable {
+    ($($retriever:ident),*) => {
+        impl<$($retriever: Retriever),*> Retriever for ($($retriever,)*) {
+            type Item<'a> = ($($retriever::Item<'a>,)*);
+
+            #[allow(unused_variables)]
+            fn requests(requests: &mut Vec<Request>) {
+                $($retriever::requests(requests);)*
+            }
+
+            #[allow(unused_variables, clippy::unused_unit)]
+            fn assemble<'a>(retrieved: &mut dyn Iterator<Item = Retrieved<'a>>) -> Self::Item<'a> {
+                ($($retriever::assemble(retrieved),)*)
+            }
+        }
+    };
+}
+
+impl_tuples!(impl_retrievable);
+
+/// Shared borrow of a resource of type `T`.
+pub struct Res<'a, T: Resource + ?Sized> {
+    resource: Ref<'a, T, Immutable>,
+    ticks: &'a ChangeTicks,
+    system: SystemTicks,
+}
+
+impl<'a, T: Resource + ?Sized> Res<'a, T> {
+    /// Borrows the resource of type `T` from the container, see [Retriever::try_retrieve].
+    pub fn try_get(container: &ResourceContainer) -> Result<Res<'_, T>, RetrievalError> {
+        Self::try_retrieve(container)
+    }
+
+    /// Borrows the resource of type `T` without blocking, failing with
+    /// [RetrievalError::WouldBlock] while it is borrowed mutably, e.g. for systems that can
+    /// skip a frame rather than wait for the resource.
+    ///
+    /// # Examples
+    /// ```
+    /// use emark::prelude::*;
+    /// use emark::store::{RetrievalError, ResourceContainer};
+    ///
+    /// let mut container = ResourceContainer::default();
+    /// container.add_resource(1u32);
+    ///
+    /// let count = ResMut::<u32>::retrieve(&container);
+    /// let error = Res::<u32>::try_retrieve_now(&container).unwrap_err();
+    /// assert_eq!(error, RetrievalError::WouldBlock("u32"));
+    ///
+    /// drop(count);
+    /// assert_eq!(*Res::<u32>::try_retrieve_now(&container).unwrap(), 1);
+    /// ```
+    pub fn try_retrieve_now(container: &ResourceContainer) -> Result<Res<'_, T>, RetrievalError> {
+        Self::try_retrieve_within(container, None)
+    }
+
+    /// Borrows the resource of type `T`, failing with [RetrievalError::Timeout] if it is still
+    /// borrowed mutably after `timeout`, e.g. to report a stuck handler instead of hanging.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::time::Duration;
+    ///
+    /// use emark::prelude::*;
+    /// use emark::store::{RetrievalError, ResourceContainer};
+    ///
+    /// let mut container = ResourceContainer::default();
+    /// container.add_resource(1u32);
+    ///
+    /// let _stuck = ResMut::<u32>::retrieve(&container);
+    /// let error = Res::<u32>::try_retrieve_timeout(&container, Duration::from_millis(1));
+    /// assert_eq!(error.unwrap_err(), RetrievalError::Timeout("u32"));
+    /// ```
+    pub fn try_retrieve_timeout(
+        container: &ResourceContainer,
+        timeout: Duration,
+    ) -> Result<Res<'_, T>, RetrievalError> {
+        Self::try_retrieve_within(container, Some(timeout))
+    }
+
+    fn try_retrieve_within(
+        container: &ResourceContainer,
+        timeout: Option<Duration>,
+    ) -> Result<Res<'_, T>, RetrievalError> {
+        let type_name = std::any::type_name::<T>();
+        let retrieved =
+            container.try_retrieve_any_within(TypeId::of::<T>(), type_name, false, timeout)?;
+        Ok(Self::from_retrieved(retrieved))
+    }
+
+    /// Narrows the borrow to a part of the resource, such as one of its fields, still holding
+    /// the lock of the whole resource.
+    ///
+    /// # Examples
+    /// ```
+    /// use emark::prelude::*;
+    /// use emark::store::{MappedRes, ResourceContainer};
+    ///
+    /// struct Config {
+    ///     graphics: Graphics,
+    /// }
+    ///
+    /// struct Graphics {
+    ///     vsync: bool,
+    /// }
+    ///
+    /// // helpers take the narrowest view they need
+    /// fn uses_vsync(graphics: &Graphics) -> bool {
+    ///     graphics.vsync
+    /// }
+    ///
+    /// let mut container = ResourceContainer::default();
+    /// container.add_resource(Config { graphics: Graphics { vsync: true } });
+    ///
+    /// let config = Res::<Config>::retrieve(&container);
+    /// let graphics: MappedRes<Graphics> = config.map(|config| &config.graphics);
+    /// assert!(uses_vsync(&graphics));
+    /// ```
+    pub fn map<U: ?Sized>(self, f: impl FnOnce(&T) -> &U) -> MappedRes<'a, U> {
+        MappedRes {
+            resource: self.resource.map(f),
+        }
+    }
+
+    /// Clones the borrow, e.g. to hand the resource to several helpers of a system without
+    /// retrieving it again, the resource staying locked until every clone is released.
+    ///
+    /// Used as `Res::clone(&resource)`, `resource.clone()` cloning the resource itself.
+    ///
+    /// # Examples
+    /// ```
+    /// use emark::prelude::*;
+    /// use emark::store::ResourceContainer;
+    ///
+    /// struct Gravity(f32);
+    ///
+    /// struct Integrator<'a> {
+    ///     gravity: Res<'a, Gravity>,
+    /// }
+    ///
+    /// struct Predictor<'a> {
+    ///     gravity: Res<'a, Gravity>,
+    /// }
+    ///
+    /// let mut container = ResourceContainer::default();
+    /// container.add_resource(Gravity(9.8));
+    ///
+    /// let gravity = Res::<Gravity>::retrieve(&container);
+    /// let predictor = Predictor { gravity: Res::clone(&gravity) };
+    /// let integrator = Integrator { gravity };
+    /// assert_eq!(predictor.gravity.0, integrator.gravity.0);
+    /// ```
+    #[allow(clippy::should_implement_trait)]
+    pub fn clone(this: &Self) -> Self {
+        Res {
+            resource: Ref::clone(&this.resource),
+            ticks: this.ticks,
+            system: this.system,
+        }
+    }
+
+    /// Returns `true` if the resource was added since the last run of the system.
+    pub fn is_added(&self) -> bool {
+        self.system.is_newer(self.ticks.added)
+    }
+
+    /// Returns `true` if the resource was added or mutably dereferenced since the last run of
+    /// the system, see [SystemTicks].
+    pub fn is_changed(&self) -> bool {
+        self.system.is_newer(self.ticks.changed())
+    }
+}
+
+impl<T: Resource + ?Sized> Retrievable for Res<'_, T> {
+    type Resource = T;
+    type Access = Immutable;
+    type Item<'a> = Res<'a, T>;
+
+    fn from_retrieved(mut retrieved: Retrieved<'_>) -> Self::Item<'_> {
+        let resource = retrieved
+            .take_shared(std::any::type_name::<Self>())
+            .unwrap_or_else(|error| panic!("{error}"));
+        Res {
+            resource,
+            ticks: retrieved.ticks.unwrap(),
+            system: retrieved.system,
+        }
+    }
+}
+
+impl<T: Resource + ?Sized> Deref for Res<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.resource
+    }
+}
+
+impl<T: Resource + Debug + ?Sized> Debug for Res<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("Res").field(&self.deref()).finish()
+    }
+}
+
+/// Exclusive borrow of a resource of type `T`.
+///
+/// Mutably dereferencing the borrow marks the resource changed, see [Res::is_changed], and
+/// notifies the observers registered with `ResourceContainer::observe_writes` once the borrow
+/// is released.
+pub struct ResMut<'a, T: Resource + ?Sized> {
+    resource: Ref<'a, T, Mutable>,
+    ticks: &'a ChangeTicks,
+    system: SystemTicks,
+    observers: Option<&'a WriteObservers>,
+    // mutably dereferenced through this borrow
+    written: bool,
+}
+
+impl<'a, T: Resource + ?Sized> ResMut<'a, T> {
+    /// Mutably borrows the resource of type `T` from the container, see
+    /// [Retriever::try_retrieve].
+    pub fn try_get(container: &ResourceContainer) -> Result<ResMut<'_, T>, RetrievalError> {
+        Self::try_retrieve(container)
+    }
+
+    /// Mutably borrows the resource of type `T` without blocking, failing with
+    /// [RetrievalError::WouldBlock] while it is borrowed, see [Res::try_retrieve_now].
+    pub fn try_retrieve_now(
+        container: &ResourceContainer,
+    ) -> Result<ResMut<'_, T>, RetrievalError> {
+        Self::try_retrieve_within(container, None)
+    }
+
+    /// Mutably borrows the resource of type `T`, failing with [RetrievalError::Timeout] if it is
+    /// still borrowed after `timeout`, see [Res::try_retrieve_timeout].
+    pub fn try_retrieve_timeout(
+        container: &ResourceContainer,
+        timeout: Duration,
+    ) -> Result<ResMut<'_, T>, RetrievalError> {
+        Self::try_retrieve_within(container, Some(timeout))
+    }
+
+    fn try_retrieve_within(
+        container: &ResourceContainer,
+        timeout: Option<Duration>,
+    ) -> Result<ResMut<'_, T>, RetrievalError> {
+        let type_name = std::any::type_name::<T>();
+        let retrieved =
+            container.try_retrieve_any_within(TypeId::of::<T>(), type_name, true, timeout)?;
+        Ok(Self::from_retrieved(retrieved))
+    }
+
+    /// Returns `true` if the resource was added since the last run of the system.
+    pub fn is_added(&self) -> bool {
+        self.system.is_newer(self.ticks.added)
+    }
+
+    /// Returns `true` if the resource was added or mutably dereferenced since the last run of
+    /// the system, including through this borrow.
+    pub fn is_changed(&self) -> bool {
+        self.system.is_newer(self.ticks.changed())
+    }
+
+    /// Narrows the borrow to a part of the resource, such as one of its fields, still holding
+    /// the lock of the whole resource, see [Res::map].
+    ///
+    /// Mutably dereferencing the narrowed borrow marks the whole resource changed, and notifies
+    /// its observers once the borrow is released.
+    pub fn map_mut<U: ?Sized>(self, f: impl FnOnce(&mut T) -> &mut U) -> MappedResMut<'a, U> {
+        let this = ManuallyDrop::new(self);
+        // the fields are moved out of the borrow once, without dropping it
+        let resource = unsafe { std::ptr::read(&this.resource) };
+        let resource_ptr = NonNull::new(resource.as_ptr()).unwrap();
+        let notify = this.observers.map(|observers| {
+            Box::new(move || {
+                // the resource is still locked by the narrowed borrow
+                observers.notify::<T>(unsafe { resource_ptr.as_ref() })
+            }) as Box<dyn FnOnce() + 'a>
+        });
+        MappedResMut {
+            resource: resource.map(f),
+            ticks: this.ticks,
+            system: this.system,
+            written: this.written,
+            notify,
+        }
+    }
+}
+
+impl<T: Resource + ?Sized> Retrievable for ResMut<'_, T> {
+    type Resource = T;
+    type Access = Mutable;
+    type Item<'a> = ResMut<'a, T>;
+
+    fn from_retrieved(mut retrieved: Retrieved<'_>) -> Self::Item<'_> {
+        let resource = retrieved
+            .take_exclusive(std::any::type_name::<Self>())
+            .unwrap_or_else(|error| panic!("{error}"));
+        ResMut {
+            resource,
+            ticks: retrieved.ticks.unwrap(),
+            system: retrieved.system,
+            observers: retrieved.observers,
+            written: false,
+        }
+    }
+}
+
+impl<T: Resource + ?Sized> Deref for ResMut<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.resource
+    }
+}
+
+impl<T: Resource + ?Sized> DerefMut for ResMut<'_, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.ticks.set_changed(self.system.this_run());
+        self.written = true;
+        &mut self.resource
+    }
+}
+
+impl<T: Resource + ?Sized> Drop for ResMut<'_, T> {
+    fn drop(&mut self) {
+        if let Some(observers) = self.observers.filter(|_| self.written) {
+            // the resource is still locked, so the observers see the write
+            observers.notify::<T>(&self.resource);
+        }
+    }
+}
+
+impl<T: Resource + Debug + ?Sized> Debug for ResMut<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("ResMut").field(&self.deref()).finish()
+    }
+}
+
+/// Shared borrow of a part of a resource, created by [Res::map].
+pub struct MappedRes<'a, T: ?Sized> {
+    resource: Ref<'a, T, Immutable>,
+}
+
+impl<'a, T: ?Sized> MappedRes<'a, T> {
+    /// Narrows the borrow further, see [Res::map].
+    pub fn map<U: ?Sized>(self, f: impl FnOnce(&T) -> &U) -> MappedRes<'a, U> {
+        MappedRes {
+            resource: self.resource.map(f),
+        }
+    }
+
+    /// Clones the borrow, see [Res::clone].
+    #[allow(clippy::should_implement_trait)]
+    pub fn clone(this: &Self) -> Self {
+        MappedRes {
+            resource: Ref::clone(&this.resource),
+        }
+    }
+}
+
+impl<T: ?Sized> Deref for MappedRes<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.resource
+    }
+}
+
+impl<T: Debug + ?Sized> Debug for MappedRes<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("MappedRes").field(&self.deref()).finish()
+    }
+}
+
+/// Exclusive borrow of a part of a resource, created by [ResMut::map_mut].
+pub struct MappedResMut<'a, T: ?Sized> {
+    resource: Ref<'a, T, Mutable>,
+    ticks: &'a ChangeTicks,
+    system: SystemTicks,
+    written: bool,
+    // notifies the observers of the whole resource
+    notify: Option<Box<dyn FnOnce() + 'a>>,
+}
+
+impl<'a, T: ?Sized> MappedResMut<'a, T> {
+    /// Narrows the borrow further, see [ResMut::map_mut].
+    pub fn map_mut<U: ?Sized>(self, f: impl FnOnce(&mut T) -> &mut U) -> MappedResMut<'a, U> {
+        let mut this = ManuallyDrop::new(self);
+        // the fields are moved out of the borrow once, without dropping it
+        let resource = unsafe { std::ptr::read(&this.resource) };
+        MappedResMut {
+            resource: resource.map(f),
+            ticks: this.ticks,
+            system: this.system,
+            written: this.written,
+            notify: this.notify.take(),
+        }
+    }
+}
+
+impl<T: ?Sized> Deref for MappedResMut<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.resource
+    }
+}
+
+impl<T: ?Sized> DerefMut for MappedResMut<'_, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.ticks.set_changed(self.system.this_run());
+        self.written = true;
+        &mut self.resource
+    }
+}
+
+impl<T: ?Sized> Drop for MappedResMut<'_, T> {
+    fn drop(&mut self) {
+        if let Some(notify) = self.notify.take().filter(|_| self.written) {
+            notify();
+        }
+    }
+}
+
+impl<T: Debug + ?Sized> Debug for MappedResMut<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("MappedResMut").field(&self.deref()).finish()
+    }
+}
+
+// narrow a borrow of a boxed resource to the resource itself
+pub(crate) fn downcast<T: Resource + ?Sized, S: LockState>(
+    resource: Ref<'_, dyn Any, S>,
+) -> Ref<'_, T, S> {
+    // the resource is kept alive and locked by the guards of the ref
+    unsafe {
+        resource.map_unchecked::<T, S>(|mut data| {
+            // only create a mutable reference from an exclusive lock
+            match S::MUTABLE {
+                true => NonNull::from(T::from_any_mut(data.as_mut()).unwrap()),
+                false => NonNull::from(T::from_any(data.as_ref()).unwrap()),
+            }
+        })
+    }
+}
+
+// type-erased borrow of a value locked on its own
+pub(crate) fn erase<T: Any, S: LockState>(resource: Ref<'_, T, S>) -> Ref<'_, dyn Any, S> {
+    unsafe { resource.map_unchecked::<dyn Any, S>(|data| data as NonNull<dyn Any>) }
+}
+
+// type-erased borrow of the content of a locked box
+pub(crate) fn unbox<S: LockState>(resource: Ref<'_, Box<dyn Any>, S>) -> Ref<'_, dyn Any, S> {
+    // the box is kept alive and locked by the guards of the ref
+    unsafe {
+        resource.map_unchecked::<dyn Any, S>(|mut data| {
+            // only create a mutable reference from an exclusive lock
+            match S::MUTABLE {
+                true => NonNull::from(&mut **data.as_mut()),
+                false => NonNull::from(&**data.as_ref()),
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod test_res {
+    use super::*;
+    use crate::{
+        event::EventManager,
+        store::Container,
+        system::{IntoSystem, System},
+    };
+
+    trait Counter: Send + Sync {
+        fn increment(&mut self) -> u32;
+    }
+
+    struct Simple(u32);
+
+    impl Counter for Simple {
+        fn increment(&mut self) -> u32 {
+            self.0 += 1;
+            self.0
+        }
+    }
+
+    resource_interface!(dyn Counter);
+
+    #[test]
+    fn test_res_trait_object() {
+        let mut container = ResourceContainer::default();
+        container.add_resource_as::<dyn Counter>(Box::new(Simple(0)));
+        container.add_resource(Simple(10));
+
+        // assert the interface and the concrete type are distinct resources
+        let (mut counter, mut simple) =
+            <(ResMut<dyn Counter>, ResMut<Simple>)>::retrieve(&container);
+        assert_eq!(counter.increment(), 1);
+        assert_eq!(simple.increment(), 11);
+        drop((counter, simple));
+
+        let system = |mut counter: ResMut<dyn Counter>| {
+            counter.increment();
+        };
+        system
+            .into_system()
+            .run(&container, &EventManager::new())
+            .unwrap();
+        assert_eq!(
+            container
+                .remove_resource_as::<dyn Counter>()
+                .unwrap()
+                .increment(),
+            3
+        );
+    }
+
+    #[test]
+    fn test_res_retrieve() {
+        let mut container = ResourceContainer::default();
+        container.add_resource(1i32);
+
+        let res = Res::<i32>::retrieve(&container);
+        assert_eq!(*res, 1);
+    }
+
+    #[test]
+    fn test_res_mut_retrieve() {
+        let mut container = ResourceContainer::default();
+        container.add_resource(1i32);
+
+        *ResMut::<i32>::retrieve(&container) += 1;
+        assert_eq!(*Res::<i32>::retrieve(&container), 2);
+    }
+
+    #[test]
+    fn test_tuple_retrieve() {
+        let mut container = ResourceContainer::default();
+        container.add_resource(1i32);
+        container.add_resource(2u64);
+        container.add_resource(String::from("a"));
+
+        // assert nested tuples retrieve in declaration order
+        let (a, (mut b, c)) = <(Res<i32>, (ResMut<u64>, Res<String>))>::retrieve(&container);
+        *b += *a as u64;
+        assert_eq!((*a, *b, c.as_str()), (1, 3, "a"));
+    }
+
+    #[test]
+    fn test_large_tuple_retrieve() {
+        struct Slot<const N: usize>(usize);
+
+        macro_rules! retrieve_slots {
+            ($container:ident; $($n:literal),*) => {{
+                $($container.add_resource(Slot::<$n>($n));)*
+                <($(Res<Slot<$n>>,)*)>::retrieve(&$container)
+            }};
+        }
+
+        // assert tuples of more than 16 retrievers retrieve in declaration order
+        let mut container = ResourceContainer::default();
+        let slots = retrieve_slots!(
+            container;
+            0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19
+        );
+        assert_eq!((slots.0 .0, slots.16 .0, slots.19 .0), (0, 16, 19));
+    }
+
+    #[cfg(feature = "derive")]
+    #[test]
+    fn test_derive_retriever() {
+        #[derive(crate::store::Retriever)]
+        struct Params<'a> {
+            factor: Res<'a, u32>,
+            values: ResMut<'a, Vec<u32>>,
+        }
+
+        #[derive(crate::store::Retriever)]
+        struct Nested<'a>(pub Params<'a>, (Res<'a, u64>,));
+
+        #[derive(crate::store::Retriever)]
+        struct Typed<'a, T: Resource>
+        where
+            T: Clone,
+        {
+            value: Res<'a, T>,
+        }
+
+        #[derive(crate::store::Retriever)]
+        struct Empty;
+
+        let mut container = ResourceContainer::default();
+        container.add_resource(2u32);
+        container.add_resource(Vec::<u32>::new());
+        container.add_resource(3u64);
+
+        let mut params = Params::retrieve(&container);
+        params.values.push(*params.factor * 2);
+        drop(params);
+
+        // assert derived retrievers nest and are system parameters
+        let Nested(params, (offset,)) = Nested::retrieve(&container);
+        assert_eq!((params.values.as_slice(), *offset), (&[4][..], 3));
+        drop((params, offset));
+        assert_eq!(*Typed::<u64>::retrieve(&container).value, 3);
+        Empty::retrieve(&container);
+        let system = |mut params: Params| params.values.push(*params.factor);
+        system
+            .into_system()
+            .run(&container, &EventManager::new())
+            .unwrap();
+        assert_eq!(*Res::<Vec<u32>>::retrieve(&container), [4, 2]);
+    }
+
+    #[test]
+    fn test_res_change_detection() {
+        fn observe(value: Res<u32>, mut log: ResMut<Vec<(bool, bool)>>) {
+            log.push((value.is_added(), value.is_changed()));
+        }
+
+        let mut container = ResourceContainer::default();
+        container.add_resource(0u32);
+        container.add_resource(Vec::<(bool, bool)>::new());
+        let event_manager = EventManager::new();
+        let mut system = observe.into_system();
+        system.run(&container, &event_manager).unwrap();
+        system.run(&container, &event_manager).unwrap();
+
+        // assert only mutable dereferences mark the resource changed
+        let _ = *ResMut::<u32>::retrieve(&container);
+        system.run(&container, &event_manager).unwrap();
+        *ResMut::<u32>::retrieve(&container) += 1;
+        system.run(&container, &event_manager).unwrap();
+        assert_eq!(
+            *Res::<Vec<(bool, bool)>>::retrieve(&container),
+            vec![(true, true), (false, false), (false, false), (false, true)]
+        );
+
+        // assert retrievals outside of systems report changes until cleared
+        assert!(Res::<u32>::retrieve(&container).is_changed());
+        container.clear_changes();
+        assert!(!Res::<u32>::retrieve(&container).is_changed());
+    }
+
+    #[test]
+    fn test_res_map() {
+        let mut container = ResourceContainer::default();
+        container.add_resource((1u32, String::from("a")));
+        let observed = std::sync::Arc::new(parking_lot::Mutex::new(Vec::new()));
+        let observer_observed = observed.clone();
+        container.observe_writes(move |resource: &(u32, String)| {
+            observer_observed.lock().push(resource.0)
+        });
+        container.clear_changes();
+
+        // assert narrowed reads neither mark changes nor notify the observers
+        let name = Res::<(u32, String)>::retrieve(&container).map(|resource| &resource.1);
+        assert_eq!(name.map(String::as_str).len(), 1);
+        let _ = *ResMut::<(u32, String)>::retrieve(&container).map_mut(|resource| &mut resource.0);
+        assert!(!Res::<(u32, String)>::retrieve(&container).is_changed());
+        assert!(observed.lock().is_empty());
+
+        *ResMut::<(u32, String)>::retrieve(&container).map_mut(|resource| &mut resource.0) += 1;
+        assert!(Res::<(u32, String)>::retrieve(&container).is_changed());
+        assert_eq!(*observed.lock(), [2]);
+    }
+
+    #[test]
+    fn test_res_clone() {
+        let mut container = ResourceContainer::default();
+        container.add_resource((1u32, String::from("a")));
+
+        let resource = Res::<(u32, String)>::retrieve(&container);
+        let name = Res::clone(&resource).map(|resource| &resource.1);
+        drop(resource);
+
+        // assert the clones keep the resource locked
+        assert_eq!(*MappedRes::clone(&name), "a");
+        assert!(ResMut::<(u32, String)>::try_retrieve_now(&container).is_err());
+        drop(name);
+        assert!(ResMut::<(u32, String)>::try_retrieve_now(&container).is_ok());
+    }
+
+    #[test]
+    fn test_try_retrieve() {
+        let mut container = ResourceContainer::default();
+        container.add_resource(1i32);
+
+        let error = Res::<u64>::try_get(&container).unwrap_err();
+        assert_eq!(error, RetrievalError::NotFound("u64"));
+        assert_eq!(error.type_name(), "u64");
+
+        // assert the resources locked before the error are released
+        let error = <(ResMut<i32>, Res<u64>)>::try_retrieve(&container).unwrap_err();
+        assert_eq!(error.to_string(), "Resource not found: u64");
+        assert_eq!(*ResMut::<i32>::try_get(&container).unwrap(), 1);
+
+        // assert every missing resource is reported at once
+        let error = <(Res<u64>, (Res<i32>, ResMut<String>))>::try_retrieve(&container).unwrap_err();
+        assert_eq!(error.type_names(), ["u64", "alloc::string::String"]);
+        assert_eq!(
+            error.to_string(),
+            "Resources not found: u64, alloc::string::String"
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Resource not found: i32")]
+    fn test_retrieve_missing() {
+        let container = ResourceContainer::default();
+        Res::<i32>::retrieve(&container);
+    }
+
+    #[test]
+    fn test_retrieved_downcast() {
+        let mut container = ResourceContainer::default();
+        container.add_resource(1u32);
+
+        let retrieved = container.retrieve_any(TypeId::of::<u32>(), false).unwrap();
+        assert_eq!(retrieved.type_name(), Some("u32"));
+        assert_eq!(retrieved.downcast_ref::<u32>(), Ok(&1));
+        assert_eq!(
+            retrieved.downcast_ref::<u64>().unwrap_err().to_string(),
+            "Retrieved borrow mismatch: expected u64, found u32"
+        );
+        drop(retrieved);
+
+        let mut retrieved = container.retrieve_any(TypeId::of::<u32>(), true).unwrap();
+        *retrieved.downcast_mut::<u32>().unwrap() += 1;
+        assert_eq!(
+            retrieved.take_shared::<u32>("Res<u32>").err().unwrap(),
+            RetrievalError::Mismatch {
+                expected: "Res<u32>",
+                found: "a mutable borrow"
+            }
+        );
+        assert_eq!(*retrieved.take_exclusive::<u32>("ResMut<u32>").unwrap(), 2);
+    }
+
+    #[test]
+    fn test_retrieval_plan() {
+        let mut container = ResourceContainer::default();
+        container.add_resource(1u32);
+        container.add_resource(2u64);
+        let ticks = container.ticks();
+
+        // assert a plan is reused across retrievals
+        let plan = RetrievalPlan::new::<(ResMut<u64>, Res<u32>)>();
+        for expected in 3..5 {
+            let (mut a, b) =
+                <(ResMut<u64>, Res<u32>)>::try_retrieve_planned(&container, ticks, &plan).unwrap();
+            *a += *b as u64;
+            assert_eq!(*a, expected);
+        }
+        let plan = RetrievalPlan::new::<(Res<u32>, ResMut<u32>)>();
+        let error = <(Res<u32>, ResMut<u32>)>::try_retrieve_planned(&container, ticks, &plan);
+        assert_eq!(error.unwrap_err(), RetrievalError::Conflict("u32"));
+    }
+
+    #[test]
+    fn test_retrieval_plan_cached() {
+        fn plan<'a>(_: &'a ResourceContainer) -> Rc<RetrievalPlan> {
+            RetrievalPlan::cached::<(ResMut<'a, u64>, Res<'a, u32>)>()
+        }
+
+        // assert the plan is shared by the retrievals of a type, whatever its lifetimes
+        let container = ResourceContainer::default();
+        let cached = RetrievalPlan::cached::<(ResMut<u64>, Res<u32>)>();
+        assert!(Rc::ptr_eq(&cached, &plan(&container)));
+        assert!(!Rc::ptr_eq(
+            &cached,
+            &RetrievalPlan::cached::<(Res<u64>, Res<u32>)>()
+        ));
+        assert_eq!(cached.requests.len(), 2);
+    }
+
+    #[test]
+    fn test_retrieve_conflict() {
+        let mut container = ResourceContainer::default();
+        container.add_resource(1u32);
+        container.add_resource(2u64);
+
+        let error = <(Res<u32>, (Res<u64>, ResMut<u32>))>::try_retrieve(&container).unwrap_err();
+        assert_eq!(error, RetrievalError::Conflict("u32"));
+        assert_eq!(
+            <(ResMut<u64>, ResMut<u64>)>::try_retrieve(&container).unwrap_err(),
+            RetrievalError::Conflict("u64")
+        );
+
+        // assert shared borrows of the same resource do not conflict
+        let (a, b) = <(Res<u32>, Res<u32>)>::retrieve(&container);
+        assert_eq!((*a, *b), (1, 1));
+    }
+
+    #[test]
+    fn test_try_retrieve_now() {
+        let mut container = ResourceContainer::default();
+        container.add_resource(1u32);
+        container.clear_changes();
+
+        let count = Res::<u32>::retrieve(&container);
+        assert_eq!(*Res::<u32>::try_retrieve_now(&container).unwrap(), 1);
+        let error = ResMut::<u32>::try_retrieve_now(&container).unwrap_err();
+        assert_eq!(error, RetrievalError::WouldBlock("u32"));
+        drop(count);
+
+        // assert writes through non-blocking borrows are detected
+        *ResMut::<u32>::try_retrieve_now(&container).unwrap() += 1;
+        assert!(Res::<u32>::retrieve(&container).is_changed());
+        assert_eq!(
+            Res::<u64>::try_retrieve_now(&container).unwrap_err(),
+            RetrievalError::NotFound("u64")
+        );
+    }
+
+    #[cfg(not(feature = "unsync"))]
+    #[test]
+    fn test_try_retrieve_timeout() {
+        let mut container = ResourceContainer::default();
+        container.add_resource(1u32);
+        let timeout = Duration::from_millis(10);
+
+        let count = ResMut::<u32>::retrieve(&container);
+        let error = ResMut::<u32>::try_retrieve_timeout(&container, timeout).unwrap_err();
+        assert_eq!(error, RetrievalError::Timeout("u32"));
+
+        // assert the borrow is acquired once released by another thread within the timeout
+        std::thread::scope(|scope| {
+            let handle = scope.spawn(|| {
+                *Res::<u32>::try_retrieve_timeout(&container, Duration::from_secs(10)).unwrap()
+            });
+            std::thread::sleep(timeout);
+            drop(count);
+            assert_eq!(handle.join().unwrap(), 1);
+        });
+    }
+
+    #[cfg(not(feature = "unsync"))]
+    #[test]
+    fn test_retrieve_shared_twice_writer_waiting() {
+        use std::sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc,
+        };
+
+        let mut container = ResourceContainer::default();
+        container.add_resource(0u32);
+        let container = Arc::new(container);
+        let done = Arc::new(AtomicBool::new(false));
+
+        // writer parking on the lock while the readers hold it, between their shared borrows
+        let writer = std::thread::spawn({
+            let (container, done) = (container.clone(), done.clone());
+            move || {
+                while !done.load(Ordering::Relaxed) {
+                    *ResMut::<u32>::retrieve(&container) += 1;
+                }
+            }
+        });
+        let reader = std::thread::spawn({
+            let container = container.clone();
+            move || {
+                for _ in 0..10_000 {
+                    let (a, b) = <(Res<u32>, Res<u32>)>::retrieve(&container);
+                    assert_eq!(*a, *b);
+                }
+            }
+        });
+
+        // assert the readers never wait behind the writer for their second borrow, a deadlock
+        // leaving both threads blocked
+        let start = std::time::Instant::now();
+        while !reader.is_finished() && start.elapsed() < Duration::from_secs(10) {
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        assert!(
+            reader.is_finished(),
+            "retrieval deadlocked behind a waiting writer"
+        );
+        done.store(true, Ordering::Relaxed);
+        reader.join().unwrap();
+        writer.join().unwrap();
+    }
+}
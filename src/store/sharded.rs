@@ -0,0 +1,172 @@
+use std::{
+    any::TypeId,
+    collections::HashMap,
+    hash::{DefaultHasher, Hash, Hasher},
+};
+
+use parking_lot::lock_api;
+
+use crate::utils::lock::grained_lock::{raw_lock, RawLock};
+
+// lock of a shard, only checking borrow flags with the `unsync` feature
+type RwLock<T> = lock_api::RwLock<RawLock, T>;
+
+// number of shards, a power of two
+const SHARDS: usize = 16;
+
+// map of `TypeId` split into independently locked shards, so that lookups of a type never wait
+// for an insertion of a type of another shard
+#[derive(Debug)]
+pub(crate) struct ShardedMap<V> {
+    shards: [RwLock<HashMap<TypeId, V>>; SHARDS],
+}
+
+impl<V> Default for ShardedMap<V> {
+    fn default() -> Self {
+        Self {
+            shards: std::array::from_fn(|_| RwLock::from_raw(raw_lock(), HashMap::new())),
+        }
+    }
+}
+
+impl<V: Clone> ShardedMap<V> {
+    fn shard_index(key: TypeId) -> usize {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        hasher.finish() as usize & (SHARDS - 1)
+    }
+
+    fn shard(&self, key: TypeId) -> &RwLock<HashMap<TypeId, V>> {
+        &self.shards[Self::shard_index(key)]
+    }
+
+    fn shard_mut(&mut self, key: TypeId) -> &mut HashMap<TypeId, V> {
+        self.shards[Self::shard_index(key)].get_mut()
+    }
+
+    pub(crate) fn get(&self, key: TypeId) -> Option<V> {
+        self.get_with(key, V::clone)
+    }
+
+    // calls `f` with the value of `key` while its shard is locked
+    pub(crate) fn get_with<R>(&self, key: TypeId, f: impl FnOnce(&V) -> R) -> Option<R> {
+        self.shard(key).read().get(&key).map(f)
+    }
+
+    pub(crate) fn contains_key(&self, key: TypeId) -> bool {
+        self.shard(key).read().contains_key(&key)
+    }
+
+    // inserts the value returned by `f` if `key` is missing, only locking the shard of `key`
+    pub(crate) fn get_or_insert_with(&self, key: TypeId, f: impl FnOnce() -> V) -> V {
+        self.shard(key).write().entry(key).or_insert_with(f).clone()
+    }
+
+    pub(crate) fn insert(&mut self, key: TypeId, value: V) -> Option<V> {
+        self.shard_mut(key).insert(key, value)
+    }
+
+    pub(crate) fn remove(&mut self, key: TypeId) -> Option<V> {
+        self.shard_mut(key).remove(&key)
+    }
+
+    pub(crate) fn retain(&mut self, mut retain: impl FnMut(TypeId, &V) -> bool) {
+        for shard in &mut self.shards {
+            shard.get_mut().retain(|&key, value| retain(key, value));
+        }
+    }
+
+    pub(crate) fn clear(&mut self) {
+        for shard in &mut self.shards {
+            shard.get_mut().clear();
+        }
+    }
+
+    // entries of every shard, each shard being locked in turn
+    pub(crate) fn entries(&self) -> Vec<(TypeId, V)> {
+        self.shards
+            .iter()
+            .flat_map(|shard| {
+                let shard = shard.read();
+                shard
+                    .iter()
+                    .map(|(key, value)| (*key, value.clone()))
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    pub(crate) fn keys(&self) -> Vec<TypeId> {
+        self.shards
+            .iter()
+            .flat_map(|shard| shard.read().keys().copied().collect::<Vec<_>>())
+            .collect()
+    }
+
+    // reserves `additional` entries spread evenly across the shards
+    pub(crate) fn reserve(&mut self, additional: usize) {
+        for shard in &mut self.shards {
+            shard.get_mut().reserve(additional.div_ceil(SHARDS));
+        }
+    }
+
+    pub(crate) fn shrink_to_fit(&mut self) {
+        for shard in &mut self.shards {
+            shard.get_mut().shrink_to_fit();
+        }
+    }
+
+    // entries the map can hold without reallocating, assuming they are spread evenly
+    pub(crate) fn capacity(&self) -> usize {
+        let min = self
+            .shards
+            .iter()
+            .map(|shard| shard.read().capacity())
+            .min();
+        min.unwrap_or(0) * SHARDS
+    }
+}
+
+#[cfg(test)]
+mod test_sharded {
+    use super::*;
+
+    #[test]
+    fn test_sharded_map() {
+        let mut map = ShardedMap::default();
+        map.insert(TypeId::of::<u8>(), 1);
+        map.insert(TypeId::of::<u16>(), 2);
+        assert_eq!(map.get_or_insert_with(TypeId::of::<u16>(), || 3), 2);
+        assert_eq!(map.get_or_insert_with(TypeId::of::<u32>(), || 3), 3);
+        assert_eq!(map.keys().len(), 3);
+
+        map.retain(|_, value| *value != 2);
+        assert!(!map.contains_key(TypeId::of::<u16>()));
+        assert_eq!(map.remove(TypeId::of::<u8>()), Some(1));
+        let mut entries = map.entries();
+        entries.sort();
+        assert_eq!(entries, [(TypeId::of::<u32>(), 3)]);
+    }
+
+    #[test]
+    fn test_sharded_insert_while_read() {
+        let map = ShardedMap::default();
+        let a = TypeId::of::<u8>();
+        let b = [
+            TypeId::of::<u16>(),
+            TypeId::of::<u32>(),
+            TypeId::of::<u64>(),
+        ]
+        .into_iter()
+        .chain([TypeId::of::<i8>(), TypeId::of::<i16>(), TypeId::of::<i32>()])
+        .find(|&b| ShardedMap::<i32>::shard_index(a) != ShardedMap::<i32>::shard_index(b))
+        .unwrap();
+        map.get_or_insert_with(a, || 1);
+
+        // assert inserting in another shard does not wait for the readers of `a`
+        let shard = map.shard(a).read();
+        map.get_or_insert_with(b, || 2);
+        assert_eq!(map.get(b), Some(2));
+        drop(shard);
+    }
+}
@@ -0,0 +1,110 @@
+#[cfg(feature = "unsync")]
+use std::cell::Cell;
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+/// Change ticks of a run of a system.
+///
+/// Every run of a function system advances the change tick of its `ResourceContainer`.
+/// Resources and components written during the run are marked changed at `this_run`, and are
+/// reported changed by later runs of systems whose `last_run` precedes it.
+///
+/// Outside of systems, retrievers report the changes made since the last call to
+/// `ResourceContainer::clear_changes`, called by the `App` at the beginning of each cycle.
+pub struct SystemTicks {
+    last_run: u64,
+    this_run: u64,
+}
+
+impl SystemTicks {
+    pub fn new(last_run: u64, this_run: u64) -> Self {
+        Self { last_run, this_run }
+    }
+
+    /// Change tick of the previous run of the system, `0` before its first run.
+    pub fn last_run(&self) -> u64 {
+        self.last_run
+    }
+
+    /// Change tick of the current run of the system.
+    pub fn this_run(&self) -> u64 {
+        self.this_run
+    }
+
+    // true if `tick` is newer than the previous run
+    pub(crate) fn is_newer(&self, tick: u64) -> bool {
+        tick > self.last_run
+    }
+}
+
+// change tick shared by a container and its `Entities`
+#[derive(Debug, Default, Clone)]
+pub(crate) struct ChangeClock(Arc<AtomicU64>);
+
+impl ChangeClock {
+    // current change tick
+    pub(crate) fn now(&self) -> u64 {
+        self.0.load(Ordering::Acquire)
+    }
+
+    // advances the change tick, returning the new tick
+    pub(crate) fn advance(&self) -> u64 {
+        self.0.fetch_add(1, Ordering::AcqRel) + 1
+    }
+}
+
+// ticks at which a resource or a component was added and last changed
+#[derive(Debug)]
+pub(crate) struct ChangeTicks {
+    pub(crate) added: u64,
+    changed: ChangedTick,
+}
+
+// tick of the last change, a plain cell with the `unsync` feature
+#[cfg(not(feature = "unsync"))]
+type ChangedTick = AtomicU64;
+#[cfg(feature = "unsync")]
+#[derive(Debug)]
+struct ChangedTick(Cell<u64>);
+
+// only accessed while the data is borrowed, the `unsync` locks of the data panicking on other
+// threads than their owner
+#[cfg(feature = "unsync")]
+unsafe impl Sync for ChangedTick {}
+
+impl ChangeTicks {
+    pub(crate) fn new(tick: u64) -> Self {
+        Self {
+            added: tick,
+            #[cfg(not(feature = "unsync"))]
+            changed: AtomicU64::new(tick),
+            #[cfg(feature = "unsync")]
+            changed: ChangedTick(Cell::new(tick)),
+        }
+    }
+
+    // only called while the data is borrowed
+    #[cfg(not(feature = "unsync"))]
+    pub(crate) fn changed(&self) -> u64 {
+        self.changed.load(Ordering::Relaxed)
+    }
+
+    #[cfg(feature = "unsync")]
+    pub(crate) fn changed(&self) -> u64 {
+        self.changed.0.get()
+    }
+
+    // marks the data changed, only called while it is borrowed mutably
+    #[cfg(not(feature = "unsync"))]
+    pub(crate) fn set_changed(&self, tick: u64) {
+        self.changed.store(tick, Ordering::Relaxed);
+    }
+
+    #[cfg(feature = "unsync")]
+    pub(crate) fn set_changed(&self, tick: u64) {
+        self.changed.0.set(tick);
+    }
+}
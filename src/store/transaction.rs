@@ -0,0 +1,96 @@
+use super::{Res, ResMut, Resource, Retriever};
+
+/// Retriever whose mutations can be rolled back, see `ResourceContainer::transaction`.
+///
+/// Implemented for `Res<T>`, `ResMut<T>` of cloneable resources, and tuples of up to 8
+/// transactions.
+pub trait Transaction: Retriever {
+    #[doc(hidden)]
+    /// Copy of the resources borrowed mutably, taken before the transaction.
+    type Backup;
+
+    #[doc(hidden)]
+    fn backup(item: &Self::Item<'_>) -> Self::Backup;
+
+    #[doc(hidden)]
+    /// Restores the resources to their copy.
+    fn rollback(item: &mut Self::Item<'_>, backup: Self::Backup);
+}
+
+impl<T: Resource + ?Sized> Transaction for Res<'_, T> {
+    type Backup = ();
+
+    fn backup(_: &Self::Item<'_>) -> Self::Backup {}
+
+    fn rollback(_: &mut Self::Item<'_>, _: Self::Backup) {}
+}
+
+impl<T: Clone + 'static> Transaction for ResMut<'_, T> {
+    type Backup = T;
+
+    fn backup(item: &Self::Item<'_>) -> Self::Backup {
+        T::clone(item)
+    }
+
+    fn rollback(item: &mut Self::Item<'_>, backup: Self::Backup) {
+        **item = backup;
+    }
+}
+
+macro_rules! impl_transaction {
+    ($($transaction:ident $index:tt),*) => {
+        impl<$($transaction: Transaction),*> Transaction for ($($transaction,)*) {
+            type Backup = ($($transaction::Backup,)*);
+
+            fn backup(item: &Self::Item<'_>) -> Self::Backup {
+                ($($transaction::backup(&item.$index),)*)
+            }
+
+            fn rollback(item: &mut Self::Item<'_>, backup: Self::Backup) {
+                $($transaction::rollback(&mut item.$index, backup.$index);)*
+            }
+        }
+    };
+}
+
+impl_transaction!(A 0);
+impl_transaction!(A 0, B 1);
+impl_transaction!(A 0, B 1, C 2);
+impl_transaction!(A 0, B 1, C 2, D 3);
+impl_transaction!(A 0, B 1, C 2, D 3, E 4);
+impl_transaction!(A 0, B 1, C 2, D 3, E 4, F 5);
+impl_transaction!(A 0, B 1, C 2, D 3, E 4, F 5, G 6);
+impl_transaction!(A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7);
+
+#[cfg(test)]
+mod test_transaction {
+    use super::*;
+    use crate::{
+        store::{Container, ResourceContainer, RetrievalError},
+        EmarkError,
+    };
+
+    #[test]
+    fn test_transaction_rollback() {
+        let mut container = ResourceContainer::default();
+        container.add_resource(1u32);
+        container.add_resource(vec![1u8]);
+
+        let result = container.transaction::<(Res<u32>, ResMut<Vec<u8>>), _>(|(count, bytes)| {
+            bytes.push(**count as u8);
+            Err::<(), _>(EmarkError::Custom("rollback".to_owned()))
+        });
+        assert!(result.is_err());
+        assert_eq!(*Res::<Vec<u8>>::retrieve(&container), [1]);
+
+        let result = container.transaction::<ResMut<Vec<u8>>, _>(|bytes| {
+            bytes.push(2);
+            Ok(bytes.len())
+        });
+        assert_eq!(result, Ok(2));
+        assert_eq!(
+            container.transaction::<ResMut<u64>, _>(|_| Ok(())),
+            Err(EmarkError::Retrieval(RetrievalError::NotFound("u64")))
+        );
+    }
+}
@@ -0,0 +1,159 @@
+use std::any::TypeId;
+
+use crate::store::{Request, Storage};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+/// Resources accessed by a system.
+///
+/// Two systems conflict if one of them writes a resource the other one reads or writes,
+/// or if one of them is exclusive. Non-conflicting systems may be executed concurrently.
+pub struct Access {
+    reads: Vec<TypeId>,
+    writes: Vec<TypeId>,
+    exclusive: bool,
+    // reads every resource
+    reads_all: bool,
+    // accesses non-send resources
+    main_thread: bool,
+    // type names of the resources, when known
+    names: Vec<(TypeId, &'static str)>,
+}
+
+impl Access {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Access conflicting with every other access.
+    ///
+    /// Used by systems whose accessed resources are unknown.
+    pub fn exclusive() -> Self {
+        Self {
+            exclusive: true,
+            ..Self::default()
+        }
+    }
+
+    /// Adds a shared access to the resource `type_id`.
+    pub fn with_read(mut self, type_id: TypeId) -> Self {
+        self.reads.push(type_id);
+        self
+    }
+
+    /// Adds a shared access to every resource, e.g. for read-only handlers whose accessed
+    /// resources are unknown.
+    pub fn with_read_all(mut self) -> Self {
+        self.reads_all = true;
+        self
+    }
+
+    /// Adds an exclusive access to the resource `type_id`.
+    pub fn with_write(mut self, type_id: TypeId) -> Self {
+        self.writes.push(type_id);
+        self
+    }
+
+    /// Restricts the access to the thread running the schedule, e.g. for systems retrieving
+    /// non-send resources.
+    pub fn with_main_thread(mut self) -> Self {
+        self.main_thread = true;
+        self
+    }
+
+    pub fn reads(&self) -> &[TypeId] {
+        &self.reads
+    }
+
+    pub fn writes(&self) -> &[TypeId] {
+        &self.writes
+    }
+
+    pub fn is_exclusive(&self) -> bool {
+        self.exclusive
+    }
+
+    /// Returns `true` if the access reads every resource.
+    pub fn reads_all(&self) -> bool {
+        self.reads_all
+    }
+
+    /// Returns `true` if the access writes no resource, so it never conflicts with another
+    /// read-only access.
+    pub fn is_read_only(&self) -> bool {
+        !self.exclusive && self.writes.is_empty()
+    }
+
+    /// Returns `true` if the system must run on the thread running the schedule.
+    ///
+    /// Exclusive accesses may access non-send resources, so they are restricted as well.
+    pub fn is_main_thread(&self) -> bool {
+        self.main_thread || self.exclusive
+    }
+
+    /// Type name of the resource `type_id`, if known.
+    ///
+    /// Names are known for the accesses of function systems.
+    pub fn type_name(&self, type_id: TypeId) -> Option<&'static str> {
+        self.names
+            .iter()
+            .find(|(id, _)| *id == type_id)
+            .map(|(_, name)| *name)
+    }
+
+    /// Returns `true` if both accesses can not be held concurrently.
+    pub fn conflicts_with(&self, other: &Access) -> bool {
+        self.exclusive
+            || other.exclusive
+            || self
+                .writes
+                .iter()
+                .any(|type_id| other.reads.contains(type_id) || other.writes.contains(type_id))
+            || other
+                .writes
+                .iter()
+                .any(|type_id| self.reads.contains(type_id))
+            || (self.reads_all && !other.writes.is_empty())
+            || (other.reads_all && !self.writes.is_empty())
+    }
+
+    pub(crate) fn from_requests(requests: &[Request]) -> Self {
+        // alternatives only mark the requests that follow them
+        requests
+            .iter()
+            .filter(|request| !matches!(request.storage, Storage::Alternatives(_)))
+            .fold(Self::new(), |mut access, request| {
+                access.names.push((request.type_id, request.type_name));
+                access.main_thread |= request.storage == Storage::NonSend;
+                match request.mutable {
+                    true => access.with_write(request.type_id),
+                    false => access.with_read(request.type_id),
+                }
+            })
+    }
+}
+
+#[cfg(test)]
+mod test_access {
+    use super::*;
+
+    #[test]
+    fn test_access_conflicts() {
+        let reads = Access::new().with_read(TypeId::of::<u32>());
+        let writes = Access::new().with_write(TypeId::of::<u32>());
+        let other = Access::new().with_write(TypeId::of::<u64>());
+
+        assert!(!reads.conflicts_with(&reads));
+        assert!(reads.conflicts_with(&writes));
+        assert!(writes.conflicts_with(&reads));
+        assert!(writes.conflicts_with(&writes));
+        assert!(!writes.conflicts_with(&other));
+        assert!(Access::exclusive().conflicts_with(&Access::new()));
+
+        // assert reading every resource only conflicts with writes
+        let reads_all = Access::new().with_read_all();
+        assert!(reads_all.is_read_only());
+        assert!(!reads_all.conflicts_with(&reads));
+        assert!(reads_all.conflicts_with(&other));
+        assert!(other.conflicts_with(&reads_all));
+    }
+}
@@ -0,0 +1,152 @@
+use std::marker::PhantomData;
+
+use crate::{
+    event::EventManager,
+    store::ResourceContainer,
+    utils::{block_on::block_on, error::EmarkError},
+};
+
+use super::{Access, IntoSystem, System};
+
+#[doc(hidden)]
+pub struct AsyncMarker;
+
+/// A [System] running an async function.
+///
+/// Async functions and closures taking the `ResourceContainer` and the `EventManager` are
+/// converted into an `AsyncSystem` by [IntoSystem]. The future is awaited on the thread running
+/// the system, so the other worker threads of a
+/// [ParallelExecutor](crate::system::ParallelExecutor) keep running systems while it is pending.
+/// The function may access any resource, so the system is exclusive unless its access is
+/// declared with `with_access`, which lets the executor await the futures of non-conflicting
+/// async systems concurrently.
+///
+/// The future is not bound to any particular async runtime. Work requiring a runtime, such as
+/// runtime-specific I/O, should be spawned onto that runtime and its handle awaited.
+///
+/// # Examples
+/// ```
+/// use emark::event::EventManager;
+/// use emark::prelude::*;
+/// use emark::store::ResourceContainer;
+/// use emark::system::Access;
+///
+/// struct Loaded;
+/// impl Event for Loaded {}
+///
+/// async fn load(_: &ResourceContainer, event_manager: &EventManager) {
+///     std::future::ready(()).await;
+///     event_manager.emit(Loaded);
+/// }
+///
+/// let mut schedule = Schedule::new();
+/// schedule.add_system(load.into_system().with_access(Access::new()));
+/// ```
+pub struct AsyncSystem<F> {
+    function: F,
+    access: Access,
+    _marker: PhantomData<fn()>,
+}
+
+impl<F> AsyncSystem<F> {
+    /// Declares the resources accessed by the function. Defaults to an exclusive access.
+    pub fn with_access(mut self, access: Access) -> Self {
+        self.access = access;
+        self
+    }
+}
+
+impl<F> System for AsyncSystem<F>
+where
+    F: AsyncFnMut(&ResourceContainer, &EventManager) + Send + Sync,
+{
+    fn name(&self) -> &str {
+        std::any::type_name::<F>()
+    }
+
+    fn run(
+        &mut self,
+        container: &ResourceContainer,
+        event_manager: &EventManager,
+    ) -> Result<(), EmarkError> {
+        block_on((self.function)(container, event_manager));
+        Ok(())
+    }
+
+    fn access(&self) -> Access {
+        self.access.clone()
+    }
+}
+
+impl<F> IntoSystem<AsyncMarker> for F
+where
+    F: AsyncFnMut(&ResourceContainer, &EventManager) + Send + Sync + 'static,
+{
+    type System = AsyncSystem<F>;
+
+    fn into_system(self) -> Self::System {
+        AsyncSystem {
+            function: self,
+            access: Access::exclusive(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_async_system {
+    use super::*;
+    use crate::{
+        event::{Event, HandlerRegistry},
+        store::Container,
+        system::{ParallelExecutor, Schedule},
+    };
+
+    struct TestEvent(u32);
+    impl Event for TestEvent {}
+
+    #[test]
+    fn test_async_system() {
+        let mut schedule = Schedule::new();
+        schedule.set_executor(ParallelExecutor::new());
+        for _ in 0..2 {
+            schedule.add_system(
+                async |container: &ResourceContainer, event_manager: &EventManager| {
+                    let value = std::future::ready(container.contains_resource::<u32>()).await;
+                    event_manager.emit(TestEvent(value as u32));
+                },
+            );
+        }
+        let mut registry = HandlerRegistry::new();
+        registry.add_async_handler(async |events: &[TestEvent]| {
+            assert_eq!(events.len(), 2);
+            assert!(events.iter().all(|event| event.0 == 1));
+        });
+        schedule.add_system(registry);
+
+        let mut container = ResourceContainer::default();
+        container.add_resource(0u32);
+        schedule.run(&container, &EventManager::new());
+    }
+
+    #[cfg(not(feature = "unsync"))]
+    #[test]
+    fn test_async_system_access() {
+        use std::sync::{Arc, Barrier};
+
+        // both systems only read, they must run concurrently to pass the barrier
+        let barrier = Arc::new(Barrier::new(2));
+        let mut schedule = Schedule::new();
+        schedule.set_executor(ParallelExecutor::with_threads(2));
+        for _ in 0..2 {
+            let barrier = barrier.clone();
+            let system = async move |_: &ResourceContainer, _: &EventManager| {
+                std::future::ready(()).await;
+                barrier.wait();
+            };
+            let access = Access::new().with_read(std::any::TypeId::of::<u32>());
+            schedule.add_system(system.into_system().with_access(access));
+        }
+        schedule.run(&ResourceContainer::default(), &EventManager::new());
+    }
+}
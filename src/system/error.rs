@@ -0,0 +1,126 @@
+use std::fmt::Display;
+
+use crate::{
+    event::{priority::Priority, Event, EventManager},
+    utils::error::EmarkError,
+};
+
+/// Return type of a function system, either `()` or `Result<(), EmarkError>`.
+pub trait SystemResult {
+    fn into_result(self) -> Result<(), EmarkError>;
+}
+
+impl SystemResult for () {
+    fn into_result(self) -> Result<(), EmarkError> {
+        Ok(())
+    }
+}
+
+impl SystemResult for Result<(), EmarkError> {
+    fn into_result(self) -> Result<(), EmarkError> {
+        self
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+/// Behavior of a [Schedule](crate::system::Schedule) when one of its systems returns an error.
+///
+/// # Examples
+/// ```
+/// use emark::event::EventManager;
+/// use emark::prelude::*;
+/// use emark::store::ResourceContainer;
+/// use emark::system::{ErrorPolicy, SystemErrorEvent, SystemOptions};
+/// use emark::EmarkError;
+///
+/// let mut schedule = Schedule::new();
+/// schedule
+///     .set_error_policy(ErrorPolicy::SkipDependents)
+///     .add_system_with(
+///         || -> Result<(), EmarkError> { Err(EmarkError::Custom("no level".to_owned())) },
+///         SystemOptions::labeled("load"),
+///     )
+///     // skipped, would panic without a `u32` resource
+///     .add_system_with(
+///         |mut level: ResMut<u32>| *level += 1,
+///         SystemOptions::new().with_after("load"),
+///     );
+///
+/// let event_manager = EventManager::new();
+/// schedule.run(&ResourceContainer::default(), &event_manager);
+/// let errors = event_manager.peek(|errors: &[SystemErrorEvent]| errors.len());
+/// assert_eq!(errors, Some(1));
+/// ```
+pub enum ErrorPolicy {
+    /// Stops the stage and panics with the error. This is the default.
+    #[default]
+    Panic,
+    /// Writes the error to the standard error stream.
+    Log,
+    /// Emits the error as a [SystemErrorEvent].
+    Emit,
+    /// Emits the error as a [SystemErrorEvent] and skips the systems ordered after the failed
+    /// system for the rest of the cycle.
+    SkipDependents,
+    /// Emits the error as a [SystemErrorEvent] and skips the remaining systems of the cycle.
+    AbortCycle,
+}
+
+impl ErrorPolicy {
+    // true if a failure stops the remaining systems
+    pub(crate) fn aborts(self) -> bool {
+        matches!(self, ErrorPolicy::Panic | ErrorPolicy::AbortCycle)
+    }
+
+    // true if a system ordered after the `halted` systems it depends on must be skipped
+    pub(crate) fn skips(self, dependencies: &[usize], halted: &[bool]) -> bool {
+        self == ErrorPolicy::SkipDependents && dependencies.iter().any(|&index| halted[index])
+    }
+
+    // reports the failure of the system `system`
+    pub(crate) fn report(self, system: &str, error: EmarkError, event_manager: &EventManager) {
+        let event = SystemErrorEvent {
+            system: system.to_owned(),
+            error,
+        };
+        match self {
+            ErrorPolicy::Panic => panic!("{event}"),
+            ErrorPolicy::Log => eprintln!("{event}"),
+            _ => {
+                event_manager.emit_priority(event, Priority::High);
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Event emitted when a system returns an error, see [ErrorPolicy].
+///
+/// The event is emitted with `High` priority, carrying the name of the system and its error.
+pub struct SystemErrorEvent {
+    system: String,
+    error: EmarkError,
+}
+
+impl Event for SystemErrorEvent {}
+
+impl SystemErrorEvent {
+    /// Name of the system that failed.
+    pub fn system(&self) -> &str {
+        &self.system
+    }
+
+    pub fn error(&self) -> &EmarkError {
+        &self.error
+    }
+
+    pub fn into_error(self) -> EmarkError {
+        self.error
+    }
+}
+
+impl Display for SystemErrorEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "system `{}` failed: {}", self.system, self.error)
+    }
+}
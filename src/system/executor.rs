@@ -0,0 +1,569 @@
+use std::{
+    any::Any,
+    collections::VecDeque,
+    fmt::Debug,
+    num::NonZeroUsize,
+    panic::{self, AssertUnwindSafe},
+    sync::Arc,
+};
+
+use parking_lot::{Condvar, Mutex};
+
+use crate::{event::EventManager, store::ResourceContainer, utils::error::EmarkError};
+
+use super::{Access, ErrorPolicy, System};
+
+/// Strategy executing the systems of a [Schedule](crate::system::Schedule).
+///
+/// Executors must preserve the order of conflicting systems, see [Access](crate::system::Access).
+/// `dependencies` lists, for each system, the indices of the systems that must have finished
+/// before it starts, as declared by their [SystemOptions](crate::system::SystemOptions).
+///
+/// Executors return the errors of the failed systems with their index, and follow `policy`
+/// to skip the systems depending on a failed system or the remaining systems.
+pub trait Executor: Send + Sync {
+    fn run(
+        &mut self,
+        systems: &mut [Box<dyn System>],
+        dependencies: &[Vec<usize>],
+        container: &ResourceContainer,
+        event_manager: &EventManager,
+        policy: ErrorPolicy,
+    ) -> Vec<(usize, EmarkError)>;
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+/// Executor running the systems one after the other on the calling thread.
+///
+/// The order of execution is fully specified, so runs are reproducible: the systems of a
+/// stage run in their sorted order, which respects their ordering constraints and otherwise
+/// keeps the order they were added in. No thread is ever spawned, which makes this executor
+/// suitable for targets without threads such as WASM.
+pub struct SequentialExecutor;
+
+impl Executor for SequentialExecutor {
+    fn run(
+        &mut self,
+        systems: &mut [Box<dyn System>],
+        dependencies: &[Vec<usize>],
+        container: &ResourceContainer,
+        event_manager: &EventManager,
+        policy: ErrorPolicy,
+    ) -> Vec<(usize, EmarkError)> {
+        let mut failures = Vec::new();
+        let mut halted = vec![false; systems.len()];
+        for (index, system) in systems.iter_mut().enumerate() {
+            if policy.skips(&dependencies[index], &halted) {
+                halted[index] = true;
+                continue;
+            }
+            if let Err(error) = system.run(container, event_manager) {
+                failures.push((index, error));
+                halted[index] = true;
+                if policy.aborts() {
+                    break;
+                }
+            }
+        }
+        failures
+    }
+}
+
+/// Pool of threads running the workers of a [ParallelExecutor].
+///
+/// Implementing this trait lets the executor share the threads of a pool already present in
+/// the process, such as a rayon or tokio pool, instead of spawning its own.
+pub trait ThreadPool: Send + Sync {
+    /// Maximum number of workers running concurrently.
+    fn threads(&self) -> usize;
+
+    /// Runs `worker` `count` times, concurrently if possible, and blocks until every call
+    /// returned.
+    ///
+    /// A worker returns once every system of the run has finished, so the calls are allowed
+    /// to run one after the other when the pool is busy.
+    fn run_workers(&self, count: usize, worker: &(dyn Fn() + Sync));
+}
+
+#[derive(Debug, Clone)]
+/// [ThreadPool] spawning scoped threads for each run.
+///
+/// This is the pool of [ParallelExecutor::new], using as many threads as the available
+/// parallelism.
+pub struct ScopedThreadPool {
+    threads: usize,
+    name: Option<String>,
+    stack_size: Option<usize>,
+}
+
+impl Default for ScopedThreadPool {
+    fn default() -> Self {
+        Self::new(std::thread::available_parallelism().map_or(1, NonZeroUsize::get))
+    }
+}
+
+impl ScopedThreadPool {
+    /// Creates a pool of `threads` threads, at least one.
+    pub fn new(threads: usize) -> Self {
+        Self {
+            threads: threads.max(1),
+            name: None,
+            stack_size: None,
+        }
+    }
+
+    /// Names the threads `{prefix}-{index}`.
+    pub fn with_name(mut self, prefix: impl Into<String>) -> Self {
+        self.name = Some(prefix.into());
+        self
+    }
+
+    /// Sets the stack size of the threads, in bytes.
+    pub fn with_stack_size(mut self, size: usize) -> Self {
+        self.stack_size = Some(size);
+        self
+    }
+}
+
+impl ThreadPool for ScopedThreadPool {
+    fn threads(&self) -> usize {
+        self.threads
+    }
+
+    fn run_workers(&self, count: usize, worker: &(dyn Fn() + Sync)) {
+        std::thread::scope(|scope| {
+            for index in 0..count {
+                let mut builder = std::thread::Builder::new();
+                if let Some(name) = &self.name {
+                    builder = builder.name(format!("{name}-{index}"));
+                }
+                if let Some(size) = self.stack_size {
+                    builder = builder.stack_size(size);
+                }
+                builder
+                    .spawn_scoped(scope, worker)
+                    .expect("failed to spawn a worker thread");
+            }
+        });
+    }
+}
+
+#[derive(Clone)]
+/// Executor running non-conflicting systems concurrently.
+///
+/// A system starts once every conflicting system added before it and every system it
+/// is ordered after have finished, so conflicting systems run in the order they were
+/// added while the others are spread across the workers of a [ThreadPool].
+///
+/// Systems restricted to the main thread, see [Access::is_main_thread], run on the thread
+/// calling the executor while the workers run the other systems.
+///
+/// With the `unsync` feature, the resources are only borrowed from the thread creating them,
+/// so schedules run with a [SequentialExecutor] instead.
+///
+/// # Examples
+/// ```
+/// use emark::system::{ParallelExecutor, Schedule, ScopedThreadPool};
+///
+/// let mut schedule = Schedule::new();
+/// schedule.set_executor(ParallelExecutor::with_pool(
+///     ScopedThreadPool::new(4).with_name("emark-worker"),
+/// ));
+/// ```
+pub struct ParallelExecutor {
+    pool: Arc<dyn ThreadPool>,
+}
+
+impl Default for ParallelExecutor {
+    fn default() -> Self {
+        Self::with_pool(ScopedThreadPool::default())
+    }
+}
+
+impl Debug for ParallelExecutor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ParallelExecutor")
+            .field("threads", &self.threads())
+            .finish()
+    }
+}
+
+// progress of a parallel run
+struct Progress {
+    ready: VecDeque<usize>,
+    // ready systems restricted to the thread running the executor
+    ready_main: VecDeque<usize>,
+    remaining: Vec<usize>,
+    finished: usize,
+    panic: Option<Box<dyn Any + Send>>,
+    // systems failed or skipped
+    halted: Vec<bool>,
+    aborted: bool,
+    failures: Vec<(usize, EmarkError)>,
+}
+
+impl ParallelExecutor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates an executor spawning `threads` scoped threads for each run.
+    pub fn with_threads(threads: usize) -> Self {
+        Self::with_pool(ScopedThreadPool::new(threads))
+    }
+
+    /// Creates an executor running its workers on `pool`.
+    pub fn with_pool(pool: impl ThreadPool + 'static) -> Self {
+        Self {
+            pool: Arc::new(pool),
+        }
+    }
+
+    /// Number of worker threads.
+    pub fn threads(&self) -> usize {
+        self.pool.threads()
+    }
+}
+
+impl Executor for ParallelExecutor {
+    fn run(
+        &mut self,
+        systems: &mut [Box<dyn System>],
+        dependencies: &[Vec<usize>],
+        container: &ResourceContainer,
+        event_manager: &EventManager,
+        policy: ErrorPolicy,
+    ) -> Vec<(usize, EmarkError)> {
+        let count = systems.len();
+
+        // build conflict graph, each system depends on the conflicting systems before it
+        // and on the systems it is ordered after, which are always sorted before it
+        let accesses = systems
+            .iter()
+            .map(|system| system.access())
+            .collect::<Vec<_>>();
+        let mut dependents = vec![Vec::new(); count];
+        let mut remaining = vec![0; count];
+        for later in 0..count {
+            for earlier in 0..later {
+                if accesses[earlier].conflicts_with(&accesses[later])
+                    || dependencies[later].contains(&earlier)
+                {
+                    dependents[earlier].push(later);
+                    remaining[later] += 1;
+                }
+            }
+        }
+
+        let main_thread = accesses
+            .iter()
+            .map(Access::is_main_thread)
+            .collect::<Vec<_>>();
+        let (ready_main, ready) = (0..count)
+            .filter(|&index| remaining[index] == 0)
+            .partition(|&index| main_thread[index]);
+        let progress = Mutex::new(Progress {
+            ready,
+            ready_main,
+            remaining,
+            finished: 0,
+            panic: None,
+            halted: vec![false; count],
+            aborted: false,
+            failures: Vec::new(),
+        });
+        let condvar = Condvar::new();
+        let systems = systems.iter_mut().map(Mutex::new).collect::<Vec<_>>();
+
+        // the main worker only runs the systems restricted to the thread running the executor
+        let worker = |main: bool| loop {
+            // wait for a ready system, the systems it depends on have all finished
+            let (index, skip) = {
+                let mut progress = progress.lock();
+                loop {
+                    if progress.finished == count {
+                        return;
+                    }
+                    let ready = match main {
+                        true => &mut progress.ready_main,
+                        false => &mut progress.ready,
+                    };
+                    if let Some(index) = ready.pop_front() {
+                        let skip = progress.aborted
+                            || policy.skips(&dependencies[index], &progress.halted);
+                        break (index, skip);
+                    }
+                    condvar.wait(&mut progress);
+                }
+            };
+
+            // run system, keeping the other workers alive if it panics
+            let result = match skip {
+                true => Ok(Ok(())),
+                false => panic::catch_unwind(AssertUnwindSafe(|| {
+                    systems[index].lock().run(container, event_manager)
+                })),
+            };
+
+            // release dependents
+            let mut progress = progress.lock();
+            progress.finished += 1;
+            progress.halted[index] = skip;
+            match result {
+                Ok(Ok(())) => {}
+                Ok(Err(error)) => {
+                    progress.failures.push((index, error));
+                    progress.halted[index] = true;
+                    progress.aborted |= policy.aborts();
+                }
+                Err(payload) => {
+                    progress.panic.get_or_insert(payload);
+                }
+            }
+            for &dependent in &dependents[index] {
+                progress.remaining[dependent] -= 1;
+                if progress.remaining[dependent] == 0 {
+                    match main_thread[dependent] {
+                        true => progress.ready_main.push_back(dependent),
+                        false => progress.ready.push_back(dependent),
+                    }
+                }
+            }
+            condvar.notify_all();
+        };
+        let workers = self.threads().min(count);
+        match main_thread.contains(&true) {
+            true => std::thread::scope(|scope| {
+                scope.spawn(|| self.pool.run_workers(workers, &|| worker(false)));
+                worker(true);
+            }),
+            false => self.pool.run_workers(workers, &|| worker(false)),
+        }
+
+        // propagate the first panic of a system
+        let progress = progress.into_inner();
+        if let Some(payload) = progress.panic {
+            panic::resume_unwind(payload);
+        }
+        progress.failures
+    }
+}
+
+#[cfg(test)]
+mod test_executor {
+    use std::sync::Arc;
+    #[cfg(not(feature = "unsync"))]
+    use std::{sync::Barrier, time::Duration};
+
+    use super::*;
+    #[cfg(not(feature = "unsync"))]
+    use crate::system::SystemOptions;
+    use crate::{
+        store::{Container, Res, ResMut, Retriever},
+        system::{IntoSystem, Schedule},
+    };
+
+    #[cfg(not(feature = "unsync"))]
+    #[test]
+    fn test_parallel_executor_concurrent() {
+        // both systems only read, they must run concurrently to pass the barrier
+        let barrier = Arc::new(Barrier::new(2));
+        let mut schedule = Schedule::new();
+        schedule.set_executor(ParallelExecutor::with_threads(2));
+        for _ in 0..2 {
+            let barrier = barrier.clone();
+            schedule.add_system(move |_: Res<u32>| {
+                barrier.wait();
+            });
+        }
+
+        let mut container = ResourceContainer::default();
+        container.add_resource(0u32);
+        schedule.run(&container, &EventManager::new());
+    }
+
+    #[cfg(not(feature = "unsync"))]
+    #[test]
+    fn test_parallel_executor_main_thread() {
+        use std::rc::Rc;
+
+        use crate::store::NonSend;
+
+        // the main thread system must run concurrently with a worker to pass the barrier
+        let barrier = Arc::new(Barrier::new(2));
+        let main = std::thread::current().id();
+        let mut schedule = Schedule::new();
+        schedule.set_executor(ParallelExecutor::with_threads(2));
+        let main_barrier = barrier.clone();
+        schedule
+            .add_system(move |_: NonSend<Rc<u32>>| {
+                main_barrier.wait();
+                assert_eq!(std::thread::current().id(), main);
+            })
+            .add_system(move |_: Res<u32>| {
+                barrier.wait();
+                assert_ne!(std::thread::current().id(), main);
+            });
+
+        let mut container = ResourceContainer::default();
+        container.add_resource(0u32);
+        container.add_non_send(Rc::new(0u32));
+        schedule.run(&container, &EventManager::new());
+    }
+
+    #[cfg(not(feature = "unsync"))]
+    #[test]
+    fn test_parallel_executor_conflict_order() {
+        let mut schedule = Schedule::new();
+        schedule.set_executor(ParallelExecutor::with_threads(4));
+        schedule
+            .add_system(|mut log: ResMut<Vec<u32>>| {
+                std::thread::sleep(Duration::from_millis(10));
+                log.push(0);
+            })
+            .add_system(|mut log: ResMut<Vec<u32>>| log.push(1))
+            .add_system(|_: Res<u64>| {})
+            .add_system(|mut log: ResMut<Vec<u32>>, _: Res<u64>| log.push(2));
+
+        // assert conflicting systems keep their order
+        let mut container = ResourceContainer::default();
+        container.add_resource(Vec::<u32>::new());
+        container.add_resource(0u64);
+        schedule.run(&container, &EventManager::new());
+        assert_eq!(*Res::<Vec<u32>>::retrieve(&container), vec![0, 1, 2]);
+    }
+
+    #[cfg(not(feature = "unsync"))]
+    #[test]
+    fn test_parallel_executor_explicit_order() {
+        let mut schedule = Schedule::new();
+        schedule.set_executor(ParallelExecutor::with_threads(4));
+        let order = Arc::new(Mutex::new(Vec::new()));
+        for (label, after) in [("render", "physics"), ("physics", "input")] {
+            let order = order.clone();
+            schedule.add_system_with(
+                move |_: Res<u32>| order.lock().push(label),
+                SystemOptions::labeled(label).with_after(after),
+            );
+        }
+        let input_order = order.clone();
+        schedule.add_system_with(
+            move |_: Res<u32>| {
+                std::thread::sleep(Duration::from_millis(10));
+                input_order.lock().push("input");
+            },
+            SystemOptions::labeled("input"),
+        );
+
+        // assert non-conflicting systems keep their explicit order
+        let mut container = ResourceContainer::default();
+        container.add_resource(0u32);
+        schedule.run(&container, &EventManager::new());
+        assert_eq!(*order.lock(), vec!["input", "physics", "render"]);
+    }
+
+    #[test]
+    fn test_parallel_executor_pool() {
+        // pool running its workers one after the other on the calling thread
+        struct InlinePool;
+        impl ThreadPool for InlinePool {
+            fn threads(&self) -> usize {
+                3
+            }
+
+            fn run_workers(&self, count: usize, worker: &(dyn Fn() + Sync)) {
+                for _ in 0..count {
+                    worker();
+                }
+            }
+        }
+
+        let mut schedule = Schedule::new();
+        schedule.set_executor(ParallelExecutor::with_pool(InlinePool));
+        let thread = std::thread::current().id();
+        for _ in 0..3 {
+            schedule.add_system(move |mut log: ResMut<Vec<u32>>, _: Res<u64>| {
+                assert_eq!(std::thread::current().id(), thread);
+                let next = log.len() as u32;
+                log.push(next);
+            });
+            schedule.add_system(|_: Res<u64>| {});
+        }
+
+        let mut container = ResourceContainer::default();
+        container.add_resource(Vec::<u32>::new());
+        container.add_resource(0u64);
+        schedule.run(&container, &EventManager::new());
+        assert_eq!(*Res::<Vec<u32>>::retrieve(&container), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn test_scoped_thread_pool_name() {
+        let names = Mutex::new(Vec::new());
+        let pool = ScopedThreadPool::new(2).with_name("worker");
+        pool.run_workers(2, &|| {
+            names
+                .lock()
+                .push(std::thread::current().name().map(str::to_owned));
+        });
+
+        let mut names = names.into_inner();
+        names.sort();
+        let expected = vec![Some("worker-0".to_owned()), Some("worker-1".to_owned())];
+        assert_eq!(names, expected);
+    }
+
+    #[test]
+    fn test_parallel_executor_skip_dependents() {
+        fn fail() -> Result<(), EmarkError> {
+            Err(EmarkError::Custom("failed".to_owned()))
+        }
+
+        let ran = Arc::new(parking_lot::Mutex::new(Vec::new()));
+        let record = |index: usize| {
+            let ran = ran.clone();
+            Box::new(move |_: &ResourceContainer, _: &EventManager| ran.lock().push(index))
+                as Box<dyn System>
+        };
+        let mut systems = vec![
+            Box::new(fail.into_system()),
+            record(1),
+            record(2),
+            record(3),
+        ];
+
+        // system 2 runs after the failed system 0, system 3 after the skipped system 2
+        let mut executor = ParallelExecutor::with_threads(2);
+        let failures = executor.run(
+            &mut systems,
+            &[vec![], vec![], vec![0], vec![2]],
+            &ResourceContainer::default(),
+            &EventManager::new(),
+            ErrorPolicy::SkipDependents,
+        );
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].0, 0);
+        assert_eq!(*ran.lock(), vec![1]);
+    }
+
+    #[test]
+    #[should_panic(expected = "system panicked")]
+    fn test_parallel_executor_panic() {
+        fn panics() {
+            panic!("system panicked");
+        }
+
+        let mut executor = ParallelExecutor::with_threads(2);
+        let mut systems: Vec<Box<dyn System>> = vec![
+            Box::new(panics.into_system()),
+            Box::new((|| {}).into_system()),
+        ];
+        executor.run(
+            &mut systems,
+            &[vec![], vec![]],
+            &ResourceContainer::default(),
+            &EventManager::new(),
+            ErrorPolicy::default(),
+        );
+    }
+}
@@ -0,0 +1,598 @@
+use std::{
+    borrow::Cow,
+    marker::PhantomData,
+    ops::{Deref, DerefMut},
+};
+
+use crate::{
+    event::EventManager,
+    store::{ResourceContainer, RetrievalPlan, Retriever, SystemTicks},
+    utils::error::EmarkError,
+};
+
+use super::{Access, System, SystemResult};
+
+/// Conversion into a [System].
+///
+/// Implemented for every system and for functions of up to 16 [Retriever]
+/// parameters, such as `fn(Res<A>, ResMut<B>)`, returning a [SystemResult]. The parameters of
+/// a function are retrieved together as a tuple, so their resources are locked in sorted order.
+///
+/// `Marker` only distinguishes the implementations and is inferred.
+pub trait IntoSystem<Marker> {
+    type System: System + 'static;
+
+    fn into_system(self) -> Self::System;
+}
+
+impl<S: System + 'static> IntoSystem<()> for S {
+    type System = S;
+
+    fn into_system(self) -> Self::System {
+        self
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+/// Output of the previous system of a pipe, see [SystemFunction::pipe].
+///
+/// Must be the first parameter of the function.
+pub struct In<T>(pub T);
+
+impl<T> In<T> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> Deref for In<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for In<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+/// A function whose parameters are retrieved from the `ResourceContainer`.
+///
+/// Implemented for functions of up to 16 [Retriever] parameters, optionally preceded by an
+/// [In] parameter receiving the output of the previous system of a pipe. `Marker` only
+/// distinguishes the implementations and is inferred.
+pub trait SystemFunction<Marker>: Send + Sync + 'static {
+    /// Tuple of the retrieved parameters.
+    type Params: Retriever;
+    /// Value received through the [In] parameter, `()` without one.
+    type Input;
+    /// Value returned by the function.
+    type Output;
+
+    /// Retrieves the parameters and calls the function, reporting changes since
+    /// `ticks.last_run()`.
+    fn call(
+        &mut self,
+        input: Self::Input,
+        container: &ResourceContainer,
+        ticks: SystemTicks,
+    ) -> Self::Output;
+
+    #[doc(hidden)]
+    /// [SystemFunction::call] retrieving the parameters with the plan of `Self::Params`.
+    fn call_planned(
+        &mut self,
+        input: Self::Input,
+        container: &ResourceContainer,
+        ticks: SystemTicks,
+        _plan: &RetrievalPlan,
+    ) -> Self::Output {
+        self.call(input, container, ticks)
+    }
+
+    /// Name of the function, used as name of its system.
+    fn name(&self) -> Cow<'static, str> {
+        Cow::Borrowed(std::any::type_name::<Self>())
+    }
+
+    /// Feeds the output of this function into the [In] parameter of `next`.
+    ///
+    /// Both functions run one after the other as a single system, each retrieving its own
+    /// parameters, so a chain of functions such as validation, action and error reporting
+    /// needs no intermediate event. Pipes can be piped again.
+    ///
+    /// # Examples
+    /// ```
+    /// use emark::event::EventManager;
+    /// use emark::prelude::*;
+    /// use emark::store::ResourceContainer;
+    /// use emark::system::{In, SystemFunction};
+    ///
+    /// fn validate(value: Res<i32>) -> Result<i32, String> {
+    ///     match *value >= 0 {
+    ///         true => Ok(*value),
+    ///         false => Err(format!("negative value {}", *value)),
+    ///     }
+    /// }
+    ///
+    /// fn report(In(result): In<Result<i32, String>>, mut errors: ResMut<Vec<String>>) {
+    ///     if let Err(error) = result {
+    ///         errors.push(error);
+    ///     }
+    /// }
+    ///
+    /// let mut schedule = Schedule::new();
+    /// schedule.add_system(validate.pipe(report));
+    ///
+    /// let mut container = ResourceContainer::default();
+    /// container.add_resource(-1i32);
+    /// container.add_resource(Vec::<String>::new());
+    /// schedule.run(&container, &EventManager::new());
+    /// assert_eq!(Res::<Vec<String>>::retrieve(&container).len(), 1);
+    /// ```
+    fn pipe<Next, NextMarker>(self, next: Next) -> Pipe<Self, Next, Marker, NextMarker>
+    where
+        Self: Sized,
+        Next: SystemFunction<NextMarker, Input = Self::Output>,
+    {
+        Pipe {
+            first: self,
+            second: next,
+            _marker: PhantomData,
+        }
+    }
+}
+
+#[doc(hidden)]
+pub struct InputMarker;
+
+#[doc(hidden)]
+pub struct PipeMarker;
+
+/// Two functions piped together, see [SystemFunction::pipe].
+pub struct Pipe<First, Second, FirstMarker, SecondMarker> {
+    first: First,
+    second: Second,
+    _marker: PhantomData<fn() -> (FirstMarker, SecondMarker)>,
+}
+
+impl<First, Second, FirstMarker, SecondMarker>
+    SystemFunction<(PipeMarker, FirstMarker, SecondMarker)>
+    for Pipe<First, Second, FirstMarker, SecondMarker>
+where
+    First: SystemFunction<FirstMarker>,
+    Second: SystemFunction<SecondMarker, Input = First::Output>,
+    FirstMarker: 'static,
+    SecondMarker: 'static,
+{
+    type Params = (First::Params, Second::Params);
+    type Input = First::Input;
+    type Output = Second::Output;
+
+    fn call(
+        &mut self,
+        input: Self::Input,
+        container: &ResourceContainer,
+        ticks: SystemTicks,
+    ) -> Self::Output {
+        // the resources of the first function are released before the second retrieves its own
+        let output = self.first.call(input, container, ticks);
+        self.second.call(output, container, ticks)
+    }
+
+    fn name(&self) -> Cow<'static, str> {
+        Cow::Owned(format!("{} | {}", self.first.name(), self.second.name()))
+    }
+}
+
+#[doc(hidden)]
+pub struct FunctionMarker;
+
+/// A [System] running a [SystemFunction].
+///
+/// Every run advances the change tick of the container, so that the function observes the
+/// changes made since its previous run, see [SystemTicks].
+pub struct FunctionSystem<F, Marker> {
+    function: F,
+    name: Cow<'static, str>,
+    last_run: u64,
+    // locking order of the parameters, sorted once rather than on every run
+    plan: RetrievalPlan,
+    _marker: PhantomData<fn() -> Marker>,
+}
+
+impl<F, Marker> System for FunctionSystem<F, Marker>
+where
+    F: SystemFunction<Marker, Input = ()>,
+    F::Output: SystemResult,
+    Marker: 'static,
+{
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn run(&mut self, container: &ResourceContainer, _: &EventManager) -> Result<(), EmarkError> {
+        let this_run = container.clock().advance();
+        let ticks = SystemTicks::new(self.last_run, this_run);
+        self.last_run = this_run;
+        self.function
+            .call_planned((), container, ticks, &self.plan)
+            .into_result()
+    }
+
+    fn access(&self) -> Access {
+        F::Params::access()
+    }
+}
+
+impl<F, Marker> IntoSystem<(FunctionMarker, Marker)> for F
+where
+    F: SystemFunction<Marker, Input = ()>,
+    F::Output: SystemResult,
+    Marker: 'static,
+{
+    type System = FunctionSystem<F, Marker>;
+
+    fn into_system(self) -> Self::System {
+        FunctionSystem {
+            name: self.name(),
+            function: self,
+            last_run: 0,
+            plan: RetrievalPlan::new::<F::Params>(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+macro_rules! impl_system_function {
+    ($(($param:ident, $value:ident)),*) => {
+        impl<Func, Out, $($param: Retriever),*> SystemFunction<fn($($param),*) -> Out> for Func
+        where
+            Func: Send + Sync + 'static,
+            for<'a> &'a mut Func: FnMut($($param),*) -> Out + FnMut($($param::Item<'_>),*) -> Out,
+        {
+            type Params = ($($param,)*);
+            type Input = ();
+            type Output = Out;
+
+            fn call(&mut self, _: (), container: &ResourceContainer, ticks: SystemTicks) -> Out {
+                let plan = RetrievalPlan::cached::<Self::Params>();
+                self.call_planned((), container, ticks, &plan)
+            }
+
+            fn call_planned(
+                &mut self,
+                _: (),
+                container: &ResourceContainer,
+                ticks: SystemTicks,
+                plan: &RetrievalPlan,
+            ) -> Out {
+                // call through a generic function to select the retrieved signature
+                #[allow(clippy::too_many_arguments)]
+                fn call_inner<Out, $($param),*>(
+                    mut function: impl FnMut($($param),*) -> Out,
+                    $($value: $param),*
+                ) -> Out {
+                    function($($value),*)
+                }
+
+                let ($($value,)*) =
+                    <($($param,)*) as Retriever>::try_retrieve_planned(container, ticks, plan)
+                        .unwrap_or_else(|error| panic!("{error}"));
+                call_inner(self, $($value),*)
+            }
+        }
+
+        impl<Func, Input, Out, $($param: Retriever),*>
+            SystemFunction<(InputMarker, fn(In<Input>, $($param),*) -> Out)> for Func
+        where
+            Func: Send + Sync + 'static,
+            for<'a> &'a mut Func:
+                FnMut(In<Input>, $($param),*) -> Out + FnMut(In<Input>, $($param::Item<'_>),*) -> Out,
+        {
+            type Params = ($($param,)*);
+            type Input = Input;
+            type Output = Out;
+
+            fn call(
+                &mut self,
+                input: Input,
+                container: &ResourceContainer,
+                ticks: SystemTicks,
+            ) -> Out {
+                let plan = RetrievalPlan::cached::<Self::Params>();
+                self.call_planned(input, container, ticks, &plan)
+            }
+
+            fn call_planned(
+                &mut self,
+                input: Input,
+                container: &ResourceContainer,
+                ticks: SystemTicks,
+                plan: &RetrievalPlan,
+            ) -> Out {
+                // call through a generic function to select the retrieved signature
+                #[allow(clippy::too_many_arguments)]
+                fn call_inner<Input, Out, $($param),*>(
+                    mut function: impl FnMut(In<Input>, $($param),*) -> Out,
+                    input: In<Input>,
+                    $($value: $param),*
+                ) -> Out {
+                    function(input, $($value),*)
+                }
+
+                let ($($value,)*) =
+                    <($($param,)*) as Retriever>::try_retrieve_planned(container, ticks, plan)
+                        .unwrap_or_else(|error| panic!("{error}"));
+                call_inner(self, In(input), $($value),*)
+            }
+        }
+    };
+}
+
+impl_system_function!();
+impl_system_function!((A, a));
+impl_system_function!((A, a), (B, b));
+impl_system_function!((A, a), (B, b), (C, c));
+impl_system_function!((A, a), (B, b), (C, c), (D, d));
+impl_system_function!((A, a), (B, b), (C, c), (D, d), (E, e));
+impl_system_function!((A, a), (B, b), (C, c), (D, d), (E, e), (F, f));
+impl_system_function!((A, a), (B, b), (C, c), (D, d), (E, e), (F, f), (G, g));
+impl_system_function!(
+    (A, a),
+    (B, b),
+    (C, c),
+    (D, d),
+    (E, e),
+    (F, f),
+    (G, g),
+    (H, h)
+);
+impl_system_function!(
+    (A, a),
+    (B, b),
+    (C, c),
+    (D, d),
+    (E, e),
+    (F, f),
+    (G, g),
+    (H, h),
+    (I, i)
+);
+impl_system_function!(
+    (A, a),
+    (B, b),
+    (C, c),
+    (D, d),
+    (E, e),
+    (F, f),
+    (G, g),
+    (H, h),
+    (I, i),
+    (J, j)
+);
+impl_system_function!(
+    (A, a),
+    (B, b),
+    (C, c),
+    (D, d),
+    (E, e),
+    (F, f),
+    (G, g),
+    (H, h),
+    (I, i),
+    (J, j),
+    (K, k)
+);
+impl_system_function!(
+    (A, a),
+    (B, b),
+    (C, c),
+    (D, d),
+    (E, e),
+    (F, f),
+    (G, g),
+    (H, h),
+    (I, i),
+    (J, j),
+    (K, k),
+    (L, l)
+);
+impl_system_function!(
+    (A, a),
+    (B, b),
+    (C, c),
+    (D, d),
+    (E, e),
+    (F, f),
+    (G, g),
+    (H, h),
+    (I, i),
+    (J, j),
+    (K, k),
+    (L, l),
+    (M, m)
+);
+impl_system_function!(
+    (A, a),
+    (B, b),
+    (C, c),
+    (D, d),
+    (E, e),
+    (F, f),
+    (G, g),
+    (H, h),
+    (I, i),
+    (J, j),
+    (K, k),
+    (L, l),
+    (M, m),
+    (N, n)
+);
+impl_system_function!(
+    (A, a),
+    (B, b),
+    (C, c),
+    (D, d),
+    (E, e),
+    (F, f),
+    (G, g),
+    (H, h),
+    (I, i),
+    (J, j),
+    (K, k),
+    (L, l),
+    (M, m),
+    (N, n),
+    (O, o)
+);
+impl_system_function!(
+    (A, a),
+    (B, b),
+    (C, c),
+    (D, d),
+    (E, e),
+    (F, f),
+    (G, g),
+    (H, h),
+    (I, i),
+    (J, j),
+    (K, k),
+    (L, l),
+    (M, m),
+    (N, n),
+    (O, o),
+    (P, p)
+);
+
+#[cfg(test)]
+mod test_function {
+    use super::*;
+    use crate::{
+        store::{Container, Res, ResMut},
+        system::Schedule,
+    };
+
+    #[test]
+    fn test_function_system() {
+        fn double(factor: Res<u32>, mut value: ResMut<u64>) {
+            *value *= *factor as u64;
+        }
+
+        let mut schedule = Schedule::new();
+        schedule
+            .add_system(double)
+            .add_system(|mut value: ResMut<u64>| {
+                *value += 1;
+            });
+
+        let mut container = ResourceContainer::default();
+        container.add_resource(2u32);
+        container.add_resource(1u64);
+        let event_manager = EventManager::new();
+        schedule.run(&container, &event_manager);
+        schedule.run(&container, &event_manager);
+        assert_eq!(*Res::<u64>::retrieve(&container), 7);
+    }
+
+    #[test]
+    fn test_function_system_pipe() {
+        fn parse(text: Res<String>) -> Result<u32, std::num::ParseIntError> {
+            text.parse()
+        }
+
+        fn double(In(value): In<Result<u32, std::num::ParseIntError>>) -> Result<u32, String> {
+            value
+                .map(|value| value * 2)
+                .map_err(|error| error.to_string())
+        }
+
+        fn store(In(result): In<Result<u32, String>>, mut log: ResMut<Vec<Result<u32, String>>>) {
+            log.push(result);
+        }
+
+        let mut system = parse.pipe(double).pipe(store).into_system();
+        let access = system.access();
+        assert_eq!(access.reads(), &[std::any::TypeId::of::<String>()]);
+        assert_eq!(
+            access.writes(),
+            &[std::any::TypeId::of::<Vec<Result<u32, String>>>()]
+        );
+
+        let mut container = ResourceContainer::default();
+        container.add_resource("21".to_owned());
+        container.add_resource(Vec::<Result<u32, String>>::new());
+        system.run(&container, &EventManager::new()).unwrap();
+        *ResMut::<String>::retrieve(&container) = "nan".to_owned();
+        system.run(&container, &EventManager::new()).unwrap();
+
+        let log = Res::<Vec<Result<u32, String>>>::retrieve(&container);
+        assert_eq!(log[0], Ok(42));
+        assert!(log[1].is_err());
+    }
+
+    #[test]
+    fn test_function_system_many_params() {
+        #[allow(clippy::too_many_arguments)]
+        fn sum(
+            a: Res<u8>,
+            b: Res<u16>,
+            c: Res<u32>,
+            d: Res<u64>,
+            e: Res<i8>,
+            f: Res<i16>,
+            g: Res<i32>,
+            h: Res<i64>,
+            i: Res<usize>,
+            j: Res<isize>,
+            k: Res<u128>,
+            l: Res<i128>,
+            m: Res<f32>,
+            n: Res<f64>,
+            o: Res<bool>,
+            mut p: ResMut<String>,
+        ) {
+            let total = *a as f64
+                + *b as f64
+                + *c as f64
+                + *d as f64
+                + *e as f64
+                + *f as f64
+                + *g as f64
+                + *h as f64
+                + *i as f64
+                + *j as f64
+                + *k as f64
+                + *l as f64
+                + *m as f64
+                + *n
+                + *o as u8 as f64;
+            *p = total.to_string();
+        }
+
+        let mut container = ResourceContainer::default();
+        container.add_resource(1u8);
+        container.add_resource(1u16);
+        container.add_resource(1u32);
+        container.add_resource(1u64);
+        container.add_resource(1i8);
+        container.add_resource(1i16);
+        container.add_resource(1i32);
+        container.add_resource(1i64);
+        container.add_resource(1usize);
+        container.add_resource(1isize);
+        container.add_resource(1u128);
+        container.add_resource(1i128);
+        container.add_resource(1f32);
+        container.add_resource(1f64);
+        container.add_resource(true);
+        container.add_resource(String::new());
+
+        let mut system = sum.into_system();
+        system.run(&container, &EventManager::new()).unwrap();
+        assert_eq!(*Res::<String>::retrieve(&container), "15");
+    }
+}
@@ -0,0 +1,378 @@
+use std::{
+    ffi::{c_char, c_void, CStr, CString},
+    fs,
+    os::unix::ffi::OsStrExt,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::SystemTime,
+};
+
+use parking_lot::{RwLock, RwLockUpgradableReadGuard};
+
+use crate::{
+    event::{Event, EventManager},
+    store::ResourceContainer,
+    utils::error::EmarkError,
+};
+
+use super::System;
+
+/// Version of the ABI between emark and hot reloaded libraries.
+///
+/// Libraries export it with [export_hot_reload_abi](crate::export_hot_reload_abi), and are
+/// rejected when built against another ABI version or another version of emark.
+pub const HOT_RELOAD_ABI_VERSION: u32 = 1;
+
+#[doc(hidden)]
+pub const EMARK_VERSION: &CStr =
+    match CStr::from_bytes_with_nul(concat!(env!("CARGO_PKG_VERSION"), "\0").as_bytes()) {
+        Ok(version) => version,
+        Err(_) => panic!("invalid emark version"),
+    };
+
+/// Exports the ABI version of a hot reloaded library.
+///
+/// Must be invoked once in the `cdylib` exporting systems and handlers.
+#[macro_export]
+macro_rules! export_hot_reload_abi {
+    () => {
+        #[no_mangle]
+        pub extern "C" fn emark_hot_reload_abi() -> u32 {
+            $crate::system::hot_reload::HOT_RELOAD_ABI_VERSION
+        }
+
+        #[no_mangle]
+        pub extern "C" fn emark_hot_reload_version() -> *const ::std::ffi::c_char {
+            $crate::system::hot_reload::EMARK_VERSION.as_ptr()
+        }
+    };
+}
+
+// number of copies loaded by the process, keeps the paths of the copies unique
+static COPIES: AtomicUsize = AtomicUsize::new(0);
+
+// handle of a loaded copy of a library, never closed as resources and events may still point to
+// its code and statics after a reload, e.g. through vtables
+struct Loaded {
+    handle: *mut c_void,
+    copy: PathBuf,
+}
+
+// the handle is only used to look up symbols
+unsafe impl Send for Loaded {}
+unsafe impl Sync for Loaded {}
+
+impl Loaded {
+    fn symbol(&self, symbol: &CStr) -> Option<*mut c_void> {
+        let address = unsafe { libc::dlsym(self.handle, symbol.as_ptr()) };
+        (!address.is_null()).then_some(address)
+    }
+}
+
+// the copy stays mapped once removed
+impl Drop for Loaded {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.copy);
+    }
+}
+
+struct LibraryState {
+    loaded: Loaded,
+    modified: SystemTime,
+    generation: usize,
+    error: Option<EmarkError>,
+}
+
+/// Dynamic library whose systems and handlers are reloaded when its file changes.
+///
+/// The library is a `cdylib` built against the same version of emark, invoking
+/// [export_hot_reload_abi](crate::export_hot_reload_abi) and exporting its systems and
+/// handlers as `#[no_mangle]` Rust functions. The file is checked before each call and
+/// reloaded once modified, while the resources in the `ResourceContainer` are kept.
+///
+/// Each reload loads a new copy of the library, the previous copies staying loaded until the
+/// process exits, as the resources and events may still refer to their code or statics. A long
+/// session of reloads grows the memory usage accordingly.
+///
+/// # Examples
+/// Library:
+/// ```
+/// use emark::event::EventManager;
+/// use emark::store::ResourceContainer;
+///
+/// emark::export_hot_reload_abi!();
+///
+/// #[no_mangle]
+/// pub fn update(container: &ResourceContainer, event_manager: &EventManager) {}
+/// ```
+///
+/// Application:
+/// ```no_run
+/// use emark::prelude::*;
+/// use emark::system::hot_reload::HotLibrary;
+///
+/// // the library is built from the crate above, exporting `update` with this signature
+/// let library = unsafe { HotLibrary::open("target/debug/libgame.so") }.unwrap();
+/// let mut schedule = Schedule::new();
+/// schedule.add_system(unsafe { library.system("update") }.unwrap());
+/// ```
+#[derive(Clone)]
+pub struct HotLibrary {
+    path: PathBuf,
+    state: Arc<RwLock<LibraryState>>,
+}
+
+impl HotLibrary {
+    /// Loads the library at `path`.
+    ///
+    /// Returns an error if the library can not be loaded or was built against another ABI.
+    ///
+    /// # Safety
+    /// Loading the library runs its initialization routines, as does each reload. The library
+    /// and its reloaded copies must be built with the same compiler and against the same
+    /// version of emark as the application, the ABI version only rejecting the libraries built
+    /// against another version of emark.
+    pub unsafe fn open(path: impl AsRef<Path>) -> Result<Self, EmarkError> {
+        let path = path.as_ref().to_path_buf();
+        let (loaded, modified) = load(&path)?;
+        Ok(Self {
+            path,
+            state: Arc::new(RwLock::new(LibraryState {
+                loaded,
+                modified,
+                generation: 0,
+                error: None,
+            })),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Number of times the library has been reloaded.
+    pub fn generation(&self) -> usize {
+        self.state.read().generation
+    }
+
+    /// Takes the error of the last failed reload, unless the library has been reloaded since.
+    pub fn take_error(&self) -> Option<EmarkError> {
+        self.state.write().error.take()
+    }
+
+    /// Reloads the library if its file changed since it was loaded.
+    ///
+    /// Returns `true` if the library has been reloaded, clearing the error of a previous
+    /// reload. On error, the previously loaded library is kept and the reload is attempted
+    /// again on the next call.
+    pub fn reload_if_changed(&self) -> Result<bool, EmarkError> {
+        let state = self.state.upgradable_read();
+        let modified = fs::metadata(&self.path).and_then(|metadata| metadata.modified());
+        if modified.is_ok_and(|modified| modified == state.modified) {
+            return Ok(false);
+        }
+
+        let (loaded, modified) = load(&self.path)?;
+        let mut state = RwLockUpgradableReadGuard::upgrade(state);
+        // the previous copy stays loaded, see `Loaded`
+        state.loaded = loaded;
+        state.modified = modified;
+        state.generation += 1;
+        state.error = None;
+        Ok(true)
+    }
+
+    /// Creates a system calling the function `symbol` of the library.
+    ///
+    /// The function has the signature `fn(&ResourceContainer, &EventManager)`. Returns an
+    /// error if the library does not export `symbol`.
+    ///
+    /// # Safety
+    /// The function `symbol` must have this signature in the library and in each of its
+    /// reloaded copies, as its type can not be checked, and be sound to call from any thread.
+    pub unsafe fn system(&self, symbol: &str) -> Result<HotSystem, EmarkError> {
+        Ok(HotSystem {
+            name: format!("{}::{symbol}", self.path.display()),
+            symbol: self.resolve(symbol)?,
+            library: self.clone(),
+        })
+    }
+
+    /// Creates a handler of event `T` calling the function `symbol` of the library.
+    ///
+    /// The function has the signature `fn(&[T])`. Returns an error if the library does not
+    /// export `symbol`.
+    ///
+    /// # Safety
+    /// The function `symbol` must have this signature in the library and in each of its
+    /// reloaded copies, as its type can not be checked, and be sound to call from any thread.
+    pub unsafe fn handler<T: Event + 'static>(
+        &self,
+        symbol: &str,
+    ) -> Result<impl Fn(&[T]) + Send + Sync + 'static, EmarkError> {
+        let symbol = self.resolve(symbol)?;
+        let library = self.clone();
+        Ok(move |events: &[T]| {
+            library.call(&symbol, |address| {
+                let handler = unsafe { std::mem::transmute::<*mut c_void, fn(&[T])>(address) };
+                handler(events)
+            })
+        })
+    }
+
+    // checks that `symbol` is exported by the loaded library
+    fn resolve(&self, symbol: &str) -> Result<CString, EmarkError> {
+        let missing = || EmarkError::MissingSymbol {
+            path: self.path.display().to_string(),
+            symbol: symbol.to_owned(),
+        };
+        let symbol = CString::new(symbol).map_err(|_| missing())?;
+        match self.state.read().loaded.symbol(&symbol) {
+            Some(_) => Ok(symbol),
+            None => Err(missing()),
+        }
+    }
+
+    // reloads the library if needed and calls `f` with the address of `symbol`
+    fn call<R>(&self, symbol: &CStr, f: impl FnOnce(*mut c_void) -> R) -> R {
+        if let Err(error) = self.reload_if_changed() {
+            self.state.write().error = Some(error);
+        }
+
+        // keep the library loaded during the call
+        let state = self.state.read();
+        let Some(address) = state.loaded.symbol(symbol) else {
+            panic!(
+                "symbol `{}` is missing from library `{}`",
+                symbol.to_string_lossy(),
+                self.path.display()
+            );
+        };
+        f(address)
+    }
+}
+
+impl std::fmt::Debug for HotLibrary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HotLibrary")
+            .field("path", &self.path)
+            .field("generation", &self.generation())
+            .finish()
+    }
+}
+
+// load a copy of the library, loaders return the library already loaded when reopening a path
+fn load(path: &Path) -> Result<(Loaded, SystemTime), EmarkError> {
+    let error = |reason: String| EmarkError::LibraryLoad {
+        path: path.display().to_string(),
+        reason,
+    };
+
+    let modified = fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .map_err(|io| error(io.to_string()))?;
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    let copy = std::env::temp_dir().join(format!(
+        "emark-{}-{}-{file_name}",
+        std::process::id(),
+        COPIES.fetch_add(1, Ordering::Relaxed)
+    ));
+    fs::copy(path, &copy).map_err(|io| error(io.to_string()))?;
+
+    let c_copy = CString::new(copy.as_os_str().as_bytes()).map_err(|nul| error(nul.to_string()))?;
+    let handle = unsafe { libc::dlopen(c_copy.as_ptr(), libc::RTLD_NOW | libc::RTLD_LOCAL) };
+    if handle.is_null() {
+        let _ = fs::remove_file(&copy);
+        let reason = unsafe { libc::dlerror() };
+        let reason = match reason.is_null() {
+            true => "unknown error".to_owned(),
+            false => unsafe { CStr::from_ptr(reason) }
+                .to_string_lossy()
+                .into_owned(),
+        };
+        return Err(error(reason));
+    }
+    let loaded = Loaded { handle, copy };
+
+    // check the abi of the library
+    let abi = |symbol: &CStr| {
+        loaded
+            .symbol(symbol)
+            .ok_or_else(|| EmarkError::MissingSymbol {
+                path: path.display().to_string(),
+                symbol: symbol.to_string_lossy().into_owned(),
+            })
+    };
+    let abi_version = abi(c"emark_hot_reload_abi")?;
+    let emark_version = abi(c"emark_hot_reload_version")?;
+    let (abi_version, emark_version) = unsafe {
+        let abi_version = std::mem::transmute::<*mut c_void, extern "C" fn() -> u32>(abi_version);
+        let emark_version =
+            std::mem::transmute::<*mut c_void, extern "C" fn() -> *const c_char>(emark_version);
+        (abi_version(), CStr::from_ptr(emark_version()))
+    };
+    if abi_version != HOT_RELOAD_ABI_VERSION || emark_version != EMARK_VERSION {
+        return Err(EmarkError::AbiMismatch {
+            path: path.display().to_string(),
+            found: format!("{abi_version} (emark {})", emark_version.to_string_lossy()),
+            expected: format!(
+                "{HOT_RELOAD_ABI_VERSION} (emark {})",
+                EMARK_VERSION.to_string_lossy()
+            ),
+        });
+    }
+
+    Ok((loaded, modified))
+}
+
+/// System calling a function of a [HotLibrary].
+pub struct HotSystem {
+    library: HotLibrary,
+    symbol: CString,
+    name: String,
+}
+
+impl HotSystem {
+    pub fn library(&self) -> &HotLibrary {
+        &self.library
+    }
+}
+
+impl System for HotSystem {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn run(
+        &mut self,
+        container: &ResourceContainer,
+        event_manager: &EventManager,
+    ) -> Result<(), EmarkError> {
+        self.library.call(&self.symbol, |address| {
+            let system = unsafe {
+                std::mem::transmute::<*mut c_void, fn(&ResourceContainer, &EventManager)>(address)
+            };
+            system(container, event_manager)
+        });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test_hot_reload {
+    use super::*;
+
+    #[test]
+    fn test_hot_library_load_error() {
+        let missing = unsafe { HotLibrary::open("missing/libgame.so") };
+        assert!(matches!(missing, Err(EmarkError::LibraryLoad { .. })));
+
+        // assert files that are not libraries are rejected
+        let manifest =
+            unsafe { HotLibrary::open(concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml")) };
+        assert!(matches!(manifest, Err(EmarkError::LibraryLoad { .. })));
+    }
+}
@@ -0,0 +1,156 @@
+//! # Systems
+//!
+//! A system is a unit of work executed once per cycle, e.g. the dispatch of the pending
+//! events to their handlers or the periodic update of a resource. Systems are collected
+//! into a [Schedule], which runs them in order against a `ResourceContainer` and an
+//! `EventManager`, providing a ready-made main loop skeleton.
+//!
+//! ## Cycle
+//!
+//! 1. **Run:** The application calls `Schedule::run` once per iteration of its main loop.
+//! 2. **Stages:** The stages of the schedule are executed one after the other.
+//! 3. **Systems:** Each system of a stage is executed in the order it was added to the stage.
+//! 4. **Dispatch:** A `HandlerRegistry` added as a system dispatches the pending events.
+//!
+//! ## Stages
+//!
+//! Stages such as [PRE_UPDATE](stage::PRE_UPDATE), [UPDATE](stage::UPDATE) and
+//! [POST_UPDATE](stage::POST_UPDATE) split the cycle into well-defined phases separated by
+//! barriers, e.g. event dispatch, simulation and rendering preparation. Custom stages can be
+//! inserted before or after existing ones.
+//!
+//! Startup systems form an additional stage run once before the first cycle following their
+//! addition, for one-time initialization.
+//!
+//! `Schedule::spawn_system` returns a [SystemId], with which the system can later be despawned
+//! or replaced while the application runs. `Schedule::add_system_once` schedules a system for a
+//! single future cycle.
+//!
+//! ## Ordering
+//!
+//! Systems can be labeled and ordered `before` or `after` other labels of their stage through
+//! [SystemOptions]. The schedule sorts the systems of each stage topologically when it is
+//! built, and reports cyclic constraints with the systems along the cycle.
+//!
+//! ## System Sets
+//!
+//! Systems can join a named set, whose [SetOptions] apply ordering constraints and run
+//! conditions to every system of the set at once. Sets can also be disabled and enabled as a
+//! whole with `Schedule::disable_set` and `Schedule::enable_set`.
+//!
+//! ## Function Systems
+//!
+//! Plain functions whose parameters are retrievers, e.g. `fn(Res<A>, ResMut<B>)`, are systems.
+//! Their parameters are retrieved from the `ResourceContainer` before each run.
+//!
+//! ## Fallible Systems
+//!
+//! Function systems may return `Result<(), EmarkError>`. The [ErrorPolicy] of the schedule
+//! decides what happens when one fails: panicking, which is the default, logging the error,
+//! emitting it as a [SystemErrorEvent], skipping the systems ordered after the failed one, or
+//! aborting the rest of the cycle.
+//!
+//! ## Piping
+//!
+//! A function system may return a value, fed by `pipe` into the [In] parameter of the next
+//! function, e.g. to chain validation, action and error reporting into a single system
+//! without intermediate events.
+//!
+//! ## Hot Reloading
+//!
+//! With the `hot-reload` feature, on unix targets, a `hot_reload::HotLibrary`
+//! loads systems and handlers from a `cdylib` sharing a versioned ABI with the application,
+//! and swaps them when the library file changes, keeping the state held in the
+//! `ResourceContainer`.
+//!
+//! ## Async Systems
+//!
+//! With the `async` feature, async functions taking the `ResourceContainer` and the
+//! `EventManager` are systems, and `HandlerRegistry::add_async_handler` registers async
+//! handlers. Their futures are awaited by the executor, without depending on a particular
+//! runtime, the futures of non-conflicting systems and batches being awaited concurrently.
+//!
+//! ## Profiling
+//!
+//! A profiling schedule records the wall-clock time of each system and of each handler batch
+//! of the registries it runs, exposed as a [ScheduleReport] of the last cycle.
+//!
+//! [Schedule::to_dot] describes the stages, their systems, ordering constraints and resource
+//! accesses as a Graphviz graph, which helps understanding why systems do not run in parallel.
+//!
+//! ## Parallel Execution
+//!
+//! Function systems know the resources they access from their retrievers. The
+//! [ParallelExecutor] builds a conflict graph from these accesses and runs non-conflicting
+//! systems on worker threads concurrently, while conflicting systems keep their order. Systems
+//! with unknown access, such as closures taking the whole container, are exclusive.
+//!
+//! Systems retrieving non-send resources with `NonSend` or `NonSendMut`, as well as systems with
+//! exclusive access, run on the thread running the schedule, which must be the thread that created
+//! the container.
+//!
+//! ## Exclusive Systems
+//!
+//! An [ExclusiveSystem] takes `&mut World` and runs alone at the barrier closing its stage,
+//! for operations that can not be expressed through retrievers, such as adding or removing
+//! resources. Schedules with exclusive systems are run with `Schedule::run_world`.
+//!
+//! Systems can also defer such structural changes with the [Commands](crate::store::Commands)
+//! retriever. `Schedule::run_world` applies the recorded commands at the barrier closing each
+//! stage, before its exclusive systems run.
+//!
+#[doc(hidden)]
+#[allow(clippy::module_inception)]
+pub mod system;
+#[doc(inline)]
+pub use system::{ExclusiveSystem, System};
+
+pub mod access;
+#[doc(inline)]
+pub use access::Access;
+
+#[doc(hidden)]
+pub mod executor;
+#[doc(inline)]
+pub use executor::{
+    Executor, ParallelExecutor, ScopedThreadPool, SequentialExecutor, ThreadPool,
+};
+
+#[cfg(feature = "async")]
+#[doc(hidden)]
+pub mod async_system;
+#[cfg(feature = "async")]
+#[doc(inline)]
+pub use async_system::AsyncSystem;
+
+#[cfg(all(feature = "hot-reload", unix))]
+pub mod hot_reload;
+
+#[doc(hidden)]
+pub mod error;
+#[doc(inline)]
+pub use error::{ErrorPolicy, SystemErrorEvent, SystemResult};
+
+#[doc(hidden)]
+pub mod function;
+#[doc(inline)]
+pub use function::{FunctionSystem, In, IntoSystem, Pipe, SystemFunction};
+
+#[doc(hidden)]
+pub mod set;
+#[doc(inline)]
+pub use set::SetOptions;
+
+#[doc(hidden)]
+pub mod report;
+#[doc(inline)]
+pub use report::{ScheduleReport, SystemTiming};
+
+pub mod stage;
+#[doc(inline)]
+pub use stage::SystemOptions;
+
+#[doc(hidden)]
+pub mod schedule;
+#[doc(inline)]
+pub use schedule::{Schedule, SystemId};
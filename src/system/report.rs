@@ -0,0 +1,152 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use crate::{
+    event::{EventManager, HandlerTiming},
+    store::ResourceContainer,
+    utils::error::EmarkError,
+};
+
+use super::{Access, System};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Wall-clock time spent by a system during a cycle.
+pub struct SystemTiming {
+    stage: &'static str,
+    name: String,
+    duration: Duration,
+}
+
+impl SystemTiming {
+    /// Label of the stage of the system.
+    pub fn stage(&self) -> &'static str {
+        self.stage
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+/// Timings of the last cycle run by a profiling [Schedule](crate::system::Schedule).
+///
+/// The report lists the time spent by each system and by each handler on each batch, so the
+/// systems and handlers exceeding a frame budget can be found.
+///
+/// The report is the only output of the profiling: no `tracing` spans are opened around the
+/// runs, `tracing` not being a dependency of emark.
+///
+/// # Examples
+/// ```
+/// use emark::system::Schedule;
+///
+/// let mut schedule = Schedule::new();
+/// schedule.set_profiling(true).add_system(|| {});
+/// schedule.run(&Default::default(), &Default::default());
+///
+/// let report = schedule.report().unwrap();
+/// assert_eq!(report.systems().len(), 1);
+/// ```
+pub struct ScheduleReport {
+    pub(crate) systems: Vec<SystemTiming>,
+    pub(crate) handlers: Vec<HandlerTiming>,
+    pub(crate) total: Duration,
+}
+
+impl ScheduleReport {
+    /// Timings of the systems run during the cycle, in execution order of their stages.
+    pub fn systems(&self) -> &[SystemTiming] {
+        &self.systems
+    }
+
+    /// Timings of the handlers of the registries run as systems, in dispatch order.
+    pub fn handlers(&self) -> &[HandlerTiming] {
+        &self.handlers
+    }
+
+    /// Wall-clock time of the whole cycle.
+    pub fn total(&self) -> Duration {
+        self.total
+    }
+
+    /// Returns the system which took the longest.
+    pub fn slowest_system(&self) -> Option<&SystemTiming> {
+        self.systems.iter().max_by_key(|timing| timing.duration)
+    }
+
+    /// Returns the handler batch which took the longest.
+    pub fn slowest_handler(&self) -> Option<&HandlerTiming> {
+        self.handlers.iter().max_by_key(|timing| timing.duration())
+    }
+
+    pub(crate) fn push_system(&mut self, stage: &'static str, name: &str, duration: Duration) {
+        self.systems.push(SystemTiming {
+            stage,
+            name: name.to_string(),
+            duration,
+        });
+    }
+}
+
+#[derive(Debug, Default)]
+// duration of the last run of a system, in nanoseconds
+pub(crate) struct SystemTimer {
+    elapsed: AtomicU64,
+}
+
+impl SystemTimer {
+    pub(crate) fn take(&self) -> Duration {
+        Duration::from_nanos(self.elapsed.swap(0, Ordering::AcqRel))
+    }
+}
+
+// system recording the duration of its runs while profiling is enabled
+pub(crate) struct TimedSystem {
+    pub(crate) system: Box<dyn System>,
+    pub(crate) profiling: Arc<AtomicBool>,
+    pub(crate) timer: Arc<SystemTimer>,
+}
+
+impl System for TimedSystem {
+    fn name(&self) -> &str {
+        self.system.name()
+    }
+
+    fn run(
+        &mut self,
+        container: &ResourceContainer,
+        event_manager: &EventManager,
+    ) -> Result<(), EmarkError> {
+        if !self.profiling.load(Ordering::Acquire) {
+            return self.system.run(container, event_manager);
+        }
+
+        let start = Instant::now();
+        let result = self.system.run(container, event_manager);
+        let elapsed = start.elapsed().as_nanos() as u64;
+        self.timer.elapsed.store(elapsed, Ordering::Release);
+        result
+    }
+
+    fn access(&self) -> Access {
+        self.system.access()
+    }
+
+    fn set_profiling(&mut self, enabled: bool) {
+        self.system.set_profiling(enabled);
+    }
+
+    fn take_handler_timings(&mut self) -> Vec<HandlerTiming> {
+        self.system.take_handler_timings()
+    }
+}
@@ -0,0 +1,1077 @@
+use std::{
+    collections::HashMap,
+    fmt::{Debug, Write},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Instant,
+};
+
+use crate::{
+    event::EventManager, store::ResourceContainer, utils::error::EmarkError, world::World,
+};
+
+use super::{
+    report::ScheduleReport,
+    set::{ConditionalSystem, SetState},
+    stage::{self, escape, Stage},
+    ErrorPolicy, ExclusiveSystem, Executor, IntoSystem, SequentialExecutor, SetOptions, System,
+    SystemOptions,
+};
+
+/// # Schedule
+///
+/// The `Schedule` owns an ordered collection of systems and runs them once per cycle.
+///
+/// Systems are grouped into named stages, run one after the other with a strict barrier
+/// in between: every system of a stage has finished before the next stage starts. A
+/// schedule starts with the [PRE_UPDATE](stage::PRE_UPDATE), [UPDATE](stage::UPDATE) and
+/// [POST_UPDATE](stage::POST_UPDATE) stages, custom stages can be inserted relative to them.
+///
+/// Systems are run by an [Executor], sequentially by default. A
+/// [ParallelExecutor](crate::system::ParallelExecutor) runs non-conflicting systems concurrently.
+///
+/// Within a stage, systems can be ordered relative to each other through the labels of their
+/// [SystemOptions]. The systems are sorted when the schedule is built, see [Schedule::build].
+/// Systems can also be grouped into sets, configured once for all their systems.
+///
+/// Startup systems run once, before the first cycle following their addition, and are then
+/// removed from the schedule. They are meant for one-time initialization such as the setup of
+/// resources or the emission of initial events.
+///
+/// Systems spawned with [Schedule::spawn_system] return a [SystemId], used to despawn or
+/// replace them at runtime. Systems added with [Schedule::add_system_once] run during a single
+/// future cycle and are then despawned.
+///
+/// # Examples
+/// ```
+/// use emark::event::{EventManager, HandlerRegistry};
+/// use emark::prelude::*;
+/// use emark::store::ResourceContainer;
+/// use emark::system::Schedule;
+///
+/// struct Tick;
+/// impl Event for Tick {}
+///
+/// let mut registry = HandlerRegistry::new();
+/// registry.add_handler(|ticks: &[Tick]| assert_eq!(ticks.len(), 1));
+///
+/// let mut schedule = Schedule::new();
+/// schedule
+///     .add_system(|_: &ResourceContainer, event_manager: &EventManager| {
+///         event_manager.emit(Tick);
+///     })
+///     .add_system(registry);
+///
+/// let container = ResourceContainer::default();
+/// let event_manager = EventManager::new();
+/// schedule.run(&container, &event_manager);
+/// ```
+pub struct Schedule {
+    startup: Stage,
+    stages: Vec<Stage>,
+    sets: HashMap<&'static str, Arc<SetState>>,
+    executor: Box<dyn Executor>,
+    profiling: Arc<AtomicBool>,
+    report: Option<ScheduleReport>,
+    error_policy: ErrorPolicy,
+    next_id: u64,
+    // systems spawned in a future cycle
+    deferred: Vec<Deferred>,
+    // systems despawned once they have run
+    once: Vec<SystemId>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// Handle of a system of a [Schedule], used to despawn or replace it.
+pub struct SystemId(pub(crate) u64);
+
+// system waiting for its cycle
+struct Deferred {
+    id: SystemId,
+    stage: &'static str,
+    // cycles to skip before spawning the system
+    cycles: usize,
+    system: Box<dyn System>,
+    options: SystemOptions,
+}
+
+impl Default for Schedule {
+    fn default() -> Self {
+        Self {
+            startup: Stage::new(STARTUP),
+            stages: [stage::PRE_UPDATE, stage::UPDATE, stage::POST_UPDATE]
+                .into_iter()
+                .map(Stage::new)
+                .collect(),
+            sets: HashMap::new(),
+            executor: Box::new(SequentialExecutor),
+            profiling: Arc::new(AtomicBool::new(false)),
+            report: None,
+            error_policy: ErrorPolicy::default(),
+            next_id: 0,
+            deferred: Vec::new(),
+            once: Vec::new(),
+        }
+    }
+}
+
+// label of the stage of the startup systems, used for diagnostics
+const STARTUP: &str = "startup";
+
+impl Schedule {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends a system to the [UPDATE](stage::UPDATE) stage.
+    ///
+    /// See [IntoSystem] for the types convertible into a system.
+    ///
+    /// # Panics
+    /// Panics if the `UPDATE` stage has been removed.
+    pub fn add_system<M>(&mut self, system: impl IntoSystem<M>) -> &mut Self {
+        self.add_system_to_stage(stage::UPDATE, system)
+    }
+
+    /// Appends a system with ordering options to the [UPDATE](stage::UPDATE) stage.
+    pub fn add_system_with<M>(
+        &mut self,
+        system: impl IntoSystem<M>,
+        options: SystemOptions,
+    ) -> &mut Self {
+        self.add_system_to_stage_with(stage::UPDATE, system, options)
+    }
+
+    /// Appends a system to the stage `label`.
+    ///
+    /// # Panics
+    /// Panics if the schedule has no stage `label`.
+    pub fn add_system_to_stage<M>(
+        &mut self,
+        label: &'static str,
+        system: impl IntoSystem<M>,
+    ) -> &mut Self {
+        self.add_system_to_stage_with(label, system, SystemOptions::new())
+    }
+
+    /// Appends a system with ordering options to the stage `label`.
+    ///
+    /// Ordering constraints only apply between systems of the same stage.
+    ///
+    /// # Panics
+    /// Panics if the schedule has no stage `label`.
+    pub fn add_system_to_stage_with<M>(
+        &mut self,
+        label: &'static str,
+        system: impl IntoSystem<M>,
+        options: SystemOptions,
+    ) -> &mut Self {
+        self.spawn_system_to_stage(label, system, options);
+        self
+    }
+
+    /// Appends a system with ordering options to the [UPDATE](stage::UPDATE) stage, returning
+    /// its handle.
+    pub fn spawn_system<M>(
+        &mut self,
+        system: impl IntoSystem<M>,
+        options: SystemOptions,
+    ) -> SystemId {
+        self.spawn_system_to_stage(stage::UPDATE, system, options)
+    }
+
+    /// Appends a system with ordering options to the stage `label`, returning its handle.
+    ///
+    /// # Panics
+    /// Panics if the schedule has no stage `label`.
+    pub fn spawn_system_to_stage<M>(
+        &mut self,
+        label: &'static str,
+        system: impl IntoSystem<M>,
+        options: SystemOptions,
+    ) -> SystemId {
+        let index = self.stage_index(label);
+        let id = self.next_system_id();
+        let system = self.scheduled(Box::new(system.into_system()), &options);
+        self.stages[index].push(id, system, options, &self.profiling);
+        id
+    }
+
+    /// Adds a system to the [UPDATE](stage::UPDATE) stage of the next cycle only.
+    pub fn add_system_once<M>(&mut self, system: impl IntoSystem<M>) -> SystemId {
+        self.add_system_once_in(0, system)
+    }
+
+    /// Adds a system to the [UPDATE](stage::UPDATE) stage of a single future cycle, run after
+    /// skipping `cycles` cycles.
+    ///
+    /// The system is despawned once it has run.
+    pub fn add_system_once_in<M>(&mut self, cycles: usize, system: impl IntoSystem<M>) -> SystemId {
+        let id = self.next_system_id();
+        let options = SystemOptions::new();
+        let system = self.scheduled(Box::new(system.into_system()), &options);
+        self.deferred.push(Deferred {
+            id,
+            stage: stage::UPDATE,
+            cycles,
+            system,
+            options,
+        });
+        self.once.push(id);
+        id
+    }
+
+    /// Removes the system `id` from the schedule, starting with the next cycle.
+    ///
+    /// Returns `true` if the system was in the schedule.
+    pub fn despawn_system(&mut self, id: SystemId) -> bool {
+        self.once.retain(|&once| once != id);
+        if let Some(index) = self.deferred.iter().position(|deferred| deferred.id == id) {
+            self.deferred.remove(index);
+            return true;
+        }
+        std::iter::once(&mut self.startup)
+            .chain(&mut self.stages)
+            .any(|stage| stage.remove(id))
+    }
+
+    /// Replaces the system `id` by `system`, keeping its options and its position.
+    ///
+    /// Returns `false`, dropping `system`, if `id` is not in the schedule.
+    pub fn replace_system<M>(&mut self, id: SystemId, system: impl IntoSystem<M>) -> bool {
+        let options = match self.deferred.iter().find(|deferred| deferred.id == id) {
+            Some(deferred) => deferred.options.clone(),
+            None => {
+                let options = std::iter::once(&self.startup)
+                    .chain(&self.stages)
+                    .find_map(|stage| Some(stage.options[stage.position(id)?].clone()));
+                let Some(options) = options else {
+                    return false;
+                };
+                options
+            }
+        };
+        let system = self.scheduled(Box::new(system.into_system()), &options);
+
+        if let Some(deferred) = self.deferred.iter_mut().find(|deferred| deferred.id == id) {
+            deferred.system = system;
+            return true;
+        }
+        for stage in std::iter::once(&mut self.startup).chain(&mut self.stages) {
+            if let Some(index) = stage.position(id) {
+                stage.replace(index, system, &self.profiling);
+                break;
+            }
+        }
+        true
+    }
+
+    /// Returns `true` if the system `id` is in the schedule.
+    pub fn contains_system(&self, id: SystemId) -> bool {
+        self.deferred.iter().any(|deferred| deferred.id == id)
+            || std::iter::once(&self.startup)
+                .chain(&self.stages)
+                .any(|stage| stage.position(id).is_some())
+    }
+
+    fn next_system_id(&mut self) -> SystemId {
+        self.next_id += 1;
+        SystemId(self.next_id - 1)
+    }
+
+    // spawn the deferred systems of the current cycle
+    fn spawn_deferred(&mut self) {
+        for deferred in std::mem::take(&mut self.deferred) {
+            match deferred.cycles {
+                0 => {
+                    let index = self.stage_index(deferred.stage);
+                    self.stages[index].push(
+                        deferred.id,
+                        deferred.system,
+                        deferred.options,
+                        &self.profiling,
+                    );
+                }
+                cycles => self.deferred.push(Deferred {
+                    cycles: cycles - 1,
+                    ..deferred
+                }),
+            }
+        }
+    }
+
+    // despawn the run-once systems that have run
+    fn despawn_once(&mut self) {
+        let once = std::mem::take(&mut self.once);
+        for id in once {
+            match self.deferred.iter().any(|deferred| deferred.id == id) {
+                true => self.once.push(id),
+                false => {
+                    self.despawn_system(id);
+                }
+            }
+        }
+    }
+
+    /// Appends an exclusive system to the [UPDATE](stage::UPDATE) stage.
+    pub fn add_exclusive_system(&mut self, system: impl ExclusiveSystem + 'static) -> &mut Self {
+        self.add_exclusive_system_to_stage(stage::UPDATE, system)
+    }
+
+    /// Appends an exclusive system to the stage `label`.
+    ///
+    /// Exclusive systems run after every other system of the stage, in the order they were
+    /// added.
+    ///
+    /// # Panics
+    /// Panics if the schedule has no stage `label`.
+    pub fn add_exclusive_system_to_stage(
+        &mut self,
+        label: &'static str,
+        system: impl ExclusiveSystem + 'static,
+    ) -> &mut Self {
+        let index = self.stage_index(label);
+        self.stages[index].exclusive.push(Box::new(system));
+        self
+    }
+
+    /// Appends a startup system, run once before the next cycle.
+    pub fn add_startup_system<M>(&mut self, system: impl IntoSystem<M>) -> &mut Self {
+        self.add_startup_system_with(system, SystemOptions::new())
+    }
+
+    /// Appends a startup system with ordering options, relative to the other startup systems.
+    pub fn add_startup_system_with<M>(
+        &mut self,
+        system: impl IntoSystem<M>,
+        options: SystemOptions,
+    ) -> &mut Self {
+        let id = self.next_system_id();
+        let system = self.scheduled(Box::new(system.into_system()), &options);
+        self.startup.push(id, system, options, &self.profiling);
+        self
+    }
+
+    /// Appends an exclusive startup system, run once before the next cycle after the other
+    /// startup systems.
+    pub fn add_exclusive_startup_system(
+        &mut self,
+        system: impl ExclusiveSystem + 'static,
+    ) -> &mut Self {
+        self.startup.exclusive.push(Box::new(system));
+        self
+    }
+
+    /// Sets the configuration shared by the systems of the set `set`.
+    ///
+    /// Replaces the previous configuration of the set, including for the systems already
+    /// added to it.
+    pub fn configure_set(&mut self, set: &'static str, options: SetOptions) -> &mut Self {
+        *self.set_state(set).options.write() = options;
+        for stage in std::iter::once(&mut self.startup).chain(&mut self.stages) {
+            stage.sorted = false;
+        }
+        self
+    }
+
+    /// Enables the systems of the set `set`.
+    ///
+    /// Returns `true` if the set was disabled.
+    pub fn enable_set(&mut self, set: &'static str) -> bool {
+        self.set_state(set).set_enabled(true)
+    }
+
+    /// Disables the systems of the set `set`, skipped until the set is enabled again.
+    ///
+    /// Returns `true` if the set was enabled.
+    pub fn disable_set(&mut self, set: &'static str) -> bool {
+        self.set_state(set).set_enabled(false)
+    }
+
+    /// Returns `true` if the systems of the set `set` are run.
+    pub fn is_set_enabled(&self, set: &str) -> bool {
+        self.sets.get(set).is_none_or(|state| state.is_enabled())
+    }
+
+    fn set_state(&mut self, set: &'static str) -> &Arc<SetState> {
+        self.sets.entry(set).or_default()
+    }
+
+    // wrap systems with run conditions or a set, to be skipped when they should not run
+    fn scheduled(&mut self, system: Box<dyn System>, options: &SystemOptions) -> Box<dyn System> {
+        if options.set().is_none() && options.conditions.is_empty() {
+            return system;
+        }
+        Box::new(ConditionalSystem {
+            system,
+            conditions: options.conditions.clone(),
+            set: options.set().map(|set| self.set_state(set).clone()),
+        })
+    }
+
+    /// Appends a stage after every other stage.
+    ///
+    /// # Panics
+    /// Panics if the schedule already has a stage `label`.
+    pub fn add_stage(&mut self, label: &'static str) -> &mut Self {
+        self.insert_stage(self.stages.len(), label)
+    }
+
+    /// Inserts a stage right before the stage `target`.
+    ///
+    /// # Panics
+    /// Panics if the schedule has no stage `target` or already has a stage `label`.
+    pub fn add_stage_before(&mut self, target: &'static str, label: &'static str) -> &mut Self {
+        let index = self.stage_index(target);
+        self.insert_stage(index, label)
+    }
+
+    /// Inserts a stage right after the stage `target`.
+    ///
+    /// # Panics
+    /// Panics if the schedule has no stage `target` or already has a stage `label`.
+    pub fn add_stage_after(&mut self, target: &'static str, label: &'static str) -> &mut Self {
+        let index = self.stage_index(target);
+        self.insert_stage(index + 1, label)
+    }
+
+    /// Returns `true` if the schedule has a stage `label`.
+    pub fn contains_stage(&self, label: &str) -> bool {
+        self.stages.iter().any(|stage| stage.label == label)
+    }
+
+    /// Iterates through the labels of the stages, in execution order.
+    pub fn stage_labels(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.stages.iter().map(|stage| stage.label)
+    }
+
+    fn insert_stage(&mut self, index: usize, label: &'static str) -> &mut Self {
+        assert!(
+            !self.contains_stage(label),
+            "stage `{label}` already exists"
+        );
+        self.stages.insert(index, Stage::new(label));
+        self
+    }
+
+    fn stage_index(&self, label: &str) -> usize {
+        self.stages
+            .iter()
+            .position(|stage| stage.label == label)
+            .unwrap_or_else(|| panic!("stage `{label}` does not exist"))
+    }
+
+    /// Sets the executor running the systems.
+    pub fn set_executor(&mut self, executor: impl Executor + 'static) -> &mut Self {
+        self.executor = Box::new(executor);
+        self
+    }
+
+    /// Sets the behavior of the schedule when a system returns an error.
+    pub fn set_error_policy(&mut self, policy: ErrorPolicy) -> &mut Self {
+        self.error_policy = policy;
+        self
+    }
+
+    pub fn error_policy(&self) -> ErrorPolicy {
+        self.error_policy
+    }
+
+    /// Enables or disables the recording of the timings of each cycle, see [ScheduleReport].
+    pub fn set_profiling(&mut self, enabled: bool) -> &mut Self {
+        self.profiling.store(enabled, Ordering::Release);
+        for stage in std::iter::once(&mut self.startup).chain(&mut self.stages) {
+            for system in stage.systems.iter_mut() {
+                system.set_profiling(enabled);
+            }
+        }
+        if !enabled {
+            self.report = None;
+        }
+        self
+    }
+
+    pub fn is_profiling(&self) -> bool {
+        self.profiling.load(Ordering::Acquire)
+    }
+
+    /// Timings of the last cycle, if it was run while profiling.
+    pub fn report(&self) -> Option<&ScheduleReport> {
+        self.report.as_ref()
+    }
+
+    /// Sorts the systems of every stage by their ordering constraints, including the ones of
+    /// their sets.
+    ///
+    /// Systems without constraints between them keep the order they were added. Only the
+    /// stages modified since the last build are sorted again.
+    ///
+    /// # Errors
+    /// Returns `EmarkError::CyclicSystemOrder` with the systems along the cycle if the
+    /// constraints of a stage are cyclic.
+    pub fn build(&mut self) -> Result<(), EmarkError> {
+        self.startup.sort(&self.sets)?;
+        self.stages
+            .iter_mut()
+            .try_for_each(|stage| stage.sort(&self.sets))
+    }
+
+    // take the pending startup systems, leaving an empty startup stage
+    fn take_startup(&mut self) -> Stage {
+        std::mem::replace(&mut self.startup, Stage::new(STARTUP))
+    }
+
+    // true if a stage, including the startup systems, has exclusive systems
+    fn has_exclusive(&self) -> bool {
+        std::iter::once(&self.startup)
+            .chain(&self.stages)
+            .any(|stage| !stage.exclusive.is_empty())
+    }
+
+    /// Runs every system once, stage by stage.
+    ///
+    /// Within a stage, conflicting systems are run in the order they were added, after
+    /// sorting by their ordering constraints. Pending startup systems run first.
+    ///
+    /// # Panics
+    /// Panics if the schedule can not be built, see [Schedule::build], or if the schedule has
+    /// exclusive systems, which require [Schedule::run_world].
+    pub fn run(&mut self, container: &ResourceContainer, event_manager: &EventManager) {
+        assert!(
+            !self.has_exclusive(),
+            "exclusive systems require `Schedule::run_world`"
+        );
+        self.spawn_deferred();
+        if let Err(error) = self.build() {
+            panic!("{error}");
+        }
+        let profiling = self.is_profiling();
+        let start = Instant::now();
+        let mut report = ScheduleReport::default();
+        let mut startup = self.take_startup();
+        for stage in std::iter::once(&mut startup).chain(self.stages.iter_mut()) {
+            let failures = self.executor.run(
+                &mut stage.systems,
+                &stage.dependencies,
+                container,
+                event_manager,
+                self.error_policy,
+            );
+            if profiling {
+                stage.record(&mut report);
+            }
+            if report_failures(self.error_policy, stage, failures, event_manager) {
+                break;
+            }
+        }
+        if profiling {
+            report.total = start.elapsed();
+            self.report = Some(report);
+        }
+        self.despawn_once();
+    }
+
+    /// Runs every system once against a world, stage by stage.
+    ///
+    /// The exclusive systems of a stage run at the barrier closing it, with full access to
+    /// the world. The expired resources of the world are removed before the systems run, see
+    /// `World::remove_expired`.
+    ///
+    /// # Panics
+    /// Panics if the schedule can not be built, see [Schedule::build].
+    pub fn run_world(&mut self, world: &mut World) {
+        world.remove_expired();
+        self.spawn_deferred();
+        if let Err(error) = self.build() {
+            panic!("{error}");
+        }
+        let profiling = self.is_profiling();
+        let start = Instant::now();
+        let mut report = ScheduleReport::default();
+        let mut startup = self.take_startup();
+        for stage in std::iter::once(&mut startup).chain(self.stages.iter_mut()) {
+            let failures = self.executor.run(
+                &mut stage.systems,
+                &stage.dependencies,
+                world.container(),
+                world.event_manager(),
+                self.error_policy,
+            );
+            if profiling {
+                stage.record(&mut report);
+            }
+            if report_failures(self.error_policy, stage, failures, world.event_manager()) {
+                break;
+            }
+            world.container_mut().apply_commands();
+            for system in stage.exclusive.iter_mut() {
+                let start = Instant::now();
+                system.run(world);
+                if profiling {
+                    report.push_system(stage.label, system.name(), start.elapsed());
+                }
+            }
+        }
+        if profiling {
+            report.total = start.elapsed();
+            self.report = Some(report);
+        }
+        self.despawn_once();
+    }
+
+    /// Number of systems of the schedule, across every stage and including the pending
+    /// startup and run-once systems.
+    pub fn len(&self) -> usize {
+        let systems = std::iter::once(&self.startup)
+            .chain(&self.stages)
+            .map(|stage| stage.systems.len() + stage.exclusive.len())
+            .sum::<usize>();
+        systems + self.deferred.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Describes the schedule as a Graphviz DOT graph.
+    ///
+    /// Each stage is a cluster of its systems, exclusive systems being drawn in bold. Solid
+    /// edges between systems are ordering constraints, dashed edges from a resource to a
+    /// system are reads and red edges from a system to a resource are writes.
+    pub fn to_dot(&self) -> String {
+        let mut out = String::from("digraph schedule {\n");
+        let mut resources = Vec::new();
+        let stages = std::iter::once(&self.startup)
+            .filter(|stage| !stage.systems.is_empty() || !stage.exclusive.is_empty())
+            .chain(&self.stages);
+
+        // writing to a string can not fail
+        for (index, stage) in stages.enumerate() {
+            stage
+                .write_dot(index, &self.sets, &mut resources, &mut out)
+                .unwrap();
+        }
+        for (node, (_, name)) in resources.iter().enumerate() {
+            writeln!(out, "    r{node} [label=\"{}\", shape=box];", escape(name)).unwrap();
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    /// Iterates through the names of the systems, in execution order.
+    pub fn system_names(&self) -> impl Iterator<Item = &str> {
+        std::iter::once(&self.startup)
+            .chain(&self.stages)
+            .flat_map(Stage::system_names)
+    }
+}
+
+// reports the failures of the systems of `stage`, returns `true` if the cycle is aborted
+fn report_failures(
+    policy: ErrorPolicy,
+    stage: &Stage,
+    failures: Vec<(usize, EmarkError)>,
+    event_manager: &EventManager,
+) -> bool {
+    let aborted = policy.aborts() && !failures.is_empty();
+    for (index, error) in failures {
+        policy.report(stage.systems[index].name(), error, event_manager);
+    }
+    aborted
+}
+
+impl Debug for Schedule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_map()
+            .entries(self.stages.iter().map(|stage| {
+                let systems = stage.system_names().collect::<Vec<_>>();
+                (stage.label, systems)
+            }))
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test_schedule {
+    use std::{
+        sync::atomic::AtomicUsize,
+        time::Duration,
+    };
+
+    use super::*;
+    use crate::{
+        event::{Event, HandlerRegistry},
+        system::SystemErrorEvent,
+        store::{Container, Res, ResMut, Retriever},
+    };
+
+    struct TestEvent(usize);
+    impl Event for TestEvent {}
+
+    #[test]
+    fn test_schedule_order() {
+        let order = Arc::new(parking_lot::Mutex::new(Vec::new()));
+        let mut schedule = Schedule::new();
+        for index in 0..3 {
+            let order = order.clone();
+            schedule.add_system(move |_: &ResourceContainer, _: &EventManager| {
+                order.lock().push(index);
+            });
+        }
+        assert_eq!(schedule.len(), 3);
+
+        schedule.run(&ResourceContainer::default(), &EventManager::new());
+        assert_eq!(*order.lock(), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn test_schedule_stages() {
+        let order = Arc::new(parking_lot::Mutex::new(Vec::new()));
+        let mut schedule = Schedule::new();
+        schedule
+            .add_stage_before(stage::UPDATE, "physics")
+            .add_stage_after(stage::POST_UPDATE, "render");
+        assert_eq!(
+            schedule.stage_labels().collect::<Vec<_>>(),
+            vec!["pre_update", "physics", "update", "post_update", "render"]
+        );
+
+        for label in ["render", "update", "physics", "pre_update", "post_update"] {
+            let order = order.clone();
+            schedule.add_system_to_stage(label, move |_: &ResourceContainer, _: &EventManager| {
+                order.lock().push(label);
+            });
+        }
+
+        // assert stages run in order regardless of the order systems were added
+        schedule.run(&ResourceContainer::default(), &EventManager::new());
+        assert_eq!(
+            *order.lock(),
+            vec!["pre_update", "physics", "update", "post_update", "render"]
+        );
+    }
+
+    #[test]
+    fn test_schedule_explicit_order() {
+        let order = Arc::new(parking_lot::Mutex::new(Vec::new()));
+        let mut schedule = Schedule::new();
+        for (label, options) in [
+            (
+                "render",
+                SystemOptions::labeled("render").with_after("physics"),
+            ),
+            ("physics", SystemOptions::labeled("physics")),
+            ("input", SystemOptions::new().with_before("physics")),
+        ] {
+            let order = order.clone();
+            schedule.add_system_with(
+                move |_: &ResourceContainer, _: &EventManager| order.lock().push(label),
+                options,
+            );
+        }
+
+        schedule.build().unwrap();
+        schedule.run(&ResourceContainer::default(), &EventManager::new());
+        assert_eq!(*order.lock(), vec!["input", "physics", "render"]);
+    }
+
+    #[test]
+    fn test_schedule_cyclic_order() {
+        let mut schedule = Schedule::new();
+        schedule
+            .add_system_with(|| {}, SystemOptions::labeled("a").with_after("b"))
+            .add_system_with(|| {}, SystemOptions::labeled("b").with_after("a"));
+
+        let error = schedule.build().unwrap_err();
+        assert!(matches!(
+            error,
+            EmarkError::CyclicSystemOrder {
+                stage: "update",
+                ..
+            }
+        ));
+        assert!(error.to_string().contains("(a) -> "));
+    }
+
+    #[test]
+    fn test_schedule_exclusive() {
+        let mut schedule = Schedule::new();
+        schedule
+            .add_exclusive_system(|world: &mut World| {
+                // assert exclusive systems run after the other systems of their stage
+                let value = *Res::<u32>::retrieve(world.container());
+                world.container_mut().add_resource(value as u64);
+            })
+            .add_system(|mut value: ResMut<u32>| *value += 1)
+            .add_system_to_stage(stage::POST_UPDATE, |value: Res<u64>| {
+                assert_eq!(*value, 2);
+            });
+        assert_eq!(schedule.len(), 3);
+
+        let mut world = World::new();
+        world.container_mut().add_resource(1u32);
+        schedule.run_world(&mut world);
+        assert!(world.container().contains_resource::<u64>());
+    }
+
+    #[test]
+    fn test_schedule_startup() {
+        let runs = Arc::new(AtomicUsize::new(0));
+        let startup_runs = runs.clone();
+        let mut schedule = Schedule::new();
+        schedule
+            .add_system(|value: Res<u32>, mut sum: ResMut<u64>| *sum += *value as u64)
+            .add_startup_system(move |_: &ResourceContainer, _: &EventManager| {
+                startup_runs.fetch_add(1, Ordering::SeqCst);
+            })
+            .add_exclusive_startup_system(|world: &mut World| {
+                world.container_mut().add_resource(2u32);
+                world.container_mut().add_resource(0u64);
+            });
+        assert_eq!(schedule.len(), 3);
+
+        // assert startup systems run once before the first cycle, then are removed
+        let mut world = World::new();
+        schedule.run_world(&mut world);
+        schedule.run_world(&mut world);
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+        assert_eq!(*Res::<u64>::retrieve(world.container()), 4);
+        assert_eq!(schedule.len(), 1);
+    }
+
+    #[test]
+    fn test_schedule_report() {
+        let mut registry = HandlerRegistry::new();
+        registry.add_handler(|_: &[TestEvent]| std::thread::sleep(Duration::from_millis(5)));
+
+        let mut schedule = Schedule::new();
+        schedule
+            .add_system(|_: &ResourceContainer, event_manager: &EventManager| {
+                std::thread::sleep(Duration::from_millis(10));
+                event_manager.emit(TestEvent(0));
+            })
+            .add_system_to_stage(stage::POST_UPDATE, registry);
+        let container = ResourceContainer::default();
+        let event_manager = EventManager::new();
+        schedule.run(&container, &event_manager);
+        assert!(schedule.report().is_none());
+
+        // assert systems and handler batches are timed while profiling
+        schedule.set_profiling(true);
+        schedule.run(&container, &event_manager);
+        let report = schedule.report().unwrap();
+        assert_eq!(report.systems().len(), 2);
+        assert_eq!(report.slowest_system().unwrap().stage(), stage::UPDATE);
+        assert!(report.systems()[0].duration() >= Duration::from_millis(10));
+        assert_eq!(report.handlers().len(), 1);
+        assert!(report.handlers()[0].duration() >= Duration::from_millis(5));
+        assert!(report.total() >= Duration::from_millis(15));
+    }
+
+    #[test]
+    fn test_schedule_to_dot() {
+        let mut schedule = Schedule::new();
+        schedule
+            .add_system_with(
+                |_: Res<u32>, _: ResMut<u64>| {},
+                SystemOptions::labeled("a"),
+            )
+            .add_system_with(
+                |_: Res<u32>| {},
+                SystemOptions::labeled("b").with_after("a"),
+            )
+            .add_exclusive_system_to_stage(stage::POST_UPDATE, |_: &mut World| {});
+
+        let dot = schedule.to_dot();
+        assert!(dot.starts_with("digraph schedule {"));
+        assert!(dot.contains("label=\"update\";"));
+        // assert ordering edges and resource accesses are described
+        assert!(dot.contains("s1_0 -> s1_1;"));
+        assert!(dot.contains("r0 -> s1_0 [style=dashed, label=\"read\"];"));
+        assert!(dot.contains("s1_0 -> r1 [color=red, label=\"write\"];"));
+        assert!(dot.contains("r0 -> s1_1 [style=dashed, label=\"read\"];"));
+        assert!(dot.contains("r0 [label=\"u32\", shape=box];"));
+        assert!(dot.contains("x2_0 ["));
+    }
+
+    #[test]
+    fn test_schedule_sets() {
+        let order = Arc::new(parking_lot::Mutex::new(Vec::new()));
+        let mut schedule = Schedule::new();
+        for (name, options) in [
+            ("step", SystemOptions::new().with_set("physics")),
+            ("input", SystemOptions::labeled("input")),
+            ("collide", SystemOptions::new().with_set("physics")),
+        ] {
+            let order = order.clone();
+            schedule.add_system_with(
+                move |_: &ResourceContainer, _: &EventManager| order.lock().push(name),
+                options,
+            );
+        }
+        schedule.configure_set(
+            "physics",
+            SetOptions::new().with_after("input").with_run_if(
+                |container: &ResourceContainer, _: &EventManager| {
+                    container.contains_resource::<u32>()
+                },
+            ),
+        );
+
+        // assert the configuration of the set applies to all of its systems
+        let mut container = ResourceContainer::default();
+        let event_manager = EventManager::new();
+        schedule.run(&container, &event_manager);
+        assert_eq!(*order.lock(), vec!["input"]);
+
+        container.add_resource(0u32);
+        schedule.run(&container, &event_manager);
+        assert_eq!(*order.lock(), vec!["input", "input", "step", "collide"]);
+
+        // assert disabled sets are skipped
+        assert!(schedule.disable_set("physics"));
+        assert!(!schedule.is_set_enabled("physics"));
+        schedule.run(&container, &event_manager);
+        assert_eq!(order.lock().len(), 5);
+        assert!(schedule.enable_set("physics"));
+    }
+
+    #[test]
+    #[should_panic(expected = "exclusive systems require `Schedule::run_world`")]
+    fn test_schedule_exclusive_without_world() {
+        let mut schedule = Schedule::new();
+        schedule.add_exclusive_system(|_: &mut World| {});
+        schedule.run(&ResourceContainer::default(), &EventManager::new());
+    }
+
+    #[test]
+    fn test_schedule_spawn_despawn() {
+        let mut schedule = Schedule::new();
+        let id = schedule.spawn_system(
+            |mut log: ResMut<Vec<&'static str>>| log.push("spawned"),
+            SystemOptions::labeled("spawned"),
+        );
+        let once = schedule.add_system_once(|mut log: ResMut<Vec<&'static str>>| log.push("once"));
+        schedule.add_system_once_in(1, |mut log: ResMut<Vec<&'static str>>| log.push("later"));
+
+        let mut container = ResourceContainer::default();
+        container.add_resource(Vec::<&'static str>::new());
+        let event_manager = EventManager::new();
+        schedule.run(&container, &event_manager);
+        assert!(!schedule.contains_system(once));
+
+        // assert replaced systems keep their position
+        assert!(
+            schedule.replace_system(id, |mut log: ResMut<Vec<&'static str>>| {
+                log.push("replaced")
+            })
+        );
+        schedule.run(&container, &event_manager);
+        assert!(schedule.despawn_system(id));
+        assert!(!schedule.despawn_system(id));
+        schedule.run(&container, &event_manager);
+
+        assert_eq!(
+            *Res::<Vec<&'static str>>::retrieve(&container),
+            vec!["spawned", "once", "replaced", "later"]
+        );
+        assert!(schedule.is_empty());
+    }
+
+    #[test]
+    fn test_schedule_error_policy() {
+        fn fail() -> Result<(), EmarkError> {
+            Err(EmarkError::Custom("failed".to_owned()))
+        }
+
+        let run = |policy: ErrorPolicy| {
+            let log =
+                |entry: &'static str| move |mut log: ResMut<Vec<&'static str>>| log.push(entry);
+            let mut schedule = Schedule::new();
+            schedule
+                .set_error_policy(policy)
+                .add_system_with(fail, SystemOptions::labeled("fail"))
+                .add_system_with(log("dependent"), SystemOptions::new().with_after("fail"))
+                .add_system(log("independent"))
+                .add_system_to_stage(stage::POST_UPDATE, log("post update"));
+
+            let mut container = ResourceContainer::default();
+            container.add_resource(Vec::<&'static str>::new());
+            let event_manager = EventManager::new();
+            schedule.run(&container, &event_manager);
+            let errors = event_manager
+                .peek(|errors: &[SystemErrorEvent]| errors.to_vec())
+                .unwrap_or_default();
+            let log = Res::<Vec<&'static str>>::retrieve(&container).clone();
+            (log, errors)
+        };
+
+        let (log, errors) = run(ErrorPolicy::Emit);
+        assert_eq!(log, vec!["dependent", "independent", "post update"]);
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].system().contains("fail"));
+        assert_eq!(*errors[0].error(), EmarkError::Custom("failed".to_owned()));
+
+        let (log, errors) = run(ErrorPolicy::SkipDependents);
+        assert_eq!(log, vec!["independent", "post update"]);
+        assert_eq!(errors.len(), 1);
+
+        let (log, errors) = run(ErrorPolicy::AbortCycle);
+        assert!(log.is_empty());
+        assert_eq!(errors.len(), 1);
+
+        // assert logged errors are not emitted
+        let (log, errors) = run(ErrorPolicy::Log);
+        assert_eq!(log.len(), 3);
+        assert!(errors.is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "failed: missing level")]
+    fn test_schedule_error_panic() {
+        let mut schedule = Schedule::new();
+        schedule.add_system(|| -> Result<(), EmarkError> {
+            Err(EmarkError::Custom("missing level".to_owned()))
+        });
+        schedule.run(&ResourceContainer::default(), &EventManager::new());
+    }
+
+    #[test]
+    #[should_panic(expected = "stage `missing` does not exist")]
+    fn test_schedule_missing_stage() {
+        Schedule::new().add_system_to_stage("missing", || {});
+    }
+
+    #[test]
+    fn test_schedule_dispatch() {
+        let sum = Arc::new(AtomicUsize::new(0));
+        let mut registry = HandlerRegistry::new();
+        let handler_sum = sum.clone();
+        registry.add_handler(move |events: &[TestEvent]| {
+            for event in events {
+                handler_sum.fetch_add(event.0, Ordering::SeqCst);
+            }
+        });
+
+        let mut schedule = Schedule::new();
+        schedule
+            .add_system(
+                |container: &ResourceContainer, event_manager: &EventManager| {
+                    if container.contains_resource::<usize>() {
+                        event_manager.emit(TestEvent(2));
+                    }
+                },
+            )
+            .add_system(registry);
+
+        // assert events emitted by systems are dispatched within the same cycle
+        let mut container = ResourceContainer::default();
+        container.add_resource(0usize);
+        let event_manager = EventManager::new();
+        schedule.run(&container, &event_manager);
+        schedule.run(&container, &event_manager);
+        assert_eq!(sum.load(Ordering::SeqCst), 4);
+    }
+}
@@ -0,0 +1,164 @@
+use std::{
+    fmt::Debug,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use parking_lot::RwLock;
+
+use crate::{
+    event::{EventManager, HandlerTiming},
+    store::ResourceContainer,
+    utils::error::EmarkError,
+};
+
+use super::{Access, System};
+
+// condition deciding whether a system runs in the current cycle
+pub(crate) type RunCondition = Arc<dyn Fn(&ResourceContainer, &EventManager) -> bool + Send + Sync>;
+
+#[derive(Default, Clone)]
+/// Configuration shared by the systems of a set.
+///
+/// Systems join a set through `SystemOptions::with_set`. The ordering constraints of a set
+/// apply to every system of the set, and its run conditions must hold for any of them to run.
+/// Labels and set names share the same namespace, so a system can be ordered before or after
+/// a whole set.
+///
+/// # Examples
+/// ```
+/// use emark::event::EventManager;
+/// use emark::prelude::*;
+/// use emark::store::ResourceContainer;
+/// use emark::system::{Schedule, SetOptions, SystemOptions};
+///
+/// let mut schedule = Schedule::new();
+/// schedule
+///     .configure_set(
+///         "physics",
+///         SetOptions::new()
+///             .with_after("input")
+///             .with_run_if(|container: &ResourceContainer, _: &EventManager| {
+///                 container.contains_resource::<u32>()
+///             }),
+///     )
+///     .add_system_with(|| {}, SystemOptions::new().with_set("physics"))
+///     .add_system_with(|| {}, SystemOptions::new().with_set("physics"))
+///     .add_system_with(|| {}, SystemOptions::labeled("input"));
+/// assert!(schedule.build().is_ok());
+/// ```
+pub struct SetOptions {
+    pub(crate) before: Vec<&'static str>,
+    pub(crate) after: Vec<&'static str>,
+    conditions: Vec<RunCondition>,
+}
+
+impl SetOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Executes the systems of the set before the systems labeled `label`.
+    pub fn with_before(mut self, label: &'static str) -> Self {
+        self.before.push(label);
+        self
+    }
+
+    /// Executes the systems of the set after the systems labeled `label`.
+    pub fn with_after(mut self, label: &'static str) -> Self {
+        self.after.push(label);
+        self
+    }
+
+    /// Runs the systems of the set only in the cycles where `condition` holds.
+    ///
+    /// The condition is evaluated before each system of the set.
+    pub fn with_run_if(
+        mut self,
+        condition: impl Fn(&ResourceContainer, &EventManager) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.conditions.push(Arc::new(condition));
+        self
+    }
+}
+
+impl Debug for SetOptions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SetOptions")
+            .field("before", &self.before)
+            .field("after", &self.after)
+            .field("conditions", &self.conditions.len())
+            .finish()
+    }
+}
+
+#[derive(Debug, Default)]
+// state of a set, shared with its systems
+pub(crate) struct SetState {
+    disabled: AtomicBool,
+    pub(crate) options: RwLock<SetOptions>,
+}
+
+impl SetState {
+    // returns `true` if the state changed
+    pub(crate) fn set_enabled(&self, enabled: bool) -> bool {
+        self.disabled.swap(!enabled, Ordering::AcqRel) == enabled
+    }
+
+    pub(crate) fn is_enabled(&self) -> bool {
+        !self.disabled.load(Ordering::Acquire)
+    }
+}
+
+// system run only if its set is enabled and every run condition holds
+pub(crate) struct ConditionalSystem {
+    pub(crate) system: Box<dyn System>,
+    pub(crate) conditions: Vec<RunCondition>,
+    pub(crate) set: Option<Arc<SetState>>,
+}
+
+impl ConditionalSystem {
+    fn should_run(&self, container: &ResourceContainer, event_manager: &EventManager) -> bool {
+        let holds = |conditions: &[RunCondition]| {
+            conditions
+                .iter()
+                .all(|condition| condition(container, event_manager))
+        };
+        let set_holds = self
+            .set
+            .as_ref()
+            .is_none_or(|set| set.is_enabled() && holds(&set.options.read().conditions));
+        set_holds && holds(&self.conditions)
+    }
+}
+
+impl System for ConditionalSystem {
+    fn name(&self) -> &str {
+        self.system.name()
+    }
+
+    fn run(
+        &mut self,
+        container: &ResourceContainer,
+        event_manager: &EventManager,
+    ) -> Result<(), EmarkError> {
+        match self.should_run(container, event_manager) {
+            true => self.system.run(container, event_manager),
+            false => Ok(()),
+        }
+    }
+
+    fn access(&self) -> Access {
+        self.system.access()
+    }
+
+    fn set_profiling(&mut self, enabled: bool) {
+        self.system.set_profiling(enabled);
+    }
+
+    fn take_handler_timings(&mut self) -> Vec<HandlerTiming> {
+        self.system.take_handler_timings()
+    }
+}
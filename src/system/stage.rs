@@ -0,0 +1,507 @@
+use std::{
+    any::TypeId,
+    collections::HashMap,
+    fmt::{Debug, Write},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use crate::{event::EventManager, store::ResourceContainer, utils::error::EmarkError};
+
+use super::{
+    report::{ScheduleReport, SystemTimer, TimedSystem},
+    set::{RunCondition, SetState},
+    ExclusiveSystem, System, SystemId,
+};
+
+/// Label of the stage running before the update, e.g. event dispatch.
+pub const PRE_UPDATE: &str = "pre_update";
+/// Label of the default stage, e.g. simulation.
+pub const UPDATE: &str = "update";
+/// Label of the stage running after the update, e.g. rendering preparation.
+pub const POST_UPDATE: &str = "post_update";
+
+#[derive(Default, Clone)]
+/// Registration options of a system.
+///
+/// A system may be given a label, which other systems of the same stage can
+/// reference to be executed before or after it. Systems without constraints are
+/// executed in the order they were added.
+///
+/// A system may also belong to a set sharing its configuration, see
+/// [SetOptions](crate::system::SetOptions), and be given run conditions.
+///
+/// # Examples
+/// ```
+/// use emark::system::{Schedule, SystemOptions};
+///
+/// let mut schedule = Schedule::new();
+/// schedule
+///     .add_system_with(|| {}, SystemOptions::labeled("render").with_after("physics"))
+///     .add_system_with(|| {}, SystemOptions::labeled("physics"));
+/// assert!(schedule.build().is_ok());
+/// ```
+pub struct SystemOptions {
+    label: Option<&'static str>,
+    before: Vec<&'static str>,
+    after: Vec<&'static str>,
+    set: Option<&'static str>,
+    pub(crate) conditions: Vec<RunCondition>,
+}
+
+impl SystemOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Options with the label `label`.
+    pub fn labeled(label: &'static str) -> Self {
+        Self::new().with_label(label)
+    }
+
+    /// Sets the label of the system.
+    pub fn with_label(mut self, label: &'static str) -> Self {
+        self.label = Some(label);
+        self
+    }
+
+    /// Executes the system before the systems labeled `label`.
+    pub fn with_before(mut self, label: &'static str) -> Self {
+        self.before.push(label);
+        self
+    }
+
+    /// Executes the system after the systems labeled `label`.
+    pub fn with_after(mut self, label: &'static str) -> Self {
+        self.after.push(label);
+        self
+    }
+
+    /// Adds the system to the set `set`.
+    pub fn with_set(mut self, set: &'static str) -> Self {
+        self.set = Some(set);
+        self
+    }
+
+    /// Runs the system only in the cycles where `condition` holds.
+    pub fn with_run_if(
+        mut self,
+        condition: impl Fn(&ResourceContainer, &EventManager) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.conditions.push(Arc::new(condition));
+        self
+    }
+
+    pub fn label(&self) -> Option<&'static str> {
+        self.label
+    }
+
+    pub fn set(&self) -> Option<&'static str> {
+        self.set
+    }
+
+    // ordering constraints of the system, including the ones of its set
+    fn order(&self, sets: &HashMap<&'static str, Arc<SetState>>) -> Order {
+        let mut order = Order {
+            names: self.label.into_iter().chain(self.set).collect(),
+            before: self.before.clone(),
+            after: self.after.clone(),
+        };
+        if let Some(state) = self.set.and_then(|set| sets.get(set)) {
+            let options = state.options.read();
+            order.before.extend(&options.before);
+            order.after.extend(&options.after);
+        }
+        order
+    }
+}
+
+// resolved ordering constraints of a system
+struct Order {
+    names: Vec<&'static str>,
+    before: Vec<&'static str>,
+    after: Vec<&'static str>,
+}
+
+impl Order {
+    // true if the system must be executed before the system with order `other`
+    fn precedes(&self, other: &Order) -> bool {
+        let before = other.names.iter().any(|name| self.before.contains(name));
+        let after = self.names.iter().any(|name| other.after.contains(name));
+        before || after
+    }
+}
+
+impl Debug for SystemOptions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SystemOptions")
+            .field("label", &self.label)
+            .field("before", &self.before)
+            .field("after", &self.after)
+            .field("set", &self.set)
+            .field("conditions", &self.conditions.len())
+            .finish()
+    }
+}
+
+// named group of systems, separated from the other stages by a barrier
+pub(crate) struct Stage {
+    pub(crate) label: &'static str,
+    pub(crate) systems: Vec<Box<dyn System>>,
+    pub(crate) options: Vec<SystemOptions>,
+    pub(crate) ids: Vec<SystemId>,
+    // timers of the systems, filled while profiling
+    pub(crate) timers: Vec<Arc<SystemTimer>>,
+    // systems run at the barrier closing the stage
+    pub(crate) exclusive: Vec<Box<dyn ExclusiveSystem>>,
+    // indices of the systems each system must be executed after, once sorted
+    pub(crate) dependencies: Vec<Vec<usize>>,
+    pub(crate) sorted: bool,
+}
+
+impl Stage {
+    pub(crate) fn new(label: &'static str) -> Self {
+        Self {
+            label,
+            systems: Vec::new(),
+            options: Vec::new(),
+            ids: Vec::new(),
+            timers: Vec::new(),
+            exclusive: Vec::new(),
+            dependencies: Vec::new(),
+            sorted: true,
+        }
+    }
+
+    pub(crate) fn push(
+        &mut self,
+        id: SystemId,
+        system: Box<dyn System>,
+        options: SystemOptions,
+        profiling: &Arc<AtomicBool>,
+    ) {
+        let timer = Arc::new(SystemTimer::default());
+        self.systems.push(timed(system, profiling, &timer));
+        self.options.push(options);
+        self.ids.push(id);
+        self.timers.push(timer);
+        self.sorted = false;
+    }
+
+    // position of the system `id`
+    pub(crate) fn position(&self, id: SystemId) -> Option<usize> {
+        self.ids.iter().position(|&other| other == id)
+    }
+
+    // removes the system `id`, returns `true` if it was in the stage
+    pub(crate) fn remove(&mut self, id: SystemId) -> bool {
+        let Some(index) = self.position(id) else {
+            return false;
+        };
+        self.systems.remove(index);
+        self.options.remove(index);
+        self.ids.remove(index);
+        self.timers.remove(index);
+        self.sorted = false;
+        true
+    }
+
+    // replaces the system at `index`, keeping its options and its position
+    pub(crate) fn replace(
+        &mut self,
+        index: usize,
+        system: Box<dyn System>,
+        profiling: &Arc<AtomicBool>,
+    ) {
+        self.systems[index] = timed(system, profiling, &self.timers[index]);
+    }
+
+    // names of the systems, in execution order
+    pub(crate) fn system_names(&self) -> impl Iterator<Item = &str> {
+        let systems = self.systems.iter().map(|system| system.name());
+        systems.chain(self.exclusive.iter().map(|system| system.name()))
+    }
+
+    // record the timings of the last run of the systems
+    pub(crate) fn record(&mut self, report: &mut ScheduleReport) {
+        for (system, timer) in self.systems.iter_mut().zip(&self.timers) {
+            report.push_system(self.label, system.name(), timer.take());
+            report.handlers.extend(system.take_handler_timings());
+        }
+    }
+
+    // resolved ordering constraints of the systems
+    fn orders(&self, sets: &HashMap<&'static str, Arc<SetState>>) -> Vec<Order> {
+        self.options
+            .iter()
+            .map(|options| options.order(sets))
+            .collect()
+    }
+
+    // write the systems of the stage as a DOT cluster, along with their ordering edges
+    // and their resource accesses, resource nodes are numbered through `resources`
+    pub(crate) fn write_dot(
+        &self,
+        index: usize,
+        sets: &HashMap<&'static str, Arc<SetState>>,
+        resources: &mut Vec<(TypeId, String)>,
+        out: &mut String,
+    ) -> std::fmt::Result {
+        writeln!(out, "    subgraph cluster_{index} {{")?;
+        writeln!(out, "        label=\"{}\";", escape(self.label))?;
+        for system_index in 0..self.systems.len() {
+            let label = escape(&self.describe(system_index));
+            writeln!(out, "        s{index}_{system_index} [label=\"{label}\"];")?;
+        }
+        for (system_index, system) in self.exclusive.iter().enumerate() {
+            let label = escape(system.name());
+            writeln!(
+                out,
+                "        x{index}_{system_index} [label=\"{label}\", style=bold];"
+            )?;
+        }
+        writeln!(out, "    }}")?;
+
+        // ordering edges
+        let orders = self.orders(sets);
+        for (earlier, order) in orders.iter().enumerate() {
+            for (later, other) in orders.iter().enumerate() {
+                if order.precedes(other) {
+                    writeln!(out, "    s{index}_{earlier} -> s{index}_{later};")?;
+                }
+            }
+        }
+
+        // resource accesses
+        for (system_index, system) in self.systems.iter().enumerate() {
+            let access = system.access();
+            let mut resource = |type_id: TypeId| {
+                let name = access
+                    .type_name(type_id)
+                    .map_or_else(|| format!("{type_id:?}"), str::to_string);
+                match resources.iter().position(|(id, _)| *id == type_id) {
+                    Some(position) => position,
+                    None => {
+                        resources.push((type_id, name));
+                        resources.len() - 1
+                    }
+                }
+            };
+            for &type_id in access.reads() {
+                let node = resource(type_id);
+                writeln!(
+                    out,
+                    "    r{node} -> s{index}_{system_index} [style=dashed, label=\"read\"];"
+                )?;
+            }
+            for &type_id in access.writes() {
+                let node = resource(type_id);
+                writeln!(
+                    out,
+                    "    s{index}_{system_index} -> r{node} [color=red, label=\"write\"];"
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    // sort systems by their ordering constraints, keeping the insertion order otherwise
+    pub(crate) fn sort(
+        &mut self,
+        sets: &HashMap<&'static str, Arc<SetState>>,
+    ) -> Result<(), EmarkError> {
+        if self.sorted {
+            return Ok(());
+        }
+
+        let count = self.systems.len();
+        let orders = self.orders(sets);
+        let precedes = |earlier: usize, later: usize| orders[earlier].precedes(&orders[later]);
+
+        // count the systems preceding each system
+        let mut preceding = (0..count)
+            .map(|later| {
+                (0..count)
+                    .filter(|&earlier| precedes(earlier, later))
+                    .count()
+            })
+            .collect::<Vec<_>>();
+
+        // repeatedly take the first added system without preceding systems
+        let mut order = Vec::with_capacity(count);
+        let mut taken = vec![false; count];
+        while order.len() < count {
+            let Some(next) = (0..count).find(|&index| !taken[index] && preceding[index] == 0)
+            else {
+                let remaining = (0..count).filter(|&index| !taken[index]).collect();
+                return Err(EmarkError::CyclicSystemOrder {
+                    stage: self.label,
+                    systems: self.find_cycle(&orders, remaining),
+                });
+            };
+
+            taken[next] = true;
+            order.push(next);
+            for later in 0..count {
+                if !taken[later] && precedes(next, later) {
+                    preceding[later] -= 1;
+                }
+            }
+        }
+
+        // record dependencies with the sorted indices
+        let mut position = vec![0; count];
+        for (sorted, &index) in order.iter().enumerate() {
+            position[index] = sorted;
+        }
+        let mut dependencies = vec![Vec::new(); count];
+        for later in 0..count {
+            for earlier in 0..count {
+                if precedes(earlier, later) {
+                    dependencies[position[later]].push(position[earlier]);
+                }
+            }
+        }
+
+        // reorder systems
+        let mut systems = std::mem::take(&mut self.systems)
+            .into_iter()
+            .zip(std::mem::take(&mut self.options))
+            .zip(std::mem::take(&mut self.ids))
+            .zip(std::mem::take(&mut self.timers))
+            .map(Some)
+            .collect::<Vec<_>>();
+        for index in order {
+            let (((system, options), id), timer) = systems[index].take().unwrap();
+            self.systems.push(system);
+            self.options.push(options);
+            self.ids.push(id);
+            self.timers.push(timer);
+        }
+        self.dependencies = dependencies;
+        self.sorted = true;
+        Ok(())
+    }
+
+    // find a cycle among the systems that could not be sorted,
+    // returned as the names of the systems along the cycle
+    fn find_cycle(&self, orders: &[Order], remaining: Vec<usize>) -> Vec<String> {
+        // every remaining system has a remaining predecessor,
+        // walking predecessors eventually visits a system twice
+        let mut path = vec![remaining[0]];
+        loop {
+            let current = *path.last().unwrap();
+            let previous = remaining
+                .iter()
+                .copied()
+                .find(|&earlier| orders[earlier].precedes(&orders[current]))
+                .unwrap();
+
+            if let Some(start) = path.iter().position(|&index| index == previous) {
+                // path is walked backwards, reverse it into execution order
+                // starting from the first added system
+                let mut cycle = path[start..].to_vec();
+                cycle.reverse();
+                let first = (0..cycle.len()).min_by_key(|&index| cycle[index]).unwrap();
+                cycle.rotate_left(first);
+                cycle.push(cycle[0]);
+                return cycle
+                    .into_iter()
+                    .map(|index| self.describe(index))
+                    .collect();
+            }
+            path.push(previous);
+        }
+    }
+
+    fn describe(&self, index: usize) -> String {
+        let name = self.systems[index].name();
+        match self.options[index].label {
+            Some(label) => format!("{name} ({label})"),
+            None => name.to_string(),
+        }
+    }
+}
+
+// wrap a system to be timed by `timer` while profiling
+fn timed(
+    mut system: Box<dyn System>,
+    profiling: &Arc<AtomicBool>,
+    timer: &Arc<SystemTimer>,
+) -> Box<dyn System> {
+    system.set_profiling(profiling.load(Ordering::Acquire));
+    Box::new(TimedSystem {
+        system,
+        profiling: profiling.clone(),
+        timer: timer.clone(),
+    })
+}
+
+// escape a DOT string literal
+pub(crate) fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod test_stage {
+    use super::*;
+    use crate::system::IntoSystem;
+
+    fn stage(options: Vec<SystemOptions>) -> Stage {
+        let mut stage = Stage::new("test");
+        for (index, options) in options.into_iter().enumerate() {
+            stage.push(
+                SystemId(index as u64),
+                Box::new((|| {}).into_system()),
+                options,
+                &Default::default(),
+            );
+        }
+        stage
+    }
+
+    #[test]
+    fn test_stage_sort() {
+        let mut stage = stage(vec![
+            SystemOptions::labeled("render").with_after("physics"),
+            SystemOptions::new(),
+            SystemOptions::labeled("physics"),
+            SystemOptions::labeled("input").with_before("physics"),
+        ]);
+        stage.sort(&HashMap::new()).unwrap();
+
+        let labels = stage
+            .options
+            .iter()
+            .map(SystemOptions::label)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            labels,
+            vec![None, Some("input"), Some("physics"), Some("render")]
+        );
+        assert_eq!(stage.dependencies, vec![vec![], vec![], vec![1], vec![2]]);
+    }
+
+    #[test]
+    fn test_stage_cycle() {
+        let mut stage = stage(vec![
+            SystemOptions::new(),
+            SystemOptions::labeled("a").with_after("c"),
+            SystemOptions::labeled("b").with_after("a"),
+            SystemOptions::labeled("c").with_after("b"),
+        ]);
+
+        let Err(EmarkError::CyclicSystemOrder { stage, systems }) = stage.sort(&HashMap::new())
+        else {
+            panic!("cycle not detected");
+        };
+        assert_eq!(stage, "test");
+        let labels = systems
+            .iter()
+            .map(|system| system.rsplit(' ').next().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(labels, vec!["(a)", "(b)", "(c)", "(a)"]);
+    }
+}
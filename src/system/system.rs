@@ -0,0 +1,110 @@
+use crate::{
+    event::{EventManager, HandlerRegistry, HandlerTiming},
+    store::ResourceContainer,
+    utils::error::EmarkError,
+    world::World,
+};
+
+use super::Access;
+
+/// A unit of work executed by a [Schedule](crate::system::Schedule).
+///
+/// Closures taking the `ResourceContainer` and the `EventManager` are systems.
+/// A `HandlerRegistry` is also a system, dispatching the pending events to its handlers.
+pub trait System: Send + Sync {
+    /// Name of the system, used for diagnostics.
+    fn name(&self) -> &str;
+
+    /// Executes the system.
+    ///
+    /// A returned error is handled by the [ErrorPolicy](crate::system::ErrorPolicy) of the
+    /// schedule.
+    fn run(
+        &mut self,
+        container: &ResourceContainer,
+        event_manager: &EventManager,
+    ) -> Result<(), EmarkError>;
+
+    /// Resources accessed by the system, used by executors to run systems concurrently.
+    ///
+    /// Defaults to an exclusive access.
+    fn access(&self) -> Access {
+        Access::exclusive()
+    }
+
+    /// Enables or disables the profiling of the work done within the system, such as the
+    /// handlers of a `HandlerRegistry`. Called by the schedule.
+    fn set_profiling(&mut self, _enabled: bool) {}
+
+    /// Takes the handler timings recorded while profiling since the last call.
+    fn take_handler_timings(&mut self) -> Vec<HandlerTiming> {
+        Vec::new()
+    }
+}
+
+impl<F> System for F
+where
+    F: FnMut(&ResourceContainer, &EventManager) + Send + Sync,
+{
+    fn name(&self) -> &str {
+        std::any::type_name::<F>()
+    }
+
+    fn run(
+        &mut self,
+        container: &ResourceContainer,
+        event_manager: &EventManager,
+    ) -> Result<(), EmarkError> {
+        self(container, event_manager);
+        Ok(())
+    }
+}
+
+/// A unit of work with full access to the [World], executed by a
+/// [Schedule](crate::system::Schedule).
+///
+/// Exclusive systems run at the barrier closing their stage, once every other system of the
+/// stage has finished. Closures taking `&mut World` are exclusive systems.
+pub trait ExclusiveSystem: Send + Sync {
+    /// Name of the system, used for diagnostics.
+    fn name(&self) -> &str;
+
+    /// Executes the system.
+    fn run(&mut self, world: &mut World);
+}
+
+impl<F> ExclusiveSystem for F
+where
+    F: FnMut(&mut World) + Send + Sync,
+{
+    fn name(&self) -> &str {
+        std::any::type_name::<F>()
+    }
+
+    fn run(&mut self, world: &mut World) {
+        self(world)
+    }
+}
+
+impl System for HandlerRegistry {
+    fn name(&self) -> &str {
+        std::any::type_name::<HandlerRegistry>()
+    }
+
+    fn run(
+        &mut self,
+        _: &ResourceContainer,
+        event_manager: &EventManager,
+    ) -> Result<(), EmarkError> {
+        self.dispatch(event_manager);
+        Ok(())
+    }
+
+    fn set_profiling(&mut self, enabled: bool) {
+        HandlerRegistry::set_profiling(self, enabled);
+    }
+
+    fn take_handler_timings(&mut self) -> Vec<HandlerTiming> {
+        self.take_timings()
+    }
+}
@@ -1 +1,187 @@
-// This module is for Error definition
\ No newline at end of file
+// This module is for Error definition
+use std::{error::Error, fmt::Display};
+
+use crate::store::RetrievalError;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+/// Error of emark.
+pub enum EmarkError {
+    /// The event name is already registered for another event type.
+    DuplicateEventName(&'static str),
+    /// The event type is already registered with another name.
+    DuplicateEventType(&'static str),
+    /// The upgrade is not between two increasing versions of the same event.
+    InvalidUpgrade {
+        name: &'static str,
+        from: u32,
+        to: u32,
+    },
+    /// The version of the event already has an upgrade.
+    DuplicateUpgrade { name: &'static str, version: u32 },
+    /// The version of the event has no upgrade.
+    MissingUpgrade { name: String, version: u32 },
+    /// The type-erased payload is not of the expected type.
+    PayloadMismatch { expected: &'static str },
+    /// The pending event type is not registered for snapshots.
+    UnregisteredSnapshot(&'static str),
+    /// The resource type is not registered for snapshots.
+    UnregisteredResourceSnapshot(&'static str),
+    /// The container already holds a resource of the type.
+    DuplicateResource(&'static str),
+    /// The resource name is already registered for another resource type.
+    DuplicateResourceName(&'static str),
+    /// The persisted data holds a resource whose name is not registered.
+    UnknownResourceName(String),
+    /// The dependencies of the resources being initialized are cyclic.
+    CyclicResourceDependency(Vec<&'static str>),
+    /// The resource depends on a resource missing from the container.
+    MissingResourceDependency {
+        resource: &'static str,
+        dependency: &'static str,
+    },
+    /// The execution order of the handlers of the event type is cyclic.
+    CyclicHandlerOrder(&'static str),
+    /// The ordering constraints of the systems of the stage are cyclic.
+    CyclicSystemOrder {
+        stage: &'static str,
+        systems: Vec<String>,
+    },
+    /// The dynamic library can not be loaded.
+    LibraryLoad { path: String, reason: String },
+    /// The dynamic library does not export the symbol.
+    MissingSymbol { path: String, symbol: String },
+    /// The dynamic library is built against another hot reload ABI.
+    AbiMismatch {
+        path: String,
+        found: String,
+        expected: String,
+    },
+    /// A resource can not be retrieved from the container.
+    Retrieval(RetrievalError),
+    /// Reading or writing persisted data failed.
+    Io(String),
+    /// A handler receiving a `Context` of event type `.0` is dispatched without a container,
+    /// see `HandlerRegistry::dispatch_with`.
+    MissingDispatchContainer(&'static str),
+    /// Error raised by user code, such as a fallible system.
+    Custom(String),
+}
+
+impl From<RetrievalError> for EmarkError {
+    fn from(error: RetrievalError) -> Self {
+        EmarkError::Retrieval(error)
+    }
+}
+
+impl Display for EmarkError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EmarkError::DuplicateEventName(name) => {
+                write!(f, "event name `{name}` is already registered")
+            }
+            EmarkError::DuplicateEventType(type_name) => {
+                write!(f, "event type `{type_name}` is already registered")
+            }
+            EmarkError::InvalidUpgrade { name, from, to } => {
+                write!(f, "invalid upgrade of `{name}` from version {from} to {to}")
+            }
+            EmarkError::DuplicateUpgrade { name, version } => {
+                write!(f, "version {version} of `{name}` already has an upgrade")
+            }
+            EmarkError::MissingUpgrade { name, version } => {
+                write!(f, "version {version} of `{name}` has no upgrade")
+            }
+            EmarkError::PayloadMismatch { expected } => {
+                write!(f, "payload is not of type `{expected}`")
+            }
+            EmarkError::UnregisteredSnapshot(type_name) => {
+                write!(
+                    f,
+                    "event type `{type_name}` is not registered for snapshots"
+                )
+            }
+            EmarkError::UnregisteredResourceSnapshot(type_name) => {
+                write!(f, "resource `{type_name}` is not registered for snapshots")
+            }
+            EmarkError::DuplicateResource(type_name) => {
+                write!(f, "resource `{type_name}` already exists")
+            }
+            EmarkError::DuplicateResourceName(name) => {
+                write!(f, "resource name `{name}` is already registered")
+            }
+            EmarkError::UnknownResourceName(name) => {
+                write!(f, "resource name `{name}` is not registered")
+            }
+            EmarkError::CyclicResourceDependency(resources) => {
+                write!(
+                    f,
+                    "resources have a cyclic dependency: {}",
+                    resources.join(" -> ")
+                )
+            }
+            EmarkError::MissingResourceDependency {
+                resource,
+                dependency,
+            } => {
+                write!(
+                    f,
+                    "resource `{resource}` depends on missing resource `{dependency}`"
+                )
+            }
+            EmarkError::CyclicHandlerOrder(type_name) => {
+                write!(
+                    f,
+                    "execution order of the handlers of `{type_name}` is cyclic"
+                )
+            }
+            EmarkError::CyclicSystemOrder { stage, systems } => {
+                write!(
+                    f,
+                    "systems of stage `{stage}` have a cyclic order: {}",
+                    systems.join(" -> ")
+                )
+            }
+            EmarkError::LibraryLoad { path, reason } => {
+                write!(f, "library `{path}` can not be loaded: {reason}")
+            }
+            EmarkError::MissingSymbol { path, symbol } => {
+                write!(f, "library `{path}` does not export `{symbol}`")
+            }
+            EmarkError::AbiMismatch {
+                path,
+                found,
+                expected,
+            } => {
+                write!(
+                    f,
+                    "library `{path}` has ABI version {found}, expected {expected}"
+                )
+            }
+            EmarkError::Retrieval(error) => write!(f, "{error}"),
+            EmarkError::Io(reason) => write!(f, "i/o error: {reason}"),
+            EmarkError::MissingDispatchContainer(event_type_name) => {
+                write!(
+                    f,
+                    "handler of `{event_type_name}` needs a container, dispatch with `dispatch_with`"
+                )
+            }
+            EmarkError::Custom(reason) => write!(f, "{reason}"),
+        }
+    }
+}
+
+impl Error for EmarkError {}
+
+#[cfg(test)]
+mod test_error {
+    use super::EmarkError;
+
+    #[test]
+    fn test_display() {
+        assert_eq!(
+            EmarkError::DuplicateEventName("app.save").to_string(),
+            "event name `app.save` is already registered"
+        );
+    }
+}
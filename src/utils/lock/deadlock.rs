@@ -0,0 +1,250 @@
+//! Wait-for graph of the `GrainedLock`s, checked before a thread blocks on a lock with the
+//! `deadlock-detection` feature.
+//!
+//! Every lock records the threads holding it. A thread about to block on a lock follows the
+//! holders of the lock to the locks they wait for, and so on: reaching the thread again means
+//! that no thread of the cycle can make progress, so the thread panics with the types of the
+//! locks of the cycle instead of hanging. The panic releases the locks held by the thread,
+//! letting the other threads of the cycle go on.
+
+use std::{
+    collections::HashMap,
+    mem::ManuallyDrop,
+    sync::OnceLock,
+    thread::{self, ThreadId},
+};
+
+use parking_lot::Mutex;
+
+// threads holding each lock, and the lock each blocked thread waits for
+#[derive(Default)]
+struct WaitForGraph {
+    holders: HashMap<usize, (&'static str, Vec<ThreadId>)>,
+    waiting: HashMap<ThreadId, usize>,
+}
+
+impl WaitForGraph {
+    fn hold(&mut self, lock: usize, type_name: &'static str, thread: ThreadId) {
+        let (_, threads) = self
+            .holders
+            .entry(lock)
+            .or_insert_with(|| (type_name, Vec::new()));
+        threads.push(thread);
+    }
+
+    fn release(&mut self, lock: usize, thread: ThreadId) {
+        let Some((_, threads)) = self.holders.get_mut(&lock) else {
+            return;
+        };
+        if let Some(index) = threads.iter().position(|&holder| holder == thread) {
+            threads.swap_remove(index);
+        }
+        if threads.is_empty() {
+            self.holders.remove(&lock);
+        }
+    }
+
+    // type names of the locks of the cycle through `thread` waiting for `lock`, if any
+    fn cycle(
+        &self,
+        thread: ThreadId,
+        lock: usize,
+        type_name: &'static str,
+    ) -> Option<Vec<&'static str>> {
+        let mut path = vec![type_name];
+        let mut visited = vec![lock];
+        self.search(thread, lock, &mut path, &mut visited)
+            .then_some(path)
+    }
+
+    fn search(
+        &self,
+        thread: ThreadId,
+        lock: usize,
+        path: &mut Vec<&'static str>,
+        visited: &mut Vec<usize>,
+    ) -> bool {
+        let Some((_, holders)) = self.holders.get(&lock) else {
+            return false;
+        };
+        for holder in holders {
+            if *holder == thread {
+                return true;
+            }
+            let Some(&next) = self.waiting.get(holder) else {
+                continue;
+            };
+            if visited.contains(&next) {
+                continue;
+            }
+            visited.push(next);
+            path.push(
+                self.holders
+                    .get(&next)
+                    .map_or("?", |(type_name, _)| type_name),
+            );
+            if self.search(thread, next, path, visited) {
+                return true;
+            }
+            path.pop();
+        }
+        false
+    }
+}
+
+fn graph() -> &'static Mutex<WaitForGraph> {
+    static GRAPH: OnceLock<Mutex<WaitForGraph>> = OnceLock::new();
+    GRAPH.get_or_init(Default::default)
+}
+
+// guard of a lock, released from the graph on drop
+pub(crate) struct Tracked<G> {
+    guard: G,
+    lock: usize,
+    type_name: &'static str,
+    thread: ThreadId,
+}
+
+impl<G> Drop for Tracked<G> {
+    fn drop(&mut self) {
+        graph().lock().release(self.lock, self.thread);
+    }
+}
+
+// releases the guard of a lock from the graph, e.g. to upgrade it
+pub(crate) fn untrack<G>(guard: Tracked<G>) -> G {
+    let guard = ManuallyDrop::new(guard);
+    graph().lock().release(guard.lock, guard.thread);
+    // the guard is moved out once, without dropping the tracked guard
+    unsafe { std::ptr::read(&guard.guard) }
+}
+
+// records the guard of a lock in the graph
+pub(crate) fn track<G>(lock: usize, type_name: &'static str, guard: G) -> Tracked<G> {
+    let thread = thread::current().id();
+    graph().lock().hold(lock, type_name, thread);
+    Tracked {
+        guard,
+        lock,
+        type_name,
+        thread,
+    }
+}
+
+// converts the guard of a lock into another guard of the lock, e.g. to downgrade it
+pub(crate) fn map_guard<G, H>(guard: Tracked<G>, f: impl FnOnce(G) -> H) -> Tracked<H> {
+    let guard = ManuallyDrop::new(guard);
+    // the guard is moved out once, the lock staying held by the thread
+    let inner = unsafe { std::ptr::read(&guard.guard) };
+    Tracked {
+        guard: f(inner),
+        lock: guard.lock,
+        type_name: guard.type_name,
+        thread: guard.thread,
+    }
+}
+
+// records another guard of the lock of `guard`, e.g. a recursive shared guard
+pub(crate) fn clone_guard<G, H>(guard: &Tracked<G>, f: impl FnOnce(&G) -> H) -> Tracked<H> {
+    track(guard.lock, guard.type_name, f(&guard.guard))
+}
+
+// acquires a lock, panicking with the cycle of locks instead of blocking into a deadlock
+pub(crate) fn acquire<G>(
+    lock: usize,
+    type_name: &'static str,
+    try_acquire: impl FnOnce() -> Option<G>,
+    acquire: impl FnOnce() -> G,
+) -> Tracked<G> {
+    if let Some(guard) = try_acquire() {
+        return track(lock, type_name, guard);
+    }
+
+    let thread = thread::current().id();
+    {
+        let mut graph = graph().lock();
+        if let Some(cycle) = graph.cycle(thread, lock, type_name) {
+            drop(graph);
+            panic!(
+                "Deadlock detected between the locks of: {} -> {type_name}",
+                cycle.join(" -> ")
+            );
+        }
+        graph.waiting.insert(thread, lock);
+    }
+    let guard = acquire();
+    graph().lock().waiting.remove(&thread);
+    track(lock, type_name, guard)
+}
+
+#[cfg(test)]
+mod test_deadlock {
+    #[cfg(not(feature = "unsync"))]
+    use std::sync::{Arc, Barrier};
+
+    use crate::utils::lock::GrainedLock;
+
+    #[cfg(not(feature = "unsync"))]
+    #[test]
+    fn test_deadlock_detected() {
+        let first = Arc::new(GrainedLock::new(1u32));
+        let second = Arc::new(GrainedLock::new(2u64));
+        let barrier = Arc::new(Barrier::new(2));
+
+        let handle = std::thread::spawn({
+            let (first, second, barrier) = (first.clone(), second.clone(), barrier.clone());
+            move || {
+                let _second = second.borrow_mut();
+                barrier.wait();
+                let _first = first.borrow_mut();
+            }
+        });
+
+        // assert one of the threads of the cycle panics, letting the other one go on
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let _first = first.borrow_mut();
+            barrier.wait();
+            let _second = second.borrow();
+        }));
+        let joined = handle.join();
+        let message = match (result, joined) {
+            (Err(payload), Ok(())) | (Ok(()), Err(payload)) => {
+                *payload.downcast::<String>().unwrap()
+            }
+            _ => panic!("expected exactly one thread to detect the deadlock"),
+        };
+        assert!(message.starts_with("Deadlock detected between the locks of:"));
+        assert!(message.contains("u32") && message.contains("u64"));
+    }
+
+    #[test]
+    fn test_deadlock_same_thread() {
+        let lock = GrainedLock::new(1u32);
+        let _shared = lock.borrow();
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| lock.borrow_mut()));
+        let message = *result.err().unwrap().downcast::<String>().unwrap();
+        assert_eq!(
+            message,
+            "Deadlock detected between the locks of: u32 -> u32"
+        );
+    }
+
+    #[test]
+    fn test_deadlock_upgrade() {
+        let lock = GrainedLock::new(1u32);
+        let upgradable = lock.borrow_upgradable();
+        let _shared = lock.borrow();
+        let result =
+            std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| upgradable.upgrade()));
+        let message = *result.err().unwrap().downcast::<String>().unwrap();
+        assert_eq!(
+            message,
+            "Deadlock detected between the locks of: u32 -> u32"
+        );
+
+        // assert the upgrade is not reported as a deadlock on its own lock
+        drop(_shared);
+        *lock.borrow_upgradable().upgrade() += 1;
+        assert_eq!(*lock.borrow(), 2);
+    }
+}
@@ -1,9 +1,9 @@
 use std::cell::UnsafeCell;
 
 #[derive(Default, Debug)]
-pub(crate) struct GrainedUnsafeCell<T>(pub(crate) UnsafeCell<T>);
-unsafe impl<T> Sync for GrainedUnsafeCell<T> {}
-unsafe impl<T> Send for GrainedUnsafeCell<T> {}
+pub(crate) struct GrainedUnsafeCell<T: ?Sized>(pub(crate) UnsafeCell<T>);
+unsafe impl<T: ?Sized + Send + Sync> Sync for GrainedUnsafeCell<T> {}
+unsafe impl<T: ?Sized + Send> Send for GrainedUnsafeCell<T> {}
 
 impl<T> GrainedUnsafeCell<T> {
     pub(crate) fn new(data: T) -> Self {
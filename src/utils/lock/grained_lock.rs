@@ -1,39 +1,302 @@
-use std::{ops::Deref, ptr::NonNull};
+use std::{
+    fmt::Debug,
+    mem::ManuallyDrop,
+    ops::Deref,
+    ptr::NonNull,
+    sync::atomic::{AtomicBool, Ordering},
+    thread,
+    time::Duration,
+};
 
-use dynstack::{dyn_push, DynStack};
-use parking_lot::RwLock;
+use parking_lot::lock_api;
 
 use super::{
     grained_cell::GrainedUnsafeCell,
-    grained_ref::{Immutable, Mutable},
+    grained_ref::{Immutable, LockGuard, Mutable},
     Ref,
 };
 
-#[derive(Default, Debug)]
-/// For internal use only.
-/// 
-/// Fine Grained Lock implementation.
-/// Allow for fine grained locking mechanism with thread safety.
-/// This is mainly used for nested locking data structure that allows
-/// for locking without much hassle.
-pub(crate) struct GrainedLock<T> {
+#[derive(Debug)]
+/// Reader-writer lock whose borrows lock the nested `GrainedLock`s of the data in turn.
+///
+/// A [Ref] keeps the guards of every lock traversed to reach its data, so a structure of
+/// nested locks, e.g. a registry of independently locked entries, is locked at the
+/// granularity of its entries: holding a shared borrow of the registry, [Ref::map_cell] narrows
+/// the borrow to the lock of an entry, and [Ref::borrow_mut] locks the entry alone for writing.
+///
+/// # Examples
+/// ```
+/// use emark::lock::GrainedLock;
+///
+/// let players = GrainedLock::new(vec![GrainedLock::new(100u32), GrainedLock::new(80)]);
+///
+/// // both entries are written concurrently, sharing the lock of the registry
+/// let mut first = players.borrow().map_cell(|players| &players[0]).borrow_mut();
+/// let mut second = players.borrow().map_cell(|players| &players[1]).borrow_mut();
+/// *first -= 10;
+/// *second += 10;
+/// drop((first, second));
+///
+/// let players = players.borrow();
+/// assert_eq!([*players[0].borrow(), *players[1].borrow()], [90, 90]);
+/// ```
+///
+/// With the `unsync` feature, the lock only checks borrow flags instead of performing atomic
+/// operations, for applications running on a single thread such as on WASM. The lock then
+/// panics when used from another thread than the one creating it, and when a borrow conflicts
+/// with a borrow already held, as it would otherwise block forever.
+///
+/// As with `RwLock`, the lock is only shared between threads if its data is `Send` and `Sync`:
+/// ```compile_fail
+/// use std::rc::Rc;
+///
+/// use emark::lock::GrainedLock;
+///
+/// let lock = GrainedLock::new(Rc::new(1u32));
+/// std::thread::scope(|scope| {
+///     scope.spawn(|| **lock.borrow());
+/// });
+/// ```
+pub struct GrainedLock<T: ?Sized> {
     pub(crate) lock: RwLock<()>,
+    // type locked, reported in the cycles of the deadlock detection
+    #[cfg(feature = "deadlock-detection")]
+    type_name: &'static str,
+    // an exclusive borrow was released while unwinding from a panic
+    poisoned: AtomicBool,
     pub(crate) data: GrainedUnsafeCell<T>,
 }
 
-impl<T> GrainedLock<T> {
+// raw lock of the `GrainedLock`s and of the shards of the containers, only checking borrow
+// flags with the `unsync` feature
+#[cfg(not(feature = "unsync"))]
+pub(crate) type RawLock = parking_lot::RawRwLock;
+#[cfg(feature = "unsync")]
+pub(crate) type RawLock = super::unsync::RawUnsyncLock;
+
+type RwLock<T> = lock_api::RwLock<RawLock, T>;
+type RwLockReadGuard<'a, T> = lock_api::RwLockReadGuard<'a, RawLock, T>;
+type RwLockWriteGuard<'a, T> = lock_api::RwLockWriteGuard<'a, RawLock, T>;
+type RwLockUpgradableReadGuard<'a, T> = lock_api::RwLockUpgradableReadGuard<'a, RawLock, T>;
+
+// unlocked raw lock, owned by the current thread with the `unsync` feature
+#[cfg(not(feature = "unsync"))]
+pub(crate) fn raw_lock() -> RawLock {
+    <RawLock as lock_api::RawRwLock>::INIT
+}
+
+#[cfg(feature = "unsync")]
+pub(crate) fn raw_lock() -> RawLock {
+    RawLock::new()
+}
+
+#[cfg(feature = "deadlock-detection")]
+use super::deadlock::{acquire, clone_guard, map_guard, track, untrack, Tracked as Guard};
+
+// guards are only tracked with the deadlock detection
+#[cfg(not(feature = "deadlock-detection"))]
+type Guard<G> = G;
+
+// guards of the locks held by a `Ref`
+pub(crate) type ReadGuard<'a> = Guard<RwLockReadGuard<'a, ()>>;
+pub(crate) type WriteGuard<'a> = PoisonGuard<'a, Guard<RwLockWriteGuard<'a, ()>>>;
+
+// shared guard of the lock of `guard`, taken even behind a waiting writer since the lock is
+// already held by the thread
+pub(crate) fn clone_read<'a>(guard: &ReadGuard<'a>) -> ReadGuard<'a> {
+    clone_guard(guard, |guard| {
+        RwLockReadGuard::rwlock(guard).read_recursive()
+    })
+}
+
+// shared guard downgraded from `guard`, letting the other shared borrows in
+pub(crate) fn downgrade(guard: WriteGuard<'_>) -> ReadGuard<'_> {
+    // the data is no longer written, so the guard no longer poisons the lock
+    map_guard(guard.into_inner(), RwLockWriteGuard::downgrade)
+}
+
+#[cfg(not(feature = "deadlock-detection"))]
+fn track<G>(_lock: usize, _type_name: &'static str, guard: G) -> G {
+    guard
+}
+
+#[cfg(not(feature = "deadlock-detection"))]
+fn untrack<G>(guard: G) -> G {
+    guard
+}
+
+#[cfg(not(feature = "deadlock-detection"))]
+fn map_guard<G, H>(guard: G, f: impl FnOnce(G) -> H) -> H {
+    f(guard)
+}
+
+#[cfg(not(feature = "deadlock-detection"))]
+fn clone_guard<G, H>(guard: &G, f: impl FnOnce(&G) -> H) -> H {
+    f(guard)
+}
+
+#[cfg(not(feature = "deadlock-detection"))]
+fn acquire<G>(
+    _lock: usize,
+    _type_name: &'static str,
+    _try_acquire: impl FnOnce() -> Option<G>,
+    acquire: impl FnOnce() -> G,
+) -> G {
+    acquire()
+}
+
+impl<T: ?Sized> GrainedLock<T> {
+    /// Borrows the data, blocking while it is borrowed mutably.
     pub fn borrow<'a>(&'a self) -> Ref<'a, T, Immutable> {
-        let mut vec: DynStack<dyn Deref<Target = ()>> = DynStack::new();
-        dyn_push!(vec, self.lock.read());
-        Ref::new(NonNull::new(self.data.0.get()).unwrap(), vec)
+        Ref::new(self.data_ptr(), LockGuard::Read(self.read()))
     }
 
+    /// Borrows the data even while a mutable borrow is waiting for the lock, e.g. for locks
+    /// already borrowed by the current thread, which would deadlock behind the waiting writer.
+    pub fn borrow_recursive<'a>(&'a self) -> Ref<'a, T, Immutable> {
+        let guard = acquire(
+            self.id(),
+            self.type_name(),
+            || self.lock.try_read_recursive(),
+            || self.lock.read_recursive(),
+        );
+        Ref::new(self.data_ptr(), LockGuard::Read(guard))
+    }
+
+    /// Mutably borrows the data, blocking while it is borrowed.
     pub fn borrow_mut<'a>(&'a self) -> Ref<'a, T, Mutable> {
-        let mut vec: DynStack<dyn Deref<Target = ()>> = DynStack::new();
-        dyn_push!(vec, self.lock.write());
-        Ref::new(NonNull::new(self.data.0.get()).unwrap(), vec)
+        Ref::new(self.data_ptr(), LockGuard::Write(self.write()))
+    }
+
+    /// Borrows the data, or returns `None` instead of blocking while it is borrowed mutably.
+    pub fn try_borrow<'a>(&'a self) -> Option<Ref<'a, T, Immutable>> {
+        let guard = track(self.id(), self.type_name(), self.lock.try_read()?);
+        Some(Ref::new(self.data_ptr(), LockGuard::Read(guard)))
+    }
+
+    /// Mutably borrows the data, or returns `None` instead of blocking while it is borrowed.
+    pub fn try_borrow_mut<'a>(&'a self) -> Option<Ref<'a, T, Mutable>> {
+        let guard = track(self.id(), self.type_name(), self.lock.try_write()?);
+        let guard = self.poison_on_panic(guard);
+        Some(Ref::new(self.data_ptr(), LockGuard::Write(guard)))
+    }
+
+    /// Borrows the data, or returns `None` if it is still borrowed mutably after `timeout`.
+    pub fn borrow_timeout<'a>(&'a self, timeout: Duration) -> Option<Ref<'a, T, Immutable>> {
+        let guard = track(
+            self.id(),
+            self.type_name(),
+            self.lock.try_read_for(timeout)?,
+        );
+        Some(Ref::new(self.data_ptr(), LockGuard::Read(guard)))
+    }
+
+    /// Mutably borrows the data, or returns `None` if it is still borrowed after `timeout`.
+    pub fn borrow_mut_timeout<'a>(&'a self, timeout: Duration) -> Option<Ref<'a, T, Mutable>> {
+        let guard = track(
+            self.id(),
+            self.type_name(),
+            self.lock.try_write_for(timeout)?,
+        );
+        let guard = self.poison_on_panic(guard);
+        Some(Ref::new(self.data_ptr(), LockGuard::Write(guard)))
+    }
+
+    /// Borrows the data with a borrow that can be upgraded to a mutable borrow without
+    /// releasing the lock in between, see [UpgradableRef].
+    ///
+    /// Upgradable borrows exclude each other and the mutable borrows, but not the shared
+    /// borrows.
+    ///
+    /// # Examples
+    /// ```
+    /// use emark::lock::GrainedLock;
+    ///
+    /// let values = GrainedLock::new(vec![3u32, 1, 2]);
+    ///
+    /// // only sort when needed, without letting another writer in between
+    /// let sorted = values.borrow_upgradable();
+    /// if !sorted.is_sorted() {
+    ///     sorted.upgrade().sort();
+    /// }
+    /// assert_eq!(*values.borrow(), [1, 2, 3]);
+    /// ```
+    pub fn borrow_upgradable<'a>(&'a self) -> UpgradableRef<'a, T> {
+        let guard = acquire(
+            self.id(),
+            self.type_name(),
+            || self.lock.try_upgradable_read(),
+            || self.lock.upgradable_read(),
+        );
+        UpgradableRef { lock: self, guard }
+    }
+
+    // shared guard of the lock, also locking the nested locks of a `Ref`
+    pub(crate) fn read(&self) -> ReadGuard<'_> {
+        acquire(
+            self.id(),
+            self.type_name(),
+            || self.lock.try_read(),
+            || self.lock.read(),
+        )
+    }
+
+    // exclusive guard of the lock, also locking the nested locks of a `Ref`
+    pub(crate) fn write(&self) -> WriteGuard<'_> {
+        let guard = acquire(
+            self.id(),
+            self.type_name(),
+            || self.lock.try_write(),
+            || self.lock.write(),
+        );
+        self.poison_on_panic(guard)
+    }
+
+    /// Returns `true` if a mutable borrow was released while unwinding from a panic, so the
+    /// data may be left half updated.
+    ///
+    /// Borrows of a poisoned lock still succeed, the poison only being reported.
+    pub fn is_poisoned(&self) -> bool {
+        self.poisoned.load(Ordering::Acquire)
+    }
+
+    /// Clears the poison once the data is known to be consistent again.
+    pub fn clear_poison(&self) {
+        self.poisoned.store(false, Ordering::Release);
+    }
+
+    fn poison_on_panic<G>(&self, guard: G) -> PoisonGuard<'_, G> {
+        PoisonGuard {
+            guard,
+            poisoned: &self.poisoned,
+        }
+    }
+
+    fn data_ptr(&self) -> NonNull<T> {
+        NonNull::new(self.data.0.get()).unwrap()
+    }
+
+    // identifies the lock in the wait-for graph of the deadlock detection
+    fn id(&self) -> usize {
+        &self.lock as *const RwLock<()> as usize
+    }
+
+    fn type_name(&self) -> &'static str {
+        #[cfg(feature = "deadlock-detection")]
+        return self.type_name;
+        #[cfg(not(feature = "deadlock-detection"))]
+        ""
+    }
+
+    /// Mutably borrows the data without locking, no borrow being held through an exclusive
+    /// reference to the lock.
+    pub fn get_mut(&mut self) -> &mut T {
+        self.data.0.get_mut()
     }
+}
 
+impl<T> GrainedLock<T> {
+    /// Consumes the lock, returning its data.
     pub fn take(self) -> T {
         // need to make sure there is no other borrow
         let _lock = self.lock.write();
@@ -42,14 +305,90 @@ impl<T> GrainedLock<T> {
         self.data.0.into_inner()
     }
 
-    pub(crate) fn new(data: T) -> Self {
+    /// Creates an unlocked lock of `data`.
+    pub fn new(data: T) -> Self {
         Self {
-            lock: RwLock::new(()),
+            lock: RwLock::const_new(raw_lock(), ()),
+            #[cfg(feature = "deadlock-detection")]
+            type_name: std::any::type_name::<T>(),
+            poisoned: AtomicBool::new(false),
             data: GrainedUnsafeCell::new(data),
         }
     }
 }
 
+/// Shared borrow of a [GrainedLock] that can be upgraded to a mutable borrow, see
+/// [GrainedLock::borrow_upgradable].
+pub struct UpgradableRef<'a, T: ?Sized> {
+    lock: &'a GrainedLock<T>,
+    guard: Guard<RwLockUpgradableReadGuard<'a, ()>>,
+}
+
+impl<'a, T: ?Sized> UpgradableRef<'a, T> {
+    /// Mutably borrows the data, blocking until the shared borrows are released, the lock
+    /// being held all along.
+    pub fn upgrade(self) -> Ref<'a, T, Mutable> {
+        let UpgradableRef { lock, guard } = self;
+        let guard = untrack(guard);
+        let guard = acquire(
+            lock.id(),
+            lock.type_name(),
+            || None,
+            || RwLockUpgradableReadGuard::upgrade(guard),
+        );
+        Ref::new(
+            lock.data_ptr(),
+            LockGuard::Write(lock.poison_on_panic(guard)),
+        )
+    }
+}
+
+// exclusive guard poisoning its lock if released while unwinding
+pub(crate) struct PoisonGuard<'a, G> {
+    // only held to be released after the poison is set
+    #[allow(dead_code)]
+    guard: G,
+    poisoned: &'a AtomicBool,
+}
+
+impl<G> PoisonGuard<'_, G> {
+    fn into_inner(self) -> G {
+        let guard = ManuallyDrop::new(self);
+        // the guard is moved out once, without poisoning the lock
+        unsafe { std::ptr::read(&guard.guard) }
+    }
+}
+
+impl<G> Drop for PoisonGuard<'_, G> {
+    fn drop(&mut self) {
+        // poisoned before the guard releases the lock
+        if thread::panicking() {
+            self.poisoned.store(true, Ordering::Release);
+        }
+    }
+}
+
+impl<T: ?Sized> Deref for UpgradableRef<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        // the lock is held by the guard
+        unsafe { &*self.lock.data.0.get() }
+    }
+}
+
+impl<T: ?Sized + Debug> Debug for UpgradableRef<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("UpgradableRef").field(&self.deref()).finish()
+    }
+}
+
+impl<T: Default> Default for GrainedLock<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
 #[cfg(test)]
 mod test_grained_lock {
     use crate::utils::lock::grained_lock::GrainedLock;
@@ -95,4 +434,95 @@ mod test_grained_lock {
         *_ref = 1;
         assert_eq!(*_ref, 1);
     }
+
+    #[test]
+    fn test_try_borrow() {
+        let lock = GrainedLock::<i32>::default();
+        let _ref = lock.try_borrow().unwrap();
+        assert!(lock.try_borrow().is_some());
+        assert!(lock.try_borrow_mut().is_none());
+        drop(_ref);
+
+        let _ref = lock.try_borrow_mut().unwrap();
+        assert!(lock.try_borrow().is_none());
+        assert!(lock.try_borrow_mut().is_none());
+    }
+
+    #[test]
+    fn test_borrow_upgradable() {
+        let lock = GrainedLock::<i32>::default();
+        let upgradable = lock.borrow_upgradable();
+        assert_eq!(*upgradable, 0);
+        assert!(lock.try_borrow().is_some());
+        assert!(lock.try_borrow_mut().is_none());
+
+        let mut _ref = upgradable.upgrade();
+        *_ref = 1;
+        assert!(lock.try_borrow().is_none());
+        drop(_ref);
+        assert_eq!(*lock.borrow(), 1);
+    }
+
+    #[test]
+    fn test_poison() {
+        let lock = GrainedLock::<i32>::default();
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let _shared = lock.borrow();
+            panic!("shared borrows do not poison");
+        }));
+        assert!(result.is_err());
+        assert!(!lock.is_poisoned());
+
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            *lock.borrow_mut() = 1;
+            let _ref = lock.borrow_upgradable().upgrade();
+            panic!("exclusive borrows poison");
+        }));
+        assert!(result.is_err());
+        assert!(lock.is_poisoned());
+
+        // assert the data is still borrowed once poisoned
+        assert_eq!(*lock.borrow(), 1);
+        lock.clear_poison();
+        assert!(!lock.is_poisoned());
+    }
+
+    #[cfg(not(feature = "unsync"))]
+    #[test]
+    fn test_borrow_timeout() {
+        let lock = std::sync::Arc::new(GrainedLock::<i32>::default());
+        let timeout = std::time::Duration::from_millis(10);
+        let _ref = lock.borrow_mut();
+        assert!(lock.borrow_timeout(timeout).is_none());
+        assert!(lock.borrow_mut_timeout(timeout).is_none());
+
+        // assert the borrow is acquired once the lock is released within the timeout
+        let handle = std::thread::spawn({
+            let lock = lock.clone();
+            move || {
+                *lock
+                    .borrow_timeout(std::time::Duration::from_secs(10))
+                    .unwrap()
+            }
+        });
+        std::thread::sleep(timeout);
+        drop(_ref);
+        assert_eq!(handle.join().unwrap(), 0);
+    }
+
+    #[cfg(feature = "unsync")]
+    #[test]
+    fn test_unsync_borrow() {
+        let lock = GrainedLock::<i32>::default();
+        let _ref = lock.borrow();
+        assert!(lock
+            .borrow_mut_timeout(std::time::Duration::from_secs(10))
+            .is_none());
+
+        // assert conflicting borrows panic instead of blocking the only thread
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| lock.borrow_mut()));
+        assert!(result.is_err());
+        drop(_ref);
+        assert!(lock.try_borrow_mut().is_some());
+    }
 }
@@ -1,28 +1,138 @@
-use super::GrainedLock;
-use dynstack::{dyn_push, DynStack};
+use super::{
+    grained_lock::{clone_read, downgrade, ReadGuard, WriteGuard},
+    GrainedLock,
+};
 use std::{
+    fmt::Debug,
     marker::PhantomData,
     ops::{Deref, DerefMut},
     ptr::NonNull,
+    rc::Rc,
 };
 
+/// Access state of a borrow, either [Immutable] or [Mutable].
+pub trait LockState {
+    /// `true` if the borrow grants mutable access.
+    const MUTABLE: bool;
+}
+/// Shared access state.
+pub struct Immutable;
+/// Exclusive access state.
+pub struct Mutable;
+
+impl LockState for Immutable {
+    const MUTABLE: bool = false;
+}
+impl LockState for Mutable {
+    const MUTABLE: bool = true;
+}
+
+// guard of a lock held by a `Ref`, only held to be released on drop
 #[allow(dead_code)]
-pub(crate) trait LockState {}
-#[allow(dead_code)]
-pub(crate) struct Immutable;
-#[allow(dead_code)]
-pub(crate) struct Mutable;
+pub(crate) enum LockGuard<'a> {
+    Read(ReadGuard<'a>),
+    Write(WriteGuard<'a>),
+    // guards other than the locks, e.g. the locking order of the retrievals, shared by the
+    // clones of a shared borrow
+    Other(Rc<dyn Deref<Target = ()> + 'a>),
+}
+
+impl<'a> LockGuard<'a> {
+    // the guard of a shared borrow
+    fn downgrade(self) -> Self {
+        match self {
+            LockGuard::Write(guard) => LockGuard::Read(downgrade(guard)),
+            guard => guard,
+        }
+    }
 
-impl LockState for Immutable {}
-impl LockState for Mutable {}
+    // another guard of a shared borrow, locking the same lock
+    fn share(&self) -> Self {
+        match self {
+            LockGuard::Read(guard) => LockGuard::Read(clone_read(guard)),
+            LockGuard::Write(_) => unreachable!("shared borrows only hold shared guards"),
+            LockGuard::Other(guard) => LockGuard::Other(guard.clone()),
+        }
+    }
+}
 
-pub(crate) struct Ref<'a, T, S>
+// guards of a `Ref`, the guards of most borrows, one lock or a lock nested in another one,
+// being stored inline without allocating
+struct LockGuards<'a> {
+    inline: [Option<LockGuard<'a>>; 2],
+    spilled: Vec<LockGuard<'a>>,
+}
+
+impl<'a> LockGuards<'a> {
+    fn new(lock: LockGuard<'a>) -> Self {
+        Self {
+            inline: [Some(lock), None],
+            spilled: Vec::new(),
+        }
+    }
+
+    fn push(&mut self, lock: LockGuard<'a>) {
+        match self.inline.iter_mut().find(|slot| slot.is_none()) {
+            Some(slot) => *slot = Some(lock),
+            None => self.spilled.push(lock),
+        }
+    }
+
+    // the guards of a shared borrow, exclusive guards being downgraded
+    fn downgrade(self) -> Self {
+        let [first, second] = self.inline;
+        Self {
+            inline: [
+                first.map(LockGuard::downgrade),
+                second.map(LockGuard::downgrade),
+            ],
+            spilled: self.spilled.into_iter().map(LockGuard::downgrade).collect(),
+        }
+    }
+
+    fn share(&self) -> Self {
+        let [first, second] = &self.inline;
+        Self {
+            inline: [
+                first.as_ref().map(LockGuard::share),
+                second.as_ref().map(LockGuard::share),
+            ],
+            spilled: self.spilled.iter().map(LockGuard::share).collect(),
+        }
+    }
+}
+
+/// Borrow of the data of a [GrainedLock], holding the guards of the locks traversed to reach
+/// it, see [GrainedLock].
+///
+/// Shared borrows are `Ref<T, Immutable>`, mutable borrows `Ref<T, Mutable>`. Only mutable
+/// borrows write the data:
+/// ```compile_fail
+/// use emark::lock::GrainedLock;
+///
+/// let lock = GrainedLock::new(1u32);
+/// let mut shared = lock.borrow();
+/// *shared = 2;
+/// ```
+///
+/// Borrows are invariant in their data, so a mutable borrow may not shorten the lifetimes of
+/// the data it writes:
+/// ```compile_fail
+/// use emark::lock::{Mutable, Ref};
+///
+/// fn shorten<'a, 'b>(borrow: Ref<'a, &'static str, Mutable>) -> Ref<'a, &'b str, Mutable> {
+///     borrow
+/// }
+/// ```
+pub struct Ref<'a, T: ?Sized, S>
 where
     S: LockState,
 {
-    locks: DynStack<dyn Deref<Target = ()> + 'a>,
+    locks: LockGuards<'a>,
     data: NonNull<T>,
     _marker: PhantomData<S>,
+    // invariant in `T`, as mutable borrows write the data
+    _invariant: PhantomData<*mut T>,
 }
 
 impl<'a, T, S> Ref<'a, GrainedLock<T>, S>
@@ -30,14 +140,21 @@ where
     S: LockState,
     T: 'static,
 {
+    /// Borrows the data of the nested lock, keeping the guards of the outer locks.
     pub fn borrow(self) -> Ref<'a, T, Immutable> {
         // destructure ref
         let Ref {
             mut locks,
             data,
             _marker,
+            _invariant,
         } = self;
 
+        // the outer data is no longer written
+        if S::MUTABLE {
+            locks = locks.downgrade();
+        }
+
         // get immutable reference to the grained lock
         // here we are turning a non-null pointer into a reference
         // since we know it is non-null, we can safely dereference it
@@ -45,7 +162,7 @@ where
 
         // push the grained lock into the stack
         // this operation allows for the grained lock
-        dyn_push!(locks, grained.lock.read());
+        locks.push(LockGuard::Read(grained.read()));
 
         // get the new data from the grained lock
         let data = NonNull::new(grained.data.0.get()).unwrap();
@@ -55,25 +172,29 @@ where
             locks,
             data,
             _marker: PhantomData::<Immutable>,
+            _invariant: PhantomData,
         }
     }
 
+    /// Mutably borrows the data of the nested lock, keeping the guards of the outer locks, so
+    /// the outer data may only be borrowed immutably.
     pub fn borrow_mut(self) -> Ref<'a, T, Mutable> {
         // destructure ref
         let Ref {
             mut locks,
-            mut data,
+            data,
             _marker,
+            _invariant,
         } = self;
 
-        // get mutable reference to the grained lock
+        // get a reference to the grained lock, whose lock grants the mutable access
         // here we are turning a non-null pointer into a reference
         // since we know it is non-null, we can safely dereference it
-        let grained = unsafe { data.as_mut() };
+        let grained = unsafe { data.as_ref() };
 
         // push the grained lock into the stack
         // this operation allows for the grained lock
-        dyn_push!(locks, grained.lock.write());
+        locks.push(LockGuard::Write(grained.write()));
 
         // get the new data from the grained lock
         let data = NonNull::new(grained.data.0.get()).unwrap();
@@ -83,11 +204,121 @@ where
             locks,
             data,
             _marker: PhantomData::<Mutable>,
+            _invariant: PhantomData,
+        }
+    }
+}
+
+impl<'a, T: ?Sized> Ref<'a, T, Immutable> {
+    /// Clones the shared borrow by locking its locks once more, the data staying borrowed
+    /// until every clone is released.
+    ///
+    /// This is an associated function, used as `Ref::clone(&borrow)`, so that
+    /// `borrow.clone()` still clones the borrowed data. Mutable borrows turned into shared
+    /// borrows, e.g. by [Ref::borrow] or [Ref::map_cell], no longer exclude the other shared
+    /// borrows of their locks.
+    ///
+    /// # Examples
+    /// ```
+    /// use emark::lock::{GrainedLock, Ref};
+    ///
+    /// let lock = GrainedLock::new(1u32);
+    /// let first = lock.borrow();
+    /// let second = Ref::clone(&first);
+    /// drop(first);
+    ///
+    /// assert!(lock.try_borrow_mut().is_none());
+    /// drop(second);
+    /// assert!(lock.try_borrow_mut().is_some());
+    /// ```
+    #[allow(clippy::should_implement_trait)]
+    pub fn clone(this: &Self) -> Self {
+        Ref {
+            locks: this.locks.share(),
+            data: this.data,
+            _marker: PhantomData,
+            _invariant: PhantomData,
+        }
+    }
+
+    /// Narrows the borrow to a part of the data, such as a field, keeping the guards of the
+    /// borrow.
+    ///
+    /// # Examples
+    /// ```
+    /// use emark::lock::GrainedLock;
+    ///
+    /// let pair = GrainedLock::new((1u32, String::from("one")));
+    /// let name = pair.borrow().map(|(_, name)| name.as_str());
+    /// assert_eq!(&*name, "one");
+    /// ```
+    pub fn map<U: ?Sized>(self, f: impl FnOnce(&T) -> &U) -> Ref<'a, U, Immutable> {
+        // the data returned by `f` is borrowed from the data locked by the guards
+        unsafe { self.map_unchecked(|data| NonNull::from(f(data.as_ref()))) }
+    }
+
+    /// Narrows the borrow to a part of the data, or returns the borrow unchanged if `f`
+    /// returns `None`.
+    ///
+    /// # Examples
+    /// ```
+    /// use emark::lock::GrainedLock;
+    ///
+    /// let values = GrainedLock::new(vec![1u32, 2, 3]);
+    /// let last = values.borrow().filter_map(|values| values.last()).unwrap();
+    /// assert_eq!(*last, 3);
+    ///
+    /// let values = values.borrow().filter_map(|values| values.get(3)).unwrap_err();
+    /// assert_eq!(values.len(), 3);
+    /// ```
+    // the borrow is returned unchanged as std's `Ref::filter_map` does, however large the
+    // guards tracked by the deadlock detection
+    #[cfg_attr(feature = "deadlock-detection", allow(clippy::result_large_err))]
+    pub fn filter_map<U: ?Sized>(
+        self,
+        f: impl FnOnce(&T) -> Option<&U>,
+    ) -> Result<Ref<'a, U, Immutable>, Self> {
+        // the data returned by `f` is borrowed from the data locked by the guards
+        match f(unsafe { self.data.as_ref() }).map(NonNull::from) {
+            Some(data) => Ok(unsafe { self.map_unchecked(|_| data) }),
+            None => Err(self),
+        }
+    }
+}
+
+impl<'a, T: ?Sized> Ref<'a, T, Mutable> {
+    /// Narrows the mutable borrow to a part of the data, such as a field, keeping the guards
+    /// of the borrow.
+    ///
+    /// # Examples
+    /// ```
+    /// use emark::lock::GrainedLock;
+    ///
+    /// let pair = GrainedLock::new((1u32, String::from("one")));
+    /// *pair.borrow_mut().map(|(count, _)| count) += 1;
+    /// assert_eq!(pair.borrow().0, 2);
+    /// ```
+    pub fn map<U: ?Sized>(self, f: impl FnOnce(&mut T) -> &mut U) -> Ref<'a, U, Mutable> {
+        // the data returned by `f` is borrowed from the data exclusively locked by the guards
+        unsafe { self.map_unchecked(|mut data| NonNull::from(f(data.as_mut()))) }
+    }
+
+    /// Narrows the mutable borrow to a part of the data, or returns the borrow unchanged if
+    /// `f` returns `None`.
+    #[cfg_attr(feature = "deadlock-detection", allow(clippy::result_large_err))]
+    pub fn filter_map<U: ?Sized>(
+        mut self,
+        f: impl FnOnce(&mut T) -> Option<&mut U>,
+    ) -> Result<Ref<'a, U, Mutable>, Self> {
+        // the data returned by `f` is borrowed from the data exclusively locked by the guards
+        match f(unsafe { self.data.as_mut() }).map(NonNull::from) {
+            Some(data) => Ok(unsafe { self.map_unchecked(|_| data) }),
+            None => Err(self),
         }
     }
 }
 
-impl<'a, T, S> Deref for Ref<'a, T, S>
+impl<'a, T: ?Sized, S> Deref for Ref<'a, T, S>
 where
     S: LockState,
 {
@@ -97,16 +328,13 @@ where
     }
 }
 
-impl<'a, T, S> DerefMut for Ref<'a, T, S>
-where
-    S: LockState,
-{
+impl<T: ?Sized> DerefMut for Ref<'_, T, Mutable> {
     fn deref_mut(&mut self) -> &mut Self::Target {
         unsafe { self.data.as_mut() }
     }
 }
 
-impl<'a, T, S> AsRef<T> for Ref<'a, T, S>
+impl<'a, T: ?Sized, S> AsRef<T> for Ref<'a, T, S>
 where
     S: LockState,
 {
@@ -115,66 +343,115 @@ where
     }
 }
 
-impl<'a, T> AsMut<T> for Ref<'a, T, Mutable>
+impl<T: ?Sized + Debug, S> Debug for Ref<'_, T, S>
+where
+    S: LockState,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("Ref").field(&self.deref()).finish()
+    }
+}
+
+impl<'a, T: ?Sized> AsMut<T> for Ref<'a, T, Mutable>
 {
     fn as_mut(&mut self) -> &mut T {
         self
     }
 }
 
-impl<'a, T, S> Ref<'a, T, S>
+impl<'a, T: ?Sized, S> Ref<'a, T, S>
 where
     S: LockState,
 {
-    pub fn map_cell<K, F: FnOnce(&T) -> &GrainedLock<K>>(self, f: F) -> Ref<'a, GrainedLock<K>, S> {
-        let Ref {
-            locks,
-            data,
-            _marker,
-        } = self;
-        let data = unsafe { f(data.as_ref()) } as *const GrainedLock<K> as *mut GrainedLock<K>;
-
-        Ref {
-            locks,
-            data: NonNull::new(data).unwrap(),
-            _marker,
-        }
+    /// Narrows the borrow to a nested lock of the data, such as an entry of a collection of
+    /// locks, to be borrowed with [Ref::borrow] or [Ref::borrow_mut].
+    ///
+    /// The nested lock itself is only borrowed immutably, its own lock granting the access to
+    /// its data.
+    pub fn map_cell<K, F: FnOnce(&T) -> &GrainedLock<K>>(
+        self,
+        f: F,
+    ) -> Ref<'a, GrainedLock<K>, Immutable> {
+        let data = NonNull::from(unsafe { f(self.data.as_ref()) });
+        // the nested lock is borrowed from the data locked by the guards
+        unsafe { self.map_unchecked(|_| data) }
     }
 
-    pub unsafe fn map<
-        K,
-        F: FnMut(NonNull<T>) -> (NonNull<K>, Option<NonNull<dyn Deref<Target = ()> + 'a>>),
+    #[doc(hidden)]
+    #[deprecated(note = "use the safe `Ref::map` or `Ref::filter_map` instead")]
+    /// Maps the borrow to other data, optionally pushing a guard kept alive by the new borrow.
+    ///
+    /// # Safety
+    /// The data returned by `f` must stay valid, and only be accessed as allowed by `NS`, until
+    /// the guards of the borrow are released.
+    pub unsafe fn map_raw<
+        K: ?Sized,
+        F: FnMut(NonNull<T>) -> (NonNull<K>, Option<Box<dyn Deref<Target = ()> + 'a>>),
         NS: LockState,
     >(
         self,
         mut f: F,
     ) -> Ref<'a, K, NS> {
-        let Ref {
-            mut locks,
-            data,
-            _marker,
-        } = self;
-        let (data, lock) = f(data);
+        let mut lock = None;
+        let mut resource = self.map_unchecked(|data| {
+            let (data, guard) = f(data);
+            lock = guard;
+            data
+        });
         if let Some(lock) = lock {
-            locks.push(lock.as_ptr());
-            // core::mem::forget(lock)
+            resource.locks.push(LockGuard::Other(Rc::from(lock)));
         }
+        resource
+    }
+
+    // maps the borrow to the data returned by `f`, with the access state `NS`
+    //
+    // the data must stay valid until the guards of the borrow are released, and only be
+    // accessed mutably if `NS` is `Mutable` and the guards are exclusive
+    pub(crate) unsafe fn map_unchecked<K: ?Sized, NS: LockState>(
+        self,
+        f: impl FnOnce(NonNull<T>) -> NonNull<K>,
+    ) -> Ref<'a, K, NS> {
+        // shared borrows only hold shared guards, to be cloned
+        let locks = match S::MUTABLE && !NS::MUTABLE {
+            true => self.locks.downgrade(),
+            false => self.locks,
+        };
         Ref {
+            data: f(self.data),
             locks,
-            data,
-            _marker: PhantomData::<NS>,
+            _marker: PhantomData,
+            _invariant: PhantomData,
         }
     }
 
+    #[doc(hidden)]
+    /// Returns a pointer to the data, releasing the guards of the borrow.
+    ///
+    /// # Safety
+    /// The pointer is not protected by any lock once the guards are released.
     pub unsafe fn leak(self) -> *mut T {
         self.data.as_ptr()
     }
 
-    pub fn new(data: NonNull<T>, locks: DynStack<dyn Deref<Target = ()> + 'a>) -> Self {
+    // keeps `guard` alive until the borrow is released
+    #[cfg_attr(not(debug_assertions), allow(dead_code))]
+    pub(crate) fn with_guard(mut self, guard: impl Deref<Target = ()> + 'a) -> Self {
+        self.locks.push(LockGuard::Other(Rc::new(guard)));
+        self
+    }
+
+    /// Pointer to the borrowed data, only dereferenced while the borrow is held.
+    pub fn as_ptr(&self) -> *mut T {
+        self.data.as_ptr()
+    }
+
+    pub(crate) fn new(data: NonNull<T>, lock: LockGuard<'a>) -> Self {
         Self {
-            locks: locks,
-            data: data,
+            locks: LockGuards::new(lock),
+            data,
             _marker: PhantomData::<S>,
+            _invariant: PhantomData,
         }
     }
 }
@@ -185,7 +462,7 @@ mod test_grained_ref {
 
     use crate::utils::lock::grained_lock::GrainedLock;
 
-    use super::Immutable;
+    use super::{Immutable, Ref};
 
     #[test]
     fn test_map_cell() {
@@ -194,21 +471,90 @@ mod test_grained_ref {
 
         let inner = resource
             .borrow()
-            .map_cell(|vec| vec.get(0).unwrap())
+            .map_cell(|vec| vec.first().unwrap())
             .borrow();
 
         assert_eq!(*inner, i32::default());
     }
 
+    #[test]
+    fn test_nested_guards() {
+        let resource = GrainedLock::new(GrainedLock::new(GrainedLock::new(1)));
+
+        // assert the guards past the inline ones are held as well
+        let mut inner = resource
+            .borrow()
+            .map_cell(|lock| lock)
+            .borrow()
+            .map_cell(|lock| lock)
+            .borrow_mut();
+        *inner += 1;
+        assert!(resource.try_borrow_mut().is_none());
+        assert!(resource.borrow().borrow().try_borrow().is_none());
+        drop(inner);
+
+        assert!(resource.try_borrow_mut().is_some());
+        assert_eq!(*resource.borrow().borrow().borrow(), 2);
+    }
+
+    #[test]
+    fn test_clone() {
+        let resource = GrainedLock::new(vec![GrainedLock::new(1)]);
+        let inner = resource.borrow().map_cell(|vec| &vec[0]).borrow();
+        let clone = Ref::clone(&inner);
+        drop(inner);
+
+        // assert the clone holds every lock of the borrow
+        assert_eq!(*clone, 1);
+        assert!(resource.try_borrow_mut().is_none());
+        assert!(resource.borrow()[0].try_borrow_mut().is_none());
+        drop(clone);
+        assert!(resource.try_borrow_mut().is_some());
+
+        // assert the exclusive guards of shared borrows are downgraded to be cloned
+        let outer = resource.borrow_mut().map_cell(|vec| &vec[0]);
+        assert!(resource.try_borrow().is_some());
+        let inner = outer.borrow_mut();
+        assert!(resource.try_borrow().is_some());
+        drop(inner);
+    }
+
     #[test]
     fn test_map() {
         let resource = GrainedLock::new(Vec::<i32>::new());
         resource.borrow_mut().push(i32::default());
 
+        let inner = resource.borrow().map(|vec| vec.first().unwrap());
+        assert_eq!(*inner, i32::default());
+        assert!(resource.try_borrow_mut().is_none());
+        drop(inner);
+
+        *resource.borrow_mut().map(|vec| vec.first_mut().unwrap()) += 1;
+        assert_eq!(*resource.borrow(), [1]);
+    }
+
+    #[test]
+    fn test_filter_map() {
+        let resource = GrainedLock::new(vec![1]);
+
+        let inner = resource.borrow().filter_map(|vec| vec.get(1));
+        assert_eq!(*inner.unwrap_err(), [1]);
+
+        let inner = resource.borrow_mut().filter_map(|vec| vec.first_mut());
+        *inner.unwrap() += 1;
+        assert_eq!(*resource.borrow(), [2]);
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_map_raw() {
+        let resource = GrainedLock::new(Vec::<i32>::new());
+        resource.borrow_mut().push(i32::default());
+
         let inner = unsafe {
-            resource
-                .borrow()
-                .map::<_, _, Immutable>(|vec| (NonNull::from(vec.as_ref().get(0).unwrap()), None))
+            resource.borrow().map_raw::<_, _, Immutable>(|vec| {
+                (NonNull::from(vec.as_ref().first().unwrap()), None)
+            })
         };
 
         assert_eq!(*inner, i32::default());
@@ -1,8 +1,19 @@
+//! Fine-grained reader-writer locks, whose borrows lock the nested locks of their data, see
+//! [GrainedLock].
+//!
+//! The `deadlock-detection` feature reports cycles of threads waiting for each other's locks,
+//! and the `unsync` feature replaces the atomic operations of the locks with borrow flags for
+//! single-threaded applications.
+
+#[cfg(feature = "deadlock-detection")]
+mod deadlock;
 mod grained_cell;
 pub(crate) mod grained_lock;
 pub(crate) mod grained_ref;
+#[cfg(feature = "unsync")]
+mod unsync;
 
 #[doc(inline)]
-pub(crate) use grained_lock::GrainedLock;
+pub use grained_lock::{GrainedLock, UpgradableRef};
 #[doc(inline)]
-pub(crate) use grained_ref::Ref;
+pub use grained_ref::{Immutable, LockState, Mutable, Ref};
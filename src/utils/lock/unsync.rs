@@ -0,0 +1,246 @@
+//! Raw lock of the `GrainedLock`s with the `unsync` feature, replacing the atomic operations of
+//! `parking_lot` with plain borrow flags for single-threaded applications, e.g. on WASM.
+//!
+//! The flags are only sound to share with the thread creating the lock, so every operation
+//! first checks the current thread, panicking on any other thread. A lock already borrowed by
+//! the thread can never be released while it waits, so blocking on it panics as `RefCell` does,
+//! and the timed operations fail at once.
+
+use std::{
+    cell::Cell,
+    time::{Duration, Instant},
+};
+
+use parking_lot::lock_api::{
+    GuardNoSend, RawRwLock, RawRwLockDowngrade, RawRwLockRecursive, RawRwLockTimed,
+    RawRwLockUpgrade,
+};
+
+// state of a lock borrowed mutably
+const WRITER: usize = usize::MAX;
+
+// identifies the current thread, no live thread sharing the address of its thread local
+fn thread_id() -> usize {
+    thread_local! {
+        static ID: u8 = const { 0 };
+    }
+    ID.with(|id| id as *const u8 as usize)
+}
+
+pub(crate) struct RawUnsyncLock {
+    // number of shared borrows, or `WRITER`
+    state: Cell<usize>,
+    upgradable: Cell<bool>,
+    // thread allowed to use the lock, `0` for the locks created by `INIT`, which no thread owns
+    owner: usize,
+}
+
+// the flags are only accessed by the owner thread, the other threads panicking before
+unsafe impl Sync for RawUnsyncLock {}
+
+impl RawUnsyncLock {
+    // unlocked lock owned by the current thread, the only constructor of usable locks, see
+    // `INIT`
+    pub(crate) fn new() -> Self {
+        Self {
+            owner: thread_id(),
+            ..Self::INIT
+        }
+    }
+
+    fn check_thread(&self) {
+        assert!(
+            self.owner != 0,
+            "GrainedLock created by `RawRwLock::INIT` with the `unsync` feature, which no thread \
+             owns, instead of `RawUnsyncLock::new`"
+        );
+        assert!(
+            self.owner == thread_id(),
+            "GrainedLock used from another thread than the one creating it with the `unsync` \
+             feature"
+        );
+    }
+
+    fn acquired(acquired: bool, borrow: &str) {
+        assert!(
+            acquired,
+            "GrainedLock already borrowed, {borrow} would never be granted"
+        );
+    }
+}
+
+unsafe impl RawRwLock for RawUnsyncLock {
+    // NOT A USABLE LOCK: the owner thread is only known at runtime, so the lock created by the
+    // constant is owned by no thread and panics on every operation. `lock_api` only uses it in
+    // `RwLock::new`, `const_new` and `Default`, which the crate never calls with this lock, every
+    // lock of the crate being created by `new` through `grained_lock::raw_lock`
+    #[allow(clippy::declare_interior_mutable_const)]
+    const INIT: Self = Self {
+        state: Cell::new(0),
+        upgradable: Cell::new(false),
+        owner: 0,
+    };
+
+    type GuardMarker = GuardNoSend;
+
+    fn lock_shared(&self) {
+        Self::acquired(self.try_lock_shared(), "a shared borrow");
+    }
+
+    fn try_lock_shared(&self) -> bool {
+        self.check_thread();
+        let state = self.state.get();
+        if state == WRITER {
+            return false;
+        }
+        self.state.set(state + 1);
+        true
+    }
+
+    unsafe fn unlock_shared(&self) {
+        self.check_thread();
+        self.state.set(self.state.get() - 1);
+    }
+
+    fn lock_exclusive(&self) {
+        Self::acquired(self.try_lock_exclusive(), "a mutable borrow");
+    }
+
+    fn try_lock_exclusive(&self) -> bool {
+        self.check_thread();
+        if self.state.get() != 0 || self.upgradable.get() {
+            return false;
+        }
+        self.state.set(WRITER);
+        true
+    }
+
+    unsafe fn unlock_exclusive(&self) {
+        self.check_thread();
+        self.state.set(0);
+    }
+}
+
+unsafe impl RawRwLockRecursive for RawUnsyncLock {
+    fn lock_shared_recursive(&self) {
+        self.lock_shared();
+    }
+
+    fn try_lock_shared_recursive(&self) -> bool {
+        self.try_lock_shared()
+    }
+}
+
+unsafe impl RawRwLockDowngrade for RawUnsyncLock {
+    unsafe fn downgrade(&self) {
+        self.check_thread();
+        self.state.set(1);
+    }
+}
+
+unsafe impl RawRwLockUpgrade for RawUnsyncLock {
+    fn lock_upgradable(&self) {
+        Self::acquired(self.try_lock_upgradable(), "an upgradable borrow");
+    }
+
+    fn try_lock_upgradable(&self) -> bool {
+        self.check_thread();
+        if self.state.get() == WRITER || self.upgradable.get() {
+            return false;
+        }
+        self.upgradable.set(true);
+        true
+    }
+
+    unsafe fn unlock_upgradable(&self) {
+        self.check_thread();
+        self.upgradable.set(false);
+    }
+
+    unsafe fn upgrade(&self) {
+        Self::acquired(self.try_upgrade(), "the upgrade of the borrow");
+    }
+
+    unsafe fn try_upgrade(&self) -> bool {
+        self.check_thread();
+        if self.state.get() != 0 {
+            return false;
+        }
+        self.upgradable.set(false);
+        self.state.set(WRITER);
+        true
+    }
+}
+
+// no other thread can release the lock while the thread waits for it
+unsafe impl RawRwLockTimed for RawUnsyncLock {
+    type Duration = Duration;
+    type Instant = Instant;
+
+    fn try_lock_shared_for(&self, _timeout: Duration) -> bool {
+        self.try_lock_shared()
+    }
+
+    fn try_lock_shared_until(&self, _timeout: Instant) -> bool {
+        self.try_lock_shared()
+    }
+
+    fn try_lock_exclusive_for(&self, _timeout: Duration) -> bool {
+        self.try_lock_exclusive()
+    }
+
+    fn try_lock_exclusive_until(&self, _timeout: Instant) -> bool {
+        self.try_lock_exclusive()
+    }
+}
+
+#[cfg(test)]
+mod test_unsync {
+    use super::*;
+
+    #[test]
+    fn test_unsync_lock() {
+        let lock = RawUnsyncLock::new();
+        assert!(lock.try_lock_shared());
+        assert!(lock.try_lock_upgradable());
+        assert!(!lock.try_lock_exclusive());
+        unsafe { lock.unlock_shared() };
+
+        unsafe { lock.upgrade() };
+        assert!(!lock.try_lock_shared());
+        unsafe { lock.downgrade() };
+        assert!(lock.try_lock_shared_recursive());
+        unsafe { lock.unlock_shared() };
+        unsafe { lock.unlock_shared() };
+        assert!(lock.try_lock_exclusive());
+
+        // assert blocking on the lock panics instead of hanging
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| lock.lock_shared()));
+        let message = *result.err().unwrap().downcast::<String>().unwrap();
+        assert_eq!(
+            message,
+            "GrainedLock already borrowed, a shared borrow would never be granted"
+        );
+    }
+
+    #[test]
+    fn test_unsync_other_thread() {
+        let lock = RawUnsyncLock::new();
+        std::thread::scope(|scope| {
+            // assert other threads panic before touching the flags
+            let result = scope.spawn(|| lock.try_lock_shared()).join();
+            assert!(result.is_err());
+        });
+        assert!(lock.try_lock_exclusive());
+    }
+
+    #[test]
+    fn test_unsync_init() {
+        // assert the locks owned by no thread are rejected at once
+        let lock = RawUnsyncLock::INIT;
+        let result =
+            std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| lock.try_lock_shared()));
+        let message = *result.err().unwrap().downcast::<&str>().unwrap();
+        assert!(message.starts_with("GrainedLock created by `RawRwLock::INIT`"));
+    }
+}
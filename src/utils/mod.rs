@@ -1,3 +1,6 @@
-pub(crate) mod lock;
+pub mod lock;
+
+#[cfg(feature = "async")]
+pub(crate) mod block_on;
 
 pub mod error;
\ No newline at end of file
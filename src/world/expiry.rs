@@ -0,0 +1,134 @@
+use std::{
+    any::TypeId,
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use crate::store::Container;
+
+use super::{ResourceExpired, World};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// Lifetime of a resource added with `World::add_resource_with_expiry`.
+pub enum Expiry {
+    /// The resource expires once the duration has elapsed since its insertion, never expiring
+    /// if the duration is too long to be represented as an `Instant`.
+    After(Duration),
+    /// The resource expires at the given cleanup pass following its insertion, `Ticks(1)` and
+    /// `Ticks(0)` expiring at the next pass.
+    ///
+    /// A cleanup pass runs at the start of each `Schedule::run_world`.
+    Ticks(u64),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Deadline {
+    Instant(Instant),
+    Pass(u64),
+    Never,
+}
+
+// deadline of an expiring resource, with the removal of its type
+#[derive(Debug, Clone, Copy)]
+struct Expiration {
+    deadline: Deadline,
+    // removes the resource and emits `ResourceExpired`, returning `false` if it is missing
+    expire: fn(&mut World) -> bool,
+}
+
+fn expire_resource<T: 'static>(world: &mut World) -> bool {
+    if world.container_mut().remove_resource::<T>().is_none() {
+        return false;
+    }
+    world.event_manager().emit(ResourceExpired::<T>::new());
+    true
+}
+
+// expiring resources of a world
+#[derive(Debug, Default)]
+pub(crate) struct ExpiryTracker {
+    expirations: HashMap<TypeId, Expiration>,
+    // number of cleanup passes run
+    passes: u64,
+}
+
+impl ExpiryTracker {
+    pub(crate) fn insert<T: 'static>(&mut self, expiry: Expiry) {
+        let deadline = match expiry {
+            Expiry::After(duration) => match Instant::now().checked_add(duration) {
+                Some(deadline) => Deadline::Instant(deadline),
+                None => Deadline::Never,
+            },
+            Expiry::Ticks(ticks) => Deadline::Pass(self.passes.saturating_add(ticks.max(1))),
+        };
+        self.expirations.insert(
+            TypeId::of::<T>(),
+            Expiration {
+                deadline,
+                expire: expire_resource::<T>,
+            },
+        );
+    }
+
+    pub(crate) fn remove(&mut self, type_id: TypeId) -> bool {
+        self.expirations.remove(&type_id).is_some()
+    }
+
+    // starts a cleanup pass, returning the removals of the expired resources
+    pub(crate) fn expired(&mut self, now: Instant) -> Vec<fn(&mut World) -> bool> {
+        self.passes += 1;
+        let passes = self.passes;
+        let mut expired = Vec::new();
+        self.expirations.retain(|_, expiration| {
+            let is_expired = match expiration.deadline {
+                Deadline::Instant(deadline) => deadline <= now,
+                Deadline::Pass(pass) => pass <= passes,
+                Deadline::Never => false,
+            };
+            if is_expired {
+                expired.push(expiration.expire);
+            }
+            !is_expired
+        });
+        expired
+    }
+}
+
+#[cfg(test)]
+mod test_expiry {
+    use super::*;
+    use crate::system::Schedule;
+
+    #[test]
+    fn test_expiry() {
+        let mut world = World::new();
+        world.add_resource_with_expiry(1u32, Expiry::After(Duration::ZERO));
+        world.add_resource_with_expiry(1u64, Expiry::Ticks(2));
+        world.add_resource_with_expiry(1u8, Expiry::Ticks(1));
+        world.add_resource(2u8);
+
+        // assert replaced resources do not expire
+        Schedule::new().run_world(&mut world);
+        assert!(!world.contains_resource::<u32>());
+        assert!(world.contains_resource::<u64>());
+        assert!(world.contains_resource::<u8>());
+
+        // assert resources removed through the container are skipped
+        world.container_mut().remove_resource::<u64>();
+        assert_eq!(world.remove_expired(), 0);
+        assert!(!world.cancel_expiry::<u64>());
+    }
+
+    #[test]
+    fn test_expiry_overflow() {
+        let mut world = World::new();
+        world.add_resource_with_expiry(1u32, Expiry::After(Duration::MAX));
+        world.add_resource_with_expiry(1u64, Expiry::Ticks(u64::MAX));
+
+        // assert deadlines past the representable instants never expire
+        assert_eq!(world.remove_expired(), 0);
+        assert!(world.contains_resource::<u32>());
+        assert!(world.contains_resource::<u64>());
+        assert!(world.cancel_expiry::<u32>());
+    }
+}
@@ -0,0 +1,45 @@
+use std::{fmt::Debug, marker::PhantomData};
+
+use crate::event::Event;
+
+/// Event emitted when a resource of type `T` is added through the [World](super::World), see
+/// `World::set_resource_events`.
+///
+/// Adding a resource that already exists emits the event again, as the resource is replaced.
+pub struct ResourceInserted<T: 'static> {
+    _marker: PhantomData<fn() -> T>,
+}
+
+/// Event emitted when a resource of type `T` is removed through the [World](super::World), see
+/// `World::set_resource_events`.
+pub struct ResourceRemoved<T: 'static> {
+    _marker: PhantomData<fn() -> T>,
+}
+
+/// Event emitted when a resource of type `T` added with `World::add_resource_with_expiry` is
+/// removed by a cleanup pass.
+pub struct ResourceExpired<T: 'static> {
+    _marker: PhantomData<fn() -> T>,
+}
+
+macro_rules! impl_lifecycle_event {
+    ($($event:ident),*) => {
+        $(impl<T: 'static> $event<T> {
+            pub(crate) fn new() -> Self {
+                Self {
+                    _marker: PhantomData,
+                }
+            }
+        }
+
+        impl<T: 'static> Event for $event<T> {}
+
+        impl<T: 'static> Debug for $event<T> {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(f, "{}<{}>", stringify!($event), std::any::type_name::<T>())
+            }
+        })*
+    };
+}
+
+impl_lifecycle_event!(ResourceInserted, ResourceRemoved, ResourceExpired);
@@ -0,0 +1,30 @@
+//! # World
+//!
+//! The [World] bundles the `ResourceContainer` and the `EventManager` of an application.
+//!
+//! Systems usually borrow both through shared references, so that non-conflicting systems can
+//! run concurrently. Exclusive systems receive `&mut World` instead, giving them full access to
+//! the resources and the events, e.g. to restructure resources in bulk. They run at the barrier
+//! closing their stage, once every other system of the stage has finished.
+//!
+//! Resources added and removed through the world itself can emit [ResourceInserted] and
+//! [ResourceRemoved] events, enabled with `World::set_resource_events`, so systems and handlers
+//! can react to configuration being added or torn down.
+//!
+//! Resources added with `World::add_resource_with_expiry` are removed once their [Expiry] is
+//! reached by the cleanup pass run at the start of each schedule run, emitting a
+//! [ResourceExpired] event, e.g. for caches and session data.
+//!
+#[doc(hidden)]
+mod expiry;
+#[doc(inline)]
+pub use expiry::Expiry;
+#[doc(hidden)]
+mod lifecycle;
+#[doc(inline)]
+pub use lifecycle::{ResourceExpired, ResourceInserted, ResourceRemoved};
+#[doc(hidden)]
+#[allow(clippy::module_inception)]
+mod world;
+#[doc(inline)]
+pub use world::World;
@@ -0,0 +1,202 @@
+use std::{any::TypeId, time::Instant};
+
+use crate::{
+    event::{Event, EventManager},
+    store::{Container, Res, ResMut, Resource, ResourceContainer, Retriever},
+    system::Schedule,
+};
+
+use super::{expiry::ExpiryTracker, Expiry, ResourceInserted, ResourceRemoved};
+
+#[derive(Default, Debug)]
+/// The resources and the events of an application.
+///
+/// # Examples
+/// ```
+/// use emark::prelude::*;
+/// use emark::world::World;
+///
+/// let mut world = World::new();
+/// world.add_resource(1u32);
+/// *world.resource_mut::<u32>() += 1;
+/// assert_eq!(*world.resource::<u32>(), 2);
+///
+/// let mut schedule = Schedule::new();
+/// schedule.add_system(|mut count: ResMut<u32>| *count *= 10);
+/// world.run_schedule(&mut schedule);
+/// assert_eq!(*world.resource::<u32>(), 20);
+/// ```
+pub struct World {
+    container: ResourceContainer,
+    event_manager: EventManager,
+    // emit lifecycle events from `add_resource` and `remove_resource`
+    resource_events: bool,
+    // resources removed by `remove_expired`
+    expiries: ExpiryTracker,
+}
+
+impl World {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// World owning an existing container and event manager.
+    pub fn from_parts(container: ResourceContainer, event_manager: EventManager) -> Self {
+        Self {
+            container,
+            event_manager,
+            resource_events: false,
+            expiries: ExpiryTracker::default(),
+        }
+    }
+
+    /// Returns the container and the event manager of the world.
+    pub fn into_parts(self) -> (ResourceContainer, EventManager) {
+        (self.container, self.event_manager)
+    }
+
+    pub fn container(&self) -> &ResourceContainer {
+        &self.container
+    }
+
+    pub fn container_mut(&mut self) -> &mut ResourceContainer {
+        &mut self.container
+    }
+
+    pub fn event_manager(&self) -> &EventManager {
+        &self.event_manager
+    }
+
+    pub fn event_manager_mut(&mut self) -> &mut EventManager {
+        &mut self.event_manager
+    }
+
+    /// Enables the emission of [ResourceInserted] and [ResourceRemoved] events by
+    /// `add_resource` and `remove_resource`. Disabled by default.
+    ///
+    /// Resources added or removed through `container_mut` never emit events.
+    ///
+    /// # Examples
+    /// ```
+    /// use emark::world::{ResourceInserted, ResourceRemoved, World};
+    ///
+    /// let mut world = World::new();
+    /// world.set_resource_events(true);
+    /// world.add_resource(1u32);
+    /// assert_eq!(world.remove_resource::<u32>(), Some(1));
+    ///
+    /// let events = world.event_manager();
+    /// assert_eq!(events.peek(|added: &[ResourceInserted<u32>]| added.len()), Some(1));
+    /// assert_eq!(events.peek(|removed: &[ResourceRemoved<u32>]| removed.len()), Some(1));
+    /// ```
+    pub fn set_resource_events(&mut self, enabled: bool) -> &mut Self {
+        self.resource_events = enabled;
+        self
+    }
+
+    pub fn resource_events(&self) -> bool {
+        self.resource_events
+    }
+
+    /// Borrows the resource of type `T`, see [Retriever::retrieve].
+    ///
+    /// # Panics
+    /// Panics if the resource is not found.
+    pub fn resource<T: Resource + ?Sized>(&self) -> Res<'_, T> {
+        Res::retrieve(&self.container)
+    }
+
+    /// Mutably borrows the resource of type `T`, see [Retriever::retrieve].
+    ///
+    /// # Panics
+    /// Panics if the resource is not found.
+    pub fn resource_mut<T: Resource + ?Sized>(&self) -> ResMut<'_, T> {
+        ResMut::retrieve(&self.container)
+    }
+
+    /// Retrieves a set of resources from the container, see [Retriever::retrieve].
+    pub fn retrieve<R: Retriever>(&self) -> R::Item<'_> {
+        R::retrieve(&self.container)
+    }
+
+    pub fn contains_resource<T: ?Sized + 'static>(&self) -> bool {
+        self.container.contains_resource::<T>()
+    }
+
+    /// Emits an event with normal priority, see `EventManager::emit`.
+    pub fn emit<T: Event + Send + Sync + 'static>(&self, event: T) -> Option<TypeId> {
+        self.event_manager.emit(event)
+    }
+
+    /// Runs every system of `schedule` once against the world, see `Schedule::run_world`.
+    pub fn run_schedule(&mut self, schedule: &mut Schedule) {
+        schedule.run_world(self);
+    }
+
+    /// Adds a resource to the container, returning the resource it replaces, and emitting a
+    /// [ResourceInserted] event if resource events are enabled.
+    ///
+    /// The expiry of the resource it replaces, if any, is cancelled.
+    pub fn add_resource<T: Send + Sync + 'static>(&mut self, resource: T) -> Option<T> {
+        self.expiries.remove(TypeId::of::<T>());
+        let previous = self.container.add_resource(resource);
+        if self.resource_events {
+            self.event_manager.emit(ResourceInserted::<T>::new());
+        }
+        previous
+    }
+
+    /// Removes a resource from the container, emitting a [ResourceRemoved] event if resource
+    /// events are enabled and the resource was found.
+    pub fn remove_resource<T: 'static>(&mut self) -> Option<T> {
+        self.expiries.remove(TypeId::of::<T>());
+        let resource = self.container.remove_resource::<T>()?;
+        if self.resource_events {
+            self.event_manager.emit(ResourceRemoved::<T>::new());
+        }
+        Some(resource)
+    }
+
+    /// Adds a resource removed by the first cleanup pass reaching its `expiry`, see
+    /// `remove_expired`.
+    ///
+    /// Replacing or removing the resource through the world cancels its expiry, while a
+    /// resource removed through `container_mut` is skipped by the cleanup pass.
+    ///
+    /// # Examples
+    /// ```
+    /// use emark::world::{Expiry, ResourceExpired, World};
+    ///
+    /// let mut world = World::new();
+    /// world.add_resource_with_expiry("session", Expiry::Ticks(2));
+    /// assert_eq!(world.remove_expired(), 0);
+    /// assert_eq!(world.remove_expired(), 1);
+    /// assert!(!world.contains_resource::<&str>());
+    ///
+    /// let expired = world.event_manager().peek(|expired: &[ResourceExpired<&str>]| expired.len());
+    /// assert_eq!(expired, Some(1));
+    /// ```
+    pub fn add_resource_with_expiry<T: Send + Sync + 'static>(
+        &mut self,
+        resource: T,
+        expiry: Expiry,
+    ) -> Option<T> {
+        let previous = self.add_resource(resource);
+        self.expiries.insert::<T>(expiry);
+        previous
+    }
+
+    /// Cancels the expiry of the resource of type `T`, returning `false` if it has none.
+    pub fn cancel_expiry<T: 'static>(&mut self) -> bool {
+        self.expiries.remove(TypeId::of::<T>())
+    }
+
+    /// Runs a cleanup pass, removing the expired resources and emitting a [ResourceExpired](super::ResourceExpired)
+    /// event for each, and returns the number of resources removed.
+    ///
+    /// `Schedule::run_world` runs a cleanup pass before running the systems.
+    pub fn remove_expired(&mut self) -> usize {
+        let expired = self.expiries.expired(Instant::now());
+        expired.into_iter().filter(|expire| expire(self)).count()
+    }
+}